@@ -3,72 +3,241 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, bail, Context, Result};
 
-fn find_hyperscan() -> Result<PathBuf> {
+/// Returns the installation directory of the library, `VECTORSCAN_ROOT` takes precedence with the `vectorscan` feature.
+fn library_root() -> Option<String> {
     cargo_emit::rerun_if_env_changed!("HYPERSCAN_ROOT");
 
-    let link_kind = if cfg!(feature = "static") { "static" } else { "dylib" };
+    if cfg!(feature = "vectorscan") {
+        cargo_emit::rerun_if_env_changed!("VECTORSCAN_ROOT");
+
+        if let Ok(prefix) = env::var("VECTORSCAN_ROOT") {
+            return Some(prefix);
+        }
+    }
+
+    env::var("HYPERSCAN_ROOT").ok()
+}
+
+/// Returns the kind of linking selected by the `static` and `dylib` features, the dynamic linking by default.
+fn link_kind() -> Result<&'static str> {
+    match (cfg!(feature = "static"), cfg!(feature = "dylib")) {
+        (true, true) => bail!("the `static` and `dylib` features are mutually exclusive"),
+        (true, false) => Ok("static"),
+        (false, _) => Ok("dylib"),
+    }
+}
+
+/// Returns true if only the runtime library `libhs_runtime` is linked, without the compiler.
+fn runtime_only() -> bool {
+    !cfg!(feature = "compile") && !cfg!(feature = "chimera") && cfg!(feature = "runtime")
+}
+
+/// Link the library installed in the prefix directory, and returns the include directory.
+fn link_library(prefix: &Path) -> Result<PathBuf> {
+    let link_kind = link_kind()?;
     let static_libstd = cfg!(feature = "contained");
 
-    if let Ok(prefix) = env::var("HYPERSCAN_ROOT") {
-        let prefix = Path::new(&prefix);
-        let inc_path = prefix.join("include/hs");
-        let link_path = prefix.join("lib");
+    let inc_path = prefix.join("include/hs");
+    let link_path = prefix.join("lib");
 
-        if cfg!(feature = "tracing") {
-            cargo_emit::warning!("use HYPERSCAN_ROOT = {}", prefix.display());
-        }
+    if cfg!(feature = "tracing") {
+        cargo_emit::warning!("use the library installed @ {}", prefix.display());
+    }
 
-        if !prefix.exists() || !prefix.is_dir() {
-            bail!("HYPERSCAN_ROOT should point to a directory that exists.");
-        }
+    if !prefix.exists() || !prefix.is_dir() {
+        bail!("HYPERSCAN_ROOT (or VECTORSCAN_ROOT) should point to a directory that exists.");
+    }
 
-        if link_path.exists() && link_path.is_dir() {
-            cargo_emit::rustc_link_search!(link_path.to_string_lossy() => "native");
+    if link_path.exists() && link_path.is_dir() {
+        cargo_emit::rustc_link_search!(link_path.to_string_lossy() => "native");
+    } else {
+        bail!("`$HYPERSCAN_ROOT/lib` subdirectory not found.");
+    }
+
+    cargo_emit::rustc_link_search!(link_path.to_string_lossy() => "native");
+
+    // the runtime library is written in C, only the compiler depends on the C++ standard library
+    if link_kind == "static" && !runtime_only() {
+        let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap();
+        let std_link = if target_os == "macos" { "c++" } else { "stdc++" };
+        if static_libstd {
+            cargo_emit::rustc_link_lib!(std_link => "static:-bundle");
         } else {
-            bail!("`$HYPERSCAN_ROOT/lib` subdirectory not found.");
+            cargo_emit::rustc_link_lib!(std_link);
         }
+    }
 
-        cargo_emit::rustc_link_search!(link_path.to_string_lossy() => "native");
+    if runtime_only() {
+        cargo_emit::rustc_link_lib!("hs_runtime" => link_kind);
+    } else {
+        cargo_emit::rustc_link_lib!("hs" => link_kind);
+    }
 
-        if cfg!(feature = "static") {
-            let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap();
-            let std_link = if target_os == "macos" { "c++" } else { "stdc++" };
-            if static_libstd {
-                cargo_emit::rustc_link_lib!(std_link => "static:-bundle");
-            } else {
-                cargo_emit::rustc_link_lib!(std_link);
+    if cfg!(feature = "chimera") {
+        cargo_emit::rustc_link_lib!("chimera" => "static");
+        cargo_emit::rustc_link_lib!("pcre" => "static");
+    }
+
+    if cfg!(feature = "tracing") {
+        cargo_emit::warning!(
+            "building with Hyperscan with {} library @ {:?}, link_paths=[{:?}], include_paths=[{:?}]",
+            link_kind,
+            prefix,
+            link_path,
+            inc_path
+        );
+    }
+
+    Ok(inc_path)
+}
+
+/// The source archive downloaded by the `vendored` feature, the directory extracted from it,
+/// and the pinned SHA-256 checksum of the archive.
+#[cfg(feature = "vendored")]
+const SOURCE_ARCHIVE: (&str, &str, &str) = if cfg!(feature = "vectorscan") {
+    (
+        "https://github.com/VectorCamp/vectorscan/archive/refs/tags/vectorscan/5.4.11.tar.gz",
+        "vectorscan-vectorscan-5.4.11",
+        "905f76ad1fa9e4ae0eb28232cac98afdb96c479666202c5a4c27871fb30a2711",
+    )
+} else {
+    (
+        "https://github.com/intel/hyperscan/archive/refs/tags/v5.4.2.tar.gz",
+        "hyperscan-5.4.2",
+        "32b0f24b3113bbc46b6bfaa05cf7cf45840b6b59333d078cc1f624e4c40b2b99",
+    )
+};
+
+/// Check the SHA-256 checksum of the downloaded archive with `sha256sum`, or `shasum` on macOS.
+///
+/// The pinned checksum could be overridden with the `HYPERSCAN_SRC_SHA256` environment variable.
+#[cfg(feature = "vendored")]
+fn verify_archive(archive: &Path, pinned: &str) -> Result<()> {
+    use std::process::Command;
+
+    cargo_emit::rerun_if_env_changed!("HYPERSCAN_SRC_SHA256");
+
+    let expected = env::var("HYPERSCAN_SRC_SHA256").unwrap_or_else(|_| pinned.to_owned());
+    let output = Command::new("sha256sum")
+        .arg(archive)
+        .output()
+        .or_else(|_| Command::new("shasum").args(["-a", "256"]).arg(archive).output())
+        .context("run `sha256sum` or `shasum`")?;
+
+    if !output.status.success() {
+        bail!("checksum of {} failed with {}", archive.display(), output.status);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let actual = stdout.split_whitespace().next().unwrap_or_default();
+
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        let _ = std::fs::remove_file(archive);
+
+        bail!(
+            "checksum mismatch of {}, expected {}, got {}",
+            archive.display(),
+            expected.trim(),
+            actual
+        );
+    }
+
+    Ok(())
+}
+
+/// Build the library from the source tree with `cmake`, and returns the directory it was installed to.
+///
+/// The source tree is located with the `HYPERSCAN_SRC` environment variable,
+/// or downloaded and extracted to `OUT_DIR` with `curl` and `tar` after checking its SHA-256 checksum.
+///
+/// Chimera isn't supported by the vendored build, which requires the PCRE source tree.
+#[cfg(feature = "vendored")]
+fn build_vendored() -> Result<PathBuf> {
+    use std::process::Command;
+
+    cargo_emit::rerun_if_env_changed!("HYPERSCAN_SRC");
+
+    if cfg!(feature = "chimera") {
+        bail!(
+            "the vendored build doesn't support Chimera, set HYPERSCAN_ROOT to a library built with Chimera and PCRE"
+        );
+    }
+
+    let src = match env::var("HYPERSCAN_SRC") {
+        Ok(src) => PathBuf::from(src),
+        Err(_) => {
+            let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+            let (url, dir, sha256) = SOURCE_ARCHIVE;
+            let src = out_dir.join(dir);
+
+            if !src.is_dir() {
+                let archive = out_dir.join(format!("{}.tar.gz", dir));
+
+                let mut curl = Command::new("curl");
+                let mut tar = Command::new("tar");
+
+                curl.args(["-sSfL", "-o"]).arg(&archive).arg(url);
+                tar.arg("xzf").arg(&archive).arg("-C").arg(&out_dir);
+
+                let run = |cmd: &mut Command| -> Result<()> {
+                    let status = cmd.status().with_context(|| format!("run {:?}", cmd))?;
+
+                    if !status.success() {
+                        bail!("{:?} failed with {}", cmd, status);
+                    }
+
+                    Ok(())
+                };
+
+                run(&mut curl)?;
+                verify_archive(&archive, sha256)?;
+                run(&mut tar)?;
             }
-        }
 
-        if !cfg!(feature = "compile") && cfg!(feature = "runtime") {
-            cargo_emit::rustc_link_lib!("hs_runtime" => link_kind);
-        } else {
-            cargo_emit::rustc_link_lib!("hs" => link_kind);
+            src
         }
+    };
 
-        if cfg!(feature = "chimera") {
-            cargo_emit::rustc_link_lib!("chimera" => "static");
-            cargo_emit::rustc_link_lib!("pcre" => "static");
-        }
+    if cfg!(feature = "tracing") {
+        cargo_emit::warning!("building the vendored library from {}", src.display());
+    }
 
-        if cfg!(feature = "tracing") {
-            cargo_emit::warning!(
-                "building with Hyperscan with {} library @ {:?}, link_paths=[{:?}], include_paths=[{:?}]",
-                link_kind,
-                prefix,
-                link_path,
-                inc_path
-            );
-        }
+    Ok(cmake::Config::new(&src)
+        .define("BUILD_SHARED_LIBS", "OFF")
+        .define("CMAKE_INSTALL_LIBDIR", "lib")
+        .define("CMAKE_POSITION_INDEPENDENT_CODE", "ON")
+        .define("FAT_RUNTIME", "OFF")
+        .build())
+}
 
-        Ok(inc_path)
+#[cfg(not(feature = "vendored"))]
+fn build_vendored() -> Result<PathBuf> {
+    bail!("the `vendored` feature is not enabled")
+}
+
+fn find_hyperscan() -> Result<PathBuf> {
+    if let Some(prefix) = library_root() {
+        link_library(Path::new(&prefix))
+    } else if cfg!(feature = "vendored") {
+        link_library(&build_vendored()?)
     } else {
+        let link_kind = link_kind()?;
+
+        // `libhs.pc` links the whole library, so only its search paths are used for the runtime library
         let libhs = pkg_config::Config::new()
-            .statik(cfg!(feature = "static"))
-            .cargo_metadata(true)
+            .statik(link_kind == "static")
+            .cargo_metadata(!runtime_only())
             .env_metadata(true)
             .probe("libhs")?;
 
+        if runtime_only() {
+            for link_path in &libhs.link_paths {
+                cargo_emit::rustc_link_search!(link_path.to_string_lossy() => "native");
+            }
+
+            cargo_emit::rustc_link_lib!("hs_runtime" => link_kind);
+        }
+
         if cfg!(feature = "tracing") {
             cargo_emit::warning!(
                 "building with Hyperscan {} with {} library, libs={:?}, link_paths={:?}, include_paths={:?}",
@@ -82,7 +251,7 @@ fn find_hyperscan() -> Result<PathBuf> {
 
         if cfg!(feature = "chimera") {
             let libch = pkg_config::Config::new()
-                .statik(cfg!(feature = "static"))
+                .statik(link_kind == "static")
                 .cargo_metadata(true)
                 .env_metadata(true)
                 .probe("libch")?;
@@ -122,7 +291,7 @@ fn generate_binding(inc_dir: &Path, out_dir: &Path) -> Result<()> {
     bindgen::builder()
         .header(inc_file)
         .use_core()
-        .ctypes_prefix("::libc")
+        .ctypes_prefix("::core::ffi")
         .clang_args(&["-x", "c++", "-std=c++11"])
         .allowlist_var("^HS_.*")
         .allowlist_type("^hs_.*")
@@ -162,7 +331,7 @@ fn generate_chimera_binding(inc_dir: &Path, out_dir: &Path) -> Result<()> {
     bindgen::builder()
         .header(inc_file)
         .use_core()
-        .ctypes_prefix("::libc")
+        .ctypes_prefix("::core::ffi")
         .clang_args(&["-x", "c++", "-std=c++11"])
         .allowlist_var("^CH_.*")
         .allowlist_type("^ch_.*")
@@ -192,11 +361,83 @@ fn generate_chimera_binding(_: &Path, _: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Check the target architecture, Hyperscan only supports x86,
+/// while Vectorscan, the fork of Hyperscan, also supports ARM (aarch64) and POWER.
+///
+/// Vectorscan keeps the API and the headers of Hyperscan, so the same bindings are used for both libraries.
+fn check_target() {
+    let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+
+    if cfg!(feature = "vectorscan") {
+        if cfg!(feature = "tracing") {
+            cargo_emit::warning!("building with Vectorscan for {}", arch);
+        }
+    } else if arch != "x86" && arch != "x86_64" {
+        cargo_emit::warning!(
+            "Hyperscan only supports the x86 targets, enable the `vectorscan` feature to build for {}",
+            arch
+        );
+    }
+}
+
+/// The version of the Hyperscan library, parsed from the `HS_MAJOR`, `HS_MINOR` and `HS_PATCH` macros of `hs.h`.
+fn detect_version(inc_dir: &Path) -> Result<(u32, u32, u32)> {
+    let inc_file = inc_dir.join("hs.h");
+    let header = std::fs::read_to_string(&inc_file).with_context(|| format!("read {}", inc_file.display()))?;
+
+    let define = |name: &str| -> Result<u32> {
+        header
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("#define"))
+            .filter_map(|line| line.trim().strip_prefix(name))
+            .find_map(|value| value.trim().parse().ok())
+            .ok_or_else(|| anyhow!("missing `{}` in {}", name, inc_file.display()))
+    };
+
+    Ok((define("HS_MAJOR")?, define("HS_MINOR")?, define("HS_PATCH")?))
+}
+
+/// Emit the version of the Hyperscan library,
+/// and the `hyperscan_vX_Y` cfgs for the API levels supported by the library.
+///
+/// The version is also available to the build scripts of dependent crates as `DEP_HS_VERSION`.
+fn emit_version((major, minor, patch): (u32, u32, u32)) {
+    if cfg!(feature = "tracing") {
+        cargo_emit::warning!("detected Hyperscan {}.{}.{}", major, minor, patch);
+    }
+
+    cargo_emit::pair!("version", "{}.{}.{}", major, minor, patch);
+    cargo_emit::rustc_env!("HYPERSCAN_VERSION", "{}.{}.{}", major, minor, patch);
+
+    for &(cfg, required) in &[
+        ("hyperscan_v4", (4, 0)),
+        ("hyperscan_v5", (5, 0)),
+        ("hyperscan_v5_2", (5, 2)),
+        ("hyperscan_v5_4", (5, 4)),
+    ] {
+        if (major, minor) >= required {
+            cargo_emit::rustc_cfg!("{}", cfg);
+        }
+    }
+}
+
 fn main() -> Result<()> {
+    check_target();
+
     let inc_dir = if std::env::var("DOCS_RS").is_ok() {
         PathBuf::new()
     } else {
-        find_hyperscan().with_context(|| "please download and install hyperscan from https://www.hyperscan.io/")?
+        let inc_dir = find_hyperscan().with_context(|| {
+            if cfg!(feature = "vectorscan") {
+                "please download and install vectorscan from https://github.com/VectorCamp/vectorscan"
+            } else {
+                "please download and install hyperscan from https://www.hyperscan.io/"
+            }
+        })?;
+
+        emit_version(detect_version(&inc_dir)?);
+
+        inc_dir
     };
     let out_dir = env::var("OUT_DIR")?;
     let out_dir = Path::new(&out_dir);
@@ -5,6 +5,11 @@
 
 include!(concat!(env!("OUT_DIR"), "/hyperscan.rs"));
 
+/// The version of the Hyperscan library detected by the build script, e.g. `5.4.0`.
+///
+/// It is `None` when the library was not probed, e.g. when building the documentation.
+pub const LINKED_VERSION: Option<&str> = option_env!("HYPERSCAN_VERSION");
+
 #[cfg(feature = "chimera")]
 pub mod chimera {
     #![allow(deref_nullptr)]
@@ -91,6 +91,50 @@ fn chimera_bench(c: &mut Criterion) {
 #[cfg(not(feature = "chimera"))]
 fn chimera_bench(c: &mut Criterion) {}
 
+#[cfg(feature = "literal")]
+fn literal_bench(c: &mut Criterion) {
+    use hyperscan::{prelude::*, BlockMode};
+
+    let mut group = c.benchmark_group("literal");
+
+    let patterns = [
+        "ABCD", "CDEF", "EFGH", "GHIJ", "IJKL", "KLMN", "MNOP", "OPQR", "QRST", "STUV",
+    ]
+    .iter()
+    .map(|&expr| literal! {expr}.into())
+    .collect::<MixedPatterns>();
+
+    assert!(patterns.is_literal());
+
+    // the whole set is literal, so it's compiled with `hs_compile_lit_multi`
+    let literal_db = patterns.build::<BlockMode>().unwrap();
+    // the same set escaped into regular expressions, compiled with `hs_compile_multi`
+    let regex_db = patterns.to_patterns().build::<BlockMode>().unwrap();
+
+    group.bench_function("compile/literal", |b| b.iter(|| patterns.build::<BlockMode>().unwrap()));
+    group.bench_function("compile/regex", |b| {
+        b.iter(|| patterns.to_patterns().build::<BlockMode>().unwrap())
+    });
+
+    for (name, db) in [("literal", &literal_db), ("regex", &regex_db)].iter() {
+        let s = db.alloc_scratch().unwrap();
+
+        for &size in BENCH_SIZE.iter() {
+            let text = BENCH_TEXT.get(..size).unwrap();
+
+            group.throughput(Throughput::Bytes(text.len() as u64));
+            group.bench_with_input(BenchmarkId::new(*name, size), &(text), |b, text| {
+                b.iter(|| db.scan(text, &s, Matching::Continue).unwrap())
+            });
+        }
+    }
+
+    group.finish();
+}
+
+#[cfg(not(feature = "literal"))]
+fn literal_bench(_c: &mut Criterion) {}
+
 fn regex_bench(c: &mut Criterion) {
     use std::str;
 
@@ -117,7 +161,7 @@ fn regex_bench(c: &mut Criterion) {
 criterion_group! {
     name = benches;
     config = Criterion::default();
-    targets = hyperscan_bench, chimera_bench, regex_bench
+    targets = hyperscan_bench, chimera_bench, literal_bench, regex_bench
 }
 
 criterion_main!(benches);
@@ -0,0 +1,73 @@
+use std::env;
+
+/// The API levels, the features which enable them, and the minimum library versions they require.
+const API_LEVELS: &[(&str, &str, (u32, u32))] = &[
+    ("hyperscan_v4", "V4", (4, 0)),
+    ("hyperscan_v5", "V5", (5, 0)),
+    ("hyperscan_v5_2", "V5_2", (5, 2)),
+    ("hyperscan_v5_4", "V5_4", (5, 4)),
+];
+
+/// The features which require a minimum library version without an API level of their own.
+const FEATURES: &[(&str, (u32, u32))] = &[("LITERAL", (5, 2))];
+
+fn feature_enabled(name: &str) -> bool {
+    env::var_os(format!("CARGO_FEATURE_{}", name)).is_some()
+}
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=DEP_HS_VERSION");
+
+    for &(cfg, _, _) in API_LEVELS {
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+    }
+    println!("cargo:rustc-check-cfg=cfg(hyperscan_x86)");
+
+    // The x86 tuning families and CPU features are only available with Hyperscan, or Vectorscan on the x86 targets.
+    let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+    if !feature_enabled("VECTORSCAN") || target_arch == "x86" || target_arch == "x86_64" {
+        println!("cargo:rustc-cfg=hyperscan_x86");
+    }
+
+    // The version of the linked library detected by `hyperscan-sys`.
+    let version = match env::var("DEP_HS_VERSION") {
+        Ok(version) => version,
+        Err(_) => {
+            // The library isn't available (e.g. on docs.rs), fall back to the API levels enabled by the features.
+            for &(cfg, feature, _) in API_LEVELS {
+                if feature_enabled(feature) {
+                    println!("cargo:rustc-cfg={}", cfg);
+                }
+            }
+            return;
+        }
+    };
+    let mut parts = version.split('.').map(|s| s.parse::<u32>().unwrap_or_default());
+    let linked = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+
+    // The APIs are gated by the API levels supported by the linked library,
+    // warn about the features which the linked library doesn't support.
+    for &(cfg, feature, required) in API_LEVELS {
+        if linked >= required {
+            println!("cargo:rustc-cfg={}", cfg);
+        } else if feature_enabled(feature) {
+            warn_unsupported(feature, required, &version);
+        }
+    }
+
+    for &(feature, required) in FEATURES {
+        if linked < required && feature_enabled(feature) {
+            warn_unsupported(feature, required, &version);
+        }
+    }
+}
+
+fn warn_unsupported(feature: &str, required: (u32, u32), version: &str) {
+    println!(
+        "cargo:warning=feature `{}` requires Hyperscan {}.{}, but the linked library is {}",
+        feature.to_lowercase(),
+        required.0,
+        required.1,
+        version
+    );
+}
@@ -84,27 +84,19 @@
 //!
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io;
-use std::net::SocketAddrV4;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::time::Instant;
 
 use anyhow::{anyhow, bail, Context, Error, Result};
-use byteorder::{BigEndian, ReadBytesExt};
 use derive_more::{Deref, Display, Index};
-use either::Either;
-use pnet::packet::{
-    ethernet::{EtherTypes, EthernetPacket},
-    ip::IpNextHeaderProtocols,
-    ipv4::Ipv4Packet,
-    udp::UdpPacket,
-    Packet, PrimitiveValues,
-};
 use rand::seq::SliceRandom;
 use structopt::StructOpt;
 
-use hyperscan::{prelude::*, Block, Streaming};
+use hyperscan::{
+    bench::{Benchmark, Corpus},
+    pcap::SessionExtractor,
+    prelude::*,
+};
 
 #[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
 enum Criterion {
@@ -186,178 +178,20 @@ struct Opt {
     pcap_file: Option<PathBuf>,
 }
 
-// Key for identifying a stream in our pcap input data, using data from its IP
-// headers.
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
-struct Session {
-    proto: u8,
-    src: SocketAddrV4,
-    dst: SocketAddrV4,
-}
+/// Read the payloads of the packets in the PCAP file as a corpus, keyed by their sessions.
+fn read_corpus<P: AsRef<Path>>(path: P) -> hyperscan::Result<Corpus> {
+    let mut sessions = HashMap::new();
+    let mut corpus = Corpus::new();
 
-impl Session {
-    fn new(ipv4: &Ipv4Packet) -> Session {
-        let mut c = io::Cursor::new(ipv4.payload());
-        let src_port = c.read_u16::<BigEndian>().unwrap();
-        let dst_port = c.read_u16::<BigEndian>().unwrap();
+    for res in SessionExtractor::open(path)? {
+        let (key, payload) = res?;
+        let id = sessions.len();
+        let stream_id = *sessions.entry(key).or_insert(id);
 
-        Session {
-            proto: ipv4.get_next_level_protocol().to_primitive_values().0,
-            src: SocketAddrV4::new(ipv4.get_source(), src_port),
-            dst: SocketAddrV4::new(ipv4.get_destination(), dst_port),
-        }
+        corpus.push(stream_id, payload);
     }
 
-    fn decode(packet: &pcap::Packet) -> Option<(Session, Vec<u8>)> {
-        let ether = EthernetPacket::new(&packet.data).unwrap();
-
-        if ether.get_ethertype() != EtherTypes::Ipv4 {
-            return None;
-        }
-
-        let ipv4 = Ipv4Packet::new(&ether.payload()).unwrap();
-
-        if ipv4.get_version() != 4 {
-            return None;
-        }
-
-        if (ipv4.get_flags() & IP_FLAG_MF) == IP_FLAG_MF || ipv4.get_fragment_offset() != 0 {
-            return None;
-        }
-
-        match ipv4.get_next_level_protocol() {
-            IpNextHeaderProtocols::Tcp => {
-                let payload = ipv4.payload();
-                let data_off = ((payload[12] >> 4) * 4) as usize;
-
-                Some((Session::new(&ipv4), Vec::from(&payload[data_off..])))
-            }
-
-            IpNextHeaderProtocols::Udp => {
-                let udp = UdpPacket::new(&ipv4.payload()).unwrap();
-
-                Some((Session::new(&ipv4), Vec::from(udp.payload())))
-            }
-            _ => None,
-        }
-    }
-}
-
-/// Class wrapping all state associated with the benchmark
-#[derive(Default)]
-struct Benchmark {
-    /// Packet data to be scanned
-    packets: Vec<Vec<u8>>,
-
-    /// Stream ID for each packet
-    stream_ids: Vec<usize>,
-
-    /// Map used to construct stream_ids
-    sessions: HashMap<Session, usize>,
-
-    // Vector of Hyperscan stream state (used in streaming mode)
-    streams: Vec<Stream>,
-
-    // Count of matches found during scanning
-    matches: usize,
-}
-
-const IP_FLAG_MF: u8 = 1;
-
-impl Benchmark {
-    fn new() -> Self {
-        Default::default()
-    }
-
-    fn read_streams<P: AsRef<Path>>(&mut self, path: P) -> Result<(), pcap::Error> {
-        let mut capture = pcap::Capture::from_file(path)?;
-
-        while let Ok(ref packet) = capture.next_packet() {
-            if let Some((key, payload)) = Session::decode(&packet) {
-                if payload.len() > 0 {
-                    let stream_id = match self.sessions.get(&key) {
-                        Some(&id) => id,
-                        None => {
-                            let id = self.sessions.len();
-
-                            assert!(self.sessions.insert(key, id).is_none());
-
-                            id
-                        }
-                    };
-
-                    self.stream_ids.push(stream_id);
-                    self.packets.push(payload);
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    // Clear the number of matches found.
-    fn clear_matches(&mut self) {
-        self.matches = 0;
-    }
-
-    // Return the number of bytes scanned
-    fn bytes(&self) -> usize {
-        self.packets.iter().fold(0, |bytes, p| bytes + p.len())
-    }
-
-    /// Open a Hyperscan stream for each stream in stream_ids
-    fn open_streams(&mut self, db: &StreamingDatabase) -> Result<()> {
-        self.streams = (0..self.sessions.len())
-            .map(|_| db.open_stream())
-            .collect::<hyperscan::Result<Vec<_>>>()?;
-
-        Ok(())
-    }
-
-    /// Close all open Hyperscan streams (potentially generating any end-anchored matches)
-    fn close_streams(&mut self, scratch: &Scratch) -> Result<()> {
-        let matches = &mut self.matches;
-
-        for stream in self.streams.drain(..) {
-            stream.close(&scratch, |_, _, _, _| {
-                *matches += 1;
-                Matching::Continue
-            })?;
-        }
-
-        Ok(())
-    }
-
-    /// Scan each packet (in the ordering given in the PCAP file) through Hyperscan using the streaming interface.
-    fn scan_streams(&mut self, scratch: &Scratch) -> Result<()> {
-        let matches = &mut self.matches;
-
-        for (i, ref packet) in self.packets.iter().enumerate() {
-            let ref stream = self.streams[self.stream_ids[i]];
-
-            stream.scan(&packet, &scratch, |_, _, _, _| {
-                *matches += 1;
-                Matching::Continue
-            })?;
-        }
-
-        Ok(())
-    }
-
-    /// Scan each packet (in the ordering given in the PCAP file) through
-    /// Hyperscan using the block-mode interface.
-    fn scan_block(&mut self, db: &BlockDatabase, scratch: &Scratch) -> Result<()> {
-        let matches = &mut self.matches;
-
-        for packet in &self.packets {
-            db.scan(packet, &scratch, |_, _, _, _| {
-                *matches += 1;
-                Matching::Continue
-            })?;
-        }
-
-        Ok(())
-    }
+    Ok(corpus)
 }
 
 #[derive(Deref, Index)]
@@ -393,7 +227,7 @@ impl SigData {
 }
 
 fn eval_set(
-    bench: &mut Benchmark,
+    corpus: &Corpus,
     patterns: &Patterns,
     streaming: bool,
     repeats: usize,
@@ -402,60 +236,33 @@ fn eval_set(
 ) -> Result<f64> {
     use Criterion::*;
 
-    let now = Instant::now();
-    let db = if streaming {
-        patterns.build::<Streaming>().map(Either::Left)?
+    let empty = Corpus::new();
+    let corpus = if criterion == Throughput { corpus } else { &empty };
+    let bench = Benchmark::new(patterns).repeats(repeats);
+    let metrics = if streaming {
+        bench.streaming(corpus).with_context(|| "benchmark streaming mode")?
     } else {
-        patterns.build::<Block>().map(Either::Right)?
+        bench.block(corpus).with_context(|| "benchmark block mode")?
     };
-    let compile_time = now.elapsed();
-    let scratch = db.as_ref().either(|db| db.alloc_scratch(), |db| db.alloc_scratch())?;
 
     match criterion {
-        ByteCodeSize => db
-            .as_ref()
-            .either(|db| db.size(), |db| db.size())
-            .map(|size| size as f64)
-            .with_context(|| "retrieve bytecode size"),
-        CompileTime => Ok(compile_time.as_secs_f64()),
-        StreamStateSize => db
-            .as_ref()
-            .either(
-                |db| db.stream_size().map(|size| size as f64).with_context(|| "stream size"),
-                |_| bail!("Cannot evaluate stream state for block mode compile."),
-            )
-            .with_context(|| "retrieve stream state size"),
-        ScratchSize => scratch
-            .size()
+        ByteCodeSize => Ok(metrics.bytecode_size as f64),
+        CompileTime => Ok(metrics.compile_time.as_secs_f64()),
+        StreamStateSize => metrics
+            .stream_state_size
             .map(|size| size as f64)
-            .with_context(|| "retrieve scratch size"),
+            .ok_or_else(|| anyhow!("Cannot evaluate stream state for block mode compile.")),
+        ScratchSize => Ok(metrics.scratch_size as f64),
         Throughput => {
-            bench.clear_matches();
-
-            let now = Instant::now();
-            for _ in 0..repeats {
-                match db {
-                    Either::Left(ref db) => {
-                        bench.open_streams(db).with_context(|| "open stream")?;
-                        bench.scan_streams(&scratch).with_context(|| "scan stream")?;
-                        bench.close_streams(&scratch).with_context(|| "close stream")?;
-                    }
-                    Either::Right(ref db) => {
-                        bench.scan_block(db, &scratch).with_context(|| "scan block")?;
-                    }
-                }
-            }
-            let scan_time = now.elapsed();
-            let bytes = bench.bytes();
-            let throughput = ((bytes * 8 * repeats) as f64) / (scan_time.as_secs_f64() * 1000_000.0);
+            let throughput = metrics.throughput();
 
             if diagnose {
                 println!(
                     "Scan time {:.3} sec, Scanned {} bytes, Throughput {:.3} Mbps, Matches {}",
-                    scan_time.as_secs_f64(),
-                    bytes,
+                    metrics.scan_time.as_secs_f64(),
+                    metrics.bytes / repeats.max(1),
                     throughput,
-                    bench.matches
+                    metrics.matches
                 );
             }
 
@@ -476,14 +283,12 @@ fn main() -> Result<()> {
         ..
     } = Opt::from_args();
 
-    let mut bench = Benchmark::new();
-
-    if criterion == Criterion::Throughput {
+    let corpus = if criterion == Criterion::Throughput {
         // Read our input PCAP file in
-        bench
-            .read_streams(pcap_file.as_ref().ok_or(anyhow!("pcap file"))?)
-            .with_context(|| "read packets from PCAP file")?;
-    }
+        read_corpus(pcap_file.as_ref().ok_or(anyhow!("pcap file"))?).with_context(|| "read packets from PCAP file")?
+    } else {
+        Corpus::new()
+    };
 
     println!("Base signatures: {:?}", pattern_file);
     if let Some(ref pcap_file) = pcap_file {
@@ -496,7 +301,7 @@ fn main() -> Result<()> {
     let mut work_sigs = (0..sigs.len()).collect::<HashSet<_>>();
     let mut excludes = HashSet::new();
 
-    let score_base = eval_set(&mut bench, &sigs, !non_streaming, repeats, criterion, true)?;
+    let score_base = eval_set(&corpus, &sigs, !non_streaming, repeats, criterion, true)?;
     let maximize = criterion.higher_is_better();
 
     let print_criterion = |score: f64| match criterion {
@@ -539,7 +344,7 @@ fn main() -> Result<()> {
                 let mut tmp = s_tmp.clone();
                 tmp.extend(excludes.iter().cloned());
                 let sigs_tmp = sigs.clone_exclude(&excludes);
-                let score = eval_set(&mut bench, &sigs_tmp, !non_streaming, repeats, criterion, false)?;
+                let score = eval_set(&corpus, &sigs_tmp, !non_streaming, repeats, criterion, false)?;
 
                 if current_group == 0 || (if !maximize { score < best } else { score > best }) {
                     s = s_tmp;
@@ -21,26 +21,19 @@
 //
 use std::collections::HashMap;
 use std::fs;
-use std::io;
 use std::iter;
-use std::net::SocketAddrV4;
 use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use byteorder::{BigEndian, ReadBytesExt};
-use pnet::packet::{
-    ethernet::{EtherTypes, EthernetPacket},
-    ip::IpNextHeaderProtocols,
-    ipv4::Ipv4Packet,
-    udp::UdpPacket,
-    Packet, PrimitiveValues,
-};
 use structopt::StructOpt;
 
-use hyperscan::prelude::*;
+use hyperscan::{
+    pcap::{SessionExtractor, SessionKey},
+    prelude::*,
+};
 
 /**
  * This function will read in the file with the specified name, with an
@@ -70,31 +63,6 @@ fn build_database<B: Builder<Err = hyperscan::Error>, T: Mode>(builder: &B) -> R
     Ok(db)
 }
 
-// Key for identifying a stream in our pcap input data, using data from its IP
-// headers.
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
-struct Session {
-    proto: u8,
-    src: SocketAddrV4,
-    dst: SocketAddrV4,
-}
-
-impl Session {
-    fn new(ipv4: &Ipv4Packet) -> Session {
-        let mut c = io::Cursor::new(ipv4.payload());
-        let src_port = c.read_u16::<BigEndian>().unwrap();
-        let dst_port = c.read_u16::<BigEndian>().unwrap();
-
-        Session {
-            proto: ipv4.get_next_level_protocol().to_primitive_values().0,
-            src: SocketAddrV4::new(ipv4.get_source(), src_port),
-            dst: SocketAddrV4::new(ipv4.get_destination(), dst_port),
-        }
-    }
-}
-
-const IP_FLAG_MF: u8 = 1;
-
 struct Benchmark {
     /// Packet data to be scanned.
     packets: Vec<Box<Vec<u8>>>,
@@ -103,7 +71,7 @@ struct Benchmark {
     stream_ids: Vec<usize>,
 
     /// Map used to construct stream_ids
-    sessions: HashMap<Session, usize>,
+    sessions: HashMap<SessionKey, usize>,
 
     /// Hyperscan compiled database (streaming mode)
     streaming_db: StreamingDatabase,
@@ -123,9 +91,7 @@ struct Benchmark {
 
 impl Benchmark {
     fn new(streaming_db: StreamingDatabase, block_db: BlockDatabase) -> Result<Benchmark> {
-        let mut s = streaming_db.alloc_scratch()?;
-
-        block_db.realloc_scratch(&mut s)?;
+        let s = Scratch::for_databases(&[&streaming_db, &block_db])?;
 
         Ok(Benchmark {
             packets: Vec::new(),
@@ -139,61 +105,14 @@ impl Benchmark {
         })
     }
 
-    fn decode_packet(packet: &pcap::Packet) -> Option<(Session, Vec<u8>)> {
-        let ether = EthernetPacket::new(&packet.data).unwrap();
-
-        if ether.get_ethertype() != EtherTypes::Ipv4 {
-            return None;
-        }
-
-        let ipv4 = Ipv4Packet::new(&ether.payload()).unwrap();
-
-        if ipv4.get_version() != 4 {
-            return None;
-        }
-
-        if (ipv4.get_flags() & IP_FLAG_MF) == IP_FLAG_MF || ipv4.get_fragment_offset() != 0 {
-            return None;
-        }
-
-        match ipv4.get_next_level_protocol() {
-            IpNextHeaderProtocols::Tcp => {
-                let payload = ipv4.payload();
-                let data_off = ((payload[12] >> 4) * 4) as usize;
-
-                Some((Session::new(&ipv4), Vec::from(&payload[data_off..])))
-            }
-
-            IpNextHeaderProtocols::Udp => {
-                let udp = UdpPacket::new(&ipv4.payload()).unwrap();
-
-                Some((Session::new(&ipv4), Vec::from(udp.payload())))
-            }
-            _ => None,
-        }
-    }
-
-    fn read_streams<P: AsRef<Path>>(&mut self, path: P) -> Result<(), pcap::Error> {
-        let mut capture = pcap::Capture::from_file(path)?;
-
-        while let Ok(ref packet) = capture.next_packet() {
-            if let Some((key, payload)) = Self::decode_packet(&packet) {
-                if payload.len() > 0 {
-                    let stream_id = match self.sessions.get(&key) {
-                        Some(&id) => id,
-                        None => {
-                            let id = self.sessions.len();
-
-                            assert!(self.sessions.insert(key, id).is_none());
-
-                            id
-                        }
-                    };
+    fn read_streams<P: AsRef<Path>>(&mut self, path: P) -> hyperscan::Result<()> {
+        for res in SessionExtractor::open(path)? {
+            let (key, payload) = res?;
+            let id = self.sessions.len();
+            let stream_id = *self.sessions.entry(key).or_insert(id);
 
-                    self.stream_ids.push(stream_id);
-                    self.packets.push(Box::new(payload));
-                }
-            }
+            self.stream_ids.push(stream_id);
+            self.packets.push(Box::new(payload));
         }
 
         println!(
@@ -0,0 +1,284 @@
+//! Benchmarking of pattern sets
+//!
+//! The `Benchmark` compiles a set of patterns and scans a `Corpus` with the database,
+//! reporting the compile time, the bytecode, scratch and stream state sizes, and the scan throughput
+//! as `Metrics`, so tools and CI jobs could track the cost of their pattern sets.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use hyperscan::{prelude::*, bench::{Benchmark, Corpus}};
+//! let patterns = patterns!["foo", "bar"];
+//! let mut corpus = Corpus::new();
+//!
+//! corpus.push(0, "foo te");
+//! corpus.push(1, "bar");
+//! corpus.push(0, "st foo");
+//!
+//! let metrics = Benchmark::new(&patterns).repeats(2).streaming(&corpus).unwrap();
+//!
+//! assert_eq!(metrics.bytes, 30);
+//! assert_eq!(metrics.matches, 6);
+//! assert!(metrics.stream_state_size.is_some());
+//!
+//! let metrics = Benchmark::new(&patterns).block(&corpus).unwrap();
+//!
+//! assert_eq!(metrics.matches, 3);
+//! assert!(metrics.stream_state_size.is_none());
+//! ```
+use std::iter::FromIterator;
+use std::time::{Duration, Instant};
+
+use crate::{
+    common::{Block, Database, Mode, Streaming},
+    compile::{Builder, Patterns},
+    runtime::Matching,
+    Result,
+};
+
+/// The data to scan, as a sequence of blocks belonging to the streams.
+///
+/// In the block mode, every block is scanned independently.
+/// In the streaming mode, the blocks are written to their streams in order,
+/// e.g. the packets of the sessions in a capture file.
+#[derive(Clone, Debug, Default)]
+pub struct Corpus {
+    blocks: Vec<(usize, Vec<u8>)>,
+    streams: usize,
+}
+
+impl<T: Into<Vec<u8>>> FromIterator<T> for Corpus {
+    /// Construct a corpus with every block in its own stream.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut corpus = Corpus::new();
+
+        for (stream, data) in iter.into_iter().enumerate() {
+            corpus.push(stream, data);
+        }
+
+        corpus
+    }
+}
+
+impl Corpus {
+    /// Construct an empty corpus.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a block of data to the stream.
+    pub fn push<T: Into<Vec<u8>>>(&mut self, stream: usize, data: T) -> &mut Self {
+        self.blocks.push((stream, data.into()));
+        self.streams = self.streams.max(stream + 1);
+        self
+    }
+
+    /// Returns the number of blocks.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns `true` if there is no block.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Returns the number of streams.
+    pub fn streams(&self) -> usize {
+        self.streams
+    }
+
+    /// Returns the total size of the blocks in bytes.
+    pub fn bytes(&self) -> usize {
+        self.blocks.iter().map(|(_, data)| data.len()).sum()
+    }
+}
+
+/// The metrics of a pattern set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metrics {
+    /// The number of patterns.
+    pub patterns: usize,
+    /// The time to compile the patterns.
+    pub compile_time: Duration,
+    /// The size of the compiled database in bytes.
+    pub bytecode_size: usize,
+    /// The size of the scratch space in bytes.
+    pub scratch_size: usize,
+    /// The size of the stream state in bytes, only available in the streaming mode.
+    pub stream_state_size: Option<usize>,
+    /// The number of bytes scanned, including the repeats.
+    pub bytes: usize,
+    /// The time to scan the corpus, including the repeats.
+    pub scan_time: Duration,
+    /// The number of matches found, including the repeats.
+    pub matches: usize,
+}
+
+impl Metrics {
+    /// The scan throughput in megabits per second, zero if nothing was scanned.
+    pub fn throughput(&self) -> f64 {
+        let secs = self.scan_time.as_secs_f64();
+
+        if secs > 0.0 {
+            (self.bytes * 8) as f64 / (secs * 1_000_000.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Measures the metrics of a pattern set.
+#[derive(Clone, Copy, Debug)]
+pub struct Benchmark<'a> {
+    patterns: &'a Patterns,
+    repeats: usize,
+}
+
+impl<'a> Benchmark<'a> {
+    /// Construct a benchmark of the patterns, which scans the corpus once.
+    pub fn new(patterns: &'a Patterns) -> Self {
+        Benchmark { patterns, repeats: 1 }
+    }
+
+    /// Set the number of times the corpus is repeatedly scanned.
+    pub fn repeats(mut self, repeats: usize) -> Self {
+        self.repeats = repeats.max(1);
+        self
+    }
+
+    /// Compile the patterns in the block mode and scan every block of the corpus.
+    pub fn block(&self, corpus: &Corpus) -> Result<Metrics> {
+        let (db, mut metrics) = self.compile::<Block>()?;
+        let scratch = db.alloc_scratch()?;
+
+        metrics.scratch_size = scratch.size()?;
+
+        let now = Instant::now();
+
+        for _ in 0..self.repeats {
+            for (_, data) in &corpus.blocks {
+                db.scan(data, &scratch, count(&mut metrics.matches))?;
+            }
+        }
+
+        metrics.scan_time = now.elapsed();
+        metrics.bytes = corpus.bytes() * self.repeats;
+
+        Ok(metrics)
+    }
+
+    /// Compile the patterns in the streaming mode and write the blocks of the corpus to their streams.
+    ///
+    /// The streams are opened before and closed after every repeat, which are included in the scan time.
+    pub fn streaming(&self, corpus: &Corpus) -> Result<Metrics> {
+        let (db, mut metrics) = self.compile::<Streaming>()?;
+        let scratch = db.alloc_scratch()?;
+
+        metrics.scratch_size = scratch.size()?;
+        metrics.stream_state_size = Some(db.stream_size()?);
+
+        let now = Instant::now();
+
+        for _ in 0..self.repeats {
+            let streams = (0..corpus.streams)
+                .map(|_| db.open_stream())
+                .collect::<Result<Vec<_>>>()?;
+
+            for (stream, data) in &corpus.blocks {
+                streams[*stream].scan(data, &scratch, count(&mut metrics.matches))?;
+            }
+
+            for stream in streams {
+                stream.close(&scratch, count(&mut metrics.matches))?;
+            }
+        }
+
+        metrics.scan_time = now.elapsed();
+        metrics.bytes = corpus.bytes() * self.repeats;
+
+        Ok(metrics)
+    }
+
+    fn compile<T: Mode>(&self) -> Result<(Database<T>, Metrics)> {
+        let now = Instant::now();
+        let db: Database<T> = self.patterns.build()?;
+        let compile_time = now.elapsed();
+
+        let metrics = Metrics {
+            patterns: self.patterns.len(),
+            compile_time,
+            bytecode_size: db.size()?,
+            scratch_size: 0,
+            stream_state_size: None,
+            bytes: 0,
+            scan_time: Duration::default(),
+            matches: 0,
+        };
+
+        Ok((db, metrics))
+    }
+}
+
+fn count(matches: &mut usize) -> impl FnMut(u32, u64, u64, u32) -> Matching + '_ {
+    move |_, _, _, _| {
+        *matches += 1;
+        Matching::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corpus() {
+        let corpus = vec!["foo", "bar baz"].into_iter().collect::<Corpus>();
+
+        assert_eq!(corpus.len(), 2);
+        assert_eq!(corpus.streams(), 2);
+        assert_eq!(corpus.bytes(), 10);
+
+        let mut corpus = Corpus::new();
+
+        corpus.push(3, "foo").push(1, "bar");
+
+        assert_eq!(corpus.streams(), 4);
+        assert!(!corpus.is_empty());
+    }
+
+    #[test]
+    fn test_benchmark() {
+        let patterns = patterns!["test", "bar$"];
+        let mut corpus = Corpus::new();
+
+        corpus.push(0, "foo te").push(1, "bar").push(0, "st bar");
+
+        let metrics = Benchmark::new(&patterns).repeats(3).streaming(&corpus).unwrap();
+
+        assert_eq!(metrics.patterns, 2);
+        assert!(metrics.bytecode_size > 0);
+        assert!(metrics.scratch_size > 0);
+        assert!(metrics.stream_state_size.unwrap() > 0);
+        assert_eq!(metrics.bytes, 45);
+        assert_eq!(metrics.matches, 9);
+
+        let metrics = Benchmark::new(&patterns).block(&corpus).unwrap();
+
+        assert_eq!(metrics.stream_state_size, None);
+        assert_eq!(metrics.bytes, 15);
+        assert_eq!(metrics.matches, 2);
+
+        let metrics = Benchmark::new(&patterns).block(&Corpus::new()).unwrap();
+
+        assert_eq!(metrics.bytes, 0);
+        assert_eq!(
+            Metrics {
+                scan_time: Duration::default(),
+                ..metrics
+            }
+            .throughput(),
+            0.0
+        );
+    }
+}
@@ -26,6 +26,21 @@ unsafe fn drop_database(db: *mut ffi::ch_database_t) {
     ffi::ch_free_database(db).expect("drop database");
 }
 
+/// Record the size of a compiled database in the `size` field of the current span.
+#[cfg(feature = "tracing")]
+pub(crate) fn traced(db: Database) -> Database {
+    if let Ok(size) = db.size() {
+        tracing::Span::current().record("size", size);
+    }
+
+    db
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn traced(db: Database) -> Database {
+    db
+}
+
 impl DatabaseRef {
     /// Returns the size of the given database.
     pub fn size(&self) -> Result<usize> {
@@ -42,7 +57,7 @@ impl DatabaseRef {
             ffi::ch_database_info(self.as_ptr(), p.as_mut_ptr()).and_then(|_| {
                 let p = p.assume_init();
                 let info = CStr::from_ptr(p).to_str()?.into();
-                libc::free(p as *mut _);
+                crate::common::free(p as *mut _);
                 Ok(info)
             })
         }
@@ -5,11 +5,11 @@ use std::mem::MaybeUninit;
 use std::ptr::null;
 use std::str::FromStr;
 
+use core::ffi::c_char;
 use foreign_types::{foreign_type, ForeignType, ForeignTypeRef};
-use libc::c_char;
 
 use crate::{
-    chimera::{ffi, Database, Error as ChError, Pattern, Patterns},
+    chimera::{common::traced, ffi, Database, Error as ChError, Pattern, Patterns},
     error::AsResult,
     Error, PlatformRef,
 };
@@ -284,12 +284,25 @@ impl Builder for Pattern {
     /// This is the function call with which an expression is compiled into a Chimera database
     /// which can be passed to the runtime function.
     ///
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "compile",
+            level = "debug",
+            skip_all,
+            fields(patterns = 1, mode = ?mode, size = tracing::field::Empty)
+        )
+    )]
     fn for_platform(
         &self,
         mode: Mode,
         match_limit: Option<MatchLimit>,
         platform: Option<&PlatformRef>,
     ) -> Result<Database, Self::Err> {
+        if !self.ext.is_empty() {
+            return Err(ChError::UnsupportedExt(0).into());
+        }
+
         let expr = CString::new(self.expression.as_str())?;
         let ptr = expr.as_bytes_with_nul().as_ptr() as *const c_char;
         let flags = self.flags.bits();
@@ -325,7 +338,7 @@ impl Builder for Pattern {
                 )
             }
             .ok_or_else(|| err.assume_init())
-            .map(|_| Database::from_ptr(db.assume_init()))
+            .map(|_| traced(Database::from_ptr(db.assume_init())))
         }
     }
 }
@@ -341,12 +354,30 @@ impl Builder for Patterns {
     /// Each expression can be labelled with a unique integer
     // which is passed into the match callback to identify the pattern that has matched.
     ///
+    /// Returns `Error::DuplicateId` if multiple patterns share an ID, see `Patterns::with_ids`,
+    /// or `Error::UnsupportedExt` if a pattern has the extended parameters, see `Patterns::without_ext`.
+    ///
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "compile",
+            level = "debug",
+            skip_all,
+            fields(patterns = self.len(), mode = ?mode, size = tracing::field::Empty)
+        )
+    )]
     fn for_platform(
         &self,
         mode: Mode,
         match_limit: Option<MatchLimit>,
         platform: Option<&PlatformRef>,
     ) -> Result<Database, Self::Err> {
+        self.check_ids()?;
+
+        if let Some(i) = self.iter().position(|pattern| !pattern.ext.is_empty()) {
+            return Err(ChError::UnsupportedExt(i).into());
+        }
+
         let expressions = self
             .iter()
             .map(|Pattern { expression, .. }| CString::new(expression.as_str()))
@@ -359,11 +390,7 @@ impl Builder for Patterns {
             .iter()
             .map(|Pattern { flags, .. }| flags.bits() as _)
             .collect::<Vec<_>>();
-        let ids = self
-            .iter()
-            .enumerate()
-            .map(|(i, Pattern { id, .. })| id.unwrap_or(i) as _)
-            .collect::<Vec<_>>();
+        let ids = self.ids().map(|id| id as _).collect::<Vec<_>>();
 
         let mut db = MaybeUninit::uninit();
         let mut err = MaybeUninit::uninit();
@@ -399,7 +426,7 @@ impl Builder for Patterns {
                 )
             }
             .ok_or_else(|| err.assume_init())
-            .map(|_| Database::from_ptr(db.assume_init()))
+            .map(|_| traced(Database::from_ptr(db.assume_init())))
         }
     }
 }
@@ -81,7 +81,7 @@ pub enum Error {
     UnknownError,
 
     /// Returned when pcre_exec (called for some expressions internally from `ch_scan`) failed due to a fatal error.
-    #[cfg(feature = "v5_4")]
+    #[cfg(hyperscan_v5_4)]
     #[error("Failed due to a fatal error")]
     FailInternal,
 
@@ -92,6 +92,23 @@ pub enum Error {
     #[error("Unexpected internal error from Hyperscan.")]
     UnknownHSError,
 
+    /// Multiple patterns share the same ID, which Chimera would reject at runtime.
+    #[error("The expressions #{first} and #{second} share the same ID {id}.")]
+    DuplicateId {
+        /// The duplicated ID.
+        id: usize,
+        /// The zero-based number of the first expression with the ID.
+        first: usize,
+        /// The zero-based number of the second expression with the ID.
+        second: usize,
+    },
+
+    /// The expression has extended parameters, which Chimera can't apply when compiling.
+    ///
+    /// Compile the patterns returned by `Patterns::without_ext`, and filter the matches with `Patterns::filter_ext`.
+    #[error("The expression #{0} has extended parameters, which should be filtered with `Patterns::filter_ext`.")]
+    UnsupportedExt(usize),
+
     /// Unknown error code
     #[error("Unknown error code: {0}")]
     Code(ffi::ch_error_t),
@@ -112,7 +129,7 @@ impl From<ffi::ch_error_t> for Error {
             ffi::CH_BAD_ALIGN => BadAlign,
             ffi::CH_BAD_ALLOC => BadAlloc,
             ffi::CH_SCRATCH_IN_USE => ScratchInUse,
-            #[cfg(feature = "v5_4")]
+            #[cfg(hyperscan_v5_4)]
             ffi::CH_FAIL_INTERNAL => FailInternal,
             ffi::CH_UNKNOWN_HS_ERROR => UnknownHSError,
             _ => Code(err),
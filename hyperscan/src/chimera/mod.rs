@@ -29,22 +29,66 @@ mod common;
 mod compile;
 mod error;
 mod pattern;
+mod pool;
 mod runtime;
 
 #[doc(hidden)]
 pub use crate::ffi::chimera as ffi;
 
 pub use self::common::{version, Database, DatabaseRef};
-pub use self::compile::{compile, Builder, CompileError, Mode};
+pub use self::compile::{compile, Builder, CompileError, MatchLimit, Mode};
 pub use self::error::Error;
 pub use self::pattern::{Flags, Pattern, Patterns};
+pub use self::pool::{PooledScratch, ScratchPool};
 pub use self::runtime::{
-    Capture, Error as MatchError, ErrorEventHandler, MatchEventHandler, Matching, Scratch, ScratchRef,
+    Capture, Error as MatchError, ErrorEvent, ErrorEventHandler, ErrorInfo, Match, MatchEventHandler, MatchFlags,
+    Matching, OnError, Scratch, ScratchRef,
 };
 
 pub mod prelude {
     //! The `chimera` Prelude
+    //!
+    //! It mirrors `hyperscan::prelude`, so the common names (e.g. `CompileFlags`, `Mode` or `Matching`)
+    //! are resolved to the `chimera` counterparts when switching the import.
     pub use crate::chimera::{
-        compile, Builder, Capture, Database, DatabaseRef, Error, Matching, Pattern, Patterns, Scratch, ScratchRef,
+        compile, Builder, Capture, CompileError, Database, DatabaseRef, Error, ErrorEventHandler, Flags,
+        Flags as CompileFlags, MatchError, MatchEventHandler, MatchLimit, Matching, Mode, Pattern, Patterns, Scratch,
+        ScratchRef,
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::prelude::*;
+
+    #[test]
+    fn test_prelude() {
+        let p: Pattern = Pattern::with_flags("test", CompileFlags::CASELESS);
+        let db: Database = p
+            .for_platform(
+                Mode::Groups,
+                Some(MatchLimit {
+                    max_matches: 1000,
+                    recursion_depth: 100,
+                }),
+                None,
+            )
+            .unwrap();
+        let s: Scratch = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan(
+            "some TEST data",
+            &s,
+            |_, from, to, _, _| {
+                matches.push(from..to);
+                Matching::Continue
+            },
+            |_: MatchError, _| Matching::Skip,
+        )
+        .unwrap();
+
+        assert_eq!(matches, vec![5..9]);
+        assert_eq!(Flags::CASELESS, CompileFlags::CASELESS);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fmt;
 use std::iter::FromIterator;
 use std::str::FromStr;
@@ -5,10 +6,17 @@ use std::str::FromStr;
 use bitflags::bitflags;
 use derive_more::{Deref, DerefMut, From, Index, IndexMut, Into, IntoIterator};
 
-use crate::{chimera::ffi, Error};
+use crate::{
+    chimera::{ffi, Capture, Error as ChError, Matching},
+    compile::{ExprError, ExprExt},
+    Error,
+};
 
 bitflags! {
     /// Pattern flags
+    ///
+    /// The flags could be parsed from the `i`, `m`, `s`, `H`, `8` and `W` letters of the Hyperscan pattern flags,
+    /// the other letters of the Hyperscan pattern flags return `Error::UnsupportedFlag`.
     #[derive(Default)]
     pub struct Flags: u32 {
         /// Set case-insensitive matching.
@@ -41,7 +49,7 @@ impl FromStr for Flags {
                 '8' => flags |= Flags::UTF8,
                 'W' => flags |= Flags::UCP,
                 _ => {
-                    return Err(Error::InvalidFlag(c));
+                    return Err(Error::unsupported_flag(c, "not supported by Chimera"));
                 }
             }
         }
@@ -76,6 +84,7 @@ impl fmt::Display for Flags {
 
 /// The pattern with basic regular expression.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct Pattern {
     /// The expression to parse.
     pub expression: String,
@@ -83,6 +92,14 @@ pub struct Pattern {
     pub flags: Flags,
     /// ID number to be associated with the corresponding pattern in the expressions array.
     pub id: Option<usize>,
+    /// Extended behaviour for this pattern
+    ///
+    /// Chimera doesn't support the extended parameters, so the `min_offset`, `max_offset` and `min_length`
+    /// are applied by filtering the matches with `Patterns::filter_ext`.
+    ///
+    /// Compiling a pattern with the extended parameters returns `Error::UnsupportedExt`,
+    /// instead of silently reporting the matches which don't satisfy them, see `Patterns::without_ext`.
+    pub ext: ExprExt,
 }
 
 impl Pattern {
@@ -92,6 +109,7 @@ impl Pattern {
             expression: expr.into(),
             flags: Flags::empty(),
             id: None,
+            ext: ExprExt::default(),
         }
     }
 
@@ -101,6 +119,7 @@ impl Pattern {
             expression: expr.into(),
             flags,
             id: None,
+            ext: ExprExt::default(),
         }
     }
 
@@ -139,6 +158,17 @@ impl Pattern {
         self.flags |= Flags::UCP;
         self
     }
+
+    /// Returns `true` if the match satisfies the extended parameters of the pattern.
+    pub fn accepts(&self, from: u64, to: u64) -> bool {
+        accepts(&self.ext, from, to)
+    }
+}
+
+fn accepts(ext: &ExprExt, from: u64, to: u64) -> bool {
+    !matches!(ext.min_offset(), Some(min_offset) if to < min_offset)
+        && !matches!(ext.max_offset(), Some(max_offset) if to > max_offset)
+        && !matches!(ext.min_length(), Some(min_length) if to - from < min_length)
 }
 
 impl fmt::Display for Pattern {
@@ -147,11 +177,17 @@ impl fmt::Display for Pattern {
             write!(f, "{}:", id)?;
         }
 
-        if self.id.is_some() || !self.flags.is_empty() {
-            write!(f, "/{}/{}", self.expression, self.flags)
+        if self.id.is_some() || !self.flags.is_empty() || !self.ext.is_empty() {
+            write!(f, "/{}/{}", self.expression, self.flags)?;
         } else {
-            write!(f, "{}", self.expression)
+            write!(f, "{}", self.expression)?;
         }
+
+        if !self.ext.is_empty() {
+            write!(f, "{}", self.ext)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -164,16 +200,37 @@ impl FromStr for Pattern {
             None => (None, expr),
         };
         let pattern = match (expr.starts_with('/'), expr.rfind('/')) {
-            (true, Some(end)) if end > 0 => Pattern {
-                expression: expr[1..end].into(),
-                flags: expr[end + 1..].parse()?,
-                id,
-            },
+            (true, Some(end)) if end > 0 => {
+                let (expr, remaining) = (&expr[1..end], &expr[end + 1..]);
+                let (flags, ext) = match (remaining.ends_with('}'), remaining.rfind('{')) {
+                    (true, Some(start)) => {
+                        let (flags, ext) = remaining.split_at(start);
+
+                        (flags.parse()?, ext.parse::<ExprExt>()?)
+                    }
+                    _ => (remaining.parse()?, ExprExt::default()),
+                };
+
+                if ext.edit_distance().is_some() {
+                    return Err(ExprError::UnexpectedParameter("edit_distance".into()).into());
+                }
+                if ext.hamming_distance().is_some() {
+                    return Err(ExprError::UnexpectedParameter("hamming_distance".into()).into());
+                }
+
+                Pattern {
+                    expression: expr.into(),
+                    flags,
+                    id,
+                    ext,
+                }
+            }
 
             _ => Pattern {
                 expression: expr.into(),
                 flags: Flags::empty(),
                 id,
+                ext: ExprExt::default(),
             },
         };
 
@@ -212,3 +269,176 @@ impl FromStr for Patterns {
             .map(Self)
     }
 }
+
+impl Patterns {
+    /// Assign the IDs to the patterns in order, the patterns without a corresponding ID keep their own.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::chimera::prelude::*;
+    /// let patterns: Patterns = "/foo/\n/bar/".parse().unwrap();
+    /// let patterns = patterns.with_ids(vec![10, 20]);
+    ///
+    /// assert_eq!(patterns.ids().collect::<Vec<_>>(), vec![10, 20]);
+    /// ```
+    pub fn with_ids<I: IntoIterator<Item = usize>>(mut self, ids: I) -> Self {
+        for (pattern, id) in self.0.iter_mut().zip(ids) {
+            pattern.id = Some(id);
+        }
+
+        self
+    }
+
+    /// Returns an iterator over the IDs of the patterns, in the order of the patterns.
+    ///
+    /// A pattern without ID is identified by its index, the same as the compiled database reports.
+    pub fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.iter().enumerate().map(|(i, Pattern { id, .. })| id.unwrap_or(i))
+    }
+
+    /// Check that every pattern has a unique ID, which is required by Chimera.
+    ///
+    /// Returns `Error::DuplicateId` with the first pair of expressions sharing an ID.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::chimera::{prelude::*, Error};
+    /// let patterns: Patterns = "1:/foo/\n/bar/".parse().unwrap();
+    ///
+    /// assert_eq!(patterns.check_ids(), Err(Error::DuplicateId { id: 1, first: 0, second: 1 }));
+    /// ```
+    pub fn check_ids(&self) -> Result<(), ChError> {
+        let mut seen = BTreeMap::new();
+
+        for (i, id) in self.ids().enumerate() {
+            if let Some(&first) = seen.get(&id) {
+                return Err(ChError::DuplicateId { id, first, second: i });
+            }
+
+            seen.insert(id, i);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the patterns without the extended parameters, which can be compiled by Chimera.
+    ///
+    /// The matches of the compiled database should be filtered with `Patterns::filter_ext` of the original patterns.
+    pub fn without_ext(&self) -> Patterns {
+        self.iter()
+            .map(|pattern| Pattern {
+                ext: ExprExt::default(),
+                ..pattern.clone()
+            })
+            .collect()
+    }
+
+    /// Wrap the match callback to drop the matches which don't satisfy the extended parameters of the patterns,
+    /// e.g. `min_offset`, `max_offset` or `min_length`, which are not supported by Chimera.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::chimera::prelude::*;
+    /// let patterns: Patterns = "1:/foo/{min_offset=10}\n2:/ba[rz]/{max_offset=10}".parse().unwrap();
+    /// let db: Database = patterns.without_ext().build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let mut matches = vec![];
+    ///
+    /// db.scan(
+    ///     "foo bar foo baz",
+    ///     &s,
+    ///     patterns.filter_ext(|id, from, to, _, _| {
+    ///         matches.push((id, from..to));
+    ///         Matching::Continue
+    ///     }),
+    ///     Matching::Skip,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(matches, vec![(2, 4..7), (1, 8..11)]);
+    /// ```
+    pub fn filter_ext<'a, F>(
+        &self,
+        mut on_match_event: F,
+    ) -> impl FnMut(u32, u64, u64, u32, Option<&'a [Capture]>) -> Matching
+    where
+        F: FnMut(u32, u64, u64, u32, Option<&'a [Capture]>) -> Matching,
+    {
+        let exts = self
+            .iter()
+            .enumerate()
+            .filter(|(_, pattern)| !pattern.ext.is_empty())
+            .map(|(i, pattern)| (pattern.id.unwrap_or(i) as u32, pattern.ext))
+            .collect::<BTreeMap<_, _>>();
+
+        move |id, from, to, flags, captured| match exts.get(&id) {
+            Some(ext) if !accepts(ext, from, to) => Matching::Continue,
+            _ => on_match_event(id, from, to, flags, captured),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::chimera::{prelude::*, Error as ChError};
+    use crate::{compile::ExprError, Error};
+
+    #[test]
+    fn test_pattern_ext() {
+        let p: Pattern = "3:/test/i{min_offset=4,min_length=4}".parse().unwrap();
+
+        assert_eq!(p.id, Some(3));
+        assert_eq!(p.flags, Flags::CASELESS);
+        assert_eq!(p.ext.min_offset(), Some(4));
+        assert_eq!(p.ext.min_length(), Some(4));
+        assert_eq!(p.to_string(), "3:/test/i{min_offset=4,min_length=4}");
+
+        assert!(p.accepts(0, 4));
+        assert!(!p.accepts(0, 3));
+        assert!(!p.accepts(2, 5));
+
+        assert_eq!(p.build().err(), Some(Error::Chimera(ChError::UnsupportedExt(0))));
+
+        let patterns: Patterns = "/foo/\n/bar/{max_offset=10}".parse().unwrap();
+
+        assert_eq!(patterns.build().err(), Some(Error::Chimera(ChError::UnsupportedExt(1))));
+        assert!(patterns.without_ext().iter().all(|pattern| pattern.ext.is_empty()));
+        assert_eq!(patterns.without_ext()[1].expression, "bar");
+
+        assert_eq!(
+            "/test/{edit_distance=1}".parse::<Pattern>().err(),
+            Some(Error::Expr(ExprError::UnexpectedParameter("edit_distance".into())))
+        );
+    }
+
+    #[test]
+    fn test_pattern_ids() {
+        let patterns: Patterns = "/foo/\n3:/bar/\n/baz/".parse().unwrap();
+
+        assert_eq!(patterns.ids().collect::<Vec<_>>(), vec![0, 3, 2]);
+        assert_eq!(patterns.check_ids(), Ok(()));
+
+        let patterns = patterns.with_ids(vec![5, 5]);
+
+        assert_eq!(patterns.ids().collect::<Vec<_>>(), vec![5, 5, 2]);
+        assert_eq!(
+            patterns.check_ids(),
+            Err(ChError::DuplicateId {
+                id: 5,
+                first: 0,
+                second: 1
+            })
+        );
+        assert_eq!(
+            patterns.build().err(),
+            Some(Error::Chimera(ChError::DuplicateId {
+                id: 5,
+                first: 0,
+                second: 1
+            }))
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use std::cell::RefCell;
+
+use crate::{
+    chimera::{DatabaseRef, ErrorEventHandler, MatchEventHandler, Scratch},
+    common, Result,
+};
+
+/// A pool of scratch spaces which can be shared between threads.
+///
+/// The Chimera `Scratch` is `Send` but not `Sync`, so it can't be shared by the concurrent callers.
+/// `ScratchPool` is `Sync`: every caller checks out an idle scratch space, cloned from the prototype
+/// when all of them are in use, and returns it to the pool when the `PooledScratch` is dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use std::thread;
+/// # use hyperscan::chimera::{prelude::*, ScratchPool};
+/// let db: Arc<Database> = Arc::new("/test/i".parse().unwrap());
+/// let pool = Arc::new(ScratchPool::new(db.alloc_scratch().unwrap()));
+///
+/// let handles = (0..4)
+///     .map(|_| {
+///         let db = db.clone();
+///         let pool = pool.clone();
+///
+///         thread::spawn(move || db.scan_collect("foo TEST bar", &pool.get()).unwrap().0.len())
+///     })
+///     .collect::<Vec<_>>();
+///
+/// for h in handles {
+///     assert_eq!(h.join().unwrap(), 1);
+/// }
+/// ```
+pub type ScratchPool = common::ScratchPool<Scratch>;
+
+/// A scratch space checked out from a `ScratchPool`, which is returned to the pool when dropped.
+pub type PooledScratch<'a> = common::PooledScratch<'a, Scratch>;
+
+thread_local! {
+    /// The scratch space of the current thread, grown for every database scanned on the thread.
+    static SCRATCH: RefCell<Option<Scratch>> = const { RefCell::new(None) };
+}
+
+impl DatabaseRef {
+    /// Scan the data with the scratch space of the current thread, which is allocated on the first scan
+    /// and grown to be suitable for every database scanned on the thread.
+    ///
+    /// Nested scans from the callbacks allocate a separate scratch space.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::chimera::prelude::*;
+    /// let db: Database = "/test/i".parse().unwrap();
+    /// let mut matches = vec![];
+    ///
+    /// db.scan_with_tls_scratch(
+    ///     "some TEST data",
+    ///     |_, from, to, _, _: Option<&[Capture]>| {
+    ///         matches.push(from..to);
+    ///         Matching::Continue
+    ///     },
+    ///     (),
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(matches, vec![5..9]);
+    /// ```
+    pub fn scan_with_tls_scratch<T, F, E>(&self, data: T, on_match_event: F, on_error_event: E) -> Result<()>
+    where
+        T: AsRef<[u8]>,
+        F: for<'a> MatchEventHandler<'a>,
+        E: ErrorEventHandler,
+    {
+        let scratch = SCRATCH.with(|cell| cell.borrow_mut().take());
+        let scratch = match scratch {
+            Some(mut scratch) => {
+                self.realloc_scratch(&mut scratch)?;
+                scratch
+            }
+            None => self.alloc_scratch()?,
+        };
+
+        let res = self.scan(data, &scratch, on_match_event, on_error_event);
+
+        SCRATCH.with(|cell| cell.borrow_mut().replace(scratch));
+
+        res
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use static_assertions::{assert_impl_all, assert_not_impl_any};
+
+    use crate::chimera::{prelude::*, PooledScratch, ScratchPool};
+
+    assert_impl_all!(ScratchPool: Send, Sync);
+    assert_impl_all!(PooledScratch<'static>: Send);
+    assert_not_impl_any!(PooledScratch<'static>: Sync);
+
+    #[test]
+    fn test_scratch_pool() {
+        let db: Arc<Database> = Arc::new("/test/i".parse().unwrap());
+        let pool = Arc::new(ScratchPool::new(db.alloc_scratch().unwrap()));
+
+        let handles = (0..4)
+            .map(|_| {
+                let db = db.clone();
+                let pool = pool.clone();
+
+                thread::spawn(move || {
+                    (0..16)
+                        .map(|_| db.scan_collect("foo TEST bar", &pool.get()).unwrap().0.len())
+                        .sum::<usize>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for h in handles {
+            assert_eq!(h.join().unwrap(), 16);
+        }
+
+        assert!(pool.idle() >= 1 && pool.idle() <= 4);
+
+        pool.shrink();
+
+        assert_eq!(pool.idle(), 0);
+    }
+
+    #[test]
+    fn test_scan_with_tls_scratch() {
+        let db: Database = "/test/i".parse().unwrap();
+        let db2: Database = r"/(foo|bar)\d+/".parse().unwrap();
+        let mut matches = vec![];
+        let mut nested = vec![];
+
+        db.scan_with_tls_scratch(
+            "some TEST data",
+            |_, from, to, _, _: Option<&[Capture]>| {
+                db2.scan_with_tls_scratch(
+                    "foo123",
+                    |_, from, to, _, _: Option<&[Capture]>| {
+                        nested.push(from..to);
+                        Matching::Continue
+                    },
+                    (),
+                )
+                .unwrap();
+
+                matches.push(from..to);
+                Matching::Continue
+            },
+            (),
+        )
+        .unwrap();
+
+        assert_eq!(matches, vec![5..9]);
+        assert_eq!(nested, vec![0..6]);
+
+        db2.scan_with_tls_scratch("bar42", Matching::Continue, ()).unwrap();
+    }
+}
@@ -3,15 +3,21 @@ use std::mem::{self, MaybeUninit};
 use std::ops::Range;
 use std::ptr;
 use std::slice;
+#[cfg(feature = "metrics")]
+use std::time::Instant;
 
 use derive_more::{Deref, From, Into};
 use foreign_types::{foreign_type, ForeignType, ForeignTypeRef};
 
 use crate::{
     chimera::{error::AsResult, ffi, DatabaseRef},
+    common::{catch_unwind, discard_unwind, resume_unwind},
     Result,
 };
 
+#[cfg(feature = "metrics")]
+use crate::metrics::{scan_metrics, ScanKind, ScanMetrics};
+
 foreign_type! {
     /// A large enough region of scratch space to support a given database.
     pub unsafe type Scratch: Send {
@@ -96,6 +102,25 @@ pub enum Error {
     RecursionLimit = ffi::CH_ERROR_RECURSIONLIMIT,
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MatchLimit => write!(
+                f,
+                "PCRE hit its match limit, some matches may not be reported; \
+                 consider raising `MatchLimit::max_matches` when compiling the database"
+            ),
+            Error::RecursionLimit => write!(
+                f,
+                "PCRE hit its recursion limit, some matches may not be reported; \
+                 consider raising `MatchLimit::recursion_depth` when compiling the database"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 /// Structure representing a captured subexpression within a match.
 #[repr(transparent)]
 #[derive(Clone, Copy, From, Into, Deref, PartialEq, Eq)]
@@ -146,17 +171,17 @@ pub trait MatchEventHandler<'a> {
     /// # Safety
     ///
     /// The returned function can only be called with the returned pointer, or a pointer to another C closure.
-    unsafe fn split(&mut self) -> (ffi::ch_match_event_handler, *mut libc::c_void);
+    unsafe fn split(&mut self) -> (ffi::ch_match_event_handler, *mut core::ffi::c_void);
 }
 
 impl MatchEventHandler<'_> for () {
-    unsafe fn split(&mut self) -> (ffi::ch_match_event_handler, *mut libc::c_void) {
+    unsafe fn split(&mut self) -> (ffi::ch_match_event_handler, *mut core::ffi::c_void) {
         (None, ptr::null_mut())
     }
 }
 
 impl MatchEventHandler<'_> for Matching {
-    unsafe fn split(&mut self) -> (ffi::ch_match_event_handler, *mut libc::c_void) {
+    unsafe fn split(&mut self) -> (ffi::ch_match_event_handler, *mut core::ffi::c_void) {
         unsafe extern "C" fn trampoline(
             _id: u32,
             _from: u64,
@@ -164,8 +189,8 @@ impl MatchEventHandler<'_> for Matching {
             _flags: u32,
             _size: u32,
             _captured: *const ffi::ch_capture_t,
-            ctx: *mut ::libc::c_void,
-        ) -> ::libc::c_int {
+            ctx: *mut ::core::ffi::c_void,
+        ) -> ::core::ffi::c_int {
             *(*(ctx as *mut (&mut Matching, *mut ()))).0 as _
         }
 
@@ -177,7 +202,7 @@ impl<'a, F> MatchEventHandler<'a> for F
 where
     F: FnMut(u32, u64, u64, u32, Option<&'a [Capture]>) -> Matching,
 {
-    unsafe fn split(&mut self) -> (ffi::ch_match_event_handler, *mut libc::c_void) {
+    unsafe fn split(&mut self) -> (ffi::ch_match_event_handler, *mut core::ffi::c_void) {
         (Some(on_match_trampoline::<'a, F>), self as *mut _ as *mut _)
     }
 }
@@ -189,24 +214,20 @@ unsafe extern "C" fn on_match_trampoline<'a, F>(
     flags: u32,
     size: u32,
     captured: *const ffi::ch_capture_t,
-    ctx: *mut ::libc::c_void,
+    ctx: *mut ::core::ffi::c_void,
 ) -> ffi::ch_callback_t
 where
     F: FnMut(u32, u64, u64, u32, Option<&'a [Capture]>) -> Matching,
 {
     let &mut (ref mut callback, _) = &mut *(ctx as *mut (&mut F, *mut ()));
 
-    callback(
-        id,
-        from,
-        to,
-        flags,
-        if captured.is_null() || size == 0 {
-            None
-        } else {
-            Some(slice::from_raw_parts(captured as *const _, size as usize))
-        },
-    ) as i32
+    let captured = if captured.is_null() || size == 0 {
+        None
+    } else {
+        Some(slice::from_raw_parts(captured as *const _, size as usize))
+    };
+
+    catch_unwind(Matching::Terminate, || callback(id, from, to, flags, captured)) as i32
 }
 
 /// Definition of the Chimera error event callback function type.
@@ -215,27 +236,29 @@ where
 /// application calling the @ref ch_scan function. This callback function
 /// will be invoked when an error event occurs during matching; this indicates
 /// that some matches for a given expression may not be reported.
+///
+/// Use `OnError` to receive the extra information of the error events as `ErrorInfo`.
 pub trait ErrorEventHandler {
     /// Split the match event handler to callback and userdata.
     ///
     /// # Safety
     ///
     /// The returned function can only be called with the returned pointer, or a pointer to another C closure.
-    unsafe fn split(&mut self) -> (ffi::ch_error_event_handler, *mut libc::c_void);
+    unsafe fn split(&mut self) -> (ffi::ch_error_event_handler, *mut core::ffi::c_void);
 }
 
 impl ErrorEventHandler for () {
-    unsafe fn split(&mut self) -> (ffi::ch_error_event_handler, *mut libc::c_void) {
+    unsafe fn split(&mut self) -> (ffi::ch_error_event_handler, *mut core::ffi::c_void) {
         (None, ptr::null_mut())
     }
 }
 impl ErrorEventHandler for Matching {
-    unsafe fn split(&mut self) -> (ffi::ch_error_event_handler, *mut libc::c_void) {
+    unsafe fn split(&mut self) -> (ffi::ch_error_event_handler, *mut core::ffi::c_void) {
         unsafe extern "C" fn trampoline(
             _error_type: ffi::ch_error_event_t,
             _id: u32,
-            _info: *mut ::libc::c_void,
-            ctx: *mut ::libc::c_void,
+            _info: *mut ::core::ffi::c_void,
+            ctx: *mut ::core::ffi::c_void,
         ) -> ffi::ch_callback_t {
             *(*(ctx as *mut (*mut (), &mut Matching))).1 as _
         }
@@ -248,7 +271,7 @@ impl<F> ErrorEventHandler for F
 where
     F: FnMut(Error, u32) -> Matching,
 {
-    unsafe fn split(&mut self) -> (ffi::ch_error_event_handler, *mut libc::c_void) {
+    unsafe fn split(&mut self) -> (ffi::ch_error_event_handler, *mut core::ffi::c_void) {
         (Some(on_error_trampoline::<F>), self as *mut _ as *mut _)
     }
 }
@@ -256,15 +279,73 @@ where
 unsafe extern "C" fn on_error_trampoline<F>(
     error_type: ffi::ch_error_event_t,
     id: u32,
-    _info: *mut ::libc::c_void,
-    ctx: *mut ::libc::c_void,
+    _info: *mut ::core::ffi::c_void,
+    ctx: *mut ::core::ffi::c_void,
 ) -> ffi::ch_callback_t
 where
     F: FnMut(Error, u32) -> Matching,
 {
     let &mut (_, ref mut callback) = &mut *(ctx as *mut (*mut (), &mut F));
 
-    callback(mem::transmute(error_type), id) as i32
+    let error = mem::transmute(error_type);
+
+    catch_unwind(Matching::Terminate, || callback(error, id)) as i32
+}
+
+/// An error event handler receiving the extra information of the error events as `ErrorInfo`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::chimera::{prelude::*, ErrorInfo, OnError};
+/// let db: Database = "/test/i".parse().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+///
+/// let mut errors = vec![];
+///
+/// db.scan("some test data", &s, (), OnError::new(|error: MatchError, id, info: ErrorInfo| {
+///     errors.push((error, id, info));
+///     Matching::Skip
+/// })).unwrap();
+///
+/// assert!(errors.is_empty());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct OnError<F>(F);
+
+impl<F> OnError<F>
+where
+    F: FnMut(Error, u32, ErrorInfo) -> Matching,
+{
+    /// Wrap the callback as an error event handler.
+    pub fn new(on_error: F) -> Self {
+        OnError(on_error)
+    }
+}
+
+impl<F> ErrorEventHandler for OnError<F>
+where
+    F: FnMut(Error, u32, ErrorInfo) -> Matching,
+{
+    unsafe fn split(&mut self) -> (ffi::ch_error_event_handler, *mut core::ffi::c_void) {
+        unsafe extern "C" fn trampoline<F>(
+            error_type: ffi::ch_error_event_t,
+            id: u32,
+            info: *mut ::core::ffi::c_void,
+            ctx: *mut ::core::ffi::c_void,
+        ) -> ffi::ch_callback_t
+        where
+            F: FnMut(Error, u32, ErrorInfo) -> Matching,
+        {
+            let &mut (_, ref mut callback) = &mut *(ctx as *mut (*mut (), &mut F));
+
+            let error = mem::transmute::<ffi::ch_error_event_t, Error>(error_type);
+
+            catch_unwind(Matching::Terminate, || callback(error, id, ErrorInfo::from_raw(info))) as i32
+        }
+
+        (Some(trampoline::<F>), &mut self.0 as *mut F as *mut _)
+    }
 }
 
 impl DatabaseRef {
@@ -319,6 +400,15 @@ impl DatabaseRef {
     ///
     /// The callback can return `Matching::Skip` to cease matching this pattern but continue matching the next pattern.
     /// Otherwise, we stop matching for all patterns with `Matching::Terminate`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "scan",
+            level = "trace",
+            skip_all,
+            fields(mode = "Block", bytes = data.as_ref().len())
+        )
+    )]
     pub fn scan<'a, T, F, E>(
         &self,
         data: T,
@@ -336,33 +426,269 @@ impl DatabaseRef {
             let (on_match_callback, on_match_data) = on_match_event.split();
             let (on_error_callback, on_error_data) = on_error_event.split();
 
-            let mut userdata = (on_match_data, on_error_data);
+            let userdata = (on_match_data, on_error_data);
 
-            ffi::ch_scan(
-                self.as_ptr(),
-                data.as_ptr() as *const _,
-                data.len() as _,
-                0,
-                scratch.as_ptr(),
+            resume_unwind(metered(
+                data.len(),
                 on_match_callback,
-                on_error_callback,
-                &mut userdata as *mut _ as *mut _,
-            )
+                userdata,
+                |on_match_callback, userdata| {
+                    ffi::ch_scan(
+                        self.as_ptr(),
+                        data.as_ptr() as *const _,
+                        data.len() as _,
+                        0,
+                        scratch.as_ptr(),
+                        on_match_callback,
+                        on_error_callback,
+                        userdata,
+                    )
+                },
+            ))
             .ok()
         }
     }
 }
 
+/// The userdata of the match and error callbacks, which is shared by the callbacks.
+type UserData = (*mut core::ffi::c_void, *mut core::ffi::c_void);
+
+/// The match callback wrapped to count the matches.
+///
+/// The userdata is the first field, so the wrapped callback and the error callback
+/// can still find their userdata at the shared context.
+#[cfg(feature = "metrics")]
+#[repr(C)]
+struct Metered<'a> {
+    userdata: UserData,
+    callback: ffi::ch_match_event_handler,
+    metrics: &'a dyn ScanMetrics,
+}
+
+#[cfg(feature = "metrics")]
+unsafe extern "C" fn metered_trampoline(
+    id: u32,
+    from: u64,
+    to: u64,
+    flags: u32,
+    size: u32,
+    captured: *const ffi::ch_capture_t,
+    ctx: *mut ::core::ffi::c_void,
+) -> ffi::ch_callback_t {
+    let metered = ctx as *const Metered<'_>;
+    let (callback, metrics) = ((*metered).callback, (*metered).metrics);
+
+    catch_unwind((), || metrics.on_match(ScanKind::Chimera, id));
+
+    match callback {
+        Some(callback) => callback(id, from, to, flags, size, captured, ctx),
+        None => ffi::CH_CALLBACK_CONTINUE as _,
+    }
+}
+
+/// Invoke the scan with the match callback and the shared userdata,
+/// and report the metrics to the installed `ScanMetrics` if any.
+#[cfg(feature = "metrics")]
+unsafe fn metered<F>(
+    bytes: usize,
+    callback: ffi::ch_match_event_handler,
+    mut userdata: UserData,
+    scan: F,
+) -> ffi::ch_error_t
+where
+    F: FnOnce(ffi::ch_match_event_handler, *mut core::ffi::c_void) -> ffi::ch_error_t,
+{
+    // a panic left by a call which wasn't resumed must not be resumed by this scan
+    discard_unwind();
+
+    let metrics = match scan_metrics() {
+        Some(metrics) => metrics,
+        None => return scan(callback, &mut userdata as *mut _ as *mut _),
+    };
+    let mut metered = Metered {
+        userdata,
+        callback,
+        metrics: &*metrics,
+    };
+
+    let started = Instant::now();
+    let res = scan(Some(metered_trampoline), &mut metered as *mut _ as *mut _);
+    let elapsed = started.elapsed();
+
+    metrics.on_scan(ScanKind::Chimera, bytes, elapsed);
+
+    if res == ffi::CH_SCAN_TERMINATED {
+        metrics.on_terminated(ScanKind::Chimera);
+    }
+
+    res
+}
+
+/// Invoke the scan with the match callback and the shared userdata.
+#[cfg(not(feature = "metrics"))]
+unsafe fn metered<F>(
+    _bytes: usize,
+    callback: ffi::ch_match_event_handler,
+    mut userdata: UserData,
+    scan: F,
+) -> ffi::ch_error_t
+where
+    F: FnOnce(ffi::ch_match_event_handler, *mut core::ffi::c_void) -> ffi::ch_error_t,
+{
+    // a panic left by a call which wasn't resumed must not be resumed by this scan
+    discard_unwind();
+
+    scan(callback, &mut userdata as *mut _ as *mut _)
+}
+
+/// The flags of a match event.
+///
+/// This is provided for future use and is unused at present.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MatchFlags(u32);
+
+impl MatchFlags {
+    /// Returns the raw value of the flags.
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for MatchFlags {
+    fn from(flags: u32) -> Self {
+        MatchFlags(flags)
+    }
+}
+
+/// The extra information of an error event.
+///
+/// It wraps the event-specific data pointer passed to the error callback,
+/// which is provided for future use by Chimera and is null at present.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ErrorInfo {
+    raw: usize,
+}
+
+impl ErrorInfo {
+    /// Wrap the event-specific data pointer of an error event.
+    pub fn from_raw(info: *mut core::ffi::c_void) -> Self {
+        ErrorInfo { raw: info as usize }
+    }
+
+    /// Returns the event-specific data pointer, which could only be dereferenced in the error callback.
+    pub fn as_ptr(&self) -> *mut core::ffi::c_void {
+        self.raw as *mut _
+    }
+
+    /// Returns `true` if Chimera populated the extra information.
+    pub fn is_populated(&self) -> bool {
+        self.raw != 0
+    }
+}
+
+/// A match collected by `DatabaseRef::scan_collect`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    /// The ID number of the expression that matched.
+    pub id: u32,
+    /// The offsets of the entire match.
+    pub range: Range<u64>,
+    /// The flags of the match event.
+    pub flags: MatchFlags,
+    /// The offsets of the entire match and each captured subexpression, or `None` if the group is inactive.
+    ///
+    /// It's empty unless the database was compiled with `Mode::Groups`.
+    pub captured: Vec<Option<Range<usize>>>,
+}
+
+/// An error event collected by `DatabaseRef::scan_collect`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErrorEvent {
+    /// The type of error event.
+    pub error: Error,
+    /// The ID number of the expression that matched.
+    pub id: u32,
+    /// The extra information of the error event.
+    pub info: ErrorInfo,
+}
+
+impl DatabaseRef {
+    /// Scan the data and collect all the matches and the error events.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::chimera::{prelude::*, MatchFlags};
+    /// let pattern: Pattern = r"/(\w+) (\w+)?/".parse().unwrap();
+    /// let db = pattern.with_groups().unwrap();
+    /// let scratch = db.alloc_scratch().unwrap();
+    ///
+    /// let (matches, errors) = db.scan_collect("some test", &scratch).unwrap();
+    ///
+    /// assert_eq!(matches.len(), 1);
+    /// assert_eq!(matches[0].range, 0..9);
+    /// assert_eq!(matches[0].flags, MatchFlags::default());
+    /// assert_eq!(matches[0].captured, vec![Some(0..9), Some(0..4), Some(5..9)]);
+    /// assert!(errors.is_empty());
+    /// ```
+    pub fn scan_collect<T>(&self, data: T, scratch: &ScratchRef) -> Result<(Vec<Match>, Vec<ErrorEvent>)>
+    where
+        T: AsRef<[u8]>,
+    {
+        let mut matches = vec![];
+        let mut errors = vec![];
+
+        self.scan(
+            data,
+            scratch,
+            |id, from, to, flags: u32, captured: Option<&[Capture]>| {
+                matches.push(Match {
+                    id,
+                    range: from..to,
+                    flags: flags.into(),
+                    captured: captured
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|capture| {
+                            if capture.is_active() {
+                                Some(capture.range())
+                            } else {
+                                None
+                            }
+                        })
+                        .collect(),
+                });
+
+                Matching::Continue
+            },
+            OnError::new(|error, id, info| {
+                errors.push(ErrorEvent { error, id, info });
+
+                Matching::Skip
+            }),
+        )
+        .map(|_| (matches, errors))
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
+    use std::panic::{self, AssertUnwindSafe};
     use std::ptr;
 
     use foreign_types::ForeignType;
+    use static_assertions::{assert_impl_all, assert_not_impl_any};
 
     use crate::chimera::prelude::*;
 
     const SCRATCH_SIZE: usize = 2000;
 
+    assert_impl_all!(Database: Send, Sync);
+    assert_impl_all!(Scratch: Send);
+    assert_not_impl_any!(Scratch: Sync);
+    assert_not_impl_any!(ScratchRef: Sync);
+
     #[test]
     fn test_scratch() {
         let db: Database = "test".parse().unwrap();
@@ -384,4 +710,28 @@ pub mod tests {
         assert!(!ptr::eq(s.as_ptr(), s2.as_ptr()));
         assert!(s2.size().unwrap() >= s.size().unwrap());
     }
+
+    #[test]
+    fn test_match_error() {
+        assert!(MatchError::MatchLimit.to_string().contains("MatchLimit::max_matches"));
+        assert!(MatchError::RecursionLimit
+            .to_string()
+            .contains("MatchLimit::recursion_depth"));
+    }
+
+    #[test]
+    fn test_panic_in_callback() {
+        let db: Database = "test".parse().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        let err = panic::catch_unwind(AssertUnwindSafe(|| {
+            db.scan("foo test bar", &s, |_, _, _, _, _| -> Matching { panic!("boom") }, ())
+        }))
+        .unwrap_err();
+
+        assert_eq!(err.downcast_ref::<&str>(), Some(&"boom"));
+        assert!(db
+            .scan("foo test bar", &s, Matching::Continue, Matching::Continue)
+            .is_ok());
+    }
 }
@@ -0,0 +1,349 @@
+use alloc::alloc::{self as global, Layout};
+use core::ptr;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+
+use crate::{error::AsResult, ffi, Result};
+
+extern "C" {
+    /// The `free` function of the C library, which releases the memory returned by the Hyperscan API
+    /// with the default allocator, e.g. the database information.
+    pub(crate) fn free(ptr: *mut core::ffi::c_void);
+}
+
+/// The allocate and free functions used by Hyperscan for stream state, scratch space,
+/// database bytecode and the other data structures returned by the Hyperscan API.
+#[derive(Clone, Copy, Debug)]
+pub struct Allocator {
+    /// Allocate memory suitably aligned for the largest representable data type on this platform.
+    pub alloc: unsafe extern "C" fn(size: usize) -> *mut core::ffi::c_void,
+    /// Free the memory allocated by `alloc`.
+    pub free: unsafe extern "C" fn(ptr: *mut core::ffi::c_void),
+}
+
+impl Allocator {
+    /// The allocator bridging to the Rust global allocator, e.g. jemalloc, mimalloc or an instrumented allocator
+    /// registered with `#[global_allocator]`.
+    ///
+    /// The size of each allocation is stored in a header before the returned memory, which is 16-byte aligned.
+    pub fn global() -> Self {
+        Allocator {
+            alloc: global_alloc,
+            free: global_free,
+        }
+    }
+}
+
+/// The alignment of the memory allocated by the global allocator, and the size of the allocation header.
+const GLOBAL_ALIGN: usize = 16;
+
+unsafe extern "C" fn global_alloc(size: usize) -> *mut core::ffi::c_void {
+    let layout = match size
+        .checked_add(GLOBAL_ALIGN)
+        .and_then(|size| Layout::from_size_align(size, GLOBAL_ALIGN).ok())
+    {
+        Some(layout) => layout,
+        None => return ptr::null_mut(),
+    };
+
+    let ptr = global::alloc(layout);
+
+    if ptr.is_null() {
+        return ptr::null_mut();
+    }
+
+    ptr.cast::<usize>().write(layout.size());
+    ptr.add(GLOBAL_ALIGN).cast()
+}
+
+unsafe extern "C" fn global_free(ptr: *mut core::ffi::c_void) {
+    if ptr.is_null() {
+        return;
+    }
+
+    let ptr = ptr.cast::<u8>().sub(GLOBAL_ALIGN);
+    let size = ptr.cast::<usize>().read();
+
+    global::dealloc(ptr, Layout::from_size_align_unchecked(size, GLOBAL_ALIGN));
+}
+
+fn split(allocator: Option<Allocator>) -> (ffi::hs_alloc_t, ffi::hs_free_t) {
+    match allocator {
+        Some(allocator) => (Some(allocator.alloc), Some(allocator.free)),
+        None => (None, None),
+    }
+}
+
+/// Set the `misc`, `scratch`, `stream` and `database` allocators.
+unsafe fn apply([misc, scratch, stream, database]: [Option<Allocator>; 4]) -> Result<()> {
+    let (alloc, free) = split(misc);
+    ffi::hs_set_misc_allocator(alloc, free).ok()?;
+
+    let (alloc, free) = split(scratch);
+    ffi::hs_set_scratch_allocator(alloc, free).ok()?;
+
+    let (alloc, free) = split(stream);
+    ffi::hs_set_stream_allocator(alloc, free).ok()?;
+
+    let (alloc, free) = split(database);
+    ffi::hs_set_database_allocator(alloc, free).ok()
+}
+
+/// Set the allocators used by Hyperscan for each type of the allocations,
+/// `None` restores the default `malloc`/`free` for that type.
+///
+/// - `misc` for the miscellaneous data structures, e.g. the compile errors, the expression information
+///   and the serialized databases.
+/// - `scratch` for the scratch spaces.
+/// - `stream` for the stream states.
+/// - `database` for the database bytecode.
+///
+/// Like `AllocatorGuard`, the allocators are process-global, and the memory is freed with the allocator
+/// installed at the time of free, so the allocators should be set before any allocation is made,
+/// e.g. at the start of the program. Installing an `AllocatorGuard` overrides all of them,
+/// and they are restored when the last guard is dropped.
+///
+/// Without the `std` feature, there is no `AllocatorGuard` and the calls are not serialized by a lock.
+///
+/// # Safety
+///
+/// No database, scratch space, stream or the other Hyperscan objects may be alive across the call,
+/// unless the memory allocated by the previous allocators can be freed by the new ones,
+/// e.g. both of them wrap `malloc`/`free`. Otherwise the objects are freed through the wrong allocator,
+/// e.g. `Allocator::global()` reads the size header before the memory which `malloc` never wrote.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, set_allocator, Allocator};
+/// let global = Some(Allocator::global());
+///
+/// unsafe { set_allocator(global, global, global, global) }.unwrap();
+///
+/// let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+///
+/// assert!(db.contains("foo test", &s).unwrap());
+/// ```
+pub unsafe fn set_allocator(
+    misc: Option<Allocator>,
+    scratch: Option<Allocator>,
+    stream: Option<Allocator>,
+    database: Option<Allocator>,
+) -> Result<()> {
+    let allocators = [misc, scratch, stream, database];
+
+    #[cfg(feature = "std")]
+    let mut installed = INSTALLED.lock().unwrap_or_else(|err| err.into_inner());
+
+    apply(allocators)?;
+
+    #[cfg(feature = "std")]
+    {
+        installed.base = allocators;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+struct Installed {
+    next_id: usize,
+    /// The allocators set by `set_allocator`, which are restored when the last guard is dropped.
+    base: [Option<Allocator>; 4],
+    stack: Vec<(usize, Allocator)>,
+}
+
+#[cfg(feature = "std")]
+static INSTALLED: Mutex<Installed> = Mutex::new(Installed {
+    next_id: 0,
+    base: [None; 4],
+    stack: Vec::new(),
+});
+
+/// A scoped allocator which restores the previous allocator on drop.
+///
+/// `hs_set_allocator` is process-global, the installed allocators are tracked in a stack
+/// protected by a process-wide lock, which serializes installing and restoring the allocators
+/// through this crate. When a guard is dropped, its allocator is removed from the stack
+/// and the latest remaining allocator is restored, or the allocators set by `set_allocator` if none,
+/// which are the default `malloc`/`free` unless they have been set.
+///
+/// Hyperscan frees the memory with the allocator installed at the time of free,
+/// so the databases, scratch spaces and streams allocated while a guard is alive
+/// must be freed before the guard is dropped, unless the allocators are compatible.
+///
+/// The allocators set by calling `hs_set_allocator` directly, e.g. from another library,
+/// bypass the lock and will be overridden when a guard is installed or dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, Allocator, AllocatorGuard};
+/// unsafe extern "C" fn alloc(size: usize) -> *mut core::ffi::c_void {
+///     libc::malloc(size)
+/// }
+///
+/// unsafe extern "C" fn free(ptr: *mut core::ffi::c_void) {
+///     libc::free(ptr)
+/// }
+///
+/// let guard = unsafe { AllocatorGuard::install(Allocator { alloc, free }) }.unwrap();
+///
+/// let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+///
+/// assert!(db.contains("test", &s).unwrap());
+///
+/// drop((s, db));
+/// drop(guard);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct AllocatorGuard {
+    id: usize,
+}
+
+#[cfg(feature = "std")]
+impl AllocatorGuard {
+    /// Install the allocator until the returned guard is dropped.
+    ///
+    /// # Safety
+    ///
+    /// No database, scratch space, stream or the other Hyperscan objects may be alive when the allocator
+    /// is installed or the guard is dropped, unless the memory allocated by the previous allocator
+    /// can be freed by the new one, e.g. both of them wrap `malloc`/`free`.
+    pub unsafe fn install(allocator: Allocator) -> Result<Self> {
+        let mut installed = INSTALLED.lock().unwrap_or_else(|err| err.into_inner());
+
+        unsafe { ffi::hs_set_allocator(Some(allocator.alloc), Some(allocator.free)).ok()? };
+
+        let id = installed.next_id;
+
+        installed.next_id += 1;
+        installed.stack.push((id, allocator));
+
+        Ok(AllocatorGuard { id })
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for AllocatorGuard {
+    fn drop(&mut self) {
+        let mut installed = INSTALLED.lock().unwrap_or_else(|err| err.into_inner());
+
+        installed.stack.retain(|&(id, _)| id != self.id);
+
+        let _ = unsafe {
+            match installed.stack.last() {
+                Some((_, allocator)) => ffi::hs_set_allocator(Some(allocator.alloc), Some(allocator.free)).ok(),
+                None => apply(installed.base),
+            }
+        };
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+pub mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    use crate::prelude::*;
+    use crate::{set_allocator, Allocator, AllocatorGuard};
+
+    // the allocators are process-global, so the tests installing them are serialized
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    static ALLOCATED: [AtomicUsize; 2] = [AtomicUsize::new(0), AtomicUsize::new(0)];
+
+    unsafe extern "C" fn counting_alloc<const N: usize>(size: usize) -> *mut core::ffi::c_void {
+        ALLOCATED[N].fetch_add(1, Ordering::SeqCst);
+
+        libc::malloc(size)
+    }
+
+    unsafe extern "C" fn counting_free(ptr: *mut core::ffi::c_void) {
+        libc::free(ptr)
+    }
+
+    /// The allocator wrapping `malloc`/`free`, which counts its allocations.
+    fn counting<const N: usize>() -> Allocator {
+        Allocator {
+            alloc: counting_alloc::<N>,
+            free: counting_free,
+        }
+    }
+
+    /// Returns `true` if a scratch space is allocated by the counting allocator `N`.
+    fn allocates_with<const N: usize>(db: &BlockDatabase) -> bool {
+        let allocated = ALLOCATED[N].load(Ordering::SeqCst);
+
+        drop(db.alloc_scratch().unwrap());
+
+        ALLOCATED[N].load(Ordering::SeqCst) > allocated
+    }
+
+    #[test]
+    fn test_nested_allocator_guards() {
+        let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+        let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+
+        unsafe {
+            let outer = AllocatorGuard::install(counting::<0>()).unwrap();
+
+            assert!(allocates_with::<0>(&db));
+
+            let inner = AllocatorGuard::install(counting::<1>()).unwrap();
+
+            assert!(allocates_with::<1>(&db));
+            assert!(!allocates_with::<0>(&db));
+
+            drop(inner);
+
+            assert!(allocates_with::<0>(&db));
+            assert!(!allocates_with::<1>(&db));
+
+            let inner = AllocatorGuard::install(counting::<1>()).unwrap();
+
+            // dropping the outer guard first keeps the inner allocator installed
+            drop(outer);
+
+            assert!(allocates_with::<1>(&db));
+
+            drop(inner);
+        }
+
+        assert!(!allocates_with::<0>(&db));
+        assert!(!allocates_with::<1>(&db));
+    }
+
+    #[test]
+    fn test_allocator_guard_over_set_allocator() {
+        let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+        let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+        let base = Some(counting::<0>());
+
+        unsafe {
+            set_allocator(base, base, base, base).unwrap();
+
+            assert!(allocates_with::<0>(&db));
+
+            let guard = AllocatorGuard::install(counting::<1>()).unwrap();
+
+            assert!(allocates_with::<1>(&db));
+            assert!(!allocates_with::<0>(&db));
+
+            drop(guard);
+
+            assert!(allocates_with::<0>(&db));
+            assert!(!allocates_with::<1>(&db));
+
+            set_allocator(None, None, None, None).unwrap();
+        }
+
+        assert!(!allocates_with::<0>(&db));
+    }
+}
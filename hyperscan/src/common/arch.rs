@@ -0,0 +1,93 @@
+use core::fmt;
+
+use crate::{common::Error as HsError, ffi};
+
+/// The current host can't run the linked Hyperscan library.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PlatformError {
+    /// The CPU doesn't support the SSSE3 instruction set, which Hyperscan requires.
+    MissingSsse3,
+
+    /// The CPU doesn't support the AVX2 instruction set, which the linked Hyperscan library was built for.
+    MissingAvx2,
+
+    /// The platform is not supported for another reason, e.g. the SIMD instruction set required by Vectorscan,
+    /// or the missing instruction set can't be detected without the `std` feature.
+    Unsupported,
+
+    /// Hyperscan failed to test the platform.
+    Hyperscan(HsError),
+}
+
+impl fmt::Display for PlatformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlatformError::MissingSsse3 => {
+                f.write_str("the CPU doesn't support the SSSE3 instruction set required by Hyperscan")
+            }
+            PlatformError::MissingAvx2 => {
+                f.write_str("the CPU doesn't support the AVX2 instruction set required by the Hyperscan build")
+            }
+            PlatformError::Unsupported => f.write_str("the platform is not supported by the linked Hyperscan library"),
+            PlatformError::Hyperscan(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PlatformError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PlatformError::Hyperscan(err) => std::error::Error::source(err),
+            _ => None,
+        }
+    }
+}
+
+/// Test the current host could run the linked Hyperscan library, so an application could fail fast
+/// on an unsupported CPU with a clear message, instead of failing on the first compile or scan.
+///
+/// # Examples
+///
+/// ```rust
+/// if let Err(err) = hyperscan::check_platform() {
+///     panic!("unsupported platform: {}", err);
+/// }
+/// ```
+pub fn check_platform() -> Result<(), PlatformError> {
+    match unsafe { ffi::hs_valid_platform() } {
+        code if code == ffi::HS_SUCCESS as ffi::hs_error_t => Ok(()),
+        ffi::HS_ARCH_ERROR => Err(arch_error()),
+        code => Err(PlatformError::Hyperscan(HsError::from(code))),
+    }
+}
+
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+fn arch_error() -> PlatformError {
+    if !is_x86_feature_detected!("ssse3") {
+        PlatformError::MissingSsse3
+    } else if !is_x86_feature_detected!("avx2") {
+        PlatformError::MissingAvx2
+    } else {
+        PlatformError::Unsupported
+    }
+}
+
+#[cfg(not(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64"))))]
+fn arch_error() -> PlatformError {
+    PlatformError::Unsupported
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{check_platform, HsError, PlatformError};
+
+    #[test]
+    fn test_check_platform() {
+        assert_eq!(check_platform(), Ok(()));
+        assert_eq!(
+            PlatformError::Hyperscan(HsError::Invalid).to_string(),
+            "A parameter passed to this function was invalid."
+        );
+    }
+}
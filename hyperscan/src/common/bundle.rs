@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::common::{Database, Error as HsError, Serialized};
+use crate::error::{Error, Result};
+
+const MAGIC: &[u8; 8] = b"HSBUNDLE";
+
+/// The CPU feature level which a serialized database was compiled for.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FeatureLevel {
+    /// Compiled without any optional CPU feature.
+    Generic = 0,
+    /// Compiled with the Intel(R) AVX2 instructions.
+    Avx2 = 1,
+    /// Compiled with the Intel(R) AVX512 instructions.
+    Avx512 = 2,
+}
+
+impl FeatureLevel {
+    /// The highest feature level supported by the current CPU.
+    #[cfg(feature = "compile")]
+    fn host() -> Self {
+        FeatureLevel::from_cpu_features(crate::compile::CpuFeatures::detect())
+    }
+
+    /// The feature level of the current CPU can't be detected without the compiler,
+    /// the databases are deserialized from the highest level until one runs on the CPU.
+    #[cfg(not(feature = "compile"))]
+    fn host() -> Self {
+        FeatureLevel::Avx512
+    }
+
+    fn from_u8(level: u8) -> Option<Self> {
+        match level {
+            0 => Some(FeatureLevel::Generic),
+            1 => Some(FeatureLevel::Avx2),
+            2 => Some(FeatureLevel::Avx512),
+            _ => None,
+        }
+    }
+}
+
+/// A bundle of serialized databases compiled for different CPU feature levels.
+///
+/// When loading the bundle, the database of the highest feature level supported by the current CPU is picked,
+/// falling back to the lower levels instead of failing with `DbPlatformError` or `ArchError`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, Bundle, FeatureLevel};
+/// let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+///
+/// let mut bundle = Bundle::new();
+/// bundle.insert(FeatureLevel::Generic, db.serialize().unwrap().to_vec());
+///
+/// let bundle = Bundle::from_bytes(&bundle.to_bytes()).unwrap();
+/// let (level, db): (_, BlockDatabase) = bundle.best().unwrap();
+///
+/// assert_eq!(level, FeatureLevel::Generic);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Bundle {
+    databases: BTreeMap<FeatureLevel, Vec<u8>>,
+}
+
+impl Bundle {
+    /// Construct an empty bundle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a serialized database compiled for the feature level, replacing the previous one.
+    pub fn insert(&mut self, level: FeatureLevel, serialized: Vec<u8>) -> Option<Vec<u8>> {
+        self.databases.insert(level, serialized)
+    }
+
+    /// Returns the serialized database of the feature level.
+    pub fn get(&self, level: FeatureLevel) -> Option<&[u8]> {
+        self.databases.get(&level).map(|buf| buf.as_slice())
+    }
+
+    /// Returns an iterator over the feature levels in the bundle, from the lowest to the highest.
+    pub fn levels(&self) -> impl Iterator<Item = FeatureLevel> + '_ {
+        self.databases.keys().copied()
+    }
+
+    /// Load the serialized databases from the files of the feature levels.
+    pub fn load<I, P>(paths: I) -> io::Result<Self>
+    where
+        I: IntoIterator<Item = (FeatureLevel, P)>,
+        P: AsRef<Path>,
+    {
+        paths
+            .into_iter()
+            .map(|(level, path)| fs::read(path).map(|buf| (level, buf)))
+            .collect::<io::Result<_>>()
+            .map(|databases| Bundle { databases })
+    }
+
+    /// Load the serialized databases from the files of the feature levels,
+    /// and deserialize the best one supported by the current CPU.
+    pub fn load_best<M, I, P>(paths: I) -> io::Result<(FeatureLevel, Database<M>)>
+    where
+        I: IntoIterator<Item = (FeatureLevel, P)>,
+        P: AsRef<Path>,
+    {
+        Self::load(paths)?
+            .best()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Deserialize the database of the highest feature level supported by the current CPU.
+    ///
+    /// With the `compile` feature, the levels above the CPU features detected by `CpuFeatures::detect()`
+    /// are skipped, the database failing to be deserialized, e.g. with `DbPlatformError` or `ArchError`,
+    /// falls back to the lower levels.
+    ///
+    /// Returns the error of the highest level if none of them could be deserialized,
+    /// or `DbPlatformError` if the bundle has no database for the current CPU.
+    pub fn best<M>(&self) -> Result<(FeatureLevel, Database<M>)> {
+        let host = FeatureLevel::host();
+        let mut first_err = None;
+
+        for (&level, buf) in self.databases.iter().rev().filter(|&(&level, _)| level <= host) {
+            match buf.deserialize() {
+                Ok(db) => return Ok((level, db)),
+                Err(err) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+
+        Err(first_err.unwrap_or(Error::Hyperscan(HsError::DbPlatformError)))
+    }
+
+    /// Encode the bundle into a stream of bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = MAGIC.to_vec();
+
+        buf.push(self.databases.len() as u8);
+
+        for (&level, db) in &self.databases {
+            buf.push(level as u8);
+            buf.extend_from_slice(&(db.len() as u64).to_le_bytes());
+            buf.extend_from_slice(db);
+        }
+
+        buf
+    }
+
+    /// Decode a bundle from a stream of bytes previously generated by `Bundle::to_bytes()`.
+    pub fn from_bytes(mut buf: &[u8]) -> io::Result<Self> {
+        fn take<'a>(buf: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+            if buf.len() < len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+
+            let (head, tail) = buf.split_at(len);
+            *buf = tail;
+            Ok(head)
+        }
+
+        if take(&mut buf, MAGIC.len())? != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid bundle"));
+        }
+
+        let count = take(&mut buf, 1)?[0];
+        let mut databases = BTreeMap::new();
+
+        for _ in 0..count {
+            let level = FeatureLevel::from_u8(take(&mut buf, 1)?[0])
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid feature level"))?;
+            let len = u64::from_le_bytes(take(&mut buf, 8)?.try_into().unwrap());
+            let db = take(&mut buf, len as usize)?;
+
+            databases.insert(level, db.to_vec());
+        }
+
+        Ok(Bundle { databases })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::common::database::tests::*;
+    use crate::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_bundle() {
+        let db: BlockDatabase = "test".parse().unwrap();
+        let data = db.serialize().unwrap();
+
+        let mut bundle = Bundle::new();
+
+        bundle.insert(FeatureLevel::Generic, data.to_vec());
+        bundle.insert(FeatureLevel::Avx2, data.to_vec());
+
+        let decoded = Bundle::from_bytes(&bundle.to_bytes()).unwrap();
+
+        assert_eq!(decoded, bundle);
+        assert_eq!(
+            decoded.levels().collect::<Vec<_>>(),
+            vec![FeatureLevel::Generic, FeatureLevel::Avx2]
+        );
+
+        let (level, db): (_, BlockDatabase) = decoded.best().unwrap();
+
+        assert_eq!(level, FeatureLevel::Avx2);
+        validate_database(&db);
+
+        assert!(Bundle::new().best::<crate::BlockMode>().is_err());
+        assert!(Bundle::from_bytes(b"HSBUNDLE\x01\x05").is_err());
+    }
+
+    #[test]
+    fn test_bundle_fallback() {
+        let db: BlockDatabase = "test".parse().unwrap();
+        let mut bundle = Bundle::new();
+
+        bundle.insert(FeatureLevel::Generic, db.serialize().unwrap().to_vec());
+        bundle.insert(FeatureLevel::Avx2, b"corrupted database".to_vec());
+
+        let (level, db): (_, BlockDatabase) = bundle.best().unwrap();
+
+        assert_eq!(level, FeatureLevel::Generic);
+        validate_database(&db);
+
+        let mut bundle = Bundle::new();
+
+        bundle.insert(FeatureLevel::Generic, b"corrupted database".to_vec());
+
+        assert!(bundle.best::<crate::BlockMode>().is_err());
+    }
+}
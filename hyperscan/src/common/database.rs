@@ -1,17 +1,26 @@
-use std::ffi::CStr;
-use std::marker::PhantomData;
-use std::mem::MaybeUninit;
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use core::ffi::CStr;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
 
 use foreign_types::{foreign_type, ForeignTypeRef};
 
 use crate::{
-    common::{Block, Mode, Streaming, Vectored},
+    common::{Block, DbMode, Mode, Streaming, Vectored},
     error::AsResult,
-    ffi, Result,
+    ffi, Error, Result,
 };
 
 foreign_type! {
     /// A compiled pattern database that can then be used to scan data.
+    ///
+    /// The database is immutable once compiled, so it is both `Send` and `Sync`
+    /// and may be shared between threads (e.g. through an `Arc`).
+    ///
+    /// The database must outlive the streams opened against it,
+    /// the debug builds panic if it is dropped while some of those streams are still alive.
     pub unsafe type Database<T>: Send + Sync {
         type CType = ffi::hs_database_t;
         type PhantomData = PhantomData<T>;
@@ -21,9 +30,106 @@ foreign_type! {
 }
 
 unsafe fn drop_database(db: *mut ffi::hs_database_t) {
+    streams::check_database(db);
+
     ffi::hs_free_database(db).expect("drop database");
 }
 
+/// Tracking of the streams opened against a database.
+///
+/// A stream refers to the database it was opened against, so dropping the database while
+/// the stream is still alive leads to undefined behavior in the Hyperscan library.
+/// The debug builds with the `std` feature keep track of the live streams and panic when such a database
+/// is dropped; the other builds have no overhead.
+///
+/// The scratch space does not refer to the database, so it doesn't need to be tracked.
+pub(crate) mod streams {
+    use crate::ffi;
+
+    #[cfg(all(debug_assertions, feature = "std"))]
+    use std::sync::Mutex;
+
+    /// The live streams and the database which they were opened against.
+    #[cfg(all(debug_assertions, feature = "std"))]
+    static STREAMS: Mutex<Vec<(usize, usize)>> = Mutex::new(Vec::new());
+
+    /// Track a stream opened against the database.
+    #[cfg(all(debug_assertions, feature = "std"))]
+    pub fn track(db: *const ffi::hs_database_t, stream: *const ffi::hs_stream_t) {
+        if let Ok(mut streams) = STREAMS.lock() {
+            streams.push((stream as usize, db as usize));
+        }
+    }
+
+    /// Track a stream duplicated from the `from` stream.
+    #[cfg(all(debug_assertions, feature = "std"))]
+    pub fn track_copy(from: *const ffi::hs_stream_t, stream: *const ffi::hs_stream_t) {
+        if let Ok(mut streams) = STREAMS.lock() {
+            if let Some(&(_, db)) = streams.iter().find(|&&(s, _)| s == from as usize) {
+                streams.push((stream as usize, db));
+            }
+        }
+    }
+
+    /// Stop tracking a dropped stream.
+    #[cfg(all(debug_assertions, feature = "std"))]
+    pub fn untrack(stream: *const ffi::hs_stream_t) {
+        if let Ok(mut streams) = STREAMS.lock() {
+            if let Some(idx) = streams.iter().position(|&(s, _)| s == stream as usize) {
+                streams.swap_remove(idx);
+            }
+        }
+    }
+
+    /// Panic if the database is dropped while some streams opened against it are still alive.
+    #[cfg(all(debug_assertions, feature = "std"))]
+    pub fn check_database(db: *const ffi::hs_database_t) {
+        let live = STREAMS
+            .lock()
+            .map_or(0, |streams| streams.iter().filter(|&&(_, d)| d == db as usize).count());
+
+        if live > 0 && !std::thread::panicking() {
+            panic!(
+                "database dropped while {} stream(s) opened against it are still alive",
+                live
+            );
+        }
+    }
+
+    #[cfg(not(all(debug_assertions, feature = "std")))]
+    pub fn track(_db: *const ffi::hs_database_t, _stream: *const ffi::hs_stream_t) {}
+
+    #[cfg(not(all(debug_assertions, feature = "std")))]
+    pub fn track_copy(_from: *const ffi::hs_stream_t, _stream: *const ffi::hs_stream_t) {}
+
+    #[cfg(not(all(debug_assertions, feature = "std")))]
+    pub fn untrack(_stream: *const ffi::hs_stream_t) {}
+
+    #[cfg(not(all(debug_assertions, feature = "std")))]
+    pub fn check_database(_db: *const ffi::hs_database_t) {}
+}
+
+/// Record the size of a compiled or deserialized database in the `size` field of the current span.
+#[cfg(feature = "tracing")]
+pub(crate) fn traced<D, T>(db: D) -> D
+where
+    D: Deref<Target = DatabaseRef<T>>,
+{
+    if let Ok(size) = db.size() {
+        tracing::Span::current().record("size", size);
+    }
+
+    db
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn traced<D, T>(db: D) -> D
+where
+    D: Deref<Target = DatabaseRef<T>>,
+{
+    db
+}
+
 /// Block scan (non-streaming) database.
 pub type BlockDatabase = Database<Block>;
 /// Streaming database.
@@ -62,11 +168,37 @@ impl<T> DatabaseRef<T> {
             ffi::hs_database_info(self.as_ptr(), p.as_mut_ptr()).and_then(|_| {
                 let p = p.assume_init();
                 let info = CStr::from_ptr(p).to_str()?.to_owned();
-                libc::free(p as *mut _);
+                crate::common::free(p as *mut _);
                 Ok(info)
             })
         }
     }
+
+    /// Provides the compiled mode of the given database, read from the database information.
+    ///
+    /// Unlike `DatabaseRef::name`, which is the mode of the type parameter, it's the mode of the bytecode,
+    /// e.g. to validate a deserialized database at startup.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, DbMode, SerializedDatabase};
+    /// let db: StreamingDatabase = pattern! {"test"}.build().unwrap();
+    ///
+    /// assert_eq!(db.mode().unwrap(), DbMode::Streaming);
+    ///
+    /// let db: BlockDatabase = db.serialize().unwrap().deserialize().unwrap();
+    ///
+    /// assert_eq!(db.mode().unwrap(), DbMode::Streaming);
+    /// ```
+    pub fn mode(&self) -> Result<DbMode> {
+        let info = self.info()?;
+
+        match info.split("Mode:").nth(1) {
+            Some(mode) => mode.trim().parse(),
+            None => Err(Error::InvalidInfo(info)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -123,4 +255,29 @@ pub mod tests {
 
         validate_database_info(&db_info);
     }
+
+    #[cfg(all(feature = "runtime", debug_assertions))]
+    #[test]
+    #[should_panic(expected = "stream(s) opened against it are still alive")]
+    fn test_drop_database_with_live_stream() {
+        let db: StreamingDatabase = "test".parse().unwrap();
+        let st = db.open_stream().unwrap();
+
+        drop(db);
+        drop(st);
+    }
+
+    #[cfg(feature = "runtime")]
+    #[test]
+    fn test_drop_database_after_streams() {
+        let db: StreamingDatabase = "test".parse().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let st = db.open_stream().unwrap();
+        let st2 = st.clone();
+
+        st.close(&s, ()).unwrap();
+        st2.close(&s, ()).unwrap();
+
+        drop(db);
+    }
 }
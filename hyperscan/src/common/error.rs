@@ -1,69 +1,83 @@
-use thiserror::Error;
+use core::fmt;
 
 use crate::ffi;
 
 /// Hyperscan Error Codes
-#[derive(Debug, Error, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum Error {
     /// A parameter passed to this function was invalid.
-    #[error("A parameter passed to this function was invalid.")]
     Invalid,
 
     /// A memory allocation failed.
-    #[error("A memory allocation failed.")]
     NoMem,
 
     /// The engine was terminated by callback.
-    #[error("The engine was terminated by callback.")]
     ScanTerminated,
 
     /// The pattern compiler failed with more detail.
     #[cfg(feature = "compile")]
-    #[error("The pattern compiler failed with more detail, {0}.")]
     CompileError(crate::compile::Error),
 
     /// The given database was built for a different version of Hyperscan.
-    #[error("The given database was built for a different version of Hyperscan.")]
     DbVersionError,
 
     /// The given database was built for a different platform (i.e., CPU type).
-    #[error("The given database was built for a different platform (i.e., CPU type).")]
     DbPlatformError,
 
     /// The given database was built for a different mode of operation.
-    #[error("The given database was built for a different mode of operation.")]
     DbModeError,
 
     /// A parameter passed to this function was not correctly aligned.
-    #[error("A parameter passed to this function was not correctly aligned.")]
     BadAlign,
 
     /// The memory allocator did not correctly return memory suitably aligned.
-    #[error("The memory allocator did not correctly return memory suitably aligned.")]
     BadAlloc,
 
     /// The scratch region was already in use.
-    #[error("The scratch region was already in use.")]
     ScratchInUse,
 
     /// Unsupported CPU architecture.
-    #[error("Unsupported CPU architecture.")]
     ArchError,
 
     /// Provided buffer was too small.
-    #[error("Provided buffer was too small.")]
     InsufficientSpace,
 
     /// Unexpected internal error.
-    #[cfg(feature = "v5")]
-    #[error("Unexpected internal error.")]
+    #[cfg(hyperscan_v5)]
     UnknownError,
 
     /// Unknown error code
-    #[error("Unknown error code: {0}")]
     Code(ffi::hs_error_t),
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Error::*;
+
+        match self {
+            Invalid => f.write_str("A parameter passed to this function was invalid."),
+            NoMem => f.write_str("A memory allocation failed."),
+            ScanTerminated => f.write_str("The engine was terminated by callback."),
+            #[cfg(feature = "compile")]
+            CompileError(err) => write!(f, "The pattern compiler failed with more detail, {}.", err),
+            DbVersionError => f.write_str("The given database was built for a different version of Hyperscan."),
+            DbPlatformError => f.write_str("The given database was built for a different platform (i.e., CPU type)."),
+            DbModeError => f.write_str("The given database was built for a different mode of operation."),
+            BadAlign => f.write_str("A parameter passed to this function was not correctly aligned."),
+            BadAlloc => f.write_str("The memory allocator did not correctly return memory suitably aligned."),
+            ScratchInUse => f.write_str("The scratch region was already in use."),
+            ArchError => f.write_str("Unsupported CPU architecture."),
+            InsufficientSpace => f.write_str("Provided buffer was too small."),
+            #[cfg(hyperscan_v5)]
+            UnknownError => f.write_str("Unexpected internal error."),
+            Code(code) => write!(f, "Unknown error code: {}", code),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
 impl From<ffi::hs_error_t> for Error {
     fn from(err: ffi::hs_error_t) -> Self {
         use Error::*;
@@ -81,7 +95,7 @@ impl From<ffi::hs_error_t> for Error {
             ffi::HS_SCRATCH_IN_USE => ScratchInUse,
             ffi::HS_ARCH_ERROR => ArchError,
             ffi::HS_INSUFFICIENT_SPACE => InsufficientSpace,
-            #[cfg(feature = "v5")]
+            #[cfg(hyperscan_v5)]
             ffi::HS_UNKNOWN_ERROR => UnknownError,
             _ => Code(err),
         }
@@ -0,0 +1,407 @@
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+use core::result::Result as StdResult;
+
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "std")]
+use crate::common::DatabaseRef;
+use crate::{
+    common::{version, Block, Database, Mode, Serialized, Streaming, Vectored},
+    Result,
+};
+
+const MAGIC: &[u8; 4] = b"HSDB";
+const HEADER_LEN: usize = 26;
+
+/// The framed serialized database is invalid or incompatible with the linked Hyperscan library.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// The frame is shorter than its header or the declared length of the bytecode.
+    Truncated(usize),
+
+    /// The frame is longer than the declared length of the bytecode, with the number of the trailing bytes.
+    TrailingBytes(usize),
+
+    /// The frame doesn't start with the magic number.
+    BadMagic,
+
+    /// The checksum of the header and the bytecode doesn't match the one in the header.
+    Checksum {
+        /// The checksum in the header.
+        expected: u32,
+        /// The checksum of the header and the bytecode.
+        actual: u32,
+    },
+
+    /// The database was serialized by a different version of Hyperscan.
+    Version {
+        /// The version of Hyperscan which serialized the database.
+        built: semver::Version,
+        /// The version of the linked Hyperscan library.
+        linked: semver::Version,
+    },
+
+    /// The database was compiled for a different mode.
+    Mode {
+        /// The name of the mode which the database was compiled for.
+        built: &'static str,
+        /// The name of the expected mode.
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::Truncated(len) => write!(f, "the frame is truncated at {} bytes", len),
+            FrameError::TrailingBytes(len) => write!(f, "the frame has {} trailing bytes", len),
+            FrameError::BadMagic => f.write_str("the frame doesn't start with the magic number"),
+            FrameError::Checksum { expected, actual } => write!(
+                f,
+                "the checksum of the frame is {:08x}, expected {:08x}",
+                actual, expected
+            ),
+            FrameError::Version { built, linked } => write!(
+                f,
+                "the database was serialized by Hyperscan {}, but Hyperscan {} is linked",
+                built, linked
+            ),
+            FrameError::Mode { built, expected } => write!(
+                f,
+                "the database was compiled for the {} mode, but the {} mode is expected",
+                built, expected
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FrameError {}
+
+/// A serialized database framed with a header, which could be cached on disk between restarts.
+///
+/// The header contains a magic number, the version of Hyperscan which serialized the database,
+/// the mode of the database, the length of the bytecode, and the CRC32 checksum of the header and the bytecode.
+/// Loading a frame validates it with a descriptive `FrameError`, instead of failing with `DbVersionError`
+/// or `DbModeError` when deserializing the bytecode.
+///
+/// With the `serde` feature, the frame is serialized as a byte array.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, Error, FrameError, FramedDatabase, StreamingMode};
+/// let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+/// let frame = FramedDatabase::new(&db).unwrap();
+///
+/// let frame = FramedDatabase::from_bytes(frame.into_bytes()).unwrap();
+/// let db: BlockDatabase = frame.deserialize().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+///
+/// assert!(db.contains("foo test bar", &s).unwrap());
+///
+/// assert!(matches!(
+///     frame.deserialize::<StreamingMode>(),
+///     Err(Error::Frame(FrameError::Mode { .. }))
+/// ));
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct FramedDatabase {
+    buf: Vec<u8>,
+}
+
+impl fmt::Debug for FramedDatabase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FramedDatabase")
+            .field("version", &self.version())
+            .field("mode", &mode_name(self.mode()))
+            .field("checksum", &self.checksum())
+            .field("len", &self.bytecode().len())
+            .finish()
+    }
+}
+
+impl FramedDatabase {
+    /// Serialize the database and frame it with a header.
+    #[cfg(feature = "std")]
+    pub fn new<T: Mode>(db: &DatabaseRef<T>) -> Result<Self> {
+        let bytecode = db.serialize()?;
+        let version = version();
+        let mut buf = Vec::with_capacity(HEADER_LEN + bytecode.len());
+
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(&(version.major as u16).to_le_bytes());
+        buf.extend_from_slice(&(version.minor as u16).to_le_bytes());
+        buf.extend_from_slice(&(version.patch as u16).to_le_bytes());
+        buf.extend_from_slice(&T::ID.to_le_bytes());
+        buf.extend_from_slice(&(bytecode.len() as u64).to_le_bytes());
+        buf.extend_from_slice(&crc32(&[&buf[4..], &bytecode]).to_le_bytes());
+        buf.extend_from_slice(&bytecode);
+
+        Ok(FramedDatabase { buf })
+    }
+
+    /// Load a frame, validating the magic number, the length of the bytecode, and the checksum of the frame.
+    pub fn from_bytes<B: Into<Vec<u8>>>(bytes: B) -> StdResult<Self, FrameError> {
+        let buf = bytes.into();
+
+        if buf.len() < HEADER_LEN {
+            return Err(FrameError::Truncated(buf.len()));
+        }
+        if &buf[..4] != MAGIC {
+            return Err(FrameError::BadMagic);
+        }
+
+        let frame = FramedDatabase { buf };
+        let len = u64::from_le_bytes(frame.buf[14..22].try_into().unwrap());
+        let bytecode_len = (frame.buf.len() - HEADER_LEN) as u64;
+
+        if bytecode_len < len {
+            return Err(FrameError::Truncated(frame.buf.len()));
+        }
+        if bytecode_len > len {
+            return Err(FrameError::TrailingBytes((bytecode_len - len) as usize));
+        }
+
+        let actual = crc32(&[&frame.buf[4..22], frame.bytecode()]);
+
+        if actual != frame.checksum() {
+            return Err(FrameError::Checksum {
+                expected: frame.checksum(),
+                actual,
+            });
+        }
+
+        Ok(frame)
+    }
+
+    /// Returns the content of the frame.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Consumes the frame and returns its content.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    /// The version of Hyperscan which serialized the database.
+    pub fn version(&self) -> semver::Version {
+        let field = |off: usize| u64::from(u16::from_le_bytes(self.buf[off..off + 2].try_into().unwrap()));
+
+        semver::Version::new(field(4), field(6), field(8))
+    }
+
+    /// The mode ID of the database.
+    pub fn mode(&self) -> u32 {
+        u32::from_le_bytes(self.buf[10..14].try_into().unwrap())
+    }
+
+    /// The CRC32 checksum of the header fields after the magic number and the bytecode.
+    pub fn checksum(&self) -> u32 {
+        u32::from_le_bytes(self.buf[22..HEADER_LEN].try_into().unwrap())
+    }
+
+    /// The serialized bytecode generated by `DatabaseRef::serialize()`.
+    pub fn bytecode(&self) -> &[u8] {
+        &self.buf[HEADER_LEN..]
+    }
+
+    /// Validate the frame is compatible with the linked Hyperscan library and the mode,
+    /// and reconstruct the database from the bytecode.
+    pub fn deserialize<T: Mode>(&self) -> Result<Database<T>> {
+        let (built, linked) = (self.version(), version());
+
+        if (built.major, built.minor, built.patch) != (linked.major, linked.minor, linked.patch) {
+            return Err(FrameError::Version { built, linked }.into());
+        }
+        if self.mode() != T::ID {
+            return Err(FrameError::Mode {
+                built: mode_name(self.mode()),
+                expected: T::NAME,
+            }
+            .into());
+        }
+
+        self.bytecode().deserialize()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for FramedDatabase {
+    fn serialize<S: Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.buf)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for FramedDatabase {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> StdResult<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> de::Visitor<'de> for BytesVisitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a framed database")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> StdResult<Self::Value, E> {
+                Ok(v.to_vec())
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> StdResult<Self::Value, E> {
+                Ok(v)
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> StdResult<Self::Value, A::Error> {
+                let mut buf = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+
+                while let Some(b) = seq.next_element()? {
+                    buf.push(b);
+                }
+
+                Ok(buf)
+            }
+        }
+
+        deserializer
+            .deserialize_byte_buf(BytesVisitor)
+            .and_then(|buf| FramedDatabase::from_bytes(buf).map_err(de::Error::custom))
+    }
+}
+
+fn mode_name(id: u32) -> &'static str {
+    match id {
+        Block::ID => Block::NAME,
+        Streaming::ID => Streaming::NAME,
+        Vectored::ID => Vectored::NAME,
+        _ => "unknown",
+    }
+}
+
+/// The lookup table of the CRC32 (IEEE 802.3) checksum, indexed by the low byte of the CRC xor the next byte.
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < table.len() {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+/// The CRC32 (IEEE 802.3) checksum of the concatenated parts.
+fn crc32(parts: &[&[u8]]) -> u32 {
+    !parts.iter().flat_map(|part| part.iter()).fold(!0, |crc, &b| {
+        CRC32_TABLE[((crc ^ u32::from(b)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::{BlockMode, Error, FrameError, FramedDatabase, VectoredMode};
+
+    use super::crc32;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(&[]), 0);
+        assert_eq!(crc32(&[b"123456789"]), 0xCBF4_3926);
+        assert_eq!(crc32(&[b"1234", b"", b"56789"]), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_framed_database() {
+        let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+        let frame = FramedDatabase::new(&db).unwrap();
+
+        assert_eq!(frame.version(), crate::version());
+        assert_eq!(frame.mode(), BlockMode::ID);
+        assert_eq!(&frame.as_bytes()[..4], b"HSDB");
+        assert_eq!(frame.bytecode(), &db.serialize().unwrap()[..]);
+
+        let bytes = frame.clone().into_bytes();
+
+        assert_eq!(FramedDatabase::from_bytes(&bytes[..]).unwrap(), frame);
+        assert_eq!(FramedDatabase::from_bytes(&bytes[..10]), Err(FrameError::Truncated(10)));
+        assert_eq!(
+            FramedDatabase::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(FrameError::Truncated(bytes.len() - 1))
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.extend_from_slice(b"xx");
+        assert_eq!(FramedDatabase::from_bytes(trailing), Err(FrameError::TrailingBytes(2)));
+
+        let mut corrupted = bytes.clone();
+        corrupted[0] = b'X';
+        assert_eq!(FramedDatabase::from_bytes(corrupted), Err(FrameError::BadMagic));
+
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 0xFF;
+        assert!(matches!(
+            FramedDatabase::from_bytes(corrupted),
+            Err(FrameError::Checksum { expected, .. }) if expected == frame.checksum()
+        ));
+
+        // the header is covered by the checksum
+        let mut outdated = bytes;
+        outdated[4] = outdated[4].wrapping_add(1);
+        assert!(matches!(
+            FramedDatabase::from_bytes(&outdated[..]),
+            Err(FrameError::Checksum { expected, .. }) if expected == frame.checksum()
+        ));
+
+        let checksum = crc32(&[&outdated[4..22], &outdated[26..]]);
+        outdated[22..26].copy_from_slice(&checksum.to_le_bytes());
+        assert!(matches!(
+            FramedDatabase::from_bytes(outdated).unwrap().deserialize::<BlockMode>(),
+            Err(Error::Frame(FrameError::Version { .. }))
+        ));
+
+        assert_eq!(
+            frame.deserialize::<VectoredMode>().err(),
+            Some(Error::Frame(FrameError::Mode {
+                built: "Block",
+                expected: "Vectored"
+            }))
+        );
+
+        let db: BlockDatabase = frame.deserialize().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        assert!(db.contains("foo test bar", &s).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_framed_database_serde() {
+        let db: StreamingDatabase = pattern! {"test"}.build().unwrap();
+        let frame = FramedDatabase::new(&db).unwrap();
+        let json = serde_json::to_string(&frame).unwrap();
+
+        assert_eq!(serde_json::from_str::<FramedDatabase>(&json).unwrap(), frame);
+        assert!(serde_json::from_str::<FramedDatabase>("[1,2,3]").is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use std::str::FromStr;
+
+use crate::{
+    common::{serialized::cpu_features, DatabaseRef, DbMode},
+    compile::CpuFeatures,
+    Error, Result,
+};
+
+/// The parsed information of a database, e.g. `Version: 5.4.0 Features: AVX2 Mode: STREAM`.
+///
+/// Only the most advanced CPU feature is named in the information, the features implied by it are included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbInfo {
+    /// The version of Hyperscan which compiled the database.
+    pub version: semver::Version,
+    /// The CPU features of the platform the database was compiled for.
+    pub features: CpuFeatures,
+    /// The compiled mode of the database.
+    pub mode: DbMode,
+}
+
+impl FromStr for DbInfo {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidInfo(s.to_owned());
+
+        let version = s
+            .strip_prefix("Version:")
+            .and_then(|s| s.split_whitespace().next())
+            .and_then(|version| semver::Version::parse(version).ok())
+            .ok_or_else(invalid)?;
+        let mode = s
+            .split("Mode:")
+            .nth(1)
+            .ok_or_else(invalid)?
+            .trim()
+            .parse()
+            .map_err(|_| invalid())?;
+
+        Ok(DbInfo {
+            version,
+            features: cpu_features(s),
+            mode,
+        })
+    }
+}
+
+impl<T> DatabaseRef<T> {
+    /// Utility function providing the parsed information about a database.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, DbMode};
+    /// let db: StreamingDatabase = "test".parse().unwrap();
+    /// let info = db.info_parsed().unwrap();
+    ///
+    /// assert_eq!(info.version, hyperscan::version());
+    /// assert_eq!(info.mode, DbMode::Streaming);
+    /// assert!(hyperscan::CpuFeatures::host().unwrap().contains(info.features));
+    /// ```
+    pub fn info_parsed(&self) -> Result<DbInfo> {
+        self.info()?.parse()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::common::{DbInfo, DbMode};
+    use crate::compile::CpuFeatures;
+    use crate::Error;
+
+    #[test]
+    fn test_db_info() {
+        let info: DbInfo = "Version: 5.4.0 Features: AVX2 Mode: STREAM".parse().unwrap();
+
+        assert_eq!(info.version, semver::Version::new(5, 4, 0));
+        #[cfg(hyperscan_x86)]
+        assert_eq!(info.features, CpuFeatures::AVX2);
+        assert_eq!(info.mode, DbMode::Streaming);
+
+        let info: DbInfo = "Version: 5.4.0 Features:  Mode: BLOCK".parse().unwrap();
+
+        assert_eq!(info.features, CpuFeatures::empty());
+        assert_eq!(info.mode, DbMode::Block);
+
+        assert_eq!(
+            "Version: 5.4.0 Features: AVX2".parse::<DbInfo>(),
+            Err(Error::InvalidInfo("Version: 5.4.0 Features: AVX2".into()))
+        );
+        assert!("Version: x Features: Mode: BLOCK".parse::<DbInfo>().is_err());
+    }
+}
@@ -1,19 +1,47 @@
+mod alloc;
+mod arch;
+#[cfg(feature = "std")]
+mod bundle;
 mod database;
 mod error;
+mod framed;
+#[cfg(feature = "compile")]
+mod info;
 mod mode;
+#[cfg(all(feature = "std", any(feature = "runtime", feature = "chimera")))]
+mod pool;
 mod serialized;
+#[cfg(any(feature = "runtime", feature = "chimera"))]
+mod unwind;
 
+pub(crate) use self::alloc::free;
+#[cfg(feature = "std")]
+pub use self::alloc::AllocatorGuard;
+pub use self::alloc::{set_allocator, Allocator};
+pub use self::arch::{check_platform, PlatformError};
+#[cfg(feature = "std")]
+pub use self::bundle::{Bundle, FeatureLevel};
+pub(crate) use self::database::{streams, traced};
 pub use self::database::{BlockDatabase, Database, DatabaseRef, StreamingDatabase, VectoredDatabase};
 pub use self::error::Error;
-pub use self::mode::{Block, Mode, Streaming, Vectored};
-pub use self::serialized::Serialized;
+pub use self::framed::{FrameError, FramedDatabase};
+#[cfg(feature = "compile")]
+pub use self::info::DbInfo;
+pub use self::mode::{Block, DbMode, Mode, Streaming, Vectored};
+#[cfg(all(feature = "std", any(feature = "runtime", feature = "chimera")))]
+pub use self::pool::{PooledScratch, ScratchPool};
+pub use self::serialized::{align_storage, AlignedBuf, Serialized, DATABASE_ALIGNMENT};
+#[cfg(any(feature = "runtime", feature = "chimera"))]
+pub(crate) use self::unwind::{catch_unwind, discard_unwind, resume_unwind};
 
 #[cfg(test)]
 pub mod tests {
     pub use super::database::tests::*;
 }
 
-use std::ffi::CStr;
+use core::ffi::CStr;
+
+use ::alloc::vec::Vec;
 
 use crate::ffi;
 
@@ -28,6 +56,28 @@ pub fn version() -> semver::Version {
     semver::Version::parse(version_str().to_string_lossy().split(' ').next().unwrap()).unwrap()
 }
 
+/// The API level features supported by the linked Hyperscan library.
+///
+/// The features are detected from the runtime version of the library,
+/// e.g. `["v4", "v5", "v5_2", "v5_4"]` for Hyperscan 5.4.
+///
+/// # Examples
+///
+/// ```rust
+/// let features = hyperscan::linked_version_features();
+///
+/// assert!(features.contains(&"v4"));
+/// ```
+pub fn linked_version_features() -> Vec<&'static str> {
+    let version = version();
+
+    [("v4", 4, 0), ("v5", 5, 0), ("v5_2", 5, 2), ("v5_4", 5, 4)]
+        .iter()
+        .filter(|&&(_, major, minor)| (version.major, version.minor) >= (major, minor))
+        .map(|&(feature, _, _)| feature)
+        .collect()
+}
+
 /// Utility function for identifying this release version.
 ///
 /// Returns a string containing the version number of this release build  and the date of the build.
@@ -1,4 +1,8 @@
-use crate::ffi;
+use alloc::borrow::ToOwned;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{ffi, Error, Result};
 
 /// Compile mode
 pub trait Mode {
@@ -49,3 +53,59 @@ impl Mode for Vectored {
     const ID: u32 = ffi::HS_MODE_VECTORED;
     const NAME: &'static str = "Vectored";
 }
+
+/// The compiled mode of a database.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DbMode {
+    /// Block scan (non-streaming) database.
+    Block,
+    /// Streaming database.
+    Streaming,
+    /// Vectored scanning database.
+    Vectored,
+}
+
+impl DbMode {
+    /// Returns the compiled mode of the database type.
+    pub fn of<T: Mode>() -> Self {
+        if T::is_streaming() {
+            DbMode::Streaming
+        } else if T::is_vectored() {
+            DbMode::Vectored
+        } else {
+            DbMode::Block
+        }
+    }
+
+    /// Id of mode
+    pub fn id(self) -> u32 {
+        match self {
+            DbMode::Block => Block::ID,
+            DbMode::Streaming => Streaming::ID,
+            DbMode::Vectored => Vectored::ID,
+        }
+    }
+}
+
+impl fmt::Display for DbMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DbMode::Block => "BLOCK",
+            DbMode::Streaming => "STREAM",
+            DbMode::Vectored => "VECTORED",
+        })
+    }
+}
+
+impl FromStr for DbMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "BLOCK" => Ok(DbMode::Block),
+            "STREAM" => Ok(DbMode::Streaming),
+            "VECTORED" => Ok(DbMode::Vectored),
+            _ => Err(Error::InvalidInfo(s.to_owned())),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Mutex;
+
+/// A pool of scratch spaces which can be shared between threads.
+///
+/// The scratch spaces are `Send` but not `Sync`, so they can't be shared by the concurrent callers.
+/// `ScratchPool` is `Sync`: every caller checks out an idle scratch space, cloned from the prototype
+/// when all of them are in use, and returns it to the pool when the `PooledScratch` is dropped.
+///
+/// It's shared by the Hyperscan `SyncScratchPool` and the Chimera `ScratchPool`.
+pub struct ScratchPool<S> {
+    prototype: Mutex<S>,
+    idle: Mutex<Vec<S>>,
+}
+
+impl<S> fmt::Debug for ScratchPool<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScratchPool").field("idle", &self.idle()).finish()
+    }
+}
+
+impl<S> From<S> for ScratchPool<S> {
+    fn from(prototype: S) -> Self {
+        ScratchPool::new(prototype)
+    }
+}
+
+impl<S> ScratchPool<S> {
+    /// Construct a pool from the prototype which will be cloned for every concurrent caller.
+    pub fn new(prototype: S) -> Self {
+        ScratchPool {
+            prototype: Mutex::new(prototype),
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the number of the idle scratch spaces in the pool.
+    pub fn idle(&self) -> usize {
+        self.idle.lock().map_or(0, |idle| idle.len())
+    }
+
+    /// Drop the idle scratch spaces, e.g. after a burst of concurrent callers.
+    pub fn shrink(&self) {
+        self.idle.lock().unwrap().clear()
+    }
+
+    /// Consumes the pool, returning the prototype.
+    pub fn into_inner(self) -> S {
+        self.prototype.into_inner().unwrap()
+    }
+}
+
+impl<S: Clone + Deref> ScratchPool<S> {
+    /// Check out an idle scratch space, or clone a new one from the prototype if all of them are in use.
+    pub fn get(&self) -> PooledScratch<'_, S> {
+        let scratch = self.idle.lock().unwrap().pop();
+        let scratch = scratch.unwrap_or_else(|| self.prototype.lock().unwrap().clone());
+
+        PooledScratch {
+            pool: self,
+            scratch: Some(scratch),
+        }
+    }
+
+    /// Calls a closure with a scratch space checked out from the pool.
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&S::Target) -> R,
+    {
+        f(&self.get())
+    }
+}
+
+/// A scratch space checked out from a `ScratchPool`, which is returned to the pool when dropped.
+pub struct PooledScratch<'a, S> {
+    pool: &'a ScratchPool<S>,
+    scratch: Option<S>,
+}
+
+impl<S> fmt::Debug for PooledScratch<'_, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PooledScratch").finish()
+    }
+}
+
+impl<S: Deref> Deref for PooledScratch<'_, S> {
+    type Target = S::Target;
+
+    fn deref(&self) -> &Self::Target {
+        self.scratch.as_ref().unwrap()
+    }
+}
+
+impl<S> Drop for PooledScratch<'_, S> {
+    fn drop(&mut self) {
+        if let Some(scratch) = self.scratch.take() {
+            if let Ok(mut idle) = self.pool.idle.lock() {
+                idle.push(scratch);
+            }
+        }
+    }
+}
@@ -1,13 +1,20 @@
-use std::ffi::CStr;
-use std::fmt;
-use std::mem::MaybeUninit;
-use std::result::Result as StdResult;
-
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ffi::CStr;
+use core::fmt;
+use core::mem::{self, MaybeUninit};
+use core::result::Result as StdResult;
+
+use core::ffi::c_char;
 use foreign_types::{ForeignType, ForeignTypeRef};
-use libc::c_char;
+#[cfg(feature = "std")]
 use malloc_buf::Malloc;
 
-use crate::common::{Database, DatabaseRef};
+use crate::common::{traced, Database, DatabaseRef, Error as HsError};
+#[cfg(feature = "compile")]
+use crate::compile::{CpuFeatures, PlatformRef};
 use crate::error::{AsResult, Error, Result};
 use crate::ffi;
 
@@ -24,6 +31,30 @@ pub trait Serialized {
 
     /// Reconstruct a pattern database from a stream of bytes previously generated by `Database::serialize()`.
     fn deserialize<M>(&self) -> StdResult<Database<M>, Self::Error>;
+
+    /// The CPU features of the platform the serialized database was compiled for.
+    ///
+    /// Only the CPU features are recorded in the serialized database, the tuning parameter is not.
+    #[cfg(feature = "compile")]
+    fn target_platform(&self) -> StdResult<CpuFeatures, Self::Error>;
+
+    /// Validate the serialized database could be deserialized on the target platform,
+    /// e.g. the deployment host, instead of failing with `DbPlatformError` at runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, CpuFeatures, Platform, SerializedDatabase, Tune};
+    /// let patterns = patterns!["foo", "bar"];
+    /// let generic = Platform::new(Tune::Generic, CpuFeatures::empty());
+    /// let buf = BlockDatabase::serialize_for(&patterns, &generic).unwrap();
+    ///
+    /// assert_eq!(buf.target_platform().unwrap(), CpuFeatures::empty());
+    /// buf.validate_for(&generic).unwrap();
+    /// buf.validate_for(&Platform::host().unwrap()).unwrap();
+    /// ```
+    #[cfg(feature = "compile")]
+    fn validate_for(&self, platform: &PlatformRef) -> StdResult<(), Self::Error>;
 }
 
 impl<T: AsRef<[u8]>> Serialized for T {
@@ -47,20 +78,211 @@ impl<T: AsRef<[u8]>> Serialized for T {
             ffi::hs_serialized_database_info(buf.as_ptr() as *const _, buf.len(), p.as_mut_ptr()).and_then(|_| {
                 let p = p.assume_init();
                 let info = CStr::from_ptr(p).to_str()?.to_owned();
-                libc::free(p as *mut _);
+                crate::common::free(p as *mut _);
                 Ok(info)
             })
         }
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "deserialize",
+            level = "debug",
+            skip_all,
+            fields(bytes = self.as_ref().len(), size = tracing::field::Empty)
+        )
+    )]
     fn deserialize<M>(&self) -> Result<Database<M>> {
         let buf = self.as_ref();
         let mut db = MaybeUninit::uninit();
 
         unsafe {
             ffi::hs_deserialize_database(buf.as_ptr() as *const c_char, buf.len(), db.as_mut_ptr())
-                .map(|_| Database::from_ptr(db.assume_init()))
+                .map(|_| traced(Database::from_ptr(db.assume_init())))
+        }
+    }
+
+    #[cfg(feature = "compile")]
+    fn target_platform(&self) -> Result<CpuFeatures> {
+        self.info().map(|info| cpu_features(&info))
+    }
+
+    #[cfg(feature = "compile")]
+    fn validate_for(&self, platform: &PlatformRef) -> Result<()> {
+        if platform.cpu_features().contains(self.target_platform()?) {
+            Ok(())
+        } else {
+            Err(HsError::DbPlatformError.into())
+        }
+    }
+}
+
+/// Parse the CPU features from the database information, e.g. `Version: 5.4.0 Features: AVX2 Mode: BLOCK`,
+/// where only the most advanced feature is named.
+#[cfg(feature = "compile")]
+pub(crate) fn cpu_features(info: &str) -> CpuFeatures {
+    let features = info
+        .split("Features:")
+        .nth(1)
+        .and_then(|s| s.split("Mode:").next())
+        .unwrap_or_default();
+
+    features
+        .split_whitespace()
+        .fold(CpuFeatures::empty(), |features, feature| match feature {
+            #[cfg(hyperscan_x86)]
+            "AVX2" => features | CpuFeatures::AVX2,
+            #[cfg(hyperscan_x86)]
+            "AVX512" => features | CpuFeatures::AVX2 | CpuFeatures::AVX512,
+            #[cfg(all(hyperscan_x86, hyperscan_v5_4))]
+            "AVX512VBMI" => features | CpuFeatures::AVX2 | CpuFeatures::AVX512 | CpuFeatures::AVX512VBMI,
+            _ => features,
+        })
+}
+
+/// The alignment required by a deserialized database.
+pub const DATABASE_ALIGNMENT: usize = mem::align_of::<u64>();
+
+/// Returns the sub-slice of the buffer starting at the first address aligned for a deserialized database,
+/// which is empty if there is no such address in the buffer.
+pub fn align_storage(buf: &mut [u8]) -> &mut [u8] {
+    let offset = buf.as_ptr().align_offset(DATABASE_ALIGNMENT).min(buf.len());
+
+    &mut buf[offset..]
+}
+
+/// A caller-managed storage with the alignment required by a deserialized database.
+///
+/// The database is placed into the storage by `Database::deserialize_into` without any allocation,
+/// and is only valid while the storage is alive.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AlignedBuf {
+    buf: Vec<u64>,
+    len: usize,
+}
+
+impl AlignedBuf {
+    /// Construct a zeroed storage of `len` bytes, aligned to 8 bytes.
+    pub fn new(len: usize) -> Self {
+        AlignedBuf {
+            buf: vec![0; len / mem::size_of::<u64>() + 1],
+            len,
+        }
+    }
+
+    /// Construct a storage large enough for the serialized database.
+    pub fn for_serialized<S: Serialized<Error = Error>>(serialized: &S) -> Result<Self> {
+        serialized.size().map(Self::new)
+    }
+
+    /// The size of the storage in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the storage is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the content of the storage.
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.buf.as_ptr() as *const u8, self.len) }
+    }
+}
+
+impl<T> Database<T> {
+    /// Reconstruct a pattern database from a stream of bytes previously generated by `DatabaseRef::serialize()`
+    /// into the caller-managed storage.
+    ///
+    /// The storage must be large enough for the database, see `Serialized::size()` and `AlignedBuf::for_serialized()`.
+    /// The database is relocatable, so the content of the storage could be copied
+    /// (e.g. into a shared memory segment) and used elsewhere.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, AlignedBuf};
+    /// let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let buf = db.serialize().unwrap();
+    ///
+    /// let mut storage = AlignedBuf::for_serialized(&buf).unwrap();
+    /// let db = BlockDatabase::deserialize_into(&buf, &mut storage).unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let mut matches = vec![];
+    ///
+    /// db.scan("foo test bar", &s, |_, from, to, _| {
+    ///     matches.push(from..to);
+    ///     Matching::Continue
+    /// }).unwrap();
+    ///
+    /// assert_eq!(matches, vec![4..8]);
+    /// ```
+    pub fn deserialize_into<B: AsRef<[u8]>>(bytes: B, storage: &mut AlignedBuf) -> Result<&DatabaseRef<T>> {
+        let bytes = bytes.as_ref();
+
+        if bytes.size()? > storage.len() {
+            return Err(HsError::InsufficientSpace.into());
+        }
+
+        unsafe { Self::deserialize_unchecked_at(bytes, storage.buf.as_mut_ptr() as *mut u8) }
+    }
+
+    /// Reconstruct a pattern database from a stream of bytes previously generated by `DatabaseRef::serialize()`
+    /// into a caller-managed region, e.g. an anonymous or a shared memory mapping.
+    ///
+    /// The serialized bytes could be borrowed from a memory-mapped file, so neither the bytecode nor
+    /// the database is copied onto the heap. The region must be aligned to `DATABASE_ALIGNMENT`
+    /// (see `align_storage()`) and large enough for the database (see `Serialized::size()`).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, align_storage, SerializedDatabase};
+    /// let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+    /// let buf = db.serialize().unwrap();
+    ///
+    /// let mut region = vec![0u8; buf.size().unwrap() + 8];
+    /// let db = BlockDatabase::deserialize_into_slice(&buf, align_storage(&mut region)).unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    ///
+    /// assert!(db.contains("foo test bar", &s).unwrap());
+    /// ```
+    pub fn deserialize_into_slice<B: AsRef<[u8]>>(bytes: B, storage: &mut [u8]) -> Result<&DatabaseRef<T>> {
+        let bytes = bytes.as_ref();
+
+        if storage.as_ptr().align_offset(DATABASE_ALIGNMENT) != 0 {
+            return Err(HsError::BadAlign.into());
+        }
+        if bytes.size()? > storage.len() {
+            return Err(HsError::InsufficientSpace.into());
         }
+
+        unsafe { Self::deserialize_unchecked_at(bytes, storage.as_mut_ptr()) }
+    }
+
+    /// Reconstruct a pattern database from a stream of bytes previously generated by `DatabaseRef::serialize()`
+    /// at a given memory location, without checking the alignment and the size of the region.
+    ///
+    /// # Safety
+    ///
+    /// The region must be aligned to `DATABASE_ALIGNMENT`, writable for at least `Serialized::size()` bytes,
+    /// and must outlive the returned database.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "deserialize",
+            level = "debug",
+            skip_all,
+            fields(bytes = bytes.len(), size = tracing::field::Empty)
+        )
+    )]
+    pub unsafe fn deserialize_unchecked_at<'a>(bytes: &[u8], storage: *mut u8) -> Result<&'a DatabaseRef<T>> {
+        let db = storage as *mut ffi::hs_database_t;
+
+        ffi::hs_deserialize_database_at(bytes.as_ptr() as *const c_char, bytes.len(), db)
+            .map(|_| traced(DatabaseRef::from_ptr(db)))
     }
 }
 
@@ -89,6 +311,15 @@ impl<T> DatabaseRef<T> {
     ///
     /// assert_eq!(matches, vec![6..12]);
     /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(size = self.size().unwrap_or_default())
+        )
+    )]
     pub fn serialize(&self) -> Result<Malloc<[u8]>> {
         let mut ptr = MaybeUninit::uninit();
         let mut size = MaybeUninit::uninit();
@@ -101,10 +332,23 @@ impl<T> DatabaseRef<T> {
 
     /// Reconstruct a pattern database from a stream of bytes
     /// previously generated by `DatabaseRef::serialize()` at a given memory location.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "deserialize",
+            level = "debug",
+            skip_all,
+            fields(bytes = bytes.as_ref().len(), size = tracing::field::Empty)
+        )
+    )]
     pub fn deserialize_at<B: AsRef<[u8]>>(&mut self, bytes: B) -> Result<()> {
         let bytes = bytes.as_ref();
 
-        unsafe { ffi::hs_deserialize_database_at(bytes.as_ptr() as *const c_char, bytes.len(), self.as_ptr()).ok() }
+        unsafe { ffi::hs_deserialize_database_at(bytes.as_ptr() as *const c_char, bytes.len(), self.as_ptr()).ok()? };
+
+        traced(&*self);
+
+        Ok(())
     }
 }
 
@@ -132,6 +376,25 @@ pub mod tests {
         assert!(!data.info().unwrap().is_empty());
     }
 
+    #[cfg(feature = "compile")]
+    #[test]
+    fn test_cpu_features() {
+        assert_eq!(
+            cpu_features("Version: 5.4.0 Features:  Mode: BLOCK"),
+            CpuFeatures::empty()
+        );
+        #[cfg(hyperscan_x86)]
+        assert_eq!(
+            cpu_features("Version: 5.4.0 Features: AVX2 Mode: BLOCK"),
+            CpuFeatures::AVX2
+        );
+        #[cfg(hyperscan_x86)]
+        assert_eq!(
+            cpu_features("Version: 5.4.0 Features: AVX512 Mode: STREAM"),
+            CpuFeatures::AVX2 | CpuFeatures::AVX512
+        );
+    }
+
     #[test]
     fn test_database_deserialize() {
         let db: VectoredDatabase = "test".parse().unwrap();
@@ -157,4 +420,177 @@ pub mod tests {
 
         validate_database(&db);
     }
+
+    #[test]
+    fn test_database_deserialize_into() {
+        let db: BlockDatabase = "test".parse().unwrap();
+
+        let data = db.serialize().unwrap();
+
+        let mut storage = AlignedBuf::new(16);
+
+        assert_eq!(
+            BlockDatabase::deserialize_into(&data, &mut storage).err(),
+            Some(HsError::InsufficientSpace.into())
+        );
+
+        let mut storage = AlignedBuf::for_serialized(&data).unwrap();
+
+        assert_eq!(storage.len(), data.size().unwrap());
+        assert_eq!(storage.as_bytes().as_ptr() as usize % 8, 0);
+
+        let db = BlockDatabase::deserialize_into(&data, &mut storage).unwrap();
+
+        validate_database(db);
+    }
+
+    #[test]
+    fn test_database_deserialize_into_slice() {
+        let db: BlockDatabase = "test".parse().unwrap();
+
+        let data = db.serialize().unwrap();
+        let size = data.size().unwrap();
+
+        let mut region = vec![0u8; size + DATABASE_ALIGNMENT * 2];
+        let storage = align_storage(&mut region);
+
+        assert_eq!(storage.as_ptr() as usize % DATABASE_ALIGNMENT, 0);
+        assert_eq!(
+            BlockDatabase::deserialize_into_slice(&data, &mut storage[1..]).err(),
+            Some(HsError::BadAlign.into())
+        );
+        assert_eq!(
+            BlockDatabase::deserialize_into_slice(&data, &mut storage[..size - 1]).err(),
+            Some(HsError::InsufficientSpace.into())
+        );
+
+        let db = BlockDatabase::deserialize_into_slice(&data, storage).unwrap();
+
+        validate_database(db);
+    }
+
+    #[cfg(feature = "tracing")]
+    type CapturedSpan = (
+        std::thread::ThreadId,
+        &'static tracing::Metadata<'static>,
+        Vec<(&'static str, String)>,
+    );
+
+    #[cfg(feature = "tracing")]
+    static SPANS: std::sync::Mutex<Vec<CapturedSpan>> = std::sync::Mutex::new(Vec::new());
+
+    #[cfg(feature = "tracing")]
+    std::thread_local! {
+        static ENTERED: std::cell::RefCell<Vec<u64>> = const { std::cell::RefCell::new(Vec::new()) };
+    }
+
+    /// A subscriber capturing the names and the fields of the spans.
+    #[cfg(feature = "tracing")]
+    struct Capture;
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for Capture {
+        fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut fields = vec![];
+
+            span.record(&mut |field: &tracing::field::Field, value: &dyn fmt::Debug| {
+                fields.push((field.name(), format!("{:?}", value)))
+            });
+
+            let mut spans = SPANS.lock().unwrap();
+
+            spans.push((std::thread::current().id(), span.metadata(), fields));
+
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+            let mut spans = SPANS.lock().unwrap();
+            let (_, _, fields) = &mut spans[span.into_u64() as usize - 1];
+
+            values.record(&mut |field: &tracing::field::Field, value: &dyn fmt::Debug| {
+                fields.push((field.name(), format!("{:?}", value)))
+            });
+        }
+
+        fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+        fn event(&self, _event: &tracing::Event<'_>) {}
+
+        fn enter(&self, span: &tracing::span::Id) {
+            ENTERED.with(|entered| entered.borrow_mut().push(span.into_u64()))
+        }
+
+        fn exit(&self, _span: &tracing::span::Id) {
+            ENTERED.with(|entered| entered.borrow_mut().pop());
+        }
+
+        fn current_span(&self) -> tracing_core::span::Current {
+            match ENTERED.with(|entered| entered.borrow().last().copied()) {
+                Some(id) => {
+                    let metadata = SPANS.lock().unwrap()[id as usize - 1].1;
+
+                    tracing_core::span::Current::new(tracing::span::Id::from_u64(id), metadata)
+                }
+                None => tracing_core::span::Current::none(),
+            }
+        }
+    }
+
+    /// Returns the spans created by the closure on the current thread.
+    #[cfg(feature = "tracing")]
+    fn capture_spans<F: FnOnce()>(f: F) -> Vec<(&'static str, Vec<(&'static str, String)>)> {
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+
+        INSTALL.call_once(|| tracing::dispatcher::set_global_default(tracing::Dispatch::new(Capture)).unwrap());
+
+        let start = SPANS.lock().unwrap().len();
+
+        f();
+
+        let thread = std::thread::current().id();
+
+        SPANS.lock().unwrap()[start..]
+            .iter()
+            .filter(|(id, _, _)| *id == thread)
+            .map(|(_, metadata, fields)| (metadata.name(), fields.clone()))
+            .collect()
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_spans() {
+        let mut bytes = 0;
+        let spans = capture_spans(|| {
+            let mut db: BlockDatabase = pattern! {"test"}.build().unwrap();
+            let data = db.serialize().unwrap();
+
+            bytes = data.len();
+
+            db.deserialize_at(&data).unwrap();
+        });
+
+        let names = spans.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["compile", "serialize", "deserialize"]);
+
+        let field = |span: usize, name: &str| {
+            spans[span]
+                .1
+                .iter()
+                .find(|(field, _)| *field == name)
+                .map(|(_, value)| value.clone())
+        };
+
+        assert_eq!(field(0, "patterns"), Some("1".to_owned()));
+        assert_eq!(field(0, "mode"), Some("\"Block\"".to_owned()));
+        assert!(field(0, "size").is_some());
+        assert!(field(1, "size").is_some());
+        assert_eq!(field(2, "bytes"), Some(bytes.to_string()));
+        assert_eq!(field(2, "size"), field(0, "size"));
+    }
 }
@@ -0,0 +1,64 @@
+#[cfg(feature = "std")]
+use std::any::Any;
+#[cfg(feature = "std")]
+use std::cell::Cell;
+#[cfg(feature = "std")]
+use std::panic::{self, AssertUnwindSafe};
+
+#[cfg(feature = "std")]
+thread_local! {
+    static PANIC: Cell<Option<Box<dyn Any + Send>>> = Cell::new(None);
+}
+
+/// Invoke the callback in a trampoline called by the C library, without unwinding through the C frames.
+///
+/// A panic of the callback is saved and `terminate` is returned to cease the matching,
+/// the panic will be resumed by `resume_unwind` after the library call returns.
+#[cfg(feature = "std")]
+pub(crate) fn catch_unwind<F, R>(terminate: R, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        PANIC.with(|panic| panic.set(Some(payload)));
+
+        terminate
+    })
+}
+
+/// Resume the panic caught during the library call if any, otherwise returns the result of the call.
+#[cfg(feature = "std")]
+pub(crate) fn resume_unwind<T>(result: T) -> T {
+    if let Some(payload) = PANIC.with(Cell::take) {
+        panic::resume_unwind(payload)
+    }
+
+    result
+}
+
+/// Discard the panic caught by a previous call which hasn't been resumed, before starting a library call.
+#[cfg(feature = "std")]
+pub(crate) fn discard_unwind() {
+    PANIC.with(Cell::take);
+}
+
+/// Invoke the callback in a trampoline called by the C library.
+///
+/// A panic can't be caught without `std`, the `no_std` targets are expected to abort on panic.
+#[cfg(not(feature = "std"))]
+pub(crate) fn catch_unwind<F, R>(_terminate: R, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    f()
+}
+
+/// Returns the result of the library call.
+#[cfg(not(feature = "std"))]
+pub(crate) fn resume_unwind<T>(result: T) -> T {
+    result
+}
+
+/// There is no panic caught without `std`.
+#[cfg(not(feature = "std"))]
+pub(crate) fn discard_unwind() {}
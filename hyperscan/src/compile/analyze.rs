@@ -0,0 +1,367 @@
+use std::convert::TryFrom;
+use std::fmt::{self, Write};
+
+use regex_syntax::{
+    ast::{self, parse::Parser},
+    hir::{self, Hir, HirKind},
+};
+use thiserror::Error;
+
+use crate::{
+    compile::{ExprExt, Flags, Pattern},
+    Error, Result,
+};
+
+/// A construct of the expression which Hyperscan rejects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Construct {
+    /// A back-reference to a capture group, e.g. `(a)\1`.
+    Backreference,
+    /// A look-ahead or look-behind assertion, e.g. `(?=a)` or `(?<!a)`.
+    LookAround,
+    /// A byte which isn't valid UTF-8 in an expression which requires the `UTF8` flag.
+    InvalidUtf8,
+}
+
+impl fmt::Display for Construct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Construct::Backreference => "back-reference",
+            Construct::LookAround => "look-around",
+            Construct::InvalidUtf8 => "invalid UTF-8 byte",
+        })
+    }
+}
+
+/// The expression uses a construct which Hyperscan rejects.
+///
+/// The expression could still be compiled with the `PREFILTER` flag, which approximates the unsupported constructs
+/// and requires the matches to be confirmed (e.g. with `hybrid::HybridDatabase`), or with Chimera.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error(
+    "{construct}{} is not supported, compile it with the `PREFILTER` flag or Chimera",
+    .offset.map(|off| format!(" at {}", off)).unwrap_or_default()
+)]
+pub struct Unsupported {
+    /// The unsupported construct.
+    pub construct: Construct,
+    /// The byte offset of the construct in the expression (if this can be determined).
+    pub offset: Option<usize>,
+}
+
+impl Pattern {
+    /// Detect the constructs of the expression which Hyperscan rejects before compiling it.
+    ///
+    /// The expression is parsed with `regex-syntax`, which understands a different dialect than Hyperscan,
+    /// so the expressions that it can't parse for any other reason are left to the compiler.
+    /// The back-references and look-around assertions are accepted with the `PREFILTER` flag.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, Construct, Error, Unsupported};
+    /// assert!(pattern! {"foo(bar)?"}.analyze().is_ok());
+    /// assert!(pattern! {r"(a)\1"; PREFILTER}.analyze().is_ok());
+    ///
+    /// match pattern! {r"(a)\1"}.analyze() {
+    ///     Err(Error::Unsupported(Unsupported { construct, offset })) => {
+    ///         assert_eq!(construct, Construct::Backreference);
+    ///         assert_eq!(offset, Some(3));
+    ///     }
+    ///     res => panic!("unexpected result: {:?}", res),
+    /// }
+    /// ```
+    pub fn analyze(&self) -> Result<()> {
+        if self.flags.contains(Flags::PREFILTER) {
+            return Ok(());
+        }
+
+        let err = match Parser::new().parse(&self.expression) {
+            Ok(_) => return Ok(()),
+            Err(err) => err,
+        };
+        let offset = err.span().start.offset;
+        let construct = match err.kind() {
+            // `\0` is an octal escape rather than a back-reference
+            ast::ErrorKind::UnsupportedBackreference if !self.expression[offset + 1..].starts_with('0') => {
+                Construct::Backreference
+            }
+            ast::ErrorKind::UnsupportedLookAround => Construct::LookAround,
+            _ => return Ok(()),
+        };
+
+        Err(Unsupported {
+            construct,
+            offset: Some(offset),
+        }
+        .into())
+    }
+}
+
+impl TryFrom<&Hir> for Pattern {
+    type Error = Error;
+
+    /// Construct a pattern from the high-level intermediate representation of `regex-syntax`.
+    ///
+    /// The `UTF8` flag is set if the expression contains any non-ASCII character,
+    /// and the `UCP` flag is also set for a Unicode-aware word boundary.
+    /// The names of the capture groups are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use std::convert::TryFrom;
+    /// # use hyperscan::prelude::*;
+    /// let hir = regex_syntax::Parser::new().parse("a+b?").unwrap();
+    /// let pattern = Pattern::try_from(&hir).unwrap();
+    ///
+    /// assert_eq!(pattern.expression, "(?:a)+(?:b)?");
+    /// assert_eq!(pattern.flags, PatternFlags::empty());
+    /// ```
+    fn try_from(hir: &Hir) -> Result<Self> {
+        let mut w = HirWriter::default();
+
+        w.write_hir(hir).expect("write expression");
+
+        let mut flags = Flags::empty();
+
+        if w.unicode {
+            if w.invalid_utf8 {
+                return Err(Unsupported {
+                    construct: Construct::InvalidUtf8,
+                    offset: None,
+                }
+                .into());
+            }
+
+            flags |= Flags::UTF8;
+        }
+        if w.ucp {
+            flags |= Flags::UTF8 | Flags::UCP;
+        }
+
+        Ok(Pattern {
+            expression: w.expr,
+            flags,
+            id: None,
+            ext: ExprExt::default(),
+            som: None,
+        })
+    }
+}
+
+#[derive(Default)]
+struct HirWriter {
+    expr: String,
+    unicode: bool,
+    ucp: bool,
+    invalid_utf8: bool,
+}
+
+impl HirWriter {
+    fn write_hir(&mut self, hir: &Hir) -> fmt::Result {
+        match hir.kind() {
+            HirKind::Empty => Ok(()),
+            HirKind::Literal(hir::Literal::Unicode(c)) => self.write_char(*c),
+            HirKind::Literal(hir::Literal::Byte(b)) => self.write_byte(*b),
+            HirKind::Class(hir::Class::Unicode(class)) => {
+                if class.iter().next().is_none() {
+                    return self.write_never();
+                }
+
+                self.expr.push('[');
+                for range in class.iter() {
+                    self.write_char(range.start())?;
+                    if range.end() != range.start() {
+                        self.expr.push('-');
+                        self.write_char(range.end())?;
+                    }
+                }
+                self.expr.push(']');
+                Ok(())
+            }
+            HirKind::Class(hir::Class::Bytes(class)) => {
+                if class.iter().next().is_none() {
+                    return self.write_never();
+                }
+
+                self.expr.push('[');
+                for range in class.iter() {
+                    self.write_byte(range.start())?;
+                    if range.end() != range.start() {
+                        self.expr.push('-');
+                        self.write_byte(range.end())?;
+                    }
+                }
+                self.expr.push(']');
+                Ok(())
+            }
+            HirKind::Anchor(anchor) => self.expr.write_str(match anchor {
+                hir::Anchor::StartLine => "(?m:^)",
+                hir::Anchor::EndLine => "(?m:$)",
+                hir::Anchor::StartText => r"\A",
+                hir::Anchor::EndText => r"\z",
+            }),
+            HirKind::WordBoundary(boundary) => {
+                if matches!(boundary, hir::WordBoundary::Unicode | hir::WordBoundary::UnicodeNegate) {
+                    self.ucp = true;
+                }
+
+                self.expr.write_str(if boundary.is_negated() { r"\B" } else { r"\b" })
+            }
+            HirKind::Repetition(rep) => {
+                self.expr.push_str("(?:");
+                self.write_hir(&rep.hir)?;
+                self.expr.push(')');
+
+                match &rep.kind {
+                    hir::RepetitionKind::ZeroOrOne => self.expr.push('?'),
+                    hir::RepetitionKind::ZeroOrMore => self.expr.push('*'),
+                    hir::RepetitionKind::OneOrMore => self.expr.push('+'),
+                    hir::RepetitionKind::Range(hir::RepetitionRange::Exactly(n)) => write!(self.expr, "{{{}}}", n)?,
+                    hir::RepetitionKind::Range(hir::RepetitionRange::AtLeast(n)) => write!(self.expr, "{{{},}}", n)?,
+                    hir::RepetitionKind::Range(hir::RepetitionRange::Bounded(m, n)) => {
+                        write!(self.expr, "{{{},{}}}", m, n)?
+                    }
+                }
+
+                if !rep.greedy {
+                    self.expr.push('?');
+                }
+
+                Ok(())
+            }
+            HirKind::Group(group) => {
+                self.expr.push_str(match group.kind {
+                    hir::GroupKind::NonCapturing => "(?:",
+                    _ => "(",
+                });
+                self.write_hir(&group.hir)?;
+                self.expr.push(')');
+                Ok(())
+            }
+            HirKind::Concat(hirs) => hirs.iter().try_for_each(|hir| self.write_hir(hir)),
+            HirKind::Alternation(hirs) => {
+                self.expr.push_str("(?:");
+                for (i, hir) in hirs.iter().enumerate() {
+                    if i > 0 {
+                        self.expr.push('|');
+                    }
+                    self.write_hir(hir)?;
+                }
+                self.expr.push(')');
+                Ok(())
+            }
+        }
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        if c.is_ascii_alphanumeric() {
+            self.expr.push(c);
+            Ok(())
+        } else if c.is_ascii_graphic() {
+            write!(self.expr, "\\{}", c)
+        } else if c.is_ascii() {
+            write!(self.expr, "\\x{:02X}", c as u32)
+        } else {
+            self.unicode = true;
+
+            write!(self.expr, "\\x{{{:X}}}", c as u32)
+        }
+    }
+
+    fn write_byte(&mut self, b: u8) -> fmt::Result {
+        if b.is_ascii() {
+            self.write_char(char::from(b))
+        } else {
+            self.invalid_utf8 = true;
+
+            write!(self.expr, "\\x{:02X}", b)
+        }
+    }
+
+    /// A class without any range never matches.
+    fn write_never(&mut self) -> fmt::Result {
+        self.expr.write_str(r"[^\x00-\xFF]")
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::convert::TryFrom;
+
+    use crate::{
+        compile::{Construct, Flags, Pattern, Unsupported},
+        Error,
+    };
+
+    fn unsupported(expr: &str) -> Option<Unsupported> {
+        match Pattern::new(expr).unwrap().analyze() {
+            Ok(()) => None,
+            Err(Error::Unsupported(err)) => Some(err),
+            Err(err) => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_analyze() {
+        assert_eq!(unsupported("foo(bar)?"), None);
+        assert_eq!(unsupported(r"a\0b"), None);
+        assert_eq!(unsupported("(?<name>a)"), None);
+        assert_eq!(
+            unsupported(r"(a)\1"),
+            Some(Unsupported {
+                construct: Construct::Backreference,
+                offset: Some(3)
+            })
+        );
+        assert_eq!(
+            unsupported("foo(?<!bar)"),
+            Some(Unsupported {
+                construct: Construct::LookAround,
+                offset: Some(3)
+            })
+        );
+        assert!(Pattern::new("(?=a)").unwrap().prefilter().analyze().is_ok());
+        assert_eq!(
+            unsupported("(?=a)").unwrap().to_string(),
+            "look-around at 0 is not supported, compile it with the `PREFILTER` flag or Chimera"
+        );
+    }
+
+    #[test]
+    fn test_pattern_from_hir() {
+        let pattern = |expr| {
+            let hir = regex_syntax::ParserBuilder::new()
+                .allow_invalid_utf8(true)
+                .build()
+                .parse(expr)
+                .unwrap();
+
+            Pattern::try_from(&hir).map(|pattern| (pattern.expression, pattern.flags))
+        };
+
+        assert_eq!(pattern("").unwrap(), ("".into(), Flags::empty()));
+        assert_eq!(
+            pattern(r"^a.c$").unwrap(),
+            (r"\Aa[\x00-\x09\x0B-\x{10FFFF}]c\z".into(), Flags::UTF8)
+        );
+        assert_eq!(
+            pattern(r"(?-u)(a|bc){2,}?\b").unwrap(),
+            (r"(?:((?:a|bc))){2,}?\b".into(), Flags::empty())
+        );
+        assert_eq!(
+            pattern(r"(?m)^é$").unwrap(),
+            (r"(?m:^)\x{E9}(?m:$)".into(), Flags::UTF8)
+        );
+        assert_eq!(pattern(r"(?-u:\xFF)").unwrap(), (r"(?:\xFF)".into(), Flags::empty()));
+        assert_eq!(pattern(r"\bx").unwrap(), (r"\bx".into(), Flags::UTF8 | Flags::UCP));
+        assert!(matches!(
+            pattern(r"é(?-u:\xFF)"),
+            Err(Error::Unsupported(Unsupported {
+                construct: Construct::InvalidUtf8,
+                ..
+            }))
+        ));
+    }
+}
@@ -3,17 +3,19 @@ use std::mem::MaybeUninit;
 use std::ptr::null_mut;
 use std::str::FromStr;
 
+use core::ffi::c_char;
 use foreign_types::{ForeignType, ForeignTypeRef};
-use libc::c_char;
+use malloc_buf::Malloc;
 
 use crate::{
-    common::{Database, Mode},
-    compile::{AsCompileResult, Flags, Pattern, Patterns, PlatformRef},
+    common::Error as HsError,
+    common::{traced, Database, Mode},
+    compile::{AsCompileResult, Error as CompileError, Flags, Pattern, Patterns, PlatformRef},
     ffi, Error,
 };
 
 #[cfg(feature = "literal")]
-use crate::compile::{Literal, LiteralFlags, Literals};
+use crate::compile::{Literal, LiteralFlags, Literals, MixedPatterns};
 
 /// The regular expression pattern database builder.
 pub trait Builder {
@@ -87,6 +89,15 @@ impl Builder for Pattern {
     /// This is the function call with which an expression is compiled
     /// into a Hyperscan database which can be passed to the runtime functions
     ///
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "compile",
+            level = "debug",
+            skip_all,
+            fields(patterns = 1, mode = T::NAME, size = tracing::field::Empty)
+        )
+    )]
     fn for_platform<T: Mode>(&self, platform: Option<&PlatformRef>) -> Result<Database<T>, Self::Err> {
         let expr = CString::new(self.expression.as_bytes())?;
         let mode = T::ID | if T::is_streaming() { self.som() } else { None }.map_or(0, |som| som as _);
@@ -103,8 +114,8 @@ impl Builder for Pattern {
                 err.as_mut_ptr(),
             )
             .ok_or_else(|| err.assume_init())
-            .map(|_| Database::from_ptr(db.assume_init()))
-            .map_err(|err| err.into())
+            .map(|_| traced(Database::from_ptr(db.assume_init())))
+            .map_err(|err| with_pattern(err, |_| Some(self.clone())))
         }
     }
 }
@@ -120,6 +131,15 @@ impl Builder for Patterns {
     /// Each expression can be labelled with a unique integer
     // which is passed into the match callback to identify the pattern that has matched.
     ///
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "compile",
+            level = "debug",
+            skip_all,
+            fields(patterns = self.len(), mode = T::NAME, size = tracing::field::Empty)
+        )
+    )]
     fn for_platform<T: Mode>(&self, platform: Option<&PlatformRef>) -> Result<Database<T>, Self::Err> {
         let expressions = self
             .iter()
@@ -133,11 +153,7 @@ impl Builder for Patterns {
             .iter()
             .map(|Pattern { flags, .. }| flags.bits() as _)
             .collect::<Vec<_>>();
-        let ids = self
-            .iter()
-            .enumerate()
-            .map(|(i, Pattern { id, .. })| id.unwrap_or(i) as _)
-            .collect::<Vec<_>>();
+        let ids = self.ids().map(|id| id as _).collect::<Vec<_>>();
         let mode = T::ID | if T::is_streaming() { self.som() } else { None }.map_or(0, |som| som as _);
         let mut db = MaybeUninit::uninit();
         let mut err = MaybeUninit::uninit();
@@ -154,8 +170,94 @@ impl Builder for Patterns {
                 err.as_mut_ptr(),
             )
             .ok_or_else(|| err.assume_init())
-            .map(|_| Database::from_ptr(db.assume_init()))
-            .map_err(|err| err.into())
+            .map(|_| traced(Database::from_ptr(db.assume_init())))
+            .map_err(|err| with_pattern(err, |n| self.get(n).cloned()))
+        }
+    }
+}
+
+/// Attach the offending pattern to the compile error if the expression index can be determined.
+fn with_pattern<F>(err: HsError, pattern: F) -> Error
+where
+    F: FnOnce(usize) -> Option<Pattern>,
+{
+    match err {
+        HsError::CompileError(err) => {
+            let pattern = err.expression_index().and_then(pattern);
+
+            HsError::CompileError(err.with_pattern(pattern)).into()
+        }
+        err => err.into(),
+    }
+}
+
+/// A pattern rejected by `Patterns::build_lossy`, with its index and the compile error.
+pub type Rejected = (usize, CompileError);
+
+impl Patterns {
+    /// Compile the valid patterns into a database, and returns the rejected patterns
+    /// with their indexes and compile errors instead of failing the whole compile.
+    ///
+    /// The IDs of the compiled patterns are preserved, a pattern without ID is still identified by its index.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let patterns = patterns!["foo", "a(", "bar"];
+    /// let (db, rejected): (BlockDatabase, _) = patterns.build_lossy().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    ///
+    /// assert_eq!(rejected.len(), 1);
+    /// assert_eq!(rejected[0].0, 1);
+    ///
+    /// let mut ids = vec![];
+    /// db.scan("foo bar", &s, |id, _, _, _| {
+    ///     ids.push(id);
+    ///     Matching::Continue
+    /// })
+    /// .unwrap();
+    ///
+    /// assert_eq!(ids, vec![0, 2]);
+    /// ```
+    pub fn build_lossy<T: Mode>(&self) -> Result<(Database<T>, Vec<Rejected>), Error> {
+        self.for_platform_lossy(None)
+    }
+
+    /// Compile the valid patterns into a database for a target platform,
+    /// and returns the rejected patterns with their indexes and compile errors.
+    ///
+    /// Hyperscan reports one rejected expression at a time, so the remaining patterns are compiled again
+    /// after each rejection. The compile still fails if none of the patterns is valid,
+    /// or the error could not be attributed to an expression.
+    pub fn for_platform_lossy<T: Mode>(
+        &self,
+        platform: Option<&PlatformRef>,
+    ) -> Result<(Database<T>, Vec<Rejected>), Error> {
+        let mut indexes = (0..self.len()).collect::<Vec<_>>();
+        let mut rejected = vec![];
+
+        loop {
+            let patterns = indexes
+                .iter()
+                .map(|&i| Pattern {
+                    id: Some(self[i].id.unwrap_or(i)),
+                    ..self[i].clone()
+                })
+                .collect::<Patterns>();
+
+            match patterns.for_platform(platform) {
+                Ok(db) => {
+                    rejected.sort_by_key(|&(i, _)| i);
+
+                    return Ok((db, rejected));
+                }
+                Err(Error::Hyperscan(HsError::CompileError(err))) => match err.expression_index() {
+                    Some(n) if n < indexes.len() && indexes.len() > 1 => rejected.push((indexes.remove(n), err)),
+                    _ => return Err(HsError::CompileError(err).into()),
+                },
+                Err(err) => return Err(err),
+            }
         }
     }
 }
@@ -170,6 +272,15 @@ impl Builder for Literal {
     /// / This is the function call with which an expression is compiled
     /// into a Hyperscan database which can be passed to the runtime functions
     ///
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "compile",
+            level = "debug",
+            skip_all,
+            fields(patterns = 1, mode = T::NAME, size = tracing::field::Empty)
+        )
+    )]
     fn for_platform<T: Mode>(&self, platform: Option<&PlatformRef>) -> Result<Database<T>, Self::Err> {
         let mode = T::ID | if T::is_streaming() { self.som() } else { None }.map_or(0, |som| som as _);
         let mut db = MaybeUninit::uninit();
@@ -186,7 +297,7 @@ impl Builder for Literal {
                 err.as_mut_ptr(),
             )
             .ok_or_else(|| err.assume_init())
-            .map(|_| Database::from_ptr(db.assume_init()))
+            .map(|_| traced(Database::from_ptr(db.assume_init())))
             .map_err(|err| err.into())
         }
     }
@@ -205,13 +316,9 @@ impl Builder for Literals {
     // which is passed into the match callback to identify the pattern that has matched.
     ///
     fn for_platform<T: Mode>(&self, platform: Option<&PlatformRef>) -> Result<Database<T>, Self::Err> {
-        let ptrs = self
-            .iter()
-            .map(|Literal { expression, .. }| expression.as_ptr() as *const _)
-            .collect::<Vec<_>>();
-        let lens = self
+        let exprs = self
             .iter()
-            .map(|Literal { expression, .. }| expression.len())
+            .map(|Literal { expression, .. }| expression.as_bytes())
             .collect::<Vec<_>>();
         let flags = self
             .iter()
@@ -223,24 +330,66 @@ impl Builder for Literals {
             .map(|(i, Literal { id, .. })| id.unwrap_or(i) as _)
             .collect::<Vec<_>>();
         let mode = T::ID | if T::is_streaming() { self.som() } else { None }.map_or(0, |som| som as _);
-        let mut db = MaybeUninit::uninit();
-        let mut err = MaybeUninit::uninit();
 
-        unsafe {
-            ffi::hs_compile_lit_multi(
-                ptrs.as_ptr(),
-                flags.as_ptr(),
-                ids.as_ptr(),
-                lens.as_ptr(),
-                self.len() as u32,
-                mode,
-                platform.map_or_else(null_mut, ForeignTypeRef::as_ptr),
-                db.as_mut_ptr(),
-                err.as_mut_ptr(),
-            )
-            .ok_or_else(|| err.assume_init())
-            .map(|_| Database::from_ptr(db.assume_init()))
-            .map_err(|err| err.into())
+        compile_literals(&exprs, &flags, &ids, mode, platform)
+    }
+}
+
+/// Compile the pure literals, which may contain any bytes, into a database with `hs_compile_lit_multi`.
+#[cfg(feature = "literal")]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "compile",
+        level = "debug",
+        skip_all,
+        fields(patterns = exprs.len(), mode = T::NAME, size = tracing::field::Empty)
+    )
+)]
+pub(crate) fn compile_literals<T: Mode>(
+    exprs: &[&[u8]],
+    flags: &[u32],
+    ids: &[u32],
+    mode: u32,
+    platform: Option<&PlatformRef>,
+) -> Result<Database<T>, Error> {
+    let ptrs = exprs.iter().map(|expr| expr.as_ptr() as *const _).collect::<Vec<_>>();
+    let lens = exprs.iter().map(|expr| expr.len()).collect::<Vec<_>>();
+    let mut db = MaybeUninit::uninit();
+    let mut err = MaybeUninit::uninit();
+
+    unsafe {
+        ffi::hs_compile_lit_multi(
+            ptrs.as_ptr(),
+            flags.as_ptr(),
+            ids.as_ptr(),
+            lens.as_ptr(),
+            exprs.len() as u32,
+            mode,
+            platform.map_or_else(null_mut, ForeignTypeRef::as_ptr),
+            db.as_mut_ptr(),
+            err.as_mut_ptr(),
+        )
+        .ok_or_else(|| err.assume_init())
+        .map(|_| traced(Database::from_ptr(db.assume_init())))
+        .map_err(|err| err.into())
+    }
+}
+
+#[cfg(feature = "literal")]
+impl Builder for MixedPatterns {
+    type Err = Error;
+
+    ///
+    /// The mixed pure literal and regular expression compiler.
+    ///
+    /// The patterns are compiled as pure literals if the whole set is literal,
+    /// otherwise as regular expressions with the literals escaped.
+    ///
+    fn for_platform<T: Mode>(&self, platform: Option<&PlatformRef>) -> Result<Database<T>, Self::Err> {
+        match self.to_literals() {
+            Some(literals) => literals.for_platform(platform),
+            None => self.to_patterns().for_platform(platform),
         }
     }
 }
@@ -250,12 +399,38 @@ impl<T: Mode> Database<T> {
     ///
     /// This is the function call with which an expression is compiled
     /// into a Hyperscan database which can be passed to the runtime functions.
-    pub fn compile<S: Into<String>>(
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db = BlockDatabase::compile("test", CompileFlags::CASELESS).unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    ///
+    /// assert!(db.contains("a TEST", &s).unwrap());
+    /// ```
+    pub fn compile<S: Into<String>>(expression: S, flags: Flags) -> Result<Database<T>, Error> {
+        Pattern::with_flags(expression, flags)?.build()
+    }
+
+    /// The basic regular expression compiler for a target platform.
+    pub fn compile_for_platform<S: Into<String>>(
         expression: S,
         flags: Flags,
-        platform: Option<&PlatformRef>,
+        platform: &PlatformRef,
     ) -> Result<Database<T>, Error> {
-        Pattern::with_flags(expression, flags)?.for_platform(platform)
+        Pattern::with_flags(expression, flags)?.for_platform(Some(platform))
+    }
+
+    /// Compile the patterns for a target platform, e.g. the deployment host, and serialize the database.
+    ///
+    /// The bytecode produced on a build machine could be validated against the deployment platform
+    /// with `SerializedDatabase::validate_for` before deserializing it.
+    pub fn serialize_for<B>(builder: &B, platform: &PlatformRef) -> Result<Malloc<[u8]>, Error>
+    where
+        B: Builder<Err = Error>,
+    {
+        builder.for_platform::<T>(Some(platform))?.serialize()
     }
 
     /// The pure literal expression compiler.
@@ -263,12 +438,18 @@ impl<T: Mode> Database<T> {
     /// This is the function call with which an pure literal expression is compiled
     /// into a Hyperscan database which can be passed to the runtime functions.
     #[cfg(feature = "literal")]
-    pub fn compile_literal<S: Into<String>>(
+    pub fn compile_literal<S: Into<String>>(expression: S, flags: LiteralFlags) -> Result<Database<T>, Error> {
+        Literal::with_flags(expression, flags)?.build()
+    }
+
+    /// The pure literal expression compiler for a target platform.
+    #[cfg(feature = "literal")]
+    pub fn compile_literal_for_platform<S: Into<String>>(
         expression: S,
         flags: LiteralFlags,
-        platform: Option<&PlatformRef>,
+        platform: &PlatformRef,
     ) -> Result<Database<T>, Error> {
-        Literal::with_flags(expression, flags)?.for_platform(platform)
+        Literal::with_flags(expression, flags)?.for_platform(Some(platform))
     }
 }
 
@@ -282,7 +463,7 @@ impl<T: Mode> FromStr for Database<T> {
 
 #[cfg(test)]
 pub mod tests {
-    use crate::common::tests::validate_database;
+    use crate::common::{tests::validate_database, Block};
     use crate::compile::{Flags, Platform};
     use crate::prelude::*;
 
@@ -290,8 +471,36 @@ pub mod tests {
     fn test_database_compile() {
         let info = Platform::host().unwrap();
 
-        let db = BlockDatabase::compile("test", Flags::empty(), Some(&info)).unwrap();
+        let db = BlockDatabase::compile_for_platform("test", Flags::empty(), &info).unwrap();
+
+        validate_database(&db);
+
+        let db = BlockDatabase::compile("test", Flags::CASELESS).unwrap();
+
+        validate_database(&db);
+    }
+
+    #[test]
+    fn test_patterns_build_lossy() {
+        let patterns = patterns!["foo", "a(", "bar", "[z", "baz"; SOM_LEFTMOST];
+        let (db, rejected): (BlockDatabase, _) = patterns.build_lossy().unwrap();
 
         validate_database(&db);
+
+        assert_eq!(rejected.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![1, 3]);
+        assert!(rejected.iter().all(|(_, err)| !err.message().is_empty()));
+
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan("foo bar baz", &s, |id, from, to, _| {
+            matches.push((id, from..to));
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![(0, 0..3), (2, 4..7), (4, 8..11)]);
+
+        assert!(patterns!["a(", "[z"].build_lossy::<Block>().is_err());
     }
 }
@@ -0,0 +1,341 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    common::{version_str, Database, FramedDatabase, Mode},
+    compile::{Builder, Patterns, Platform, PlatformRef},
+    Error, Result,
+};
+
+/// A storage of the framed serialized databases, keyed by the hash of the pattern set.
+pub trait Cache {
+    /// Returns the framed database stored with the key, `None` if there is no such entry.
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Stores the framed database with the key, replacing the existing entry.
+    fn put(&self, key: &str, frame: &[u8]) -> io::Result<()>;
+}
+
+impl<C: Cache + ?Sized> Cache for &C {
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        (**self).get(key)
+    }
+
+    fn put(&self, key: &str, frame: &[u8]) -> io::Result<()> {
+        (**self).put(key, frame)
+    }
+}
+
+/// A cache storing the framed databases as `<key>.hsdb` files in a directory.
+#[derive(Clone, Debug)]
+pub struct DirCache {
+    dir: PathBuf,
+}
+
+impl DirCache {
+    /// Construct a cache in the directory, which is created on the first store.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        DirCache { dir: dir.into() }
+    }
+
+    /// The directory of the cache.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(key).with_extension("hsdb")
+    }
+}
+
+impl Cache for DirCache {
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(key)) {
+            Ok(frame) => Ok(Some(frame)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn put(&self, key: &str, frame: &[u8]) -> io::Result<()> {
+        let path = self.path(key);
+        let tmp = path.with_extension("hsdb.tmp");
+
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&tmp, frame)?;
+        fs::rename(tmp, path)
+    }
+}
+
+/// A builder which looks up the compiled database in a cache before compiling the patterns.
+///
+/// The cache key is a hash of the patterns (with their IDs, flags and extended parameters),
+/// the mode, the target platform and the version of Hyperscan. The database is stored as a `FramedDatabase`,
+/// a corrupted or incompatible entry is compiled again and replaced.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, CachedBuilder};
+/// let dir = std::env::temp_dir().join("hyperscan-cache-doc");
+/// let builder = CachedBuilder::with_dir(patterns!["foo", "bar"], &dir);
+///
+/// let db: BlockDatabase = builder.build().unwrap(); // compiled and stored
+/// let db: BlockDatabase = builder.build().unwrap(); // loaded from the cache
+/// let s = db.alloc_scratch().unwrap();
+///
+/// assert!(db.contains("foo", &s).unwrap());
+/// # std::fs::remove_dir_all(dir).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct CachedBuilder<C> {
+    patterns: Patterns,
+    cache: C,
+}
+
+impl CachedBuilder<DirCache> {
+    /// Construct a builder which caches the databases in the directory.
+    pub fn with_dir<P: Into<PathBuf>>(patterns: Patterns, dir: P) -> Self {
+        Self::new(patterns, DirCache::new(dir))
+    }
+}
+
+impl<C: Cache> CachedBuilder<C> {
+    /// Construct a builder which caches the databases in the cache.
+    pub fn new(patterns: Patterns, cache: C) -> Self {
+        CachedBuilder { patterns, cache }
+    }
+
+    /// The patterns to compile.
+    pub fn patterns(&self) -> &Patterns {
+        &self.patterns
+    }
+
+    /// The cache of the databases.
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+
+    /// The cache key of the database compiled for the mode and the target platform, or the host if `None`.
+    ///
+    /// The key is the hash of a length-prefixed encoding of the library version, the mode, the platform,
+    /// and the ID, expression, flags and extended parameters of every pattern.
+    pub fn key<T: Mode>(&self, platform: Option<&PlatformRef>) -> Result<String> {
+        let host;
+        let platform: &PlatformRef = match platform {
+            Some(platform) => platform,
+            None => {
+                host = Platform::host()?;
+                &host
+            }
+        };
+
+        let mut key = KeyEncoder::default();
+
+        key.bytes(version_str().to_bytes())
+            .u64(T::ID.into())
+            .str(&format!("{:?}", self.patterns.som()))
+            .str(&format!("{:?}", platform.tune()))
+            .u64(platform.cpu_features().bits())
+            .u64(self.patterns.len() as u64);
+
+        for pattern in self.patterns.iter() {
+            key.str(&format!("{:?}", pattern.id))
+                .str(&pattern.expression)
+                .u64(pattern.flags.bits().into())
+                .str(&pattern.ext.to_string())
+                .str(&format!("{:?}", pattern.som));
+        }
+
+        Ok(format!("{:016x}", fnv1a(&key.0)))
+    }
+}
+
+/// The unambiguous encoding of the cache key, every field is prefixed with its length.
+#[derive(Default)]
+struct KeyEncoder(Vec<u8>);
+
+impl KeyEncoder {
+    fn u64(&mut self, n: u64) -> &mut Self {
+        self.0.extend_from_slice(&n.to_le_bytes());
+        self
+    }
+
+    fn bytes(&mut self, b: &[u8]) -> &mut Self {
+        self.u64(b.len() as u64);
+        self.0.extend_from_slice(b);
+        self
+    }
+
+    fn str(&mut self, s: &str) -> &mut Self {
+        self.bytes(s.as_bytes())
+    }
+}
+
+impl<C: Cache> Builder for CachedBuilder<C> {
+    type Err = Error;
+
+    fn for_platform<T: Mode>(&self, platform: Option<&PlatformRef>) -> Result<Database<T>> {
+        let key = self.key::<T>(platform)?;
+        if let Some(frame) = self.cache.get(&key)? {
+            if let Ok(db) = FramedDatabase::from_bytes(frame)
+                .map_err(Error::from)
+                .and_then(|frame| frame.deserialize())
+            {
+                return Ok(db);
+            }
+        }
+
+        let db = self.patterns.for_platform(platform)?;
+
+        self.cache.put(&key, FramedDatabase::new(&db)?.as_bytes())?;
+
+        Ok(db)
+    }
+}
+
+/// The 64-bit FNV-1a hash, which is stable across the Rust releases unlike the `DefaultHasher`.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::io;
+
+    use crate::prelude::*;
+    use crate::{BlockMode, Cache, CachedBuilder, VectoredMode};
+
+    use super::fnv1a;
+
+    #[derive(Default)]
+    struct MemCache {
+        entries: RefCell<HashMap<String, Vec<u8>>>,
+        puts: RefCell<usize>,
+    }
+
+    impl Cache for MemCache {
+        fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+            Ok(self.entries.borrow().get(key).cloned())
+        }
+
+        fn put(&self, key: &str, frame: &[u8]) -> io::Result<()> {
+            *self.puts.borrow_mut() += 1;
+            self.entries.borrow_mut().insert(key.to_owned(), frame.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_cached_builder() {
+        let cache = MemCache::default();
+        let builder = CachedBuilder::new(patterns!["foo", "bar"], &cache);
+
+        let key = builder.key::<BlockMode>(None).unwrap();
+
+        assert_eq!(key.len(), 16);
+        assert_eq!(builder.key::<BlockMode>(None).unwrap(), key);
+        assert_ne!(builder.key::<VectoredMode>(None).unwrap(), key);
+        assert_ne!(
+            CachedBuilder::new(patterns!["foo", "bar"; CASELESS], &cache)
+                .key::<BlockMode>(None)
+                .unwrap(),
+            key
+        );
+        assert_ne!(
+            CachedBuilder::new(patterns!["foo\nbar"], &cache)
+                .key::<BlockMode>(None)
+                .unwrap(),
+            CachedBuilder::new(patterns!["foo", "bar"], &cache)
+                .key::<BlockMode>(None)
+                .unwrap()
+        );
+        assert_ne!(
+            CachedBuilder::new(patterns!["a/i", "b"], &cache)
+                .key::<BlockMode>(None)
+                .unwrap(),
+            CachedBuilder::new(patterns!["a", "i/b"], &cache)
+                .key::<BlockMode>(None)
+                .unwrap()
+        );
+        #[cfg(hyperscan_x86)]
+        {
+            use crate::{CpuFeatures, Platform, Tune};
+
+            assert_ne!(
+                builder
+                    .key::<BlockMode>(Some(&Platform::new(Tune::Generic, CpuFeatures::empty())))
+                    .unwrap(),
+                builder
+                    .key::<BlockMode>(Some(&Platform::new(Tune::Haswell, CpuFeatures::AVX2)))
+                    .unwrap()
+            );
+        }
+
+        let _: BlockDatabase = builder.build().unwrap();
+        let db: BlockDatabase = builder.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        assert!(db.contains("bar", &s).unwrap());
+        assert_eq!(*cache.puts.borrow(), 1);
+
+        cache.entries.borrow_mut().get_mut(&key).unwrap().truncate(10);
+
+        let _: BlockDatabase = builder.build().unwrap();
+
+        assert_eq!(*cache.puts.borrow(), 2);
+    }
+
+    #[test]
+    fn test_cache_error() {
+        struct Broken;
+
+        impl Cache for Broken {
+            fn get(&self, _key: &str) -> io::Result<Option<Vec<u8>>> {
+                Err(io::ErrorKind::PermissionDenied.into())
+            }
+
+            fn put(&self, _key: &str, _frame: &[u8]) -> io::Result<()> {
+                unreachable!()
+            }
+        }
+
+        let err = match CachedBuilder::new(patterns!["foo"], Broken).build::<BlockMode>() {
+            Err(err) => err,
+            Ok(_) => panic!("expected a cache error"),
+        };
+        let source = std::error::Error::source(&err).unwrap();
+
+        assert_eq!(
+            source.downcast_ref::<io::Error>().unwrap().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test]
+    fn test_dir_cache() {
+        let dir = std::env::temp_dir().join(format!("hyperscan-cache-{}", std::process::id()));
+        let builder = CachedBuilder::with_dir(patterns!["foo"], &dir);
+        let key = builder.key::<BlockMode>(None).unwrap();
+
+        assert_eq!(builder.cache().get(&key).unwrap(), None);
+
+        let _: BlockDatabase = builder.build().unwrap();
+
+        assert!(dir.join(format!("{}.hsdb", key)).exists());
+        assert!(builder.cache().get(&key).unwrap().is_some());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
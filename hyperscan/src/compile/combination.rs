@@ -0,0 +1,266 @@
+use std::collections::BTreeSet;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use crate::{
+    compile::{ExprExt, Flags, Pattern, Patterns},
+    Error, Result,
+};
+
+/// A builder of the logical combinations of the sub-patterns.
+///
+/// The logical expression of a combination references the sub-patterns by their IDs,
+/// with the `!` (NOT), `&` (AND) and `|` (OR) operators and parentheses, e.g. `(101&102)|!103`.
+///
+/// The syntax of the expressions and the referenced IDs are validated when building the patterns,
+/// the sub-patterns referenced by any combination are flagged with `QUIET`,
+/// so only the matches of the combinations are reported.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, Combination};
+/// let patterns = Combination::new()
+///     .pattern(101, pattern! {"foo"})
+///     .pattern(102, pattern! {"bar"})
+///     .pattern(103, pattern! {"baz"})
+///     .combine(1, "101&(102|103)")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(patterns.len(), 4);
+/// assert_eq!(patterns[3].to_string(), "1:/101&(102|103)/C");
+///
+/// let db: BlockDatabase = patterns.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+///
+/// assert_eq!(db.scan_matches("foo bar", &s).unwrap().len(), 1);
+/// assert!(db.scan_matches("foo", &s).unwrap().is_empty());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Combination {
+    patterns: Vec<Pattern>,
+    combinations: Vec<Pattern>,
+}
+
+impl Combination {
+    /// Construct an empty builder.
+    pub fn new() -> Self {
+        Combination::default()
+    }
+
+    /// Add a sub-pattern with the ID.
+    pub fn pattern(&mut self, id: usize, pattern: Pattern) -> &mut Self {
+        self.patterns.push(Pattern {
+            id: Some(id),
+            ..pattern
+        });
+        self
+    }
+
+    /// Add a logical combination of the sub-patterns with the ID.
+    pub fn combine<S: Into<String>>(&mut self, id: usize, expr: S) -> &mut Self {
+        self.combinations.push(Pattern {
+            expression: expr.into(),
+            flags: Flags::COMBINATION,
+            id: Some(id),
+            ext: ExprExt::default(),
+            som: None,
+        });
+        self
+    }
+
+    /// Validate the combinations, and returns the sub-patterns followed by the combinations.
+    pub fn build(&self) -> Result<Patterns> {
+        let ids = self
+            .patterns
+            .iter()
+            .filter_map(|pattern| pattern.id)
+            .collect::<BTreeSet<_>>();
+        let mut referenced = BTreeSet::new();
+
+        for combination in &self.combinations {
+            for id in parse(&combination.expression)? {
+                if !ids.contains(&id) {
+                    return Err(Error::InvalidCombination(format!(
+                        "{}, unknown sub-pattern {}",
+                        combination.expression, id
+                    )));
+                }
+
+                referenced.insert(id);
+            }
+        }
+
+        Ok(self
+            .patterns
+            .iter()
+            .cloned()
+            .map(|pattern| match pattern.id {
+                Some(id) if referenced.contains(&id) => pattern.quiet(),
+                _ => pattern,
+            })
+            .chain(self.combinations.iter().cloned())
+            .collect())
+    }
+}
+
+/// Parse the logical expression, and returns the referenced sub-pattern IDs.
+///
+/// ```text
+/// expr   := term ('|' term)*
+/// term   := factor ('&' factor)*
+/// factor := '!' factor | '(' expr ')' | id
+/// ```
+fn parse(expr: &str) -> Result<Vec<usize>> {
+    let mut parser = Parser {
+        expr,
+        chars: expr.char_indices().peekable(),
+        ids: vec![],
+    };
+
+    parser.expr()?;
+
+    match parser.next() {
+        None => Ok(parser.ids),
+        Some((pos, c)) => Err(parser.error(pos, c)),
+    }
+}
+
+struct Parser<'a> {
+    expr: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    ids: Vec<usize>,
+}
+
+impl Parser<'_> {
+    fn expr(&mut self) -> Result<()> {
+        self.term()?;
+
+        while self.eat('|') {
+            self.term()?;
+        }
+
+        Ok(())
+    }
+
+    fn term(&mut self) -> Result<()> {
+        self.factor()?;
+
+        while self.eat('&') {
+            self.factor()?;
+        }
+
+        Ok(())
+    }
+
+    fn factor(&mut self) -> Result<()> {
+        match self.next() {
+            Some((_, '!')) => self.factor(),
+            Some((_, '(')) => {
+                self.expr()?;
+
+                match self.next() {
+                    Some((_, ')')) => Ok(()),
+                    Some((pos, c)) => Err(self.error(pos, c)),
+                    None => Err(self.unexpected_end()),
+                }
+            }
+            Some((start, c)) if c.is_ascii_digit() => {
+                let mut end = start + 1;
+
+                while let Some(&(pos, c)) = self.chars.peek() {
+                    if !c.is_ascii_digit() {
+                        break;
+                    }
+
+                    end = pos + 1;
+                    self.chars.next();
+                }
+
+                let id = self.expr[start..end].parse()?;
+
+                self.ids.push(id);
+
+                Ok(())
+            }
+            Some((pos, c)) => Err(self.error(pos, c)),
+            None => Err(self.unexpected_end()),
+        }
+    }
+
+    fn eat(&mut self, op: char) -> bool {
+        self.skip_whitespace();
+
+        if matches!(self.chars.peek(), Some(&(_, c)) if c == op) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        self.skip_whitespace();
+        self.chars.next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.chars.peek(), Some(&(_, c)) if c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn error(&self, pos: usize, c: char) -> Error {
+        Error::InvalidCombination(format!("{}, unexpected `{}` at {}", self.expr, c, pos))
+    }
+
+    fn unexpected_end(&self) -> Error {
+        Error::InvalidCombination(format!("{}, unexpected end", self.expr))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{Combination, Error};
+
+    use super::parse;
+
+    #[test]
+    fn test_parse_combination() {
+        assert_eq!(parse("(101&102)|!103").unwrap(), vec![101, 102, 103]);
+        assert_eq!(parse(" 1 & ( 2 | !!3 ) ").unwrap(), vec![1, 2, 3]);
+
+        for expr in &["", "1&", "(1|2", "1 2", "1&&2", "a|1", "1)"] {
+            assert!(
+                matches!(parse(expr), Err(Error::InvalidCombination(_))),
+                "{} should be invalid",
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn test_combination() {
+        let patterns = Combination::new()
+            .pattern(1, pattern! {"foo"; CASELESS})
+            .pattern(2, pattern! {"bar"})
+            .pattern(3, pattern! {"baz"})
+            .combine(10, "1 & 2")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            patterns.iter().map(|pattern| pattern.to_string()).collect::<Vec<_>>(),
+            vec!["1:/foo/iQ", "2:/bar/Q", "3:/baz/", "10:/1 & 2/C"]
+        );
+
+        assert!(matches!(
+            Combination::new()
+                .pattern(1, pattern! {"foo"})
+                .combine(10, "1|2")
+                .build(),
+            Err(Error::InvalidCombination(_))
+        ));
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt;
 
 use foreign_types::{foreign_type, ForeignType};
 
-use crate::{common::Error as HsError, error::AsResult, ffi};
+use crate::{common::Error as HsError, compile::Pattern, error::AsResult, ffi};
 
 pub trait AsCompileResult: Sized {
     type Output;
@@ -29,7 +29,10 @@ impl AsCompileResult for ffi::hs_error_t {
         if self == ffi::HS_SUCCESS as ffi::hs_error_t {
             Ok(())
         } else if self == ffi::HS_COMPILER_ERROR {
-            Err(HsError::CompileError(unsafe { Error::from_ptr(err()) }))
+            Err(HsError::CompileError(Error {
+                raw: unsafe { RawError::from_ptr(err()) },
+                pattern: None,
+            }))
         } else {
             Err(HsError::from(self))
         }
@@ -37,8 +40,7 @@ impl AsCompileResult for ffi::hs_error_t {
 }
 
 foreign_type! {
-    /// Providing details of the compile error condition.
-    pub unsafe type Error: Send + Sync {
+    unsafe type RawError: Send + Sync {
         type CType = ffi::hs_compile_error_t;
 
         fn drop = free_compile_error;
@@ -49,9 +51,20 @@ unsafe fn free_compile_error(err: *mut ffi::hs_compile_error_t) {
     ffi::hs_free_compile_error(err).expect("free compile error");
 }
 
+/// Providing details of the compile error condition.
+pub struct Error {
+    raw: RawError,
+    pattern: Option<Pattern>,
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(self.message())
+        f.write_str(self.message())?;
+
+        match (self.expression_index(), &self.pattern) {
+            (Some(n), Some(pattern)) => write!(f, " in expression #{}: {}", n, pattern),
+            _ => Ok(()),
+        }
     }
 }
 
@@ -59,14 +72,15 @@ impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Error")
             .field("message", &self.message())
-            .field("expression", &self.expression())
+            .field("expression", &self.expression_index())
+            .field("pattern", &self.pattern)
             .finish()
     }
 }
 
 impl PartialEq for Error {
     fn eq(&self, other: &Self) -> bool {
-        self.as_ptr() == other.as_ptr()
+        self.raw.as_ptr() == other.raw.as_ptr()
     }
 }
 
@@ -74,7 +88,7 @@ impl Eq for Error {}
 
 impl Error {
     unsafe fn as_ref(&self) -> &ffi::hs_compile_error_t {
-        self.as_ptr().as_ref().unwrap()
+        self.raw.as_ptr().as_ref().unwrap()
     }
 
     /// A human-readable error message describing the error.
@@ -82,8 +96,14 @@ impl Error {
         unsafe { CStr::from_ptr(self.as_ref().message).to_str().unwrap() }
     }
 
-    /// The zero-based number of the expression that caused the error (if this can be determined).
+    #[doc(hidden)]
+    #[deprecated = "use `expression_index` instead"]
     pub fn expression(&self) -> Option<usize> {
+        self.expression_index()
+    }
+
+    /// The zero-based index of the expression that caused the error (if this can be determined).
+    pub fn expression_index(&self) -> Option<usize> {
         let n = unsafe { self.as_ref().expression };
 
         if n < 0 {
@@ -92,4 +112,94 @@ impl Error {
             Some(n as usize)
         }
     }
+
+    /// The zero-based byte offset in the expression at which the error was detected (if this can be determined).
+    ///
+    /// Hyperscan reports the location of a parse error as `at index N` in the error message.
+    pub fn position(&self) -> Option<usize> {
+        const AT_INDEX: &str = "at index ";
+
+        let msg = self.message();
+        let off = msg.rfind(AT_INDEX)? + AT_INDEX.len();
+        let len = msg[off..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(msg.len() - off);
+
+        msg[off..off + len].parse().ok()
+    }
+
+    /// The part of the expression starting at the position of the error (if this can be determined).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, BlockMode, Error, HsError};
+    /// let expr = "foo(bar";
+    ///
+    /// match Pattern::new(expr).unwrap().build::<BlockMode>() {
+    ///     Err(Error::Hyperscan(HsError::CompileError(err))) => {
+    ///         assert_eq!(err.position(), Some(3));
+    ///         assert_eq!(err.fragment(expr), Some("(bar"));
+    ///     }
+    ///     res => panic!("unexpected result: {:?}", res.map(|_| ())),
+    /// }
+    /// ```
+    pub fn fragment<'a>(&self, expression: &'a str) -> Option<&'a str> {
+        self.position().and_then(|pos| expression.get(pos..))
+    }
+
+    /// The pattern that caused the error (if this can be determined).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, BlockMode, Error, HsError};
+    /// match patterns!["foo", "bar(", "baz"].build::<BlockMode>() {
+    ///     Err(Error::Hyperscan(HsError::CompileError(err))) => {
+    ///         assert_eq!(err.expression_index(), Some(1));
+    ///         assert_eq!(err.pattern().unwrap().expression, "bar(");
+    ///     }
+    ///     res => panic!("unexpected result: {:?}", res.map(|_| ())),
+    /// }
+    /// ```
+    pub fn pattern(&self) -> Option<&Pattern> {
+        self.pattern.as_ref()
+    }
+
+    /// Attach the pattern that caused the error.
+    pub(crate) fn with_pattern(self, pattern: Option<Pattern>) -> Self {
+        Error { pattern, ..self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::{BlockMode, Error, HsError};
+
+    #[test]
+    fn test_compile_error_position() {
+        let expr = "a(b";
+
+        match Pattern::new(expr).unwrap().build::<BlockMode>() {
+            Err(Error::Hyperscan(HsError::CompileError(err))) => {
+                assert_eq!(err.expression_index(), Some(0));
+                assert_eq!(err.pattern().unwrap().expression, expr);
+                assert_eq!(err.position(), Some(1));
+                assert_eq!(err.fragment(expr), Some("(b"));
+            }
+            res => panic!("unexpected result: {:?}", res.map(|_| ())),
+        }
+
+        match "test\na*".parse::<Patterns>().unwrap().build::<BlockMode>() {
+            Err(Error::Hyperscan(HsError::CompileError(err))) => {
+                assert_eq!(err.expression_index(), Some(1));
+                assert_eq!(err.pattern().unwrap().expression, "a*");
+                assert!(err.to_string().ends_with(" in expression #1: a*"));
+                assert_eq!(err.position(), None);
+                assert_eq!(err.fragment("a*"), None);
+            }
+            res => panic!("unexpected result: {:?}", res.map(|_| ())),
+        }
+    }
 }
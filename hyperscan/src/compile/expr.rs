@@ -1,17 +1,19 @@
-use std::ffi::CString;
-use std::fmt::{self, Write};
+use std::ffi::{CStr, CString};
+use std::fmt;
 use std::mem::MaybeUninit;
 use std::ops::Deref;
 use std::str::FromStr;
 
 use bitflags::bitflags;
+use core::ffi::c_char;
 use derive_more::{From, Into};
 use foreign_types::{foreign_type, ForeignType, ForeignTypeRef};
-use libc::c_char;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
 use crate::{
-    compile::{AsCompileResult, Pattern},
+    compile::{AsCompileResult, Flags as PatternFlags, Pattern, Patterns},
     ffi, Result,
 };
 
@@ -68,30 +70,39 @@ impl fmt::Debug for ExprExt {
     }
 }
 
-impl fmt::Display for ExprExt {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl ExprExt {
+    /// Write the extended parameters to the writer without any intermediate allocation.
+    pub fn write_to<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
         let flags = self.flags();
-        let mut fields = vec![];
-
-        if flags.contains(Flags::MIN_OFFSET) {
-            fields.push(format!("min_offset={}", self.0.min_offset));
-        }
-        if flags.contains(Flags::MAX_OFFSET) {
-            fields.push(format!("max_offset={}", self.0.max_offset));
-        }
-        if flags.contains(Flags::MIN_LENGTH) {
-            fields.push(format!("min_length={}", self.0.min_length));
-        }
-        if flags.contains(Flags::EDIT_DISTANCE) {
-            fields.push(format!("edit_distance={}", self.0.edit_distance));
-        }
-        if flags.contains(Flags::HAMMING_DISTANCE) {
-            fields.push(format!("hamming_distance={}", self.0.hamming_distance));
+        let fields = [
+            (Flags::MIN_OFFSET, "min_offset", self.0.min_offset),
+            (Flags::MAX_OFFSET, "max_offset", self.0.max_offset),
+            (Flags::MIN_LENGTH, "min_length", self.0.min_length),
+            (Flags::EDIT_DISTANCE, "edit_distance", u64::from(self.0.edit_distance)),
+            (
+                Flags::HAMMING_DISTANCE,
+                "hamming_distance",
+                u64::from(self.0.hamming_distance),
+            ),
+        ];
+
+        w.write_char('{')?;
+
+        for (i, (_, name, value)) in fields.iter().filter(|(flag, _, _)| flags.contains(*flag)).enumerate() {
+            if i > 0 {
+                w.write_char(',')?;
+            }
+
+            write!(w, "{}={}", name, value)?;
         }
 
-        f.write_char('{')?;
-        f.write_str(&fields.join(","))?;
-        f.write_char('}')
+        w.write_char('}')
+    }
+}
+
+impl fmt::Display for ExprExt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_to(f)
     }
 }
 
@@ -135,6 +146,63 @@ impl FromStr for ExprExt {
     }
 }
 
+/// The extended parameters are serialized as a map of the parameters which are set, e.g. `{"min_offset":10}`.
+#[cfg(feature = "serde")]
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ExprExtDef {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    edit_distance: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hamming_distance: Option<u32>,
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for ExprExt {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        ExprExtDef {
+            min_offset: self.min_offset(),
+            max_offset: self.max_offset(),
+            min_length: self.min_length(),
+            edit_distance: self.edit_distance(),
+            hamming_distance: self.hamming_distance(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for ExprExt {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let def = ExprExtDef::deserialize(deserializer)?;
+        let mut ext = ExprExt::default();
+
+        if let Some(n) = def.min_offset {
+            ext.set_min_offset(n);
+        }
+        if let Some(n) = def.max_offset {
+            ext.set_max_offset(n);
+        }
+        if let Some(n) = def.min_length {
+            ext.set_min_length(n);
+        }
+        if let Some(n) = def.edit_distance {
+            ext.set_edit_distance(n);
+        }
+        if let Some(n) = def.hamming_distance {
+            ext.set_hamming_distance(n);
+        }
+
+        Ok(ext)
+    }
+}
+
 impl ExprExt {
     fn flags(&self) -> Flags {
         Flags::from_bits_truncate(self.0.flags)
@@ -254,7 +322,7 @@ foreign_type! {
 }
 
 unsafe fn drop_expr_info(info: *mut ffi::hs_expr_info) {
-    libc::free(info as *mut _);
+    crate::common::free(info as *mut _);
 }
 
 impl Deref for ExprInfoRef {
@@ -314,22 +382,93 @@ impl Pattern {
     ///
     pub fn info(&self) -> Result<ExprInfo> {
         let expr = CString::new(self.expression.as_str())?;
-        let mut info = MaybeUninit::uninit();
-        let mut err = MaybeUninit::uninit();
-
-        let info = unsafe {
-            ffi::hs_expression_ext_info(
-                expr.as_ptr() as *const c_char,
-                self.flags.bits(),
-                &self.ext.0 as *const _,
-                info.as_mut_ptr(),
-                err.as_mut_ptr(),
-            )
-            .ok_or_else(|| err.assume_init())?;
-
-            ExprInfo::from_ptr(info.assume_init())
-        };
-
-        Ok(info)
+
+        expr_info(&expr, self.flags, &self.ext)
+    }
+}
+
+fn expr_info(expr: &CStr, flags: PatternFlags, ext: &ExprExt) -> Result<ExprInfo> {
+    let mut info = MaybeUninit::uninit();
+    let mut err = MaybeUninit::uninit();
+
+    let info = unsafe {
+        ffi::hs_expression_ext_info(
+            expr.as_ptr() as *const c_char,
+            flags.bits(),
+            &ext.0 as *const _,
+            info.as_mut_ptr(),
+            err.as_mut_ptr(),
+        )
+        .ok_or_else(|| err.assume_init())?;
+
+        ExprInfo::from_ptr(info.assume_init())
+    };
+
+    Ok(info)
+}
+
+impl Patterns {
+    /// Utility function providing information about the regular expressions, in the order of the patterns.
+    ///
+    /// The buffer of the C string is reused across the patterns.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let patterns = patterns!["foo", r"\d{2,4}", "bar$"];
+    /// let infos = patterns.infos().unwrap();
+    ///
+    /// assert_eq!(infos.iter().map(|info| info.max_width()).collect::<Vec<_>>(), vec![3, 4, 3]);
+    /// assert_eq!(patterns.max_width().unwrap(), Some(4));
+    /// assert!(patterns.any_matches_at_eod().unwrap());
+    ///
+    /// assert_eq!(patterns!["foo", "ba+r"].max_width().unwrap(), None);
+    /// ```
+    pub fn infos(&self) -> Result<Vec<ExprInfo>> {
+        let mut infos = Vec::with_capacity(self.len());
+
+        self.for_each_info(|info| infos.push(info))?;
+
+        Ok(infos)
+    }
+
+    /// The maximum length in bytes of a match for any of the patterns, `None` if any of them is unbounded.
+    pub fn max_width(&self) -> Result<Option<usize>> {
+        let mut max_width = Some(0);
+
+        self.for_each_info(|info| {
+            max_width = max_width
+                .filter(|_| info.max_width != u32::MAX)
+                .map(|width| width.max(info.max_width()))
+        })?;
+
+        Ok(max_width)
+    }
+
+    /// Whether any of the patterns can produce matches at end of data (EOD).
+    pub fn any_matches_at_eod(&self) -> Result<bool> {
+        let mut matches_at_eod = false;
+
+        self.for_each_info(|info| matches_at_eod |= info.matches_at_eod())?;
+
+        Ok(matches_at_eod)
+    }
+
+    fn for_each_info<F: FnMut(ExprInfo)>(&self, mut f: F) -> Result<()> {
+        let mut buf = Vec::new();
+
+        for pattern in self.iter() {
+            buf.extend_from_slice(pattern.expression.as_bytes());
+
+            let expr = CString::new(buf)?;
+
+            f(expr_info(&expr, pattern.flags, &pattern.ext)?);
+
+            buf = expr.into_bytes();
+            buf.clear();
+        }
+
+        Ok(())
     }
 }
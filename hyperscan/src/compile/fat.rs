@@ -0,0 +1,184 @@
+use std::ops::Deref;
+
+use crate::{
+    common::{Bundle, Database, DatabaseRef, FeatureLevel, Mode},
+    compile::{Builder, CpuFeatures, Platform, Tune},
+    Error, Result,
+};
+
+#[cfg(hyperscan_x86)]
+const LEVELS: &[FeatureLevel] = &[FeatureLevel::Generic, FeatureLevel::Avx2, FeatureLevel::Avx512];
+#[cfg(not(hyperscan_x86))]
+const LEVELS: &[FeatureLevel] = &[FeatureLevel::Generic];
+
+impl FeatureLevel {
+    /// The CPU features which a database of the feature level is compiled with.
+    ///
+    /// Without the x86 CPU features (e.g. Vectorscan on aarch64), all the levels are compiled without any of them.
+    pub fn cpu_features(self) -> CpuFeatures {
+        match self {
+            #[cfg(hyperscan_x86)]
+            FeatureLevel::Avx2 => CpuFeatures::AVX2,
+            #[cfg(hyperscan_x86)]
+            FeatureLevel::Avx512 => CpuFeatures::AVX2 | CpuFeatures::AVX512,
+            _ => CpuFeatures::empty(),
+        }
+    }
+
+    /// The highest feature level supported by the CPU features.
+    #[cfg(not(hyperscan_x86))]
+    pub fn from_cpu_features(_cpu_features: CpuFeatures) -> Self {
+        FeatureLevel::Generic
+    }
+
+    /// The highest feature level supported by the CPU features.
+    #[cfg(hyperscan_x86)]
+    pub fn from_cpu_features(cpu_features: CpuFeatures) -> Self {
+        if cpu_features.contains(CpuFeatures::AVX512) {
+            FeatureLevel::Avx512
+        } else if cpu_features.contains(CpuFeatures::AVX2) {
+            FeatureLevel::Avx2
+        } else {
+            FeatureLevel::Generic
+        }
+    }
+}
+
+/// A database compiled for several CPU feature levels, which picks the best variant at runtime.
+///
+/// The variants are kept serialized in a `Bundle`, so they could be shipped together as a single file,
+/// and the one of the highest feature level supported by `CpuFeatures::detect()` is deserialized on load.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// # use hyperscan::{CpuFeatures, FatDatabase, FeatureLevel};
+/// let db: FatDatabase<BlockMode> = FatDatabase::build(&pattern! {"test"}).unwrap();
+///
+/// assert_eq!(db.bundle().levels().count(), 3);
+/// assert_eq!(db.level(), FeatureLevel::from_cpu_features(CpuFeatures::detect()));
+///
+/// let s = db.alloc_scratch().unwrap();
+/// let mut matches = vec![];
+///
+/// db.scan("foo test bar", &s, |_, from, to, _| {
+///     matches.push(from..to);
+///     Matching::Continue
+/// })
+/// .unwrap();
+///
+/// assert_eq!(matches, vec![0..8]);
+///
+/// let db: FatDatabase<BlockMode> = FatDatabase::from_bundle(db.into_bundle()).unwrap();
+/// ```
+pub struct FatDatabase<T> {
+    level: FeatureLevel,
+    db: Database<T>,
+    bundle: Bundle,
+}
+
+impl<T> Deref for FatDatabase<T> {
+    type Target = DatabaseRef<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.db
+    }
+}
+
+impl<T: Mode> FatDatabase<T> {
+    /// Compile the variants for the generic, AVX2 and AVX512 feature levels, and pick the best one for the host.
+    pub fn build<B>(builder: &B) -> Result<Self>
+    where
+        B: Builder,
+        Error: From<B::Err>,
+    {
+        Self::build_for(builder, LEVELS.iter().copied())
+    }
+
+    /// Compile the variants for the feature levels, and pick the best one for the host.
+    pub fn build_for<B, I>(builder: &B, levels: I) -> Result<Self>
+    where
+        B: Builder,
+        Error: From<B::Err>,
+        I: IntoIterator<Item = FeatureLevel>,
+    {
+        let mut bundle = Bundle::new();
+
+        for level in levels {
+            let platform = Platform::new(Tune::Generic, level.cpu_features());
+            let db: Database<T> = builder.for_platform(Some(&platform))?;
+
+            bundle.insert(level, db.serialize()?.to_vec());
+        }
+
+        Self::from_bundle(bundle)
+    }
+
+    /// Deserialize the variant of the highest feature level supported by the host with `Bundle::best`.
+    ///
+    /// Returns `DbPlatformError` if the bundle has no variant which could run on the host.
+    pub fn from_bundle(bundle: Bundle) -> Result<Self> {
+        let (level, db) = bundle.best()?;
+
+        Ok(FatDatabase { level, db, bundle })
+    }
+}
+
+impl<T> FatDatabase<T> {
+    /// The feature level of the variant picked for the host.
+    pub fn level(&self) -> FeatureLevel {
+        self.level
+    }
+
+    /// Returns the bundle of the serialized variants.
+    pub fn bundle(&self) -> &Bundle {
+        &self.bundle
+    }
+
+    /// Consumes the database, returning the bundle of the serialized variants.
+    pub fn into_bundle(self) -> Bundle {
+        self.bundle
+    }
+
+    /// Consumes the database, returning the variant picked for the host.
+    pub fn into_inner(self) -> Database<T> {
+        self.db
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::common::tests::*;
+    use crate::{BlockMode, Bundle, CpuFeatures, Error, FatDatabase, FeatureLevel, HsError};
+
+    #[test]
+    fn test_feature_level() {
+        for &level in &[FeatureLevel::Generic, FeatureLevel::Avx2, FeatureLevel::Avx512] {
+            assert_eq!(FeatureLevel::from_cpu_features(level.cpu_features()), level);
+        }
+    }
+
+    #[test]
+    fn test_fat_database() {
+        let db: FatDatabase<BlockMode> = FatDatabase::build(&pattern! {"test"}).unwrap();
+
+        assert_eq!(
+            db.bundle().levels().collect::<Vec<_>>(),
+            vec![FeatureLevel::Generic, FeatureLevel::Avx2, FeatureLevel::Avx512]
+        );
+        assert!(db.level() <= FeatureLevel::from_cpu_features(CpuFeatures::detect()));
+        validate_database(&db);
+
+        let db: FatDatabase<BlockMode> =
+            FatDatabase::build_for(&pattern! {"test"}, vec![FeatureLevel::Generic]).unwrap();
+
+        assert_eq!(db.level(), FeatureLevel::Generic);
+        validate_database(&db.into_inner());
+
+        assert_eq!(
+            FatDatabase::<BlockMode>::from_bundle(Bundle::new()).err(),
+            Some(Error::Hyperscan(HsError::DbPlatformError))
+        );
+    }
+}
@@ -5,10 +5,16 @@ use std::str::FromStr;
 use bitflags::bitflags;
 use derive_more::{Deref, DerefMut, From, Index, IndexMut, Into, IntoIterator};
 
-use crate::{compile::SomHorizon, ffi, Error, Result};
+use crate::{
+    compile::{Flags as PatternFlags, Pattern, Patterns, SomHorizon},
+    ffi, Error, Result,
+};
 
 bitflags! {
     /// Literal flags
+    ///
+    /// The flags could be parsed from the `i`, `m`, `H` and `L` letters of the pattern flags,
+    /// the other letters of the pattern flags return `Error::UnsupportedFlag`.
     #[derive(Default)]
     pub struct Flags: u32 {
         /// Matching will be performed case-insensitively.
@@ -33,7 +39,8 @@ impl FromStr for Flags {
                 'i' => flags |= Flags::CASELESS,
                 'm' => flags |= Flags::MULTILINE,
                 'H' => flags |= Flags::SINGLEMATCH,
-                _ => return Err(Error::InvalidFlag(c)),
+                'L' => flags |= Flags::SOM_LEFTMOST,
+                _ => return Err(Error::unsupported_flag(c, "not supported by the pure literal")),
             }
         }
 
@@ -52,6 +59,9 @@ impl fmt::Display for Flags {
         if self.contains(Flags::SINGLEMATCH) {
             write!(f, "H")?
         }
+        if self.contains(Flags::SOM_LEFTMOST) {
+            write!(f, "L")?
+        }
         Ok(())
     }
 }
@@ -220,6 +230,132 @@ impl Literals {
     }
 }
 
+impl From<Literal> for Pattern {
+    /// Escape the pure literal into a regular expression matching the same bytes.
+    fn from(literal: Literal) -> Pattern {
+        let mut expression = String::with_capacity(literal.expression.len() * 4);
+
+        for &b in literal.expression.as_bytes() {
+            if b.is_ascii_alphanumeric() {
+                expression.push(b as char);
+            } else {
+                expression.push_str(&format!("\\x{:02x}", b));
+            }
+        }
+
+        Pattern {
+            expression,
+            flags: PatternFlags::from_bits_truncate(literal.flags.bits()),
+            id: literal.id,
+            ext: Default::default(),
+            som: literal.som,
+        }
+    }
+}
+
+/// A pattern of the mixed pattern set, either a pure literal or a regular expression.
+#[derive(Clone, Debug, PartialEq, Eq, From)]
+pub enum MixedPattern {
+    /// The pure literal expression.
+    Literal(Literal),
+    /// The regular expression.
+    Regex(Pattern),
+}
+
+impl From<MixedPattern> for Pattern {
+    fn from(pattern: MixedPattern) -> Pattern {
+        match pattern {
+            MixedPattern::Literal(literal) => literal.into(),
+            MixedPattern::Regex(pattern) => pattern,
+        }
+    }
+}
+
+/// Vec of the pure literal and regular expression patterns, which are compiled into one database.
+///
+/// The patterns are compiled with `hs_compile_lit_multi` when the whole set is literal,
+/// otherwise the literals are escaped into regular expressions and compiled with `hs_compile_multi`.
+///
+/// A pattern without ID is identified by its index in the set.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// let patterns = MixedPatterns(vec![literal! {"a.b"}.into(), pattern! {"[0-9]+"}.into()]);
+///
+/// assert!(!patterns.is_literal());
+///
+/// let db: BlockDatabase = patterns.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut matches = vec![];
+///
+/// db.scan("axb a.b 42", &s, |id, _, to, _| {
+///     matches.push((id, to));
+///     Matching::Continue
+/// })
+/// .unwrap();
+///
+/// assert_eq!(matches, vec![(0, 7), (1, 9), (1, 10)]);
+/// ```
+#[repr(transparent)]
+#[derive(Clone, Debug, Default, Deref, DerefMut, From, Index, IndexMut, Into, IntoIterator)]
+#[deref(forward)]
+#[deref_mut(forward)]
+pub struct MixedPatterns(pub Vec<MixedPattern>);
+
+impl FromIterator<MixedPattern> for MixedPatterns {
+    fn from_iter<T: IntoIterator<Item = MixedPattern>>(iter: T) -> Self {
+        Self(Vec::from_iter(iter))
+    }
+}
+
+impl From<Literals> for MixedPatterns {
+    fn from(literals: Literals) -> Self {
+        literals.0.into_iter().map(MixedPattern::Literal).collect()
+    }
+}
+
+impl From<Patterns> for MixedPatterns {
+    fn from(patterns: Patterns) -> Self {
+        patterns.0.into_iter().map(MixedPattern::Regex).collect()
+    }
+}
+
+impl MixedPatterns {
+    /// Returns `true` if all the patterns are pure literals, which will be compiled with `hs_compile_lit_multi`.
+    pub fn is_literal(&self) -> bool {
+        self.iter().all(|pattern| matches!(pattern, MixedPattern::Literal(_)))
+    }
+
+    /// Returns the pure literals if all the patterns are pure literals.
+    pub fn to_literals(&self) -> Option<Literals> {
+        self.iter()
+            .map(|pattern| match pattern {
+                MixedPattern::Literal(literal) => Some(literal.clone()),
+                MixedPattern::Regex(_) => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(Literals)
+    }
+
+    /// Returns the regular expressions with the literals escaped, and the IDs of the patterns preserved.
+    pub fn to_patterns(&self) -> Patterns {
+        self.iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, pattern)| {
+                let pattern = Pattern::from(pattern);
+
+                Pattern {
+                    id: pattern.id.or(Some(i)),
+                    ..pattern
+                }
+            })
+            .collect()
+    }
+}
+
 /// Define `Literal` with flags
 #[macro_export]
 macro_rules! literal {
@@ -280,7 +416,12 @@ mod tests {
         assert_eq!(flags.to_string(), "i");
 
         assert_eq!("im".parse::<Flags>().unwrap(), flags | Flags::MULTILINE);
+        assert_eq!("HL".parse::<Flags>().unwrap().to_string(), "HL");
         assert!("test".parse::<Flags>().is_err());
+        assert_eq!(
+            "s".parse::<Flags>().err(),
+            Some(Error::UnsupportedFlag('s', "not supported by the pure literal"))
+        );
     }
 
     #[test]
@@ -367,4 +508,45 @@ mod tests {
 
         validate_database_with_size(&db, DATABASE_SIZE);
     }
+
+    #[test]
+    fn test_literal_escape() {
+        let p = Pattern::from(literal! {5 => "a.b\0(c)"; CASELESS});
+
+        assert_eq!(p.expression, r"a\x2eb\x00\x28c\x29");
+        assert_eq!(p.flags, PatternFlags::CASELESS);
+        assert_eq!(p.id, Some(5));
+    }
+
+    #[test]
+    fn test_mixed_patterns_build() {
+        let patterns = MixedPatterns::from(literals!("test", "foo", "bar"));
+
+        assert!(patterns.is_literal());
+        assert_eq!(patterns.to_literals().unwrap().len(), 3);
+
+        let db: BlockDatabase = patterns.build().unwrap();
+
+        validate_database_with_size(&db, DATABASE_SIZE);
+
+        let mut patterns = patterns;
+
+        patterns.0.push(pattern! {"b.z"; SOM_LEFTMOST}.into());
+        patterns[1] = literal! {"f|o"; SOM_LEFTMOST}.into();
+
+        assert!(!patterns.is_literal());
+        assert!(patterns.to_literals().is_none());
+
+        let db: BlockDatabase = patterns.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan("foo f|o bar baz test", &s, |id, from, to, _| {
+            matches.push((id, from, to));
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![(1, 4, 7), (2, 0, 11), (3, 12, 15), (0, 0, 20)]);
+    }
 }
@@ -1,14 +1,26 @@
+mod analyze;
 mod error;
 #[macro_use]
 mod pattern;
 mod builder;
+mod cache;
+#[cfg(hyperscan_v5)]
+mod combination;
 mod expr;
+mod fat;
 #[macro_use]
 #[cfg(feature = "literal")]
 mod literal;
 mod platform;
+mod registry;
 
-pub use self::builder::{compile, Builder};
+pub use self::analyze::{Construct, Unsupported};
+#[cfg(feature = "literal")]
+pub(crate) use self::builder::compile_literals;
+pub use self::builder::{compile, Builder, Rejected};
+pub use self::cache::{Cache, CachedBuilder, DirCache};
+#[cfg(hyperscan_v5)]
+pub use self::combination::Combination;
 pub use self::error::{AsCompileResult, Error};
 #[doc(hidden)]
 #[deprecated = "use `ExprExt` instead"]
@@ -17,7 +29,9 @@ pub use self::expr::ExprExt as ExpressionExt;
 #[deprecated = "use `ExprInfo` instead"]
 pub use self::expr::ExprInfo as ExpressionInfo;
 pub use self::expr::{Error as ExprError, ExprExt, ExprInfo};
+pub use self::fat::FatDatabase;
 #[cfg(feature = "literal")]
-pub use self::literal::{Flags as LiteralFlags, Literal, Literals};
-pub use self::pattern::{Flags, Pattern, Patterns, SomHorizon};
-pub use self::platform::{CpuFeatures, Platform, PlatformRef, Tune};
+pub use self::literal::{Flags as LiteralFlags, Literal, Literals, MixedPattern, MixedPatterns};
+pub use self::pattern::{Flags, ParseError, Pattern, PatternIndex, Patterns, SomHorizon};
+pub use self::platform::{CpuFeatures, Platform, PlatformBuilder, PlatformRef, Tune};
+pub use self::registry::PatternRegistry;
@@ -1,9 +1,16 @@
+use std::collections::BTreeMap;
 use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
 use std::iter::FromIterator;
+use std::path::Path;
+use std::result::Result as StdResult;
 use std::str::FromStr;
 
 use bitflags::bitflags;
 use derive_more::{Deref, DerefMut, From, Index, IndexMut, Into, IntoIterator};
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
     compile::ExprExt,
@@ -13,6 +20,25 @@ use crate::{
 
 bitflags! {
     /// Pattern flags
+    ///
+    /// The flags could be parsed from the letters used by the `/expression/flags` grammar of `hsbench`.
+    ///
+    /// | Letter | Flag           | Requires       |
+    /// |--------|----------------|----------------|
+    /// | `i`    | `CASELESS`     |                |
+    /// | `m`    | `MULTILINE`    |                |
+    /// | `s`    | `DOTALL`       |                |
+    /// | `H`    | `SINGLEMATCH`  |                |
+    /// | `V`    | `ALLOWEMPTY`   |                |
+    /// | `8`    | `UTF8`         |                |
+    /// | `W`    | `UCP`          |                |
+    /// | `P`    | `PREFILTER`    |                |
+    /// | `L`    | `SOM_LEFTMOST` |                |
+    /// | `C`    | `COMBINATION`  | `v5` feature   |
+    /// | `Q`    | `QUIET`        | `v5` feature   |
+    ///
+    /// A letter which requires a disabled feature returns `Error::UnsupportedFlag`,
+    /// any other letter returns `Error::InvalidFlag`.
     #[derive(Default)]
     pub struct Flags: u32 {
         /// Set case-insensitive matching.
@@ -34,10 +60,10 @@ bitflags! {
         /// Enable leftmost start of match reporting.
         const SOM_LEFTMOST = ffi::HS_FLAG_SOM_LEFTMOST;
         /// Logical combination.
-        #[cfg(feature = "v5")]
+        #[cfg(hyperscan_v5)]
         const COMBINATION = ffi::HS_FLAG_COMBINATION;
         /// Don't do any match reporting.
-        #[cfg(feature = "v5")]
+        #[cfg(hyperscan_v5)]
         const QUIET = ffi::HS_FLAG_QUIET;
     }
 }
@@ -59,11 +85,11 @@ impl FromStr for Flags {
                 'W' => flags |= Flags::UCP,
                 'P' => flags |= Flags::PREFILTER,
                 'L' => flags |= Flags::SOM_LEFTMOST,
-                #[cfg(feature = "v5")]
+                #[cfg(hyperscan_v5)]
                 'C' => flags |= Flags::COMBINATION,
-                #[cfg(feature = "v5")]
+                #[cfg(hyperscan_v5)]
                 'Q' => flags |= Flags::QUIET,
-                _ => return Err(Error::InvalidFlag(c)),
+                _ => return Err(Error::unsupported_flag(c, "requires the `v5` feature")),
             }
         }
 
@@ -71,50 +97,73 @@ impl FromStr for Flags {
     }
 }
 
-impl fmt::Display for Flags {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Flags {
+    /// Write the flags to the writer without any intermediate allocation.
+    pub fn write_to<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
         if self.contains(Flags::CASELESS) {
-            write!(f, "i")?
+            w.write_char('i')?
         }
         if self.contains(Flags::MULTILINE) {
-            write!(f, "m")?
+            w.write_char('m')?
         }
         if self.contains(Flags::DOTALL) {
-            write!(f, "s")?
+            w.write_char('s')?
         }
         if self.contains(Flags::SINGLEMATCH) {
-            write!(f, "H")?
+            w.write_char('H')?
         }
         if self.contains(Flags::ALLOWEMPTY) {
-            write!(f, "V")?
+            w.write_char('V')?
         }
         if self.contains(Flags::UTF8) {
-            write!(f, "8")?
+            w.write_char('8')?
         }
         if self.contains(Flags::UCP) {
-            write!(f, "W")?
+            w.write_char('W')?
         }
         if self.contains(Flags::PREFILTER) {
-            write!(f, "P")?
+            w.write_char('P')?
         }
         if self.contains(Flags::SOM_LEFTMOST) {
-            write!(f, "L")?
+            w.write_char('L')?
         }
-        #[cfg(feature = "v5")]
+        #[cfg(hyperscan_v5)]
         if self.contains(Flags::COMBINATION) {
-            write!(f, "C")?
+            w.write_char('C')?
         }
-        #[cfg(feature = "v5")]
+        #[cfg(hyperscan_v5)]
         if self.contains(Flags::QUIET) {
-            write!(f, "Q")?
+            w.write_char('Q')?
         }
         Ok(())
     }
 }
 
+impl fmt::Display for Flags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_to(f)
+    }
+}
+
+/// The flags are serialized as the letters of the `/expression/flags` grammar, e.g. `"imsL"`.
+#[cfg(feature = "serde")]
+impl Serialize for Flags {
+    fn serialize<S: Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Flags {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> StdResult<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
 /// Defines the precision to track start of match offsets in stream state.
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
 pub enum SomHorizon {
     /// use full precision to track start of match offsets in stream state.
     ///
@@ -137,17 +186,25 @@ pub enum SomHorizon {
 }
 
 /// The pattern with basic regular expression.
+///
+/// With the `serde` feature, only the `expression` field is required when deserializing,
+/// and the empty fields are skipped when serializing.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Pattern {
     /// The expression to parse.
     pub expression: String,
     /// Flags which modify the behaviour of the expression.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Flags::is_empty"))]
     pub flags: Flags,
     /// ID number to be associated with the corresponding pattern in the expressions array.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
     pub id: Option<usize>,
     /// Extended behaviour for this pattern
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "ExprExt::is_empty"))]
     pub ext: ExprExt,
     /// The precision to track start of match offsets in stream state.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
     pub som: Option<SomHorizon>,
 }
 
@@ -229,19 +286,38 @@ impl Pattern {
     }
 
     /// Logical combination.
-    #[cfg(feature = "v5")]
+    #[cfg(hyperscan_v5)]
     pub fn combination(mut self) -> Self {
         self.flags |= Flags::COMBINATION;
         self
     }
 
     /// Don't do any match reporting.
-    #[cfg(feature = "v5")]
+    #[cfg(hyperscan_v5)]
     pub fn quiet(mut self) -> Self {
         self.flags |= Flags::QUIET;
         self
     }
 
+    /// Move the leading inline flag groups of the expression, e.g. `(?i)(?ms)`, into the pattern flags.
+    ///
+    /// Hyperscan supports the inline flags inconsistently across the constructs,
+    /// hoisting them improves the compile success rate of the imported rule sets.
+    ///
+    /// Only the `i`, `m` and `s` flags are hoisted, a group with any other flag
+    /// or a negation, e.g. `(?-i)`, stops the hoisting.
+    pub fn hoist_inline_flags(mut self) -> Self {
+        let mut hoisted = 0;
+
+        while let Some((flags, len)) = leading_inline_flags(&self.expression[hoisted..]) {
+            self.flags |= flags;
+            hoisted += len;
+        }
+
+        self.expression.drain(..hoisted);
+        self
+    }
+
     pub(crate) fn som(&self) -> Option<SomHorizon> {
         if self.flags.contains(Flags::SOM_LEFTMOST) {
             self.som.or(Some(SomHorizon::Medium))
@@ -251,29 +327,65 @@ impl Pattern {
     }
 }
 
-impl fmt::Display for Pattern {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+fn leading_inline_flags(expr: &str) -> Option<(Flags, usize)> {
+    use regex_syntax::ast::{parse::Parser, Ast, Flag, FlagsItemKind};
+
+    if !expr.starts_with("(?") {
+        return None;
+    }
+
+    let len = expr.find(')')? + 1;
+
+    match &Parser::new().parse(&expr[..len]).ok()? {
+        Ast::Flags(set) => set
+            .flags
+            .items
+            .iter()
+            .try_fold(Flags::empty(), |flags, item| match item.kind {
+                FlagsItemKind::Flag(Flag::CaseInsensitive) => Some(flags | Flags::CASELESS),
+                FlagsItemKind::Flag(Flag::MultiLine) => Some(flags | Flags::MULTILINE),
+                FlagsItemKind::Flag(Flag::DotMatchesNewLine) => Some(flags | Flags::DOTALL),
+                _ => None,
+            })
+            .map(|flags| (flags, len)),
+        _ => None,
+    }
+}
+
+impl Pattern {
+    /// Write the pattern to the writer without any intermediate allocation.
+    ///
+    /// The output could be parsed back with `Pattern::from_str`.
+    pub fn write_to<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
         if let Some(id) = self.id {
-            write!(f, "{}:", id)?;
+            write!(w, "{}:", id)?;
         }
 
         if self.id.is_some() || !self.flags.is_empty() || !self.ext.is_empty() {
-            write!(f, "/{}/", self.expression)?;
+            w.write_char('/')?;
+            w.write_str(&self.expression)?;
+            w.write_char('/')?;
         } else {
-            write!(f, "{}", self.expression)?;
+            w.write_str(&self.expression)?;
         }
 
         if !self.flags.is_empty() {
-            write!(f, "{}", self.flags)?;
+            self.flags.write_to(w)?;
         }
         if !self.ext.is_empty() {
-            write!(f, "{}", self.ext)?;
+            self.ext.write_to(w)?;
         }
 
         Ok(())
     }
 }
 
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_to(f)
+    }
+}
+
 impl FromStr for Pattern {
     type Err = Error;
 
@@ -318,6 +430,7 @@ impl FromStr for Pattern {
 /// Vec of `Pattern`
 #[repr(transparent)]
 #[derive(Clone, Debug, Deref, DerefMut, From, Index, IndexMut, Into, IntoIterator)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 #[deref(forward)]
 #[deref_mut(forward)]
 pub struct Patterns(pub Vec<Pattern>);
@@ -328,6 +441,42 @@ impl FromIterator<Pattern> for Patterns {
     }
 }
 
+/// An error of parsing a pattern file, with the position of the rejected pattern.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("line {line}, column {column}: {source}")]
+pub struct ParseError {
+    /// The line number, starting from 1.
+    pub line: usize,
+    /// The column of the rejected part of the pattern, starting from 1.
+    pub column: usize,
+    /// The error of parsing the pattern.
+    #[source]
+    pub source: Error,
+}
+
+impl ParseError {
+    /// Locate the rejected part of the pattern, e.g. the ID, the invalid flag or the extended parameters.
+    fn new(line: usize, text: &str, source: Error) -> Self {
+        let indent = text.len() - text.trim_start().len();
+        let pattern = text.trim();
+        let flags = pattern.rfind('/').map_or(pattern.len(), |off| off + 1);
+
+        let offset = match source {
+            Error::InvalidFlag(flag) | Error::UnsupportedFlag(flag, _) => {
+                pattern[flags..].find(flag).map_or(flags, |off| flags + off)
+            }
+            Error::Expr(_) => pattern.rfind('{').unwrap_or(flags),
+            _ => 0,
+        };
+
+        ParseError {
+            line,
+            column: indent + pattern[..offset].chars().count() + 1,
+            source,
+        }
+    }
+}
+
 impl FromStr for Patterns {
     type Err = Error;
 
@@ -348,6 +497,109 @@ impl FromStr for Patterns {
 }
 
 impl Patterns {
+    /// Parse the patterns in the `hsbench`/`hscollider` pattern file dialect, one `id:/expression/flags{ext}`
+    /// per line, skipping the empty lines and the comments starting with `#`.
+    ///
+    /// Returns the position of the first rejected pattern.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, Error, ParseError};
+    /// let patterns = Patterns::parse_lines("# rules\n1:/foo/i\n2:/bar/s{min_length=4}").unwrap();
+    ///
+    /// assert_eq!(patterns.len(), 2);
+    ///
+    /// let err = Patterns::parse_lines("1:/foo/i\n\n  3:/bar/sx").unwrap_err();
+    ///
+    /// assert_eq!(err.line, 3);
+    /// assert_eq!(err.column, 11);
+    /// assert_eq!(err.source, Error::InvalidFlag('x'));
+    /// ```
+    pub fn parse_lines(s: &str) -> StdResult<Self, ParseError> {
+        s.lines()
+            .enumerate()
+            .filter_map(|(i, line)| Self::parse_line(i + 1, line))
+            .collect::<StdResult<Vec<_>, _>>()
+            .map(Self)
+    }
+
+    /// Read the patterns in the `hsbench`/`hscollider` pattern file dialect from the reader.
+    ///
+    /// A rejected pattern fails with `io::ErrorKind::InvalidData` wrapping the `ParseError`.
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut patterns = vec![];
+
+        for (i, line) in reader.lines().enumerate() {
+            if let Some(pattern) = Self::parse_line(i + 1, &line?) {
+                patterns.push(pattern.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?);
+            }
+        }
+
+        Ok(Self(patterns))
+    }
+
+    /// Read the patterns in the `hsbench`/`hscollider` pattern file dialect from the file.
+    ///
+    /// A rejected pattern fails with `io::ErrorKind::InvalidData` wrapping the `ParseError`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        File::open(path).map(BufReader::new).and_then(Self::from_reader)
+    }
+
+    fn parse_line(line: usize, text: &str) -> Option<StdResult<Pattern, ParseError>> {
+        let pattern = text.trim();
+
+        if pattern.is_empty() || pattern.starts_with('#') {
+            None
+        } else {
+            Some(pattern.parse().map_err(|err| ParseError::new(line, text, err)))
+        }
+    }
+
+    /// Report the leftmost start of match offset for all the patterns, e.g. for `Patterns::scan_overlapping`.
+    pub fn left_most(mut self) -> Self {
+        for pattern in self.iter_mut() {
+            pattern.flags |= Flags::SOM_LEFTMOST;
+        }
+        self
+    }
+
+    /// Returns an iterator over the IDs of the patterns, in the order of the patterns.
+    ///
+    /// A pattern without ID is identified by its index, the same as the compiled database reports.
+    pub fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.iter().enumerate().map(|(i, Pattern { id, .. })| id.unwrap_or(i))
+    }
+
+    /// Returns the first pattern with the ID reported by the match callback.
+    ///
+    /// Use `Patterns::index` to resolve the IDs repeatedly.
+    pub fn get_by_id(&self, id: usize) -> Option<&Pattern> {
+        self.ids().position(|pattern_id| pattern_id == id).map(|i| &self[i])
+    }
+
+    /// Build an index to resolve the IDs reported by the match callback back to the patterns.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let patterns: Patterns = "5:/foo/\n/bar/\n3:/baz/".parse().unwrap();
+    /// let index = patterns.index();
+    ///
+    /// assert_eq!(index.ids().collect::<Vec<_>>(), vec![1, 3, 5]);
+    /// assert_eq!(index.get(3).unwrap().expression, "baz");
+    /// ```
+    pub fn index(&self) -> PatternIndex<'_> {
+        let mut index = BTreeMap::new();
+
+        for (id, pattern) in self.ids().zip(self.iter()) {
+            index.entry(id).or_insert(pattern);
+        }
+
+        PatternIndex(index)
+    }
+
     pub(crate) fn som(&self) -> Option<SomHorizon> {
         if self
             .iter()
@@ -363,9 +615,87 @@ impl Patterns {
     }
 }
 
+/// An index of the patterns by ID, iterated in the order of the IDs.
+///
+/// When multiple patterns share an ID, the first one is indexed.
+#[derive(Clone, Debug)]
+pub struct PatternIndex<'a>(BTreeMap<usize, &'a Pattern>);
+
+impl<'a> PatternIndex<'a> {
+    /// Returns the pattern with the ID.
+    pub fn get(&self, id: usize) -> Option<&'a Pattern> {
+        self.0.get(&id).copied()
+    }
+
+    /// Returns an iterator over the indexed IDs in ascending order.
+    pub fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.keys().copied()
+    }
+
+    /// Returns an iterator over the IDs and the patterns in ascending order of the IDs.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &'a Pattern)> + '_ {
+        self.0.iter().map(|(&id, &pattern)| (id, pattern))
+    }
+
+    /// The number of indexed IDs.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if there is no indexed ID.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
 /// Define `Pattern` with flags
+///
+/// The extended parameters could follow the flags, e.g. `min_offset`, `max_offset`, `min_length`,
+/// `edit_distance` and `hamming_distance`, an unknown parameter is a compile error.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// let p = pattern! {"foo.*bar"; DOTALL; min_offset = 10, edit_distance = 2};
+///
+/// assert_eq!(p.to_string(), "/foo.*bar/s{min_offset=10,edit_distance=2}");
+/// ```
+///
+/// ```rust,compile_fail
+/// # use hyperscan::prelude::*;
+/// let p = pattern! {"foo.*bar"; DOTALL; min_ofset = 10};
+/// ```
 #[macro_export]
 macro_rules! pattern {
+    ( @ext $pattern:ident, min_offset = $value:expr ) => {
+        $pattern.ext.set_min_offset($value);
+    };
+    ( @ext $pattern:ident, max_offset = $value:expr ) => {
+        $pattern.ext.set_max_offset($value);
+    };
+    ( @ext $pattern:ident, min_length = $value:expr ) => {
+        $pattern.ext.set_min_length($value);
+    };
+    ( @ext $pattern:ident, edit_distance = $value:expr ) => {
+        $pattern.ext.set_edit_distance($value);
+    };
+    ( @ext $pattern:ident, hamming_distance = $value:expr ) => {
+        $pattern.ext.set_hamming_distance($value);
+    };
+    ( @ext $pattern:ident, $param:ident = $value:expr ) => {
+        compile_error!(concat!("unknown extended parameter: ", stringify!($param)));
+    };
+    ( $expr:expr ; $( $flag:ident )|* ; $( $param:ident = $value:expr ),+ $(,)? ) => {{
+        let mut pattern = pattern! { $expr ; $( $flag )|* };
+        $( pattern! { @ext pattern, $param = $value } )+
+        pattern
+    }};
+    ( $id:literal => $expr:expr ; $( $flag:ident )|* ; $( $param:ident = $value:expr ),+ $(,)? ) => {{
+        let mut pattern = pattern! { $id => $expr ; $( $flag )|* };
+        $( pattern! { @ext pattern, $param = $value } )+
+        pattern
+    }};
     ( $expr:expr ) => {{
         pattern! { $expr ; $crate::CompileFlags::default() }
     }};
@@ -425,7 +755,9 @@ mod tests {
         assert_eq!(flags.to_string(), "is");
 
         assert_eq!("ism".parse::<Flags>().unwrap(), flags | Flags::MULTILINE);
+        assert_eq!("PL".parse::<Flags>().unwrap(), Flags::PREFILTER | Flags::SOM_LEFTMOST);
         assert!("test".parse::<Flags>().is_err());
+        assert_eq!("iO".parse::<Flags>().err(), Some(Error::InvalidFlag('O')));
     }
 
     #[test]
@@ -473,6 +805,10 @@ mod tests {
         assert_eq!(p.ext.min_offset().unwrap(), 50);
         assert_eq!(p.ext.max_offset().unwrap(), 100);
         assert_eq!(p.to_string(), s);
+        assert_eq!(
+            p,
+            pattern! { 1 => "hatstand.*teakettle"; DOTALL; min_offset = 50, max_offset = 100 }
+        );
 
         let p: Pattern = "test/i".parse().unwrap();
 
@@ -489,6 +825,44 @@ mod tests {
         assert_eq!(p.id, None);
     }
 
+    #[test]
+    fn test_pattern_write_to() {
+        let s = r#"1:/hatstand.*teakettle/sL{min_offset=50,max_offset=100}"#;
+        let p: Pattern = s.parse().unwrap();
+        let mut buf = String::with_capacity(64);
+
+        p.write_to(&mut buf).unwrap();
+        assert_eq!(buf, s);
+
+        buf.clear();
+        p.flags.write_to(&mut buf).unwrap();
+        assert_eq!(buf, "sL");
+
+        buf.clear();
+        p.ext.write_to(&mut buf).unwrap();
+        assert_eq!(buf, "{min_offset=50,max_offset=100}");
+    }
+
+    #[test]
+    fn test_pattern_hoist_inline_flags() {
+        let p = pattern! {"(?i)(?ms)test(?i)"; SOM_LEFTMOST}.hoist_inline_flags();
+
+        assert_eq!(p.expression, "test(?i)");
+        assert_eq!(
+            p.flags,
+            Flags::CASELESS | Flags::MULTILINE | Flags::DOTALL | Flags::SOM_LEFTMOST
+        );
+
+        let p = pattern! {"(?i)(?x)test"}.hoist_inline_flags();
+
+        assert_eq!(p.expression, "(?x)test");
+        assert_eq!(p.flags, Flags::CASELESS);
+
+        for expr in &["(?i-m)test", "(?i:test)", "(?#test)", "test"] {
+            assert_eq!(pattern! {*expr}.hoist_inline_flags(), pattern! {*expr});
+        }
+    }
+
     #[test]
     fn test_pattern_build() {
         let p = &pattern! {"test"};
@@ -523,6 +897,32 @@ mod tests {
         validate_database(&db);
     }
 
+    #[test]
+    fn test_patterns_parse_lines() {
+        let patterns = Patterns::parse_lines("# comment\n\n1:/foo/i\n2:/ba(r|z)/sL{min_offset=4}\n").unwrap();
+
+        assert_eq!(patterns.len(), 2);
+        assert_eq!(patterns[1].id, Some(2));
+        assert_eq!(patterns[1].ext.min_offset(), Some(4));
+
+        let err = Patterns::parse_lines("1:/foo/i\nx:/bar/").unwrap_err();
+
+        assert_eq!((err.line, err.column), (2, 1));
+
+        let err = Patterns::parse_lines("1:/foo/i{foo=1}").unwrap_err();
+
+        assert_eq!((err.line, err.column), (1, 9));
+        assert!(matches!(err.source, Error::Expr(_)));
+
+        let err = Patterns::from_reader("1:/foo/iq".as_bytes()).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.into_inner().unwrap().downcast::<ParseError>().unwrap().source,
+            Error::InvalidFlag('q')
+        );
+    }
+
     #[test]
     fn test_patterns_build() {
         let db: BlockDatabase = patterns!("test", "foo", "bar").build().unwrap();
@@ -536,4 +936,46 @@ mod tests {
 
         validate_database_with_size(&db, DATABASE_SIZE);
     }
+
+    #[test]
+    fn test_patterns_by_id() {
+        let patterns: Patterns = "5:/foo/\n/bar/\n3:/baz/\n1:/qux/".parse().unwrap();
+
+        assert_eq!(patterns.ids().collect::<Vec<_>>(), vec![5, 1, 3, 1]);
+        assert_eq!(patterns.get_by_id(1).unwrap().expression, "bar");
+        assert!(patterns.get_by_id(2).is_none());
+
+        let index = patterns.index();
+
+        assert_eq!(index.len(), 3);
+        assert_eq!(
+            index
+                .iter()
+                .map(|(id, p)| (id, p.expression.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(1, "bar"), (3, "baz"), (5, "foo")]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let patterns: Patterns = "/foo/i\n1:/bar/sL{min_offset=10}".parse().unwrap();
+        let json = serde_json::to_string(&patterns).unwrap();
+
+        assert_eq!(
+            json,
+            r#"[{"expression":"foo","flags":"i"},{"expression":"bar","flags":"sL","id":1,"ext":{"min_offset":10}}]"#
+        );
+        assert_eq!(serde_json::from_str::<Patterns>(&json).unwrap().0, patterns.0);
+
+        let p: Pattern = serde_json::from_str(r#"{"expression":"baz","som":"small"}"#).unwrap();
+
+        assert_eq!(p.expression, "baz");
+        assert!(p.flags.is_empty());
+        assert_eq!(p.som, Some(SomHorizon::Small));
+
+        assert!(serde_json::from_str::<Flags>(r#""iO""#).is_err());
+        assert!(serde_json::from_str::<ExprExt>(r#"{"max_length":1}"#).is_err());
+    }
 }
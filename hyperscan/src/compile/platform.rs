@@ -1,11 +1,15 @@
+use std::fmt;
 use std::mem::{self, MaybeUninit};
 
 use bitflags::bitflags;
-use foreign_types::{foreign_type, ForeignType};
+use foreign_types::{foreign_type, ForeignType, ForeignTypeRef};
 
 use crate::{error::AsResult, ffi, Result};
 
 /// Tuning Parameter
+///
+/// The tuning families are Intel microarchitectures, with the `vectorscan` feature on the non-x86 targets
+/// (e.g. aarch64), only `Generic` is available.
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Tune {
@@ -13,35 +17,43 @@ pub enum Tune {
     Generic = ffi::HS_TUNE_FAMILY_GENERIC,
 
     /// Intel(R) microarchitecture code name Sandy Bridge
+    #[cfg(hyperscan_x86)]
     SandyBridge = ffi::HS_TUNE_FAMILY_SNB,
 
     /// Intel(R) microarchitecture code name Ivy Bridge
+    #[cfg(hyperscan_x86)]
     IvyBridge = ffi::HS_TUNE_FAMILY_IVB,
 
     /// Intel(R) microarchitecture code name Haswell
+    #[cfg(hyperscan_x86)]
     Haswell = ffi::HS_TUNE_FAMILY_HSW,
 
     /// Intel(R) microarchitecture code name Silvermont
+    #[cfg(hyperscan_x86)]
     Silvermont = ffi::HS_TUNE_FAMILY_SLM,
 
     /// Intel(R) microarchitecture code name Broadwell
+    #[cfg(hyperscan_x86)]
     Broadwell = ffi::HS_TUNE_FAMILY_BDW,
 
     /// Intel(R) microarchitecture code name Skylake
+    #[cfg(hyperscan_x86)]
     Skylake = ffi::HS_TUNE_FAMILY_SKL,
 
     /// Intel(R) microarchitecture code name Skylake Server
+    #[cfg(hyperscan_x86)]
     SkylakeServer = ffi::HS_TUNE_FAMILY_SKX,
 
     /// Intel(R) microarchitecture code name Goldmont
+    #[cfg(hyperscan_x86)]
     Goldmont = ffi::HS_TUNE_FAMILY_GLM,
 
     /// Intel(R) microarchitecture code name Icelake
-    #[cfg(feature = "v5_4")]
+    #[cfg(all(hyperscan_x86, hyperscan_v5_4))]
     Icelake = ffi::HS_TUNE_FAMILY_ICL,
 
     /// Intel(R) microarchitecture code name Icelake Server
-    #[cfg(feature = "v5_4")]
+    #[cfg(all(hyperscan_x86, hyperscan_v5_4))]
     IcelakeServer = ffi::HS_TUNE_FAMILY_ICX,
 }
 
@@ -51,17 +63,72 @@ impl Default for Tune {
     }
 }
 
+impl Tune {
+    /// Converts the raw tuning value of the platform information.
+    pub fn from_raw(tune: u32) -> Option<Tune> {
+        use Tune::*;
+
+        match tune {
+            ffi::HS_TUNE_FAMILY_GENERIC => Some(Generic),
+            #[cfg(hyperscan_x86)]
+            ffi::HS_TUNE_FAMILY_SNB => Some(SandyBridge),
+            #[cfg(hyperscan_x86)]
+            ffi::HS_TUNE_FAMILY_IVB => Some(IvyBridge),
+            #[cfg(hyperscan_x86)]
+            ffi::HS_TUNE_FAMILY_HSW => Some(Haswell),
+            #[cfg(hyperscan_x86)]
+            ffi::HS_TUNE_FAMILY_SLM => Some(Silvermont),
+            #[cfg(hyperscan_x86)]
+            ffi::HS_TUNE_FAMILY_BDW => Some(Broadwell),
+            #[cfg(hyperscan_x86)]
+            ffi::HS_TUNE_FAMILY_SKL => Some(Skylake),
+            #[cfg(hyperscan_x86)]
+            ffi::HS_TUNE_FAMILY_SKX => Some(SkylakeServer),
+            #[cfg(hyperscan_x86)]
+            ffi::HS_TUNE_FAMILY_GLM => Some(Goldmont),
+            #[cfg(all(hyperscan_x86, hyperscan_v5_4))]
+            ffi::HS_TUNE_FAMILY_ICL => Some(Icelake),
+            #[cfg(all(hyperscan_x86, hyperscan_v5_4))]
+            ffi::HS_TUNE_FAMILY_ICX => Some(IcelakeServer),
+            _ => None,
+        }
+    }
+}
+
 bitflags! {
     /// CPU feature support flags
+    ///
+    /// The x86 instruction sets are only available with Hyperscan, or Vectorscan on the x86 targets,
+    /// and the Arm(R) ones with Vectorscan on aarch64, where NEON is always required and has no flag.
     #[derive(Default)]
     pub struct CpuFeatures: u64 {
         /// Intel(R) Advanced Vector Extensions 2 (Intel(R) AVX2)
+        #[cfg(hyperscan_x86)]
         const AVX2 = ffi::HS_CPU_FEATURES_AVX2 as u64;
         /// Intel(R) Advanced Vector Extensions 512 (Intel(R) AVX512)
+        #[cfg(hyperscan_x86)]
         const AVX512 = ffi::HS_CPU_FEATURES_AVX512 as u64;
         /// Intel(R) Advanced Vector Extensions 512 Vector Byte Manipulation Instructions (Intel(R) AVX512VBMI)
-        #[cfg(feature = "v5_4")]
+        #[cfg(all(hyperscan_x86, hyperscan_v5_4))]
         const AVX512VBMI = ffi::HS_CPU_FEATURES_AVX512VBMI as u64;
+        /// Arm(R) Scalable Vector Extension (SVE), `HS_CPU_FEATURES_SVE` of Vectorscan
+        #[cfg(all(feature = "vectorscan", target_arch = "aarch64"))]
+        const SVE = 1 << 5;
+        /// Arm(R) Scalable Vector Extension 2 (SVE2), `HS_CPU_FEATURES_SVE2` of Vectorscan
+        #[cfg(all(feature = "vectorscan", target_arch = "aarch64"))]
+        const SVE2 = 1 << 6;
+    }
+}
+
+impl CpuFeatures {
+    /// Detects the CPU features supported by the current host.
+    pub fn host() -> Result<CpuFeatures> {
+        Platform::host().map(|platform| platform.cpu_features())
+    }
+
+    /// Detects the CPU features supported by the current host, without any optional feature if it failed.
+    pub fn detect() -> CpuFeatures {
+        Self::host().unwrap_or_default()
     }
 }
 
@@ -79,6 +146,39 @@ unsafe fn free_platform_info(p: *mut ffi::hs_platform_info_t) {
     mem::drop(Box::from_raw(p));
 }
 
+impl fmt::Debug for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Platform")
+            .field("tune", &self.tune())
+            .field("cpu_features", &self.cpu_features())
+            .finish()
+    }
+}
+
+impl PlatformRef {
+    unsafe fn as_ref(&self) -> &ffi::hs_platform_info_t {
+        self.as_ptr().as_ref().unwrap()
+    }
+
+    /// The tuning parameter of the target platform, `None` if it is not known by this crate.
+    pub fn tune(&self) -> Option<Tune> {
+        Tune::from_raw(unsafe { self.as_ref().tune })
+    }
+
+    /// The CPU features supported by the target platform.
+    pub fn cpu_features(&self) -> CpuFeatures {
+        CpuFeatures::from_bits_truncate(unsafe { self.as_ref().cpu_features })
+    }
+
+    /// Returns a builder starting from the tuning parameter and the CPU features of the target platform.
+    pub fn to_builder(&self) -> PlatformBuilder {
+        PlatformBuilder {
+            tune: self.tune().unwrap_or_default(),
+            cpu_features: self.cpu_features(),
+        }
+    }
+}
+
 impl Platform {
     /// Utility function to test the current system architecture.
     ///
@@ -89,10 +189,42 @@ impl Platform {
     /// This function does not test for more advanced features
     /// if Hyperscan has been built for a more specific architecture,
     /// for example the AVX2 instruction set.
+    ///
+    /// With the `vectorscan` feature, it tests the SIMD instruction set required by Vectorscan
+    /// on the target architecture, e.g. NEON on aarch64.
     pub fn is_valid() -> Result<()> {
         unsafe { ffi::hs_valid_platform().ok() }
     }
 
+    /// Populates the platform information based on the current host.
+    ///
+    /// This is an alias of `Platform::host`.
+    pub fn current() -> Result<Platform> {
+        Self::host()
+    }
+
+    /// Returns a builder of the target platform, starting from the generic tuning without any optional CPU features.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// # use hyperscan::{CpuFeatures, PatternFlags, Platform, Tune};
+    /// let platform = Platform::builder()
+    ///     .tune(Tune::SkylakeServer)
+    ///     .cpu_features(CpuFeatures::AVX2 | CpuFeatures::AVX512)
+    ///     .no_avx512()
+    ///     .build();
+    ///
+    /// assert_eq!(platform.tune(), Some(Tune::SkylakeServer));
+    /// assert_eq!(platform.cpu_features(), CpuFeatures::AVX2);
+    ///
+    /// let db = BlockDatabase::compile_for_platform("test", PatternFlags::empty(), &platform).unwrap();
+    /// ```
+    pub fn builder() -> PlatformBuilder {
+        PlatformBuilder::default()
+    }
+
     /// Populates the platform information based on the current host.
     pub fn host() -> Result<Platform> {
         let mut platform = MaybeUninit::zeroed();
@@ -116,6 +248,102 @@ impl Platform {
     }
 }
 
+/// A builder of the target platform, e.g. the deployment CPU of a cross-compiled database.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PlatformBuilder {
+    tune: Tune,
+    cpu_features: CpuFeatures,
+}
+
+impl PlatformBuilder {
+    /// Set the tuning parameter of the target platform.
+    pub fn tune(&mut self, tune: Tune) -> &mut Self {
+        self.tune = tune;
+        self
+    }
+
+    /// Set the CPU features supported by the target platform.
+    pub fn cpu_features(&mut self, cpu_features: CpuFeatures) -> &mut Self {
+        self.cpu_features = cpu_features;
+        self
+    }
+
+    /// Add the Intel(R) AVX2 instruction set.
+    #[cfg(hyperscan_x86)]
+    pub fn avx2(&mut self) -> &mut Self {
+        self.cpu_features |= CpuFeatures::AVX2;
+        self
+    }
+
+    /// Remove the Intel(R) AVX2 instruction set, and the AVX512 ones which imply it.
+    #[cfg(hyperscan_x86)]
+    pub fn no_avx2(&mut self) -> &mut Self {
+        self.cpu_features = CpuFeatures::empty();
+        self
+    }
+
+    /// Add the Intel(R) AVX512 instruction set, which implies AVX2.
+    #[cfg(hyperscan_x86)]
+    pub fn avx512(&mut self) -> &mut Self {
+        self.cpu_features |= CpuFeatures::AVX2 | CpuFeatures::AVX512;
+        self
+    }
+
+    /// Remove the Intel(R) AVX512 instruction sets.
+    #[cfg(hyperscan_x86)]
+    pub fn no_avx512(&mut self) -> &mut Self {
+        self.cpu_features &= CpuFeatures::AVX2;
+        self
+    }
+
+    /// Add the Intel(R) AVX512VBMI instruction set, which implies AVX512.
+    #[cfg(all(hyperscan_x86, hyperscan_v5_4))]
+    pub fn avx512vbmi(&mut self) -> &mut Self {
+        self.cpu_features |= CpuFeatures::all();
+        self
+    }
+
+    /// Remove the Intel(R) AVX512VBMI instruction set.
+    #[cfg(all(hyperscan_x86, hyperscan_v5_4))]
+    pub fn no_avx512vbmi(&mut self) -> &mut Self {
+        self.cpu_features.remove(CpuFeatures::AVX512VBMI);
+        self
+    }
+
+    /// Add the Arm(R) SVE instruction set.
+    #[cfg(all(feature = "vectorscan", target_arch = "aarch64"))]
+    pub fn sve(&mut self) -> &mut Self {
+        self.cpu_features |= CpuFeatures::SVE;
+        self
+    }
+
+    /// Remove the Arm(R) SVE instruction set, and the SVE2 one which implies it.
+    #[cfg(all(feature = "vectorscan", target_arch = "aarch64"))]
+    pub fn no_sve(&mut self) -> &mut Self {
+        self.cpu_features.remove(CpuFeatures::SVE | CpuFeatures::SVE2);
+        self
+    }
+
+    /// Add the Arm(R) SVE2 instruction set, which implies SVE.
+    #[cfg(all(feature = "vectorscan", target_arch = "aarch64"))]
+    pub fn sve2(&mut self) -> &mut Self {
+        self.cpu_features |= CpuFeatures::SVE | CpuFeatures::SVE2;
+        self
+    }
+
+    /// Remove the Arm(R) SVE2 instruction set.
+    #[cfg(all(feature = "vectorscan", target_arch = "aarch64"))]
+    pub fn no_sve2(&mut self) -> &mut Self {
+        self.cpu_features.remove(CpuFeatures::SVE2);
+        self
+    }
+
+    /// Constructs the target platform.
+    pub fn build(&self) -> Platform {
+        Platform::new(self.tune, self.cpu_features)
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -124,4 +352,61 @@ pub mod tests {
     pub fn test_platform() {
         assert!(Platform::is_valid().is_ok())
     }
+
+    #[test]
+    pub fn test_platform_info() {
+        #[cfg(hyperscan_x86)]
+        {
+            let platform = Platform::new(Tune::Skylake, CpuFeatures::AVX2);
+
+            assert_eq!(platform.tune(), Some(Tune::Skylake));
+            assert_eq!(platform.cpu_features(), CpuFeatures::AVX2);
+        }
+
+        let host = Platform::host().unwrap();
+
+        assert!(host.tune().is_some());
+        assert_eq!(CpuFeatures::host().unwrap(), host.cpu_features());
+    }
+
+    #[test]
+    pub fn test_platform_builder() {
+        let platform = Platform::builder().build();
+
+        assert_eq!(platform.tune(), Some(Tune::Generic));
+        assert_eq!(platform.cpu_features(), CpuFeatures::empty());
+
+        #[cfg(hyperscan_x86)]
+        {
+            let platform = Platform::builder().tune(Tune::Skylake).avx512().build();
+
+            assert_eq!(platform.tune(), Some(Tune::Skylake));
+            assert_eq!(platform.cpu_features(), CpuFeatures::AVX2 | CpuFeatures::AVX512);
+
+            let platform = platform.to_builder().no_avx512().build();
+
+            assert_eq!(platform.tune(), Some(Tune::Skylake));
+            assert_eq!(platform.cpu_features(), CpuFeatures::AVX2);
+            assert_eq!(
+                platform.to_builder().no_avx2().build().cpu_features(),
+                CpuFeatures::empty()
+            );
+        }
+
+        #[cfg(all(feature = "vectorscan", target_arch = "aarch64"))]
+        {
+            let platform = Platform::builder().sve2().build();
+
+            assert_eq!(platform.cpu_features(), CpuFeatures::SVE | CpuFeatures::SVE2);
+            assert_eq!(platform.to_builder().no_sve2().build().cpu_features(), CpuFeatures::SVE);
+            assert_eq!(
+                platform.to_builder().no_sve().build().cpu_features(),
+                CpuFeatures::empty()
+            );
+        }
+
+        let current = Platform::current().unwrap();
+
+        assert_eq!(current.to_builder().build().cpu_features(), current.cpu_features());
+    }
 }
@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "runtime")]
+use std::{
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+#[cfg(feature = "runtime")]
+use crate::runtime::ReloadableDatabase;
+use crate::{
+    common::{Database, Mode},
+    compile::{Builder, Pattern, Patterns},
+    Result,
+};
+
+/// A registry of the patterns with the stable IDs, which rebuilds the database on demand.
+///
+/// The ID is assigned when a pattern is added, and never reused after it is removed,
+/// so the IDs reported in the matches keep identifying the same rules across the rebuilds.
+///
+/// With the `runtime` feature, the database could be rebuilt into a `ReloadableDatabase`,
+/// either in the current thread or in a background thread, while the scans are in flight.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, PatternRegistry, ReloadableDatabase};
+/// let mut registry = PatternRegistry::new();
+///
+/// let foo = registry.add(pattern! {"foo"});
+/// let bar = registry.add(pattern! {"bar"});
+///
+/// let db = ReloadableDatabase::new(registry.build::<BlockMode>().unwrap()).unwrap();
+/// let mut s = db.load().alloc_scratch().unwrap();
+///
+/// registry.remove(foo);
+/// registry.update(bar, pattern! {"baz"}).unwrap();
+/// let qux = registry.add(pattern! {"qux"});
+///
+/// registry.rebuild(&db).unwrap();
+///
+/// // the scratch space allocated for the previous generation must be reallocated
+/// let scan = db.load();
+/// scan.realloc_scratch(&mut s).unwrap();
+///
+/// let ids = scan
+///     .scan_matches("foo bar baz qux", &s)
+///     .unwrap()
+///     .into_iter()
+///     .map(|m| m.id as usize)
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(ids, vec![bar, qux]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PatternRegistry {
+    patterns: BTreeMap<usize, Pattern>,
+    next_id: usize,
+    revision: u64,
+}
+
+impl PatternRegistry {
+    /// Construct an empty registry.
+    pub fn new() -> Self {
+        PatternRegistry::default()
+    }
+
+    /// Add a pattern, and returns the ID assigned to it.
+    ///
+    /// The ID of the pattern itself is ignored.
+    pub fn add(&mut self, pattern: Pattern) -> usize {
+        let id = self.next_id;
+
+        self.next_id += 1;
+        self.patterns.insert(id, pattern);
+        self.revision += 1;
+
+        id
+    }
+
+    /// Remove the pattern with the ID, and returns it if it was registered.
+    pub fn remove(&mut self, id: usize) -> Option<Pattern> {
+        let pattern = self.patterns.remove(&id);
+
+        if pattern.is_some() {
+            self.revision += 1;
+        }
+
+        pattern
+    }
+
+    /// Replace the pattern with the ID, and returns the previous one,
+    /// or gives back the new pattern if the ID isn't registered.
+    pub fn update(&mut self, id: usize, pattern: Pattern) -> std::result::Result<Pattern, Pattern> {
+        match self.patterns.get_mut(&id) {
+            Some(registered) => {
+                self.revision += 1;
+
+                Ok(std::mem::replace(registered, pattern))
+            }
+            None => Err(pattern),
+        }
+    }
+
+    /// Returns the pattern with the ID.
+    pub fn get(&self, id: usize) -> Option<&Pattern> {
+        self.patterns.get(&id)
+    }
+
+    /// Returns the number of the registered patterns.
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Returns true if no pattern is registered.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Returns the revision of the registry, which is increased by every change of the patterns.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns the registered patterns with their IDs, in the order of the IDs.
+    pub fn patterns(&self) -> Patterns {
+        self.patterns
+            .iter()
+            .map(|(&id, pattern)| Pattern {
+                id: Some(id),
+                ..pattern.clone()
+            })
+            .collect()
+    }
+
+    /// Build the registered patterns into a database.
+    pub fn build<T: Mode>(&self) -> Result<Database<T>> {
+        self.patterns().build()
+    }
+}
+
+#[cfg(feature = "runtime")]
+impl PatternRegistry {
+    /// Build the registered patterns, and activate the database in the `ReloadableDatabase`.
+    pub fn rebuild<T: Mode>(&self, db: &ReloadableDatabase<T>) -> Result<()> {
+        db.reload(|| self.build())
+    }
+
+    /// Build a snapshot of the registered patterns in a background thread,
+    /// and activate the database in the `ReloadableDatabase` when it is built.
+    ///
+    /// The registry could be changed while the database is being built,
+    /// the changes are applied by the next rebuild.
+    ///
+    /// The scratch spaces allocated for the previous generation must be reallocated for the activated database,
+    /// or checked out with `ScanGuard::scratch`.
+    pub fn rebuild_in_background<T>(&self, db: Arc<ReloadableDatabase<T>>) -> JoinHandle<Result<()>>
+    where
+        T: Mode + Send + Sync + 'static,
+    {
+        let patterns = self.patterns();
+
+        thread::spawn(move || db.reload(|| patterns.build()))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::PatternRegistry;
+
+    #[test]
+    fn test_pattern_registry() {
+        let mut registry = PatternRegistry::new();
+
+        let foo = registry.add(pattern! {"foo"});
+        let bar = registry.add(pattern! {"bar"});
+
+        assert_eq!((foo, bar), (0, 1));
+        assert_eq!(registry.revision(), 2);
+
+        assert!(registry.remove(foo).is_some());
+        assert!(registry.remove(foo).is_none());
+        assert!(registry.update(foo, pattern! {"baz"}).is_err());
+        assert_eq!(registry.update(bar, pattern! {"baz"}).unwrap().expression, "bar");
+
+        let qux = registry.add(pattern! {"qux"; CASELESS});
+
+        assert_eq!(qux, 2);
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.revision(), 5);
+        assert_eq!(
+            registry
+                .patterns()
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect::<Vec<_>>(),
+            vec!["1:/baz/", "2:/qux/i"]
+        );
+
+        let db: BlockDatabase = registry.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        assert_eq!(
+            db.scan_matches("foo bar baz QUX", &s)
+                .unwrap()
+                .into_iter()
+                .map(|m| m.id)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn test_rebuild_in_background() {
+        use std::sync::Arc;
+
+        use crate::{BlockMode, ReloadableDatabase};
+
+        let mut registry = PatternRegistry::new();
+        let foo = registry.add(pattern! {"foo"});
+
+        let db = Arc::new(ReloadableDatabase::new(registry.build::<BlockMode>().unwrap()).unwrap());
+        let mut s = db.load().alloc_scratch().unwrap();
+
+        registry.update(foo, pattern! {"bar"}).unwrap();
+        registry.rebuild_in_background(db.clone()).join().unwrap().unwrap();
+
+        let scan = db.load();
+        scan.realloc_scratch(&mut s).unwrap();
+
+        assert_eq!(scan.generation(), 1);
+        assert!(scan.contains("bar", &scan.scratch()).unwrap());
+        assert!(!scan.contains("foo", &s).unwrap());
+        assert!(scan.contains("bar", &s).unwrap());
+    }
+}
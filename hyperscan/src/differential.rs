@@ -0,0 +1,154 @@
+//! Differential testing against the `regex` crate
+//!
+//! The `check` function scans the inputs with both Hyperscan and the `regex` crate,
+//! and compares the end offsets of the matches reported for every expression.
+//! It is intended to be driven by generated patterns and inputs (e.g. with `proptest` or `quickcheck`),
+//! so that rule authors can validate their rule sets as well.
+//!
+//! Hyperscan reports every end offset at which an expression matches, so an offset is expected
+//! whenever the `regex` crate finds a match of the expression ending at it. Both engines run in
+//! byte-oriented mode (without the `UTF8` flag and with Unicode support disabled in `regex`).
+//!
+//! The semantics only overlap for a subset of the syntax, the expressions are skipped when
+//! either engine fails to compile them, e.g. for back-references, look-around or patterns
+//! that match an empty buffer. Assertions depending on the data after the match
+//! (such as `$` or `\b` at the end of a match) are not supported.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use hyperscan::differential;
+//! let expressions = ["foo", "ba[rz]+", "a.c", "x{2,3}"];
+//! let inputs = ["foo bar", "bazzz abc", "xxxx", "a\nc"];
+//!
+//! differential::check(&expressions, &inputs).unwrap();
+//! ```
+use std::error::Error as StdError;
+use std::fmt;
+
+use regex::bytes::{Regex, RegexBuilder};
+
+use crate::{
+    common::BlockDatabase,
+    compile::{Builder, Pattern},
+    runtime::Matching,
+};
+
+/// The end offsets of an expression disagree between Hyperscan and the `regex` crate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The expression being checked.
+    pub expression: String,
+    /// The scanned data.
+    pub input: Vec<u8>,
+    /// The end offsets of the matches found by the `regex` crate.
+    pub expected: Vec<u64>,
+    /// The end offsets of the matches reported by Hyperscan.
+    pub actual: Vec<u64>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expression `{}` on {:?}: regex matches end at {:?}, but hyperscan reports {:?}",
+            self.expression,
+            String::from_utf8_lossy(&self.input),
+            self.expected,
+            self.actual
+        )
+    }
+}
+
+impl StdError for Mismatch {}
+
+/// Compares the end offsets of the matches of every expression on every input.
+///
+/// Returns the first `Mismatch` found, the expressions unsupported by either engine are skipped.
+pub fn check<S, D>(expressions: &[S], inputs: &[D]) -> Result<(), Mismatch>
+where
+    S: AsRef<str>,
+    D: AsRef<[u8]>,
+{
+    for expression in expressions {
+        let expression = expression.as_ref();
+
+        let (db, re) = match compile(expression) {
+            Some(engines) => engines,
+            None => continue,
+        };
+        let scratch = match db.alloc_scratch() {
+            Ok(scratch) => scratch,
+            Err(_) => continue,
+        };
+
+        for input in inputs {
+            let input = input.as_ref();
+            let expected = (0..=input.len())
+                .filter(|&end| re.is_match(&input[..end]))
+                .map(|end| end as u64)
+                .collect::<Vec<_>>();
+            let mut actual = vec![];
+
+            if db
+                .scan(input, &scratch, |_, _, to, _| {
+                    actual.push(to);
+                    Matching::Continue
+                })
+                .is_err()
+            {
+                continue;
+            }
+
+            actual.sort_unstable();
+            actual.dedup();
+
+            if actual != expected {
+                return Err(Mismatch {
+                    expression: expression.to_owned(),
+                    input: input.to_vec(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Compiles the expression with both engines, the `regex` one only matches at the end of the data.
+fn compile(expression: &str) -> Option<(BlockDatabase, Regex)> {
+    let db = Pattern::new(expression).ok()?.build().ok()?;
+    let re = RegexBuilder::new(&format!(r"(?:{})\z", expression))
+        .unicode(false)
+        .build()
+        .ok()?;
+
+    Some((db, re))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let expressions = ["test", "a+b", "[0-9]{2,4}", "(foo|bar)baz", "a.b", "(a|b)*c"];
+        let inputs = [
+            "test testtest",
+            "aaab ab b",
+            "12345 6 78",
+            "foobaz barbaz baz",
+            "a\nb axb",
+            "ababc c",
+        ];
+
+        assert_eq!(check(&expressions, &inputs), Ok(()));
+    }
+
+    #[test]
+    fn test_check_skipped() {
+        assert_eq!(check(&["(a)\\1", "a*"], &["aa"]), Ok(()));
+    }
+}
@@ -0,0 +1,257 @@
+//! A common interface of the Hyperscan and Chimera engines
+//!
+//! The `ScanEngine` trait abstracts compiling the patterns, allocating the scratch space and scanning
+//! the data, reporting the matches as `MatchEvent`, so an application could switch between the performance
+//! of Hyperscan and the full PCRE semantics of Chimera (with the `chimera` feature) with a type parameter.
+//!
+//! The `Scanner` binds a database to its scratch space, which hides the engine behind a `dyn DynScanner`
+//! trait object when the engine is chosen at runtime.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use hyperscan::{prelude::*, MatchEvent};
+//! # use hyperscan::engine::{DynScanner, ScanEngine, Scanner};
+//! fn find<E: ScanEngine>(patterns: &str, data: &str) -> Vec<MatchEvent> {
+//!     let db = E::compile(patterns).unwrap();
+//!     let s = db.alloc_scratch().unwrap();
+//!
+//!     db.scan_all(data.as_bytes(), &s).unwrap()
+//! }
+//!
+//! assert_eq!(
+//!     find::<BlockDatabase>("1:/foo/\n2:/bar/i", "foo BAR"),
+//!     vec![MatchEvent { id: 1, from: 0, to: 3 }, MatchEvent { id: 2, from: 4, to: 7 }]
+//! );
+//!
+//! let scanner: Box<dyn DynScanner> = Box::new(Scanner::<BlockDatabase>::compile("/test/").unwrap());
+//! let mut matches = vec![];
+//!
+//! scanner
+//!     .scan(b"foo test bar", &mut |m| {
+//!         matches.push(m);
+//!         Matching::Continue
+//!     })
+//!     .unwrap();
+//!
+//! assert_eq!(matches, vec![MatchEvent { id: 0, from: 4, to: 8 }]);
+//! ```
+use std::fmt;
+
+use crate::{
+    common::BlockDatabase,
+    compile::{Builder, Patterns},
+    runtime::{MatchEvent, Matching, Scratch},
+    Result,
+};
+
+/// A regular expression engine which compiles the patterns into a database and scans the data with it.
+pub trait ScanEngine {
+    /// The scratch space required to scan with the database.
+    type Scratch;
+
+    /// Compile the patterns in the `id:/expression/flags` dialect, one pattern per line.
+    fn compile(patterns: &str) -> Result<Self>
+    where
+        Self: Sized;
+
+    /// Allocate a scratch space for the database.
+    fn alloc_scratch(&self) -> Result<Self::Scratch>;
+
+    /// Scan the data, calling `on_match` for every match until it returns `Matching::Terminate`.
+    ///
+    /// Returns `HsError::ScanTerminated` if the scan was terminated, whatever the engine.
+    fn scan_events(
+        &self,
+        data: &[u8],
+        scratch: &Self::Scratch,
+        on_match: &mut dyn FnMut(MatchEvent) -> Matching,
+    ) -> Result<()>;
+
+    /// Scan the data and collect all the matches, in the order they are reported.
+    fn scan_all(&self, data: &[u8], scratch: &Self::Scratch) -> Result<Vec<MatchEvent>> {
+        let mut matches = vec![];
+
+        self.scan_events(data, scratch, &mut |m| {
+            matches.push(m);
+            Matching::Continue
+        })?;
+
+        Ok(matches)
+    }
+}
+
+/// The Hyperscan engine in the block mode.
+///
+/// The patterns are compiled with `SOM_LEFTMOST`, so the start of the matches are reported like Chimera.
+impl ScanEngine for BlockDatabase {
+    type Scratch = Scratch;
+
+    fn compile(patterns: &str) -> Result<Self> {
+        patterns.parse::<Patterns>()?.left_most().build()
+    }
+
+    fn alloc_scratch(&self) -> Result<Scratch> {
+        (**self).alloc_scratch()
+    }
+
+    fn scan_events(
+        &self,
+        data: &[u8],
+        scratch: &Scratch,
+        on_match: &mut dyn FnMut(MatchEvent) -> Matching,
+    ) -> Result<()> {
+        self.scan(data, scratch, |id, from, to, _| on_match(MatchEvent { id, from, to }))
+    }
+}
+
+/// The Chimera engine, which reports the matches with the PCRE semantics.
+///
+/// The match limit and recursion limit errors of PCRE are ignored and the scan continues.
+#[cfg(feature = "chimera")]
+impl ScanEngine for crate::chimera::Database {
+    type Scratch = crate::chimera::Scratch;
+
+    fn compile(patterns: &str) -> Result<Self> {
+        use crate::chimera::Builder as _;
+
+        patterns.parse::<crate::chimera::Patterns>()?.build()
+    }
+
+    fn alloc_scratch(&self) -> Result<Self::Scratch> {
+        (**self).alloc_scratch()
+    }
+
+    fn scan_events(
+        &self,
+        data: &[u8],
+        scratch: &Self::Scratch,
+        on_match: &mut dyn FnMut(MatchEvent) -> Matching,
+    ) -> Result<()> {
+        use crate::{chimera, common::Error as HsError, Error};
+
+        self.scan(
+            data,
+            scratch,
+            |id, from, to, _, _: Option<&[chimera::Capture]>| match on_match(MatchEvent { id, from, to }) {
+                Matching::Continue => chimera::Matching::Continue,
+                Matching::Terminate => chimera::Matching::Terminate,
+            },
+            (),
+        )
+        .map_err(|err| match err {
+            Error::Chimera(chimera::Error::ScanTerminated) => Error::Hyperscan(HsError::ScanTerminated),
+            err => err,
+        })
+    }
+}
+
+/// A scanner hiding the engine behind a trait object.
+pub trait DynScanner {
+    /// Scan the data, calling `on_match` for every match until it returns `Matching::Terminate`.
+    fn scan(&self, data: &[u8], on_match: &mut dyn FnMut(MatchEvent) -> Matching) -> Result<()>;
+}
+
+/// A database bound to its scratch space.
+pub struct Scanner<E: ScanEngine> {
+    db: E,
+    scratch: E::Scratch,
+}
+
+impl<E: ScanEngine> fmt::Debug for Scanner<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scanner").finish()
+    }
+}
+
+impl<E: ScanEngine> Scanner<E> {
+    /// Bind the database to a new scratch space.
+    pub fn new(db: E) -> Result<Self> {
+        let scratch = db.alloc_scratch()?;
+
+        Ok(Scanner { db, scratch })
+    }
+
+    /// Compile the patterns in the `id:/expression/flags` dialect, and bind the database to a new scratch space.
+    pub fn compile(patterns: &str) -> Result<Self> {
+        E::compile(patterns).and_then(Self::new)
+    }
+
+    /// Returns the database.
+    pub fn database(&self) -> &E {
+        &self.db
+    }
+
+    /// Returns the scratch space.
+    pub fn scratch(&self) -> &E::Scratch {
+        &self.scratch
+    }
+}
+
+impl<E: ScanEngine> DynScanner for Scanner<E> {
+    fn scan(&self, data: &[u8], on_match: &mut dyn FnMut(MatchEvent) -> Matching) -> Result<()> {
+        self.db.scan_events(data, &self.scratch, on_match)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Error as HsError;
+    use crate::Error;
+
+    const PATTERNS: &str = "1:/foo/\n2:/ba[rz]/i";
+
+    fn scan<E: ScanEngine>(data: &str) -> Vec<MatchEvent> {
+        let db = E::compile(PATTERNS).unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        db.scan_all(data.as_bytes(), &s).unwrap()
+    }
+
+    #[test]
+    fn test_hyperscan_engine() {
+        assert_eq!(
+            scan::<BlockDatabase>("foo BAR baz"),
+            vec![
+                MatchEvent { id: 1, from: 0, to: 3 },
+                MatchEvent { id: 2, from: 4, to: 7 },
+                MatchEvent { id: 2, from: 8, to: 11 }
+            ]
+        );
+    }
+
+    #[cfg(feature = "chimera")]
+    #[test]
+    fn test_chimera_engine() {
+        assert_eq!(
+            scan::<crate::chimera::Database>("foo BAR baz"),
+            scan::<BlockDatabase>("foo BAR baz")
+        );
+    }
+
+    #[test]
+    fn test_dyn_scanner() {
+        #[cfg_attr(not(feature = "chimera"), allow(unused_mut))]
+        let mut scanners: Vec<Box<dyn DynScanner>> =
+            vec![Box::new(Scanner::<BlockDatabase>::compile(PATTERNS).unwrap())];
+
+        #[cfg(feature = "chimera")]
+        scanners.push(Box::new(
+            Scanner::<crate::chimera::Database>::compile(PATTERNS).unwrap(),
+        ));
+
+        for scanner in scanners {
+            let mut matches = vec![];
+
+            assert_eq!(
+                scanner.scan(b"foo bar", &mut |m| {
+                    matches.push(m);
+                    Matching::Terminate
+                }),
+                Err(Error::Hyperscan(HsError::ScanTerminated))
+            );
+            assert_eq!(matches, vec![MatchEvent { id: 1, from: 0, to: 3 }]);
+        }
+    }
+}
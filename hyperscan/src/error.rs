@@ -1,7 +1,6 @@
-use std::fmt;
-use std::result::Result as StdResult;
-
-use thiserror::Error;
+use alloc::string::String;
+use core::fmt;
+use core::result::Result as StdResult;
 
 use crate::{common::Error as HsError, ffi};
 
@@ -9,36 +8,232 @@ use crate::{common::Error as HsError, ffi};
 pub type Result<T> = StdResult<T, Error>;
 
 /// Hyperscan Error
-#[derive(Debug, Error, PartialEq, Eq)]
+///
+/// Without the `std` feature, the error implements `Display` but not `std::error::Error`.
+#[derive(Debug, PartialEq, Eq)]
 pub enum Error {
     /// Hyperscan error
-    #[error(transparent)]
-    Hyperscan(#[from] crate::common::Error),
+    Hyperscan(crate::common::Error),
 
     /// Chimera error
     #[cfg(feature = "chimera")]
-    #[error(transparent)]
-    Chimera(#[from] crate::chimera::Error),
+    Chimera(crate::chimera::Error),
 
     /// Expression error
-    #[error(transparent)]
-    Expr(#[from] crate::compile::ExprError),
+    #[cfg(feature = "compile")]
+    Expr(crate::compile::ExprError),
+
+    /// The expression uses a construct which Hyperscan rejects
+    #[cfg(feature = "compile")]
+    Unsupported(crate::compile::Unsupported),
+
+    /// The framed serialized database is invalid or incompatible
+    Frame(crate::common::FrameError),
+
+    /// The current host can't run the linked Hyperscan library
+    Platform(crate::common::PlatformError),
 
     /// Invalid UTF-8 string
-    #[error(transparent)]
-    Utf8(#[from] std::str::Utf8Error),
+    Utf8(core::str::Utf8Error),
 
     /// Parse integer error
-    #[error(transparent)]
-    ParseInt(#[from] std::num::ParseIntError),
+    ParseInt(core::num::ParseIntError),
 
     /// Parse C string error
-    #[error(transparent)]
-    NulByte(#[from] std::ffi::NulError),
+    NulByte(alloc::ffi::NulError),
 
     /// Invalid flag
-    #[error("invalid pattern flag: {0}")]
     InvalidFlag(char),
+
+    /// The pattern flag is valid but unsupported by the parser or the enabled features
+    UnsupportedFlag(char, &'static str),
+
+    /// The database information could not be parsed
+    InvalidInfo(String),
+
+    /// The logical combination is invalid
+    #[cfg(hyperscan_v5)]
+    InvalidCombination(String),
+
+    /// The pattern ID is shared by multiple tagged patterns, or doesn't fit in the reported `u32` ID
+    #[cfg(feature = "compile")]
+    InvalidId(usize),
+
+    /// The replacement references a capture group which doesn't exist in the regex
+    #[cfg(all(feature = "compile", feature = "runtime"))]
+    InvalidGroup(String),
+
+    /// I/O error, e.g. the input, the database cache or the capture file could not be read
+    #[cfg(feature = "std")]
+    Io(IoError),
+
+    /// The scratch space is already in use, with the backtrace of the conflicting user
+    #[cfg(feature = "debug-scratch")]
+    ScratchInUse(String),
+
+    /// The stream was terminated by the match callback
+    StreamTerminated,
+
+    /// The scan pool has no worker thread
+    #[cfg(all(feature = "runtime", feature = "std"))]
+    NoWorkers,
+
+    /// A worker thread of the scan pool exited before scanning its inputs
+    #[cfg(all(feature = "runtime", feature = "std"))]
+    WorkerExited,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Hyperscan(err) => err.fmt(f),
+            #[cfg(feature = "chimera")]
+            Error::Chimera(err) => err.fmt(f),
+            #[cfg(feature = "compile")]
+            Error::Expr(err) => err.fmt(f),
+            #[cfg(feature = "compile")]
+            Error::Unsupported(err) => err.fmt(f),
+            Error::Frame(err) => err.fmt(f),
+            Error::Platform(err) => err.fmt(f),
+            Error::Utf8(err) => err.fmt(f),
+            Error::ParseInt(err) => err.fmt(f),
+            Error::NulByte(err) => err.fmt(f),
+            Error::InvalidFlag(flag) => write!(f, "invalid pattern flag: {}", flag),
+            Error::UnsupportedFlag(flag, reason) => write!(f, "unsupported pattern flag: {}, {}", flag, reason),
+            Error::InvalidInfo(info) => write!(f, "invalid database information: {}", info),
+            #[cfg(hyperscan_v5)]
+            Error::InvalidCombination(err) => write!(f, "invalid logical combination: {}", err),
+            #[cfg(feature = "compile")]
+            Error::InvalidId(id) => write!(f, "invalid pattern ID: {}, duplicated or out of range", id),
+            #[cfg(all(feature = "compile", feature = "runtime"))]
+            Error::InvalidGroup(name) => write!(f, "invalid capture group reference: ${}", name),
+            #[cfg(feature = "std")]
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            #[cfg(feature = "debug-scratch")]
+            Error::ScratchInUse(backtrace) => {
+                write!(f, "scratch space is already in use, acquired again by:\n{}", backtrace)
+            }
+            Error::StreamTerminated => f.write_str("the stream was terminated by the match callback"),
+            #[cfg(all(feature = "runtime", feature = "std"))]
+            Error::NoWorkers => f.write_str("the scan pool has no worker thread"),
+            #[cfg(all(feature = "runtime", feature = "std"))]
+            Error::WorkerExited => f.write_str("a worker thread of the scan pool exited"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Hyperscan(err) => std::error::Error::source(err),
+            #[cfg(feature = "chimera")]
+            Error::Chimera(err) => std::error::Error::source(err),
+            #[cfg(feature = "compile")]
+            Error::Expr(err) => std::error::Error::source(err),
+            #[cfg(feature = "compile")]
+            Error::Unsupported(err) => std::error::Error::source(err),
+            Error::Frame(err) => std::error::Error::source(err),
+            Error::Platform(err) => std::error::Error::source(err),
+            Error::Utf8(err) => std::error::Error::source(err),
+            Error::ParseInt(err) => std::error::Error::source(err),
+            Error::NulByte(err) => std::error::Error::source(err),
+            Error::Io(err) => Some(&err.0),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_from_error {
+    ($($(#[$attr:meta])* $variant:ident($ty:ty)),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            impl From<$ty> for Error {
+                fn from(err: $ty) -> Self {
+                    Error::$variant(err)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_error! {
+    Hyperscan(crate::common::Error),
+    #[cfg(feature = "chimera")]
+    Chimera(crate::chimera::Error),
+    #[cfg(feature = "compile")]
+    Expr(crate::compile::ExprError),
+    #[cfg(feature = "compile")]
+    Unsupported(crate::compile::Unsupported),
+    Frame(crate::common::FrameError),
+    Platform(crate::common::PlatformError),
+    Utf8(core::str::Utf8Error),
+    ParseInt(core::num::ParseIntError),
+    NulByte(alloc::ffi::NulError),
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(IoError(err))
+    }
+}
+
+/// An I/O error, which compares equal to the errors of the same kind.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct IoError(std::io::Error);
+
+#[cfg(feature = "std")]
+impl IoError {
+    /// Returns the corresponding `ErrorKind` of the error.
+    pub fn kind(&self) -> std::io::ErrorKind {
+        self.0.kind()
+    }
+
+    /// Returns a reference to the inner `io::Error`.
+    pub fn get_ref(&self) -> &std::io::Error {
+        &self.0
+    }
+
+    /// Consumes the error, returning the inner `io::Error`.
+    pub fn into_inner(self) -> std::io::Error {
+        self.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl PartialEq for IoError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind() == other.kind()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Eq for IoError {}
+
+#[cfg(feature = "std")]
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The letters of all pattern flags in the `/expression/flags` grammar.
+#[cfg(any(feature = "compile", feature = "chimera"))]
+const FLAG_LETTERS: &str = "imsHV8WPLCQ";
+
+#[cfg(any(feature = "compile", feature = "chimera"))]
+impl Error {
+    /// Returns the error of a flag letter which isn't accepted by a parser,
+    /// with the reason if it's a valid letter of the grammar.
+    pub(crate) fn unsupported_flag(flag: char, reason: &'static str) -> Self {
+        if FLAG_LETTERS.contains(flag) {
+            Error::UnsupportedFlag(flag, reason)
+        } else {
+            Error::InvalidFlag(flag)
+        }
+    }
 }
 
 pub trait AsResult
@@ -0,0 +1,541 @@
+//! Hybrid prefilter matcher
+//!
+//! `HybridDatabase` compiles the patterns in the `PREFILTER` mode, so Hyperscan accepts patterns
+//! it would otherwise reject as too large or too complex, and confirms every candidate pattern
+//! with the `regex` crate, or a user-provided `Verifier`, before reporting its matches.
+//!
+//! Only the patterns reported by the prefilter are confirmed, and the matches are reported
+//! with their start and end offsets, ordered by the end offset. The confirmation uses the
+//! leftmost-first semantics of the `regex` crate, which reports non-overlapping matches,
+//! unlike Hyperscan which reports every end offset.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use hyperscan::{prelude::*, hybrid::HybridDatabase, BlockMode};
+//! let db: HybridDatabase<BlockMode> = HybridDatabase::new(&patterns!["fo+", "ba[rz]"]).unwrap();
+//! let s = db.prefilter().alloc_scratch().unwrap();
+//!
+//! let mut matches = vec![];
+//!
+//! db.scan("foo baz", &s, |id, range| {
+//!     matches.push((id, range));
+//!     Matching::Continue
+//! })
+//! .unwrap();
+//!
+//! assert_eq!(matches, vec![(0, 0..3), (1, 4..7)]);
+//! ```
+use std::fmt;
+use std::ops::Range;
+
+use regex::bytes::{Regex, RegexBuilder};
+
+use crate::{
+    common::{Block, Database, DatabaseRef, Error as HsError, Mode, Streaming, Vectored},
+    compile::{Builder, Flags, Pattern, Patterns},
+    runtime::{Matching, ScratchRef, Stream},
+    Result,
+};
+
+/// A verifier confirming the candidates reported by the prefilter database.
+///
+/// It's implemented for the confirmation regexes of the patterns, e.g. `Vec<regex::bytes::Regex>`,
+/// and the closures `Fn(usize, &[u8]) -> Vec<Range<usize>>`, which could wrap any other matcher (e.g. Chimera).
+pub trait Verifier {
+    /// Returns the confirmed matches of the pattern at `index` of the pattern set in the data.
+    fn verify(&self, index: usize, data: &[u8]) -> Vec<Range<usize>>;
+}
+
+impl Verifier for Vec<Regex> {
+    fn verify(&self, index: usize, data: &[u8]) -> Vec<Range<usize>> {
+        self.get(index)
+            .map(|re| re.find_iter(data).map(|m| m.range()).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// The confirmation regexes of `HybridDatabase::new`, `None` for the patterns matched exactly by Hyperscan,
+/// which are never passed to the verifier.
+impl Verifier for Vec<Option<Regex>> {
+    fn verify(&self, index: usize, data: &[u8]) -> Vec<Range<usize>> {
+        self.get(index)
+            .and_then(Option::as_ref)
+            .map(|re| re.find_iter(data).map(|m| m.range()).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl<F> Verifier for F
+where
+    F: Fn(usize, &[u8]) -> Vec<Range<usize>>,
+{
+    fn verify(&self, index: usize, data: &[u8]) -> Vec<Range<usize>> {
+        self(index, data)
+    }
+}
+
+/// A database confirming the candidates of a prefilter database with a verifier,
+/// the `regex` crate by default.
+pub struct HybridDatabase<T, V = Vec<Option<Regex>>> {
+    prefilter: Database<T>,
+    ids: Vec<u32>,
+    exact: Vec<bool>,
+    verifier: V,
+}
+
+/// A block mode prefilter database confirming the candidates with a user-provided verifier.
+///
+/// The patterns are compiled in the `PREFILTER` mode, only the patterns reported by the prefilter
+/// are passed to the verifier, and only the confirmed matches are reported, ordered by the end offset.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, hybrid::Prefilter};
+/// let patterns = patterns!["fo+", "ba[rz]"];
+/// let db = Prefilter::with_verifier(&patterns, |index: usize, data: &[u8]| {
+///     let needle: &[u8] = if index == 0 { b"foo" } else { b"baz" };
+///
+///     data.windows(3)
+///         .enumerate()
+///         .filter(|(_, window)| *window == needle)
+///         .map(|(start, _)| start..start + 3)
+///         .collect()
+/// })
+/// .unwrap();
+/// let s = db.prefilter().alloc_scratch().unwrap();
+///
+/// let mut matches = vec![];
+///
+/// db.scan("foo bar baz", &s, |id, range| {
+///     matches.push((id, range));
+///     Matching::Continue
+/// })
+/// .unwrap();
+///
+/// assert_eq!(matches, vec![(0, 0..3), (1, 8..11)]);
+/// ```
+pub type Prefilter<V> = HybridDatabase<Block, V>;
+
+impl<T, V> fmt::Debug for HybridDatabase<T, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HybridDatabase")
+            .field("ids", &self.ids)
+            .field("exact", &self.exact)
+            .finish()
+    }
+}
+
+impl<T: Mode> HybridDatabase<T> {
+    /// Compile the patterns into a prefilter database and their confirmation regexes.
+    ///
+    /// The `CASELESS`, `MULTILINE`, `DOTALL` and `UTF8` flags are applied to the confirmation regexes,
+    /// the extended parameters are only applied by the prefilter.
+    ///
+    /// The patterns which the `regex` crate can't parse, e.g. the PCRE `\Q...\E` quoting,
+    /// are compiled in the exact mode with the `SOM_LEFTMOST` flag, and reported as Hyperscan reports them
+    /// without confirmation. Returns the compile error if Hyperscan can't compile them either,
+    /// e.g. a back reference, which requires `with_verifier` and a backtracking verifier.
+    pub fn new(patterns: &Patterns) -> Result<Self> {
+        let verifier = patterns.iter().map(confirm_regex).collect::<Vec<_>>();
+        let exact = verifier.iter().map(Option::is_none).collect::<Vec<_>>();
+
+        Self::compile(patterns, exact, verifier)
+    }
+}
+
+impl<T: Mode, V: Verifier> HybridDatabase<T, V> {
+    /// Compile the patterns into a prefilter database, confirming all the candidates with the verifier.
+    pub fn with_verifier(patterns: &Patterns, verifier: V) -> Result<Self> {
+        Self::compile(patterns, vec![false; patterns.len()], verifier)
+    }
+
+    fn compile(patterns: &Patterns, exact: Vec<bool>, verifier: V) -> Result<Self> {
+        Ok(HybridDatabase {
+            prefilter: prefilter_patterns(patterns, &exact).build()?,
+            ids: patterns.ids().map(|id| id as u32).collect(),
+            exact,
+            verifier,
+        })
+    }
+
+    /// Returns the prefilter database, e.g. to allocate the scratch space for the scans.
+    pub fn prefilter(&self) -> &DatabaseRef<T> {
+        &self.prefilter
+    }
+
+    /// Returns the verifier.
+    pub fn verifier(&self) -> &V {
+        &self.verifier
+    }
+
+    /// Returns the candidate collector of a scan.
+    fn candidates(&self) -> Candidates<'_> {
+        Candidates {
+            exact: &self.exact,
+            ids: &self.ids,
+            candidates: vec![false; self.ids.len()],
+            matches: vec![],
+        }
+    }
+
+    /// Confirm the candidates in the data, reporting the matches ending at or after `min_end`
+    /// with the offsets relative to `base`, and the exact matches, ordered by the end offset.
+    fn confirm<F>(&self, data: &[u8], base: u64, min_end: usize, found: Candidates<'_>, mut on_match: F) -> Result<()>
+    where
+        F: FnMut(u32, Range<u64>) -> Matching,
+    {
+        let Candidates {
+            candidates,
+            mut matches,
+            ..
+        } = found;
+
+        if candidates.contains(&true) {
+            matches.extend(
+                candidates
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &candidate)| candidate)
+                    .flat_map(|(index, _)| {
+                        let id = self.ids[index];
+
+                        self.verifier
+                            .verify(index, data)
+                            .into_iter()
+                            .filter(|range| range.end >= min_end)
+                            .map(move |range| (base + range.end as u64, id, base + range.start as u64))
+                    }),
+            );
+        }
+
+        matches.sort_unstable();
+
+        for (end, id, start) in matches {
+            if on_match(id, start..end) == Matching::Terminate {
+                return Err(HsError::ScanTerminated.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The candidates reported by the prefilter, and the matches of the exact patterns.
+struct Candidates<'a> {
+    exact: &'a [bool],
+    ids: &'a [u32],
+    candidates: Vec<bool>,
+    matches: Vec<(u64, u32, u64)>,
+}
+
+impl Candidates<'_> {
+    fn on_match(&mut self, index: u32, from: u64, to: u64) -> Matching {
+        let index = index as usize;
+
+        if self.exact[index] {
+            self.matches.push((to, self.ids[index], from));
+        } else {
+            self.candidates[index] = true;
+        }
+
+        Matching::Continue
+    }
+}
+
+/// Returns the patterns in the `PREFILTER` mode, or the exact mode reporting the start of match,
+/// identified by their indexes.
+fn prefilter_patterns(patterns: &Patterns, exact: &[bool]) -> Patterns {
+    patterns
+        .iter()
+        .zip(exact)
+        .enumerate()
+        .map(|(i, (pattern, &exact))| {
+            if exact {
+                Pattern {
+                    flags: pattern.flags | Flags::SOM_LEFTMOST,
+                    id: Some(i),
+                    ..pattern.clone()
+                }
+            } else {
+                Pattern {
+                    flags: (pattern.flags | Flags::PREFILTER) - Flags::SOM_LEFTMOST,
+                    id: Some(i),
+                    som: None,
+                    ..pattern.clone()
+                }
+            }
+        })
+        .collect()
+}
+
+/// Build the confirmation regex of the pattern, or `None` if the `regex` crate can't parse it,
+/// so the pattern falls back to the exact mode instead of failing the compile.
+fn confirm_regex(pattern: &Pattern) -> Option<Regex> {
+    RegexBuilder::new(&pattern.expression)
+        .case_insensitive(pattern.flags.contains(Flags::CASELESS))
+        .multi_line(pattern.flags.contains(Flags::MULTILINE))
+        .dot_matches_new_line(pattern.flags.contains(Flags::DOTALL))
+        .unicode(pattern.flags.contains(Flags::UTF8))
+        .build()
+        .ok()
+}
+
+impl<V: Verifier> HybridDatabase<Block, V> {
+    /// Scan the data and report the confirmed matches with the pattern ID and the match range.
+    pub fn scan<D, F>(&self, data: D, scratch: &ScratchRef, on_match: F) -> Result<()>
+    where
+        D: AsRef<[u8]>,
+        F: FnMut(u32, Range<u64>) -> Matching,
+    {
+        let data = data.as_ref();
+        let mut found = self.candidates();
+
+        self.prefilter
+            .scan(data, scratch, |id, from, to, _| found.on_match(id, from, to))?;
+
+        self.confirm(data, 0, 0, found, on_match)
+    }
+}
+
+impl<V: Verifier> HybridDatabase<Vectored, V> {
+    /// Scan the buffers and report the confirmed matches with the pattern ID and the match range,
+    /// the candidates are confirmed against a concatenated view of the buffers.
+    pub fn scan<I, D, F>(&self, data: I, scratch: &ScratchRef, on_match: F) -> Result<()>
+    where
+        I: IntoIterator<Item = D>,
+        D: AsRef<[u8]>,
+        F: FnMut(u32, Range<u64>) -> Matching,
+    {
+        let bufs = data.into_iter().collect::<Vec<_>>();
+        let mut found = self.candidates();
+
+        self.prefilter
+            .scan(&bufs, scratch, |id, from, to, _| found.on_match(id, from, to))?;
+
+        let view = if found.candidates.contains(&true) {
+            bufs.iter().flat_map(|buf| buf.as_ref()).copied().collect::<Vec<_>>()
+        } else {
+            vec![]
+        };
+
+        self.confirm(&view, 0, 0, found, on_match)
+    }
+}
+
+impl<V: Verifier> HybridDatabase<Streaming, V> {
+    /// Open a stream confirming the candidates within a replay window of `window` bytes
+    /// preceding the written data.
+    pub fn open_stream(&self, window: usize) -> Result<HybridStream<'_, V>> {
+        Ok(HybridStream {
+            db: self,
+            stream: self.prefilter.open_stream()?,
+            window,
+            tail: Vec::with_capacity(window),
+            offset: 0,
+        })
+    }
+}
+
+/// A stream of the hybrid prefilter matcher.
+///
+/// The stream keeps the last `window` bytes written, and confirms the candidates against
+/// them followed by the data being written, so a match starting before the replay window is missed.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, hybrid::HybridDatabase, StreamingMode};
+/// let db: HybridDatabase<StreamingMode> = HybridDatabase::new(&patterns!["foo+"]).unwrap();
+/// let s = db.prefilter().alloc_scratch().unwrap();
+/// let mut st = db.open_stream(16).unwrap();
+///
+/// let mut matches = vec![];
+/// let mut on_match = |id: u32, range: std::ops::Range<u64>| {
+///     matches.push((id, range));
+///     Matching::Continue
+/// };
+///
+/// st.scan("bar fo", &s, &mut on_match).unwrap();
+/// st.scan("oo baz", &s, &mut on_match).unwrap();
+/// st.close(&s, &mut on_match).unwrap();
+///
+/// assert_eq!(matches, vec![(0, 4..8)]);
+/// ```
+pub struct HybridStream<'a, V = Vec<Option<Regex>>> {
+    db: &'a HybridDatabase<Streaming, V>,
+    stream: Stream,
+    window: usize,
+    tail: Vec<u8>,
+    offset: u64,
+}
+
+impl<V> fmt::Debug for HybridStream<'_, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HybridStream")
+            .field("window", &self.window)
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
+
+impl<V: Verifier> HybridStream<'_, V> {
+    /// Write data to be scanned to the stream,
+    /// and report the confirmed matches ending in the data with their absolute offsets.
+    pub fn scan<D, F>(&mut self, data: D, scratch: &ScratchRef, on_match: F) -> Result<()>
+    where
+        D: AsRef<[u8]>,
+        F: FnMut(u32, Range<u64>) -> Matching,
+    {
+        let data = data.as_ref();
+        let mut found = self.db.candidates();
+
+        self.stream
+            .scan(data, scratch, |id, from, to, _| found.on_match(id, from, to))?;
+
+        let replayed = self.tail.len();
+        let base = self.offset - replayed as u64;
+
+        self.tail.extend_from_slice(data);
+        self.offset += data.len() as u64;
+
+        let res = self.db.confirm(&self.tail, base, replayed + 1, found, on_match);
+
+        let excess = self.tail.len().saturating_sub(self.window);
+        self.tail.drain(..excess);
+
+        res
+    }
+
+    /// Close the stream, and report the confirmed matches of the candidates found at the end of data,
+    /// which end at the end of the stream.
+    pub fn close<F>(self, scratch: &ScratchRef, on_match: F) -> Result<()>
+    where
+        F: FnMut(u32, Range<u64>) -> Matching,
+    {
+        let mut found = self.db.candidates();
+
+        self.stream
+            .close(scratch, |id, from, to, _| found.on_match(id, from, to))?;
+
+        let base = self.offset - self.tail.len() as u64;
+
+        self.db.confirm(&self.tail, base, self.tail.len(), found, on_match)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use crate::hybrid::{HybridDatabase, Prefilter};
+    use crate::prelude::*;
+    use crate::Error;
+    use crate::{BlockMode, StreamingMode, VectoredMode};
+
+    #[test]
+    fn test_prefilter() {
+        let patterns = patterns!["foo", "ba[rz]"];
+        let mut matches = vec![];
+
+        let db: Prefilter<_> = HybridDatabase::new(&patterns).unwrap();
+        let s = db.prefilter().alloc_scratch().unwrap();
+
+        db.scan("foobar baz", &s, |id, range| {
+            matches.push((id, range));
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![(0, 0..3), (1, 3..6), (1, 7..10)]);
+
+        let db = Prefilter::with_verifier(&patterns, |index: usize, data: &[u8]| {
+            data.windows(3)
+                .position(|window| index == 1 && window == b"bar")
+                .map(|start| start..start + 3)
+                .into_iter()
+                .collect()
+        })
+        .unwrap();
+        let s = db.prefilter().alloc_scratch().unwrap();
+
+        matches.clear();
+
+        db.scan("bar foo baz", &s, |id, range| {
+            matches.push((id, range));
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![(1, 0..3)]);
+        assert!(db.scan("bar", &s, |_, _| Matching::Terminate).is_err());
+    }
+
+    #[test]
+    fn test_hybrid_exact_fallback() {
+        // the `regex` crate doesn't support the PCRE quoting, which Hyperscan matches exactly
+        let patterns = patterns![r"\Qa.b\E", "ba[rz]"];
+        let db: HybridDatabase<BlockMode> = HybridDatabase::new(&patterns).unwrap();
+        let s = db.prefilter().alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan("axb a.b baz", &s, |id, range| {
+            matches.push((id, range));
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![(0, 4..7), (1, 8..11)]);
+        assert!(db.verifier()[0].is_none());
+
+        // neither the `regex` crate nor Hyperscan in the exact mode support the back references
+        assert!(matches!(
+            HybridDatabase::<BlockMode>::new(&patterns![r"(a)\1"]),
+            Err(Error::Hyperscan(_))
+        ));
+    }
+
+    #[test]
+    fn test_hybrid_database() {
+        let patterns = patterns!["foo", "ba[rz]"];
+        let mut matches = vec![];
+        let mut on_match = |id: u32, range: Range<u64>| {
+            matches.push((id, range));
+            Matching::Continue
+        };
+
+        let db: HybridDatabase<BlockMode> = HybridDatabase::new(&patterns).unwrap();
+        let s = db.prefilter().alloc_scratch().unwrap();
+
+        db.scan("foobar baz", &s, &mut on_match).unwrap();
+
+        let db: HybridDatabase<VectoredMode> = HybridDatabase::new(&patterns).unwrap();
+        let s = db.prefilter().alloc_scratch().unwrap();
+
+        db.scan(vec!["foob", "ar baz"], &s, &mut on_match).unwrap();
+
+        let db: HybridDatabase<StreamingMode> = HybridDatabase::new(&patterns).unwrap();
+        let s = db.prefilter().alloc_scratch().unwrap();
+        let mut st = db.open_stream(8).unwrap();
+
+        st.scan("foob", &s, &mut on_match).unwrap();
+        st.scan("ar baz", &s, &mut on_match).unwrap();
+        st.close(&s, &mut on_match).unwrap();
+
+        assert_eq!(
+            matches,
+            vec![
+                (0, 0..3),
+                (1, 3..6),
+                (1, 7..10),
+                (0, 0..3),
+                (1, 3..6),
+                (1, 7..10),
+                (0, 0..3),
+                (1, 3..6),
+                (1, 7..10),
+            ]
+        );
+    }
+}
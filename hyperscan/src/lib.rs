@@ -35,8 +35,11 @@
 //! ```
 #![deny(missing_docs, rust_2018_compatibility, rust_2018_idioms)]
 #![cfg_attr(test, deny(warnings))]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![cfg_attr(feature = "pattern", feature(pattern))]
 
+extern crate alloc;
+
 mod ffi {
     pub use hyperscan_sys::*;
 }
@@ -46,12 +49,34 @@ mod error;
 #[cfg(feature = "compile")]
 #[macro_use]
 mod compile;
+#[cfg(all(feature = "compile", feature = "runtime"))]
+pub mod bench;
 #[cfg(feature = "chimera")]
 pub mod chimera;
+#[cfg(feature = "testing")]
+pub mod differential;
+#[cfg(all(feature = "compile", feature = "runtime"))]
+pub mod engine;
+#[cfg(feature = "hybrid")]
+pub mod hybrid;
+#[cfg(all(feature = "literal", feature = "runtime"))]
+mod literal_set;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "pcap")]
+pub mod pcap;
+#[cfg(feature = "runtime")]
+pub mod plugin;
 #[cfg(all(feature = "compile", feature = "runtime"))]
 pub mod regex;
+#[cfg(all(feature = "runtime", feature = "std"))]
+pub mod report;
 #[cfg(feature = "runtime")]
 mod runtime;
+#[cfg(all(feature = "runtime", feature = "std"))]
+pub mod shadow;
+#[cfg(all(feature = "compile", feature = "runtime"))]
+mod tagged;
 
 #[doc(hidden)]
 #[deprecated = "use `BlockMode` instead"]
@@ -66,10 +91,15 @@ pub use crate::common::Streaming;
 #[deprecated = "use `VectoredMode` instead"]
 pub use crate::common::Vectored;
 pub use crate::common::{
-    version, version_str, Block as BlockMode, BlockDatabase, Database, DatabaseRef, Error as HsError, Mode,
-    Serialized as SerializedDatabase, Streaming as StreamingMode, StreamingDatabase, Vectored as VectoredMode,
-    VectoredDatabase,
+    align_storage, check_platform, linked_version_features, set_allocator, version, version_str, AlignedBuf, Allocator,
+    Block as BlockMode, BlockDatabase, Database, DatabaseRef, DbMode, Error as HsError, FrameError, FramedDatabase,
+    Mode, PlatformError, Serialized as SerializedDatabase, Streaming as StreamingMode, StreamingDatabase,
+    Vectored as VectoredMode, VectoredDatabase, DATABASE_ALIGNMENT,
 };
+#[cfg(feature = "std")]
+pub use crate::common::{AllocatorGuard, Bundle, FeatureLevel};
+#[cfg(feature = "std")]
+pub use crate::error::IoError;
 pub use crate::error::{Error, Result};
 
 cfg_if::cfg_if! {
@@ -83,23 +113,48 @@ cfg_if::cfg_if! {
         #[doc(hidden)]
         #[deprecated = "use `PatternFlags` instead"]
         pub use crate::compile::Flags as CompileFlags;
+        pub use crate::common::DbInfo;
         pub use crate::compile::{
-            compile, Builder as DatabaseBuilder, Builder, CpuFeatures, Error as CompileError, ExprExt, ExprInfo,
-            Flags as PatternFlags, Pattern, Patterns, Platform, PlatformRef, SomHorizon, Tune,
+            compile, Builder as DatabaseBuilder, Builder, Cache, CachedBuilder, Construct, CpuFeatures, DirCache,
+            Error as CompileError, ExprExt, ExprInfo, FatDatabase, Flags as PatternFlags, ParseError, Pattern, PatternIndex,
+            PatternRegistry, Patterns, Platform, PlatformBuilder, PlatformRef, Rejected, SomHorizon, Tune, Unsupported,
         };
+        #[cfg(hyperscan_v5)]
+        pub use crate::compile::Combination;
         #[cfg(feature = "literal")]
-        pub use crate::compile::{Literal, LiteralFlags, Literals};
+        pub use crate::compile::{Literal, LiteralFlags, Literals, MixedPattern, MixedPatterns};
     }
 }
 
+#[cfg(all(feature = "runtime", feature = "async"))]
+pub use crate::runtime::set_blocking_threads;
+#[cfg(feature = "debug-scratch")]
+pub use crate::runtime::ScratchStats;
+#[cfg(all(feature = "runtime", feature = "std"))]
+pub use crate::runtime::{
+    AccumulatePolicy, Line, MatchAccumulator, Matches, PerThread, PooledScratch, ReloadStats, ReloadableDatabase,
+    ScanGuard, ScanPool, ScopedStream, SessionMap, StreamMap, StreamScanner, StreamSink, SyncScratchPool,
+};
 #[cfg(feature = "runtime")]
-pub use crate::runtime::{MatchEventHandler, Matching, Scratch, ScratchRef, Stream, StreamRef};
+pub use crate::runtime::{
+    AnyDatabase, Checkpoint, CheckpointingStream, ChunkMatch, ChunkedStream, Filtered, ManagedStream, Match,
+    MatchEvent, MatchEventHandler, MatchStart, Matching, OnMatch, OnTerminate, OverlapScanner, ScanFilter, ScanFlags,
+    ScanOutcome, Scratch, ScratchRef, Stream, StreamRef, VectoredData,
+};
+
+#[cfg(all(feature = "literal", feature = "runtime"))]
+pub use crate::literal_set::LiteralSet;
+#[cfg(all(feature = "compile", feature = "runtime"))]
+pub use crate::tagged::{TaggedDatabase, TaggedPatterns};
 
 /// The `hyperscan` Prelude
 pub mod prelude {
     #[cfg(feature = "compile")]
     pub use crate::{compile, pattern, Builder, CompileFlags, Pattern, Patterns};
 
+    #[cfg(feature = "literal")]
+    pub use crate::{literal, Literal, Literals, MixedPatterns};
+
     #[cfg(feature = "runtime")]
     pub use crate::{Matching, Scratch, Stream};
 
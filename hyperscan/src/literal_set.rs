@@ -0,0 +1,169 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::iter::FromIterator;
+
+use crate::{
+    common::BlockDatabase,
+    compile::{compile_literals, LiteralFlags},
+    ffi,
+    runtime::{Matching, Scratch},
+    Result,
+};
+
+/// A set of the pure literals identified by their IDs, e.g. the indicators of compromise.
+///
+/// The literals may contain any bytes, they are compiled with `hs_compile_lit_multi` on demand,
+/// and the scanning only reports the IDs of the literals found in the data.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::LiteralSet;
+/// let mut iocs = LiteralSet::new();
+///
+/// iocs.caseless(true)
+///     .insert("evil.example.com", 1)
+///     .insert(b"\xde\xad\xbe\xef", 2)
+///     .insert("203.0.113.7", 3);
+///
+/// assert_eq!(iocs.scan_collect("GET http://EVIL.example.com/ from 203.0.113.7").unwrap(), vec![1, 3]);
+/// assert!(iocs.scan_collect("nothing").unwrap().is_empty());
+/// ```
+#[derive(Default)]
+pub struct LiteralSet {
+    literals: Vec<(Vec<u8>, usize)>,
+    caseless: bool,
+    compiled: Option<(BlockDatabase, Scratch)>,
+}
+
+impl fmt::Debug for LiteralSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LiteralSet")
+            .field("literals", &self.literals.len())
+            .field("caseless", &self.caseless)
+            .field("built", &self.compiled.is_some())
+            .finish()
+    }
+}
+
+impl<B: AsRef<[u8]>> FromIterator<(B, usize)> for LiteralSet {
+    fn from_iter<T: IntoIterator<Item = (B, usize)>>(iter: T) -> Self {
+        let mut set = LiteralSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<B: AsRef<[u8]>> Extend<(B, usize)> for LiteralSet {
+    fn extend<T: IntoIterator<Item = (B, usize)>>(&mut self, iter: T) {
+        self.literals
+            .extend(iter.into_iter().map(|(literal, id)| (literal.as_ref().to_vec(), id)));
+        self.compiled = None;
+    }
+}
+
+impl LiteralSet {
+    /// Construct an empty literal set.
+    pub fn new() -> Self {
+        LiteralSet::default()
+    }
+
+    /// Insert a literal with the ID, the same ID could be shared by many literals.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, literal: B, id: usize) -> &mut Self {
+        self.literals.push((literal.as_ref().to_vec(), id));
+        self.compiled = None;
+        self
+    }
+
+    /// Set whether the literals are matched case-insensitively.
+    pub fn caseless(&mut self, yes: bool) -> &mut Self {
+        if self.caseless != yes {
+            self.caseless = yes;
+            self.compiled = None;
+        }
+        self
+    }
+
+    /// Returns the number of the literals in the set.
+    pub fn len(&self) -> usize {
+        self.literals.len()
+    }
+
+    /// Returns true if the set contains no literal.
+    pub fn is_empty(&self) -> bool {
+        self.literals.is_empty()
+    }
+
+    /// Compile all the literals into a database at once.
+    ///
+    /// The set is compiled on the first scan if it isn't built yet,
+    /// inserting a literal or changing the options requires the set to be rebuilt.
+    pub fn build(&mut self) -> Result<&mut Self> {
+        self.compiled = None;
+
+        if !self.literals.is_empty() {
+            let mut flags = LiteralFlags::SINGLEMATCH;
+            flags.set(LiteralFlags::CASELESS, self.caseless);
+
+            let exprs = self
+                .literals
+                .iter()
+                .map(|(literal, _)| literal.as_slice())
+                .collect::<Vec<_>>();
+            let flags = vec![flags.bits(); exprs.len()];
+            let ids = self.literals.iter().map(|&(_, id)| id as u32).collect::<Vec<_>>();
+
+            let db: BlockDatabase = compile_literals(&exprs, &flags, &ids, ffi::HS_MODE_BLOCK, None)?;
+            let scratch = db.alloc_scratch()?;
+
+            self.compiled = Some((db, scratch));
+        }
+
+        Ok(self)
+    }
+
+    /// Scan the data, and returns the sorted IDs of the literals found in it.
+    pub fn scan_collect<T: AsRef<[u8]>>(&mut self, data: T) -> Result<Vec<usize>> {
+        if self.compiled.is_none() {
+            self.build()?;
+        }
+
+        let mut hits = BTreeSet::new();
+
+        if let Some((db, scratch)) = &self.compiled {
+            db.scan(data, scratch, |id, _, _, _| {
+                hits.insert(id as usize);
+                Matching::Continue
+            })?;
+        }
+
+        Ok(hits.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::LiteralSet;
+
+    #[test]
+    fn test_literal_set() {
+        let mut set = vec![("foo", 1), ("bar", 2), ("baz", 2)]
+            .into_iter()
+            .collect::<LiteralSet>();
+
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.scan_collect("foo bar baz FOO").unwrap(), vec![1, 2]);
+        assert_eq!(set.scan_collect("BAZ").unwrap(), Vec::<usize>::new());
+
+        set.caseless(true).insert(&b"\x00qux"[..], 3);
+
+        assert_eq!(set.scan_collect("BAZ \x00QUX").unwrap(), vec![2, 3]);
+
+        assert!(LiteralSet::new()
+            .build()
+            .unwrap()
+            .scan_collect("foo")
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,280 @@
+//! The metrics hooks of the scans
+//!
+//! A `ScanMetrics` installed with `set_scan_metrics` is called by the runtime after each scan with the scanned bytes
+//! and the elapsed time, for each reported match, and when a scan is terminated by the match callback,
+//! e.g. to export the scan throughput, the latency histogram and the match counts to Prometheus.
+//!
+//! `Metrics` reports them to the [metrics](https://docs.rs/metrics) facade, which is exported by the installed
+//! recorder, e.g. `metrics-exporter-prometheus`.
+//!
+//! | Metric | Type | Labels |
+//! |--------|------|--------|
+//! | `hyperscan_scans_total` | counter | `mode` |
+//! | `hyperscan_scanned_bytes_total` | counter | `mode` |
+//! | `hyperscan_scan_duration_seconds` | histogram | `mode` |
+//! | `hyperscan_matches_total` | counter | `mode`, `id` |
+//! | `hyperscan_scans_terminated_total` | counter | `mode` |
+//!
+//! The matches are counted by wrapping the match callback, which costs an extra indirect call per match,
+//! no overhead is added to the scans before the metrics are installed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+//! # use std::time::Duration;
+//! # use hyperscan::prelude::*;
+//! # use hyperscan::metrics::{set_scan_metrics, ScanKind, ScanMetrics};
+//! #[derive(Default)]
+//! struct Counters {
+//!     bytes: AtomicUsize,
+//!     matches: AtomicUsize,
+//! }
+//!
+//! impl ScanMetrics for Counters {
+//!     fn on_scan(&self, _kind: ScanKind, bytes: usize, _elapsed: Duration) {
+//!         self.bytes.fetch_add(bytes, Ordering::Relaxed);
+//!     }
+//!
+//!     fn on_match(&self, _kind: ScanKind, _id: u32) {
+//!         self.matches.fetch_add(1, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! let counters = Arc::new(Counters::default());
+//! set_scan_metrics(Some(counters.clone()));
+//!
+//! let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+//! let s = db.alloc_scratch().unwrap();
+//!
+//! db.scan("foo test bar test", &s, |_, _, _, _| Matching::Continue).unwrap();
+//!
+//! set_scan_metrics(None);
+//!
+//! assert_eq!(counters.bytes.load(Ordering::Relaxed), 17);
+//! assert_eq!(counters.matches.load(Ordering::Relaxed), 2);
+//! ```
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, RwLock,
+};
+use std::time::Duration;
+
+use crate::common::DbMode;
+
+/// The kind of the scan reporting the metrics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScanKind {
+    /// Scanning a block database.
+    Block,
+    /// Scanning a vectored database.
+    Vectored,
+    /// Writing to or closing a stream.
+    Streaming,
+    /// Scanning a Chimera database.
+    Chimera,
+}
+
+impl ScanKind {
+    /// Returns the name of the kind, which is used as the `mode` label of `Metrics`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScanKind::Block => "block",
+            ScanKind::Vectored => "vectored",
+            ScanKind::Streaming => "streaming",
+            ScanKind::Chimera => "chimera",
+        }
+    }
+}
+
+impl From<DbMode> for ScanKind {
+    fn from(mode: DbMode) -> Self {
+        match mode {
+            DbMode::Block => ScanKind::Block,
+            DbMode::Vectored => ScanKind::Vectored,
+            DbMode::Streaming => ScanKind::Streaming,
+        }
+    }
+}
+
+impl fmt::Display for ScanKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The hooks called by the runtime to collect the metrics of the scans.
+///
+/// The hooks are called on the scanning thread, during or right after the library call,
+/// so they should be cheap and must not block.
+pub trait ScanMetrics: Send + Sync {
+    /// Called after a scan with the number of the scanned bytes and the elapsed time of the library call.
+    fn on_scan(&self, kind: ScanKind, bytes: usize, elapsed: Duration) {
+        let _ = (kind, bytes, elapsed);
+    }
+
+    /// Called for each match reported to the match callback, with the ID of the expression that matched.
+    fn on_match(&self, kind: ScanKind, id: u32) {
+        let _ = (kind, id);
+    }
+
+    /// Called when a scan is terminated by the match callback.
+    fn on_terminated(&self, kind: ScanKind) {
+        let _ = kind;
+    }
+}
+
+static SCAN_METRICS: RwLock<Option<Arc<dyn ScanMetrics>>> = RwLock::new(None);
+
+/// Whether the metrics hooks are installed, checked by the scans without taking the lock.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Install the metrics hooks called by the following scans, `None` uninstalls them.
+///
+/// Returns the previously installed hooks.
+pub fn set_scan_metrics(metrics: Option<Arc<dyn ScanMetrics>>) -> Option<Arc<dyn ScanMetrics>> {
+    let mut installed = match SCAN_METRICS.write() {
+        Ok(installed) => installed,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    INSTALLED.store(metrics.is_some(), Ordering::Release);
+
+    std::mem::replace(&mut *installed, metrics)
+}
+
+/// Returns the installed metrics hooks.
+pub fn scan_metrics() -> Option<Arc<dyn ScanMetrics>> {
+    if !INSTALLED.load(Ordering::Acquire) {
+        return None;
+    }
+
+    match SCAN_METRICS.read() {
+        Ok(installed) => installed.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// The `ScanMetrics` reporting to the `metrics` facade.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use hyperscan::metrics::{set_scan_metrics, Metrics};
+/// set_scan_metrics(Some(Arc::new(Metrics)));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Metrics;
+
+thread_local! {
+    /// The `id` labels of the matches, formatted once per thread.
+    static ID_LABELS: RefCell<HashMap<u32, Arc<str>>> = RefCell::new(HashMap::new());
+}
+
+fn id_label(id: u32) -> Arc<str> {
+    ID_LABELS.with(|labels| {
+        labels
+            .borrow_mut()
+            .entry(id)
+            .or_insert_with(|| id.to_string().into())
+            .clone()
+    })
+}
+
+impl ScanMetrics for Metrics {
+    fn on_scan(&self, kind: ScanKind, bytes: usize, elapsed: Duration) {
+        ::metrics::counter!("hyperscan_scans_total", "mode" => kind.as_str()).increment(1);
+        ::metrics::counter!("hyperscan_scanned_bytes_total", "mode" => kind.as_str()).increment(bytes as u64);
+        ::metrics::histogram!("hyperscan_scan_duration_seconds", "mode" => kind.as_str()).record(elapsed);
+    }
+
+    fn on_match(&self, kind: ScanKind, id: u32) {
+        ::metrics::counter!("hyperscan_matches_total", "mode" => kind.as_str(), "id" => id_label(id)).increment(1);
+    }
+
+    fn on_terminated(&self, kind: ScanKind) {
+        ::metrics::counter!("hyperscan_scans_terminated_total", "mode" => kind.as_str()).increment(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::prelude::*;
+
+    const ID: u32 = 4242;
+
+    #[derive(Default)]
+    struct Recorded {
+        scans: Mutex<Vec<(ScanKind, usize)>>,
+        matches: Mutex<Vec<ScanKind>>,
+        terminated: Mutex<Vec<ScanKind>>,
+    }
+
+    impl ScanMetrics for Recorded {
+        fn on_scan(&self, kind: ScanKind, bytes: usize, _elapsed: Duration) {
+            self.scans.lock().unwrap().push((kind, bytes));
+        }
+
+        fn on_match(&self, kind: ScanKind, id: u32) {
+            if id == ID {
+                self.matches.lock().unwrap().push(kind);
+            }
+        }
+
+        fn on_terminated(&self, kind: ScanKind) {
+            self.terminated.lock().unwrap().push(kind);
+        }
+    }
+
+    #[test]
+    fn test_scan_metrics() {
+        let recorded = Arc::new(Recorded::default());
+        let prev = set_scan_metrics(Some(recorded.clone()));
+
+        let mut p = pattern! {"metered"};
+        p.id = Some(ID as usize);
+
+        let db: BlockDatabase = p.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = 0;
+
+        db.scan("one metered, two metered", &s, |_, _, _, _| {
+            matches += 1;
+            Matching::Continue
+        })
+        .unwrap();
+        db.scan("metered once", &s, Matching::Terminate).unwrap_err();
+
+        let db: StreamingDatabase = p.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let st = db.open_stream().unwrap();
+
+        st.scan("mete", &s, ()).unwrap();
+        st.scan("red!", &s, ()).unwrap();
+        st.close(&s, ()).unwrap();
+
+        set_scan_metrics(prev);
+
+        assert_eq!(matches, 2);
+
+        let scans = recorded.scans.lock().unwrap();
+
+        assert!(scans.contains(&(ScanKind::Block, 24)));
+        assert!(scans.contains(&(ScanKind::Block, 12)));
+        assert!(scans.contains(&(ScanKind::Streaming, 4)));
+        assert!(scans.contains(&(ScanKind::Streaming, 0)));
+
+        let matches = recorded.matches.lock().unwrap();
+
+        assert_eq!(matches.iter().filter(|&&kind| kind == ScanKind::Block).count(), 3);
+        assert_eq!(matches.iter().filter(|&&kind| kind == ScanKind::Streaming).count(), 1);
+        assert!(recorded.terminated.lock().unwrap().contains(&ScanKind::Block));
+    }
+}
@@ -0,0 +1,440 @@
+//! Extraction of the session payloads from network captures
+//!
+//! The `SessionExtractor` reads a capture file in the classic `pcap` format, decodes the Ethernet
+//! (or raw IP) frames, and yields the TCP and UDP payloads of the unfragmented IPv4 packets keyed by
+//! their `SessionKey`, which could be scanned with a `StreamMap` keeping a stream per session.
+//!
+//! The `pcapng` format isn't supported, unlike the examples which used to read the captures through libpcap,
+//! a `pcapng` capture could be converted with `editcap -F pcap in.pcapng out.pcap`.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! # use hyperscan::{prelude::*, pcap::{SessionExtractor, SessionKey}, StreamMap};
+//! let db: StreamingDatabase = pattern! {"test"}.build().unwrap();
+//! let s = db.alloc_scratch().unwrap();
+//! let mut sessions = StreamMap::new(&db);
+//! let mut matches = 0;
+//! let mut on_match = |_: &SessionKey, _, _, _, _| {
+//!     matches += 1;
+//!     Matching::Continue
+//! };
+//!
+//! let packets = SessionExtractor::open("traffic.pcap")
+//!     .unwrap()
+//!     .scan_into(&mut sessions, &s, &mut on_match)
+//!     .unwrap();
+//!
+//! sessions.close_all(&s, &mut on_match).unwrap();
+//!
+//! println!("scanned {} packets with {} matches", packets, matches);
+//! ```
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{self, BufReader, ErrorKind, Read};
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::path::Path;
+
+use crate::{
+    runtime::{Matching, ScratchRef, StreamMap},
+    Error, Result,
+};
+
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+
+/// The largest record accepted whatever the snapshot length of the capture, the same as libpcap.
+const MAX_SNAPLEN: usize = 256 * 1024;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+
+const IPPROTO_TCP: u8 = 6;
+const IPPROTO_UDP: u8 = 17;
+
+/// The key identifying the session of a packet, from its IP and transport headers.
+///
+/// The key is directional, the packets of the two directions of a connection belong to different sessions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SessionKey {
+    /// The transport protocol number, e.g. 6 for TCP and 17 for UDP.
+    pub proto: u8,
+    /// The source address and port.
+    pub src: SocketAddrV4,
+    /// The destination address and port.
+    pub dst: SocketAddrV4,
+}
+
+/// Decode an Ethernet frame, returning the session and the payload of a TCP or UDP packet.
+///
+/// Returns `None` for the other protocols, the truncated packets and the IPv4 fragments.
+pub fn decode(frame: &[u8]) -> Option<(SessionKey, &[u8])> {
+    let mut ethertype = u16::from_be_bytes(frame.get(12..14)?.try_into().ok()?);
+    let mut off = 14;
+
+    if ethertype == ETHERTYPE_VLAN {
+        ethertype = u16::from_be_bytes(frame.get(16..18)?.try_into().ok()?);
+        off = 18;
+    }
+
+    if ethertype == ETHERTYPE_IPV4 {
+        decode_ipv4(&frame[off..])
+    } else {
+        None
+    }
+}
+
+/// Decode an IPv4 packet, returning the session and the payload of a TCP or UDP packet.
+///
+/// Returns `None` for the other protocols, the truncated packets and the fragments.
+pub fn decode_ipv4(packet: &[u8]) -> Option<(SessionKey, &[u8])> {
+    let header = packet.get(..20)?;
+
+    if header[0] >> 4 != 4 {
+        return None;
+    }
+
+    let header_len = usize::from(header[0] & 0x0F) * 4;
+    let total_len = usize::from(u16::from_be_bytes([header[2], header[3]]));
+    let more_fragments = header[6] & 0x20 != 0;
+    let fragment_offset = u16::from_be_bytes([header[6], header[7]]) & 0x1FFF;
+
+    if more_fragments || fragment_offset != 0 || header_len < 20 {
+        return None;
+    }
+
+    let proto = header[9];
+    let src = Ipv4Addr::new(header[12], header[13], header[14], header[15]);
+    let dst = Ipv4Addr::new(header[16], header[17], header[18], header[19]);
+    // the Ethernet frames may be padded after the IP packet
+    let segment = packet.get(header_len..total_len.min(packet.len()))?;
+    let payload = match proto {
+        IPPROTO_TCP => segment.get(usize::from(segment.get(12)? >> 4) * 4..)?,
+        IPPROTO_UDP => segment.get(8..)?,
+        _ => return None,
+    };
+    let port = |off: usize| u16::from_be_bytes([segment[off], segment[off + 1]]);
+
+    Some((
+        SessionKey {
+            proto,
+            src: SocketAddrV4::new(src, port(0)),
+            dst: SocketAddrV4::new(dst, port(2)),
+        },
+        payload,
+    ))
+}
+
+/// Reads a capture file in the classic `pcap` format, yielding the non-empty TCP and UDP payloads with their sessions.
+///
+/// Only the Ethernet and raw IP link types are supported.
+/// A record truncated at the end of the file (e.g. the capture was interrupted) ends the iteration,
+/// a record longer than the snapshot length of the capture is rejected as `ErrorKind::InvalidData`.
+#[derive(Debug)]
+pub struct SessionExtractor<R> {
+    reader: R,
+    swapped: bool,
+    snaplen: usize,
+    link_type: u32,
+    buf: Vec<u8>,
+}
+
+impl SessionExtractor<BufReader<File>> {
+    /// Open a capture file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        SessionExtractor::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> SessionExtractor<R> {
+    /// Read a capture from the reader, validating its header.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut header = [0; 24];
+
+        reader.read_exact(&mut header)?;
+
+        let swapped = match u32::from_le_bytes(header[..4].try_into().unwrap()) {
+            0xA1B2_C3D4 | 0xA1B2_3C4D => false,
+            0xD4C3_B2A1 | 0x4D3C_B2A1 => true,
+            magic => return Err(invalid_data(format!("unknown magic number {:08x}", magic))),
+        };
+        let mut extractor = SessionExtractor {
+            reader,
+            swapped,
+            snaplen: 0,
+            link_type: 0,
+            buf: vec![],
+        };
+
+        extractor.snaplen = match extractor.u32(&header[16..20]) as usize {
+            0 => MAX_SNAPLEN,
+            snaplen => snaplen.min(MAX_SNAPLEN),
+        };
+        extractor.link_type = extractor.u32(&header[20..24]);
+
+        match extractor.link_type {
+            LINKTYPE_ETHERNET | LINKTYPE_RAW => Ok(extractor),
+            link_type => Err(invalid_data(format!("unsupported link type {}", link_type))),
+        }
+    }
+
+    /// The link type of the capture.
+    pub fn link_type(&self) -> u32 {
+        self.link_type
+    }
+
+    /// Scan the payloads with the streams of their sessions, reporting the matches with the session
+    /// to the `on_match_event` handler.
+    ///
+    /// The streams are left open, the remaining matches could be reported by closing them,
+    /// e.g. with `StreamMap::close_all`. Returns the number of scanned payloads.
+    pub fn scan_into<F>(
+        self,
+        sessions: &mut StreamMap<'_, SessionKey>,
+        scratch: &ScratchRef,
+        mut on_match_event: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(&SessionKey, u32, u64, u64, u32) -> Matching,
+    {
+        let mut count = 0;
+
+        for res in self {
+            let (key, payload) = res?;
+
+            sessions.scan(key, payload, scratch, |id, from, to, flags| {
+                on_match_event(&key, id, from, to, flags)
+            })?;
+
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    fn u32(&self, b: &[u8]) -> u32 {
+        let b = b.try_into().unwrap();
+
+        if self.swapped {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    }
+
+    fn next_record(&mut self) -> io::Result<()> {
+        let mut header = [0; 16];
+
+        self.reader.read_exact(&mut header)?;
+
+        let len = self.u32(&header[8..12]) as usize;
+
+        if len > self.snaplen {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("record length {} exceeds the snapshot length {}", len, self.snaplen),
+            ));
+        }
+
+        self.buf.resize(len, 0);
+        self.reader.read_exact(&mut self.buf)
+    }
+}
+
+impl<R: Read> Iterator for SessionExtractor<R> {
+    type Item = Result<(SessionKey, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_record() {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => return None,
+                Err(err) => return Some(Err(err.into())),
+            }
+
+            let decoded = if self.link_type == LINKTYPE_RAW {
+                decode_ipv4(&self.buf)
+            } else {
+                decode(&self.buf)
+            };
+
+            match decoded {
+                Some((key, payload)) if !payload.is_empty() => return Some(Ok((key, payload.to_vec()))),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn invalid_data(msg: String) -> Error {
+    io::Error::new(ErrorKind::InvalidData, msg).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    use super::*;
+
+    fn ipv4(proto: u8, src_port: u16, frag: u16, payload: &[u8]) -> Vec<u8> {
+        let mut segment = vec![];
+
+        segment.extend_from_slice(&src_port.to_be_bytes());
+        segment.extend_from_slice(&80u16.to_be_bytes());
+
+        if proto == IPPROTO_TCP {
+            segment.extend_from_slice(&[0; 8]);
+            segment.extend_from_slice(&[5 << 4, 0x18]);
+            segment.extend_from_slice(&[0; 6]);
+        } else {
+            segment.extend_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+            segment.extend_from_slice(&[0; 2]);
+        }
+
+        segment.extend_from_slice(payload);
+
+        let mut packet = vec![0x45, 0];
+
+        packet.extend_from_slice(&((20 + segment.len()) as u16).to_be_bytes());
+        packet.extend_from_slice(&[0, 0]);
+        packet.extend_from_slice(&frag.to_be_bytes());
+        packet.extend_from_slice(&[64, proto, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2]);
+        packet.extend_from_slice(&segment);
+        packet
+    }
+
+    fn ethernet(ethertype: u16, packet: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0; 12];
+
+        frame.extend_from_slice(&ethertype.to_be_bytes());
+        frame.extend_from_slice(packet);
+        frame.resize(frame.len().max(60), 0);
+        frame
+    }
+
+    fn capture(frames: &[Vec<u8>]) -> Vec<u8> {
+        capture_with_snaplen(65535, frames)
+    }
+
+    fn capture_with_snaplen(snaplen: u32, frames: &[Vec<u8>]) -> Vec<u8> {
+        let mut buf = vec![];
+
+        buf.extend_from_slice(&0xA1B2_C3D4u32.to_le_bytes());
+        buf.extend_from_slice(&[2, 0, 4, 0]);
+        buf.extend_from_slice(&[0; 8]);
+        buf.extend_from_slice(&snaplen.to_le_bytes());
+        buf.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+
+        for frame in frames {
+            buf.extend_from_slice(&[0; 8]);
+            buf.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            buf.extend_from_slice(frame);
+        }
+
+        buf
+    }
+
+    fn key(proto: u8, src_port: u16) -> SessionKey {
+        SessionKey {
+            proto,
+            src: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), src_port),
+            dst: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 80),
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let frame = ethernet(ETHERTYPE_IPV4, &ipv4(IPPROTO_TCP, 1234, 0, b"foo"));
+
+        assert_eq!(decode(&frame), Some((key(IPPROTO_TCP, 1234), &b"foo"[..])));
+
+        let frame = ethernet(ETHERTYPE_IPV4, &ipv4(IPPROTO_UDP, 53, 0, b"bar"));
+
+        assert_eq!(decode(&frame), Some((key(IPPROTO_UDP, 53), &b"bar"[..])));
+
+        let mut vlan = ethernet(ETHERTYPE_VLAN, &[0, 1, 0x08, 0x00]);
+        vlan.truncate(18);
+        vlan.extend_from_slice(&ipv4(IPPROTO_TCP, 1234, 0, b"foo"));
+
+        assert_eq!(decode(&vlan), Some((key(IPPROTO_TCP, 1234), &b"foo"[..])));
+
+        assert_eq!(
+            decode(&ethernet(ETHERTYPE_IPV4, &ipv4(IPPROTO_TCP, 1234, 0x2000, b"foo"))),
+            None
+        );
+        assert_eq!(decode(&ethernet(ETHERTYPE_IPV4, &ipv4(1, 0, 0, b"ping"))), None);
+        assert_eq!(decode(&ethernet(0x0806, &[0; 28])), None);
+        assert_eq!(decode(&frame[..30]), None);
+    }
+
+    #[test]
+    fn test_session_extractor() {
+        let buf = capture(&[
+            ethernet(ETHERTYPE_IPV4, &ipv4(IPPROTO_TCP, 1234, 0, b"foo te")),
+            ethernet(0x0806, &[0; 28]),
+            ethernet(ETHERTYPE_IPV4, &ipv4(IPPROTO_UDP, 53, 0, b"test")),
+            ethernet(ETHERTYPE_IPV4, &ipv4(IPPROTO_TCP, 1234, 0, b"")),
+            ethernet(ETHERTYPE_IPV4, &ipv4(IPPROTO_TCP, 1234, 0, b"st bar")),
+        ]);
+
+        let payloads = SessionExtractor::new(&buf[..])
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(
+            payloads,
+            vec![
+                (key(IPPROTO_TCP, 1234), b"foo te".to_vec()),
+                (key(IPPROTO_UDP, 53), b"test".to_vec()),
+                (key(IPPROTO_TCP, 1234), b"st bar".to_vec()),
+            ]
+        );
+
+        let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut sessions = StreamMap::new(&db);
+        let mut matches = vec![];
+
+        let count = SessionExtractor::new(&buf[..])
+            .unwrap()
+            .scan_into(&mut sessions, &s, |key, _, from, to, _| {
+                matches.push((key.src.port(), from..to));
+                Matching::Continue
+            })
+            .unwrap();
+
+        assert_eq!(count, 3);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(matches, vec![(53, 0..4), (1234, 4..8)]);
+
+        sessions.close_all(&s, |_, _, _, _, _| Matching::Continue).unwrap();
+
+        assert_eq!(
+            SessionExtractor::new(&buf[..4]).unwrap_err(),
+            Error::from(io::Error::from(ErrorKind::UnexpectedEof))
+        );
+        assert!(matches!(
+            SessionExtractor::new(&[0; 24][..]),
+            Err(Error::Io(err)) if err.to_string().starts_with("unknown magic number")
+        ));
+    }
+
+    #[test]
+    fn test_oversized_record() {
+        let frame = ethernet(ETHERTYPE_IPV4, &ipv4(IPPROTO_TCP, 1234, 0, &[0; 100]));
+        let buf = capture_with_snaplen(64, &[frame]);
+
+        let err = SessionExtractor::new(&buf[..]).unwrap().next().unwrap().unwrap_err();
+
+        assert_eq!(err, Error::from(io::Error::from(ErrorKind::InvalidData)));
+
+        let mut buf = capture(&[]);
+        buf.extend_from_slice(&[0; 8]);
+        buf.extend_from_slice(&u32::MAX.to_le_bytes());
+        buf.extend_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(SessionExtractor::new(&buf[..]).unwrap().next().unwrap().is_err());
+    }
+}
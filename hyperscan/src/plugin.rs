@@ -0,0 +1,164 @@
+//! The C ABI of the match event handlers
+//!
+//! A `RawMatchHandler` is a `#[repr(C)]` pair of an `extern "C"` callback and its context, which is passed
+//! to the safe scan methods as it is, so the matches could be processed by the code written in another language,
+//! or by a plugin loaded with `dlopen`, without wrapping the callback in a closure.
+//!
+//! The layout is the same as the following C declaration, e.g. a plugin could export a function
+//! returning the handler from its entry point.
+//!
+//! ```c
+//! typedef int (*match_event_handler)(unsigned int id, unsigned long long from,
+//!                                    unsigned long long to, unsigned int flags, void *context);
+//!
+//! typedef struct {
+//!     match_event_handler callback;
+//!     void *context;
+//! } raw_match_handler;
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use hyperscan::{prelude::*, plugin::RawMatchHandler};
+//! unsafe extern "C" fn sum(_id: u32, _from: u64, to: u64, _flags: u32, context: *mut core::ffi::c_void) -> core::ffi::c_int {
+//!     *(context as *mut u64) += to;
+//!     0
+//! }
+//!
+//! let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+//! let s = db.alloc_scratch().unwrap();
+//! let mut total = 0u64;
+//!
+//! let handler = unsafe { RawMatchHandler::new(sum, &mut total as *mut u64 as *mut _) };
+//!
+//! db.scan("foo test bar test", &s, handler).unwrap();
+//!
+//! assert_eq!(total, 8 + 17);
+//! ```
+use core::ptr;
+
+use core::ffi::{c_int, c_uint, c_ulonglong, c_void};
+
+use crate::{ffi, runtime::MatchEventHandler};
+
+/// The match event callback of the C ABI, which returns non-zero to cease the matching.
+pub type RawMatchCallback =
+    unsafe extern "C" fn(id: c_uint, from: c_ulonglong, to: c_ulonglong, flags: c_uint, context: *mut c_void) -> c_int;
+
+/// A match event handler implemented with the C ABI, e.g. in another language or in a plugin.
+///
+/// The handler is passed to Hyperscan as it is, so the callback is called directly by the library.
+/// A null callback suppresses the matches, like `()`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct RawMatchHandler {
+    callback: Option<RawMatchCallback>,
+    context: *mut c_void,
+}
+
+impl Default for RawMatchHandler {
+    fn default() -> Self {
+        RawMatchHandler::null()
+    }
+}
+
+impl RawMatchHandler {
+    /// Construct a handler from the callback and its context.
+    ///
+    /// # Safety
+    ///
+    /// The callback must be safe to call with the context during the scans which the handler is passed to,
+    /// and it must not unwind. The context is shared by the copies of the handler.
+    pub const unsafe fn new(callback: RawMatchCallback, context: *mut c_void) -> Self {
+        RawMatchHandler {
+            callback: Some(callback),
+            context,
+        }
+    }
+
+    /// Construct a handler from a nullable callback and its context, e.g. a handler returned by a plugin.
+    ///
+    /// # Safety
+    ///
+    /// Same as `RawMatchHandler::new`.
+    pub const unsafe fn from_raw(callback: Option<RawMatchCallback>, context: *mut c_void) -> Self {
+        RawMatchHandler { callback, context }
+    }
+
+    /// Construct a handler which suppresses the matches.
+    pub const fn null() -> Self {
+        RawMatchHandler {
+            callback: None,
+            context: ptr::null_mut(),
+        }
+    }
+
+    /// Returns the callback, or `None` if the matches are suppressed.
+    pub fn callback(&self) -> Option<RawMatchCallback> {
+        self.callback
+    }
+
+    /// Returns the context passed to the callback.
+    pub fn context(&self) -> *mut c_void {
+        self.context
+    }
+}
+
+impl MatchEventHandler for RawMatchHandler {
+    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut core::ffi::c_void) {
+        (self.callback, self.context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, Error, HsError};
+
+    unsafe extern "C" fn collect(id: c_uint, from: c_ulonglong, to: c_ulonglong, _: c_uint, ctx: *mut c_void) -> c_int {
+        (*(ctx as *mut Vec<(u32, u64, u64)>)).push((id, from, to));
+        0
+    }
+
+    unsafe extern "C" fn terminate(_: c_uint, _: c_ulonglong, _: c_ulonglong, _: c_uint, _: *mut c_void) -> c_int {
+        1
+    }
+
+    #[test]
+    fn test_raw_match_handler() {
+        let db: StreamingDatabase = patterns!["test", "bar$"; SOM_LEFTMOST].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches: Vec<(u32, u64, u64)> = vec![];
+        let handler = unsafe { RawMatchHandler::new(collect, &mut matches as *mut _ as *mut _) };
+
+        let st = db.open_stream().unwrap();
+
+        st.scan("foo test", &s, handler).unwrap();
+        st.scan(" bar", &s, handler).unwrap();
+        st.close(&s, handler).unwrap();
+
+        assert_eq!(matches, vec![(0, 4, 8), (1, 9, 12)]);
+
+        let st = db.open_stream().unwrap();
+
+        st.scan("test bar", &s, RawMatchHandler::null()).unwrap();
+        st.close(&s, RawMatchHandler::default()).unwrap();
+
+        assert_eq!(matches.len(), 2);
+    }
+
+    #[test]
+    fn test_raw_match_handler_terminate() {
+        let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let handler = unsafe { RawMatchHandler::new(terminate, ptr::null_mut()) };
+
+        assert!(handler.callback().is_some());
+        assert!(handler.context().is_null());
+        assert_eq!(
+            db.scan("test", &s, handler),
+            Err(Error::Hyperscan(HsError::ScanTerminated))
+        );
+    }
+}
@@ -18,6 +18,7 @@ pub type RegexSetBuilder = Builder<Vec<String>>;
 pub struct Builder<T> {
     expr: T,
     flags: Flags,
+    anchored: bool,
 }
 
 impl Builder<String> {
@@ -28,6 +29,7 @@ impl Builder<String> {
         Builder {
             expr: pattern.into(),
             flags: Flags::empty(),
+            anchored: false,
         }
     }
 
@@ -36,7 +38,38 @@ impl Builder<String> {
     /// Note that calling `as_str` on the resulting Regex will produce the pattern given to new verbatim.
     /// Notably, it will not incorporate any of the flags set on this builder.
     pub fn build(&self) -> Result<Regex> {
-        Regex::with_flags(&self.expr, self.flags)
+        if self.anchored {
+            Regex::with_flags(format!(r"\A(?:{})", self.expr), self.flags)
+        } else {
+            Regex::with_flags(&self.expr, self.flags)
+        }
+    }
+
+    /// Set whether the matches must start at the beginning of the haystack.
+    ///
+    /// The expression is wrapped as `\A(?:...)`, which Hyperscan compiles into an anchored database
+    /// only examining the start of the data, regardless of the multi-line (`m`) flag.
+    ///
+    /// Unlike the PCRE `PCRE_ANCHORED` option or the `find_at` of the `regex` crate,
+    /// there is no starting position to anchor at: the haystack is always scanned from its beginning,
+    /// so searching a suffix of the text requires slicing the text and offsetting the matches.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::regex::RegexBuilder;
+    /// let re = RegexBuilder::new("[a-z]+").anchored(true).build().unwrap();
+    ///
+    /// assert_eq!(re.find_iter("foo bar").map(|m| m.range()).collect::<Vec<_>>(), vec![0..3]);
+    /// assert!(!re.is_match("123 bar"));
+    ///
+    /// let re = RegexBuilder::new("bar").anchored(true).multi_line(true).build().unwrap();
+    ///
+    /// assert!(!re.is_match("foo\nbar"));
+    /// ```
+    pub fn anchored(&mut self, yes: bool) -> &mut Self {
+        self.anchored = yes;
+        self
     }
 }
 
@@ -0,0 +1,323 @@
+//! Match regular expressions on arbitrary bytes.
+//!
+//! This module mirrors the `regex::bytes` API, the haystacks are `&[u8]` which need not be valid UTF-8,
+//! e.g. raw packet payloads or binary logs.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use hyperscan::regex::bytes::{Regex, RegexSet};
+//! let re = Regex::new(r"\x00\x01").unwrap();
+//! let m = re.find(b"\xff\xfe\x00\x01\x01").unwrap().unwrap();
+//!
+//! assert_eq!(m.range(), 2..4);
+//! assert_eq!(m.as_bytes(), b"\x00\x01");
+//!
+//! let set = RegexSet::new(&[r"\xff", r"foo", r"\x01$"]).unwrap();
+//! let matches = set.matches(b"\xff\xfe\x00\x01\x01").unwrap();
+//!
+//! assert_eq!(matches.iter().collect::<Vec<_>>(), vec![0, 2]);
+//! ```
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::{
+    compile::{Flags, Pattern, Patterns},
+    regex::matcher::{self, LazyMatcher, Matcher},
+    runtime::Matching,
+    Error, Result,
+};
+
+/// Match represents a single match of a regex in a byte string.
+///
+/// The lifetime parameter `'t` refers to the lifetime of the matched text.
+pub type Match<'t> = matcher::Match<'t, [u8]>;
+
+/// An iterator over all non-overlapping matches for a particular byte string.
+///
+/// The iterator yields a `Match` value. The iterator stops when no more
+/// matches can be found.
+///
+/// `'t` is the lifetime of the matched byte string.
+pub type Matches<'t> = matcher::Matches<'t, [u8]>;
+
+/// Yields all byte substrings delimited by a regular expression match.
+///
+/// `'t` is the lifetime of the byte string being split.
+pub type Split<'t> = matcher::Split<'t, [u8]>;
+
+/// A compiled regular expression for matching arbitrary bytes.
+///
+/// The scratch space is allocated when the expression is compiled, and cloned for every thread using it.
+#[derive(Clone)]
+pub struct Regex(Matcher);
+
+impl FromStr for Regex {
+    type Err = Error;
+
+    /// Attempts to parse a string into a regular expression
+    fn from_str(s: &str) -> Result<Regex> {
+        Regex::new(s)
+    }
+}
+
+/// Core regular expression methods.
+impl Regex {
+    /// Compiles a regular expression.
+    /// Once compiled, it can be used repeatedly to search or split the byte strings.
+    ///
+    /// If an invalid expression is given, then an error is returned.
+    pub fn new<S: Into<String>>(re: S) -> Result<Regex> {
+        Matcher::new(&Pattern::with_flags(re, Flags::SOM_LEFTMOST)?).map(Regex)
+    }
+
+    /// Returns true if and only if the regex matches the byte string given.
+    pub fn is_match(&self, text: &[u8]) -> Result<bool> {
+        self.0.is_match(text)
+    }
+
+    /// Returns the start and end byte range of the leftmost-first match in text. If no match exists, then None is returned.
+    pub fn find<'t>(&self, text: &'t [u8]) -> Result<Option<Match<'t>>> {
+        Ok(self.0.find(text)?.map(|range| Match::new(text, range.start, range.end)))
+    }
+
+    /// Returns an iterator for each successive non-overlapping match in
+    /// `text`, returning the start and end byte indices with respect to
+    /// `text`.
+    pub fn find_iter<'t>(&self, text: &'t [u8]) -> Result<Matches<'t>> {
+        Ok(Matches::new(text, self.0.find_iter(text)?))
+    }
+
+    /// Returns an iterator of byte substrings of `text` delimited by a match of the
+    /// regular expression. Namely, each element of the iterator corresponds to
+    /// text that *isn't* matched by the regular expression.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::regex::bytes::Regex;
+    /// let re = Regex::new(r"\x00+").unwrap();
+    /// let fields: Vec<&[u8]> = re.split(b"a\x00b\x00\x00c").unwrap().collect();
+    /// assert_eq!(fields, vec![&b"a"[..], &b"b"[..], &b"c"[..]]);
+    /// ```
+    pub fn split<'t>(&self, text: &'t [u8]) -> Result<Split<'t>> {
+        Ok(Split::new(self.find_iter(text)?))
+    }
+}
+
+/// Match multiple (possibly overlapping) regular expressions in a single scan.
+///
+/// The regular expressions are compiled without reporting the start of match, which is slower to scan,
+/// the database reporting it is only compiled on the first call of `matches_with_offsets`.
+#[derive(Clone)]
+pub struct RegexSet {
+    matcher: Matcher,
+    offsets: LazyMatcher,
+    patterns: Vec<String>,
+    flags: Flags,
+}
+
+impl RegexSet {
+    /// Create a new regex set with the given regular expressions.
+    ///
+    /// If any of the expressions are invalid, then an error is returned.
+    pub fn new<I, S>(exprs: I) -> Result<RegexSet>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self::with_flags(exprs, Flags::empty())
+    }
+
+    /// Create a new regex set with the given regular expressions, compiled with the flags.
+    pub(crate) fn with_flags<I, S>(exprs: I, flags: Flags) -> Result<RegexSet>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns = exprs.into_iter().map(|s| s.as_ref().to_owned()).collect::<Vec<_>>();
+        let matcher = Matcher::new(&Self::compile(&patterns, flags | Flags::SINGLEMATCH)?)?;
+
+        Ok(RegexSet {
+            matcher,
+            offsets: LazyMatcher::default(),
+            patterns,
+            flags,
+        })
+    }
+
+    fn compile(patterns: &[String], flags: Flags) -> Result<Patterns> {
+        patterns
+            .iter()
+            .enumerate()
+            .map(|(id, expr)| {
+                Pattern::with_flags(expr.as_str(), flags).map(|pattern| Pattern {
+                    id: Some(id),
+                    ..pattern
+                })
+            })
+            .collect()
+    }
+
+    /// Returns true if and only if one of the regexes in this set matches the byte string given.
+    pub fn is_match(&self, text: &[u8]) -> Result<bool> {
+        self.matcher.is_match(text)
+    }
+
+    /// Returns the set of regular expressions that match in the given byte string.
+    pub fn matches(&self, text: &[u8]) -> Result<SetMatches> {
+        let mut matched = vec![false; self.patterns.len()];
+
+        self.matcher.scratch.with(|s| {
+            self.matcher.db.scan(text, s, |id, _, _, _| {
+                matched[id as usize] = true;
+
+                Matching::Continue
+            })
+        })?;
+
+        Ok(SetMatches(matched))
+    }
+
+    /// Returns the index and the offsets of the first match of every regular expression matching the byte string,
+    /// ordered by the index.
+    ///
+    /// Every regular expression reports only its first match, i.e. the one ending first,
+    /// with its leftmost starting offset, all in a single scan.
+    ///
+    /// The regular expressions are compiled again with the `SOM_LEFTMOST` flag on the first call,
+    /// which returns an error if one of them doesn't support it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::regex::bytes::RegexSet;
+    /// let set = RegexSet::new(&[r"GET", r"\.\./", r"POST"]).unwrap();
+    ///
+    /// assert_eq!(
+    ///     set.matches_with_offsets(b"GET /a/../../etc/passwd").unwrap(),
+    ///     vec![(0, 0..3), (1, 7..10)]
+    /// );
+    /// ```
+    pub fn matches_with_offsets(&self, text: &[u8]) -> Result<Vec<(usize, Range<usize>)>> {
+        let offsets = self.offsets.get_or_try_init(|| {
+            Matcher::new(&Self::compile(
+                &self.patterns,
+                self.flags | Flags::SINGLEMATCH | Flags::SOM_LEFTMOST,
+            )?)
+        })?;
+
+        let mut matched = vec![];
+
+        offsets.scratch.with(|s| {
+            offsets.db.scan(text, s, |id, from, to, _| {
+                matched.push((id as usize, from as usize..to as usize));
+
+                Matching::Continue
+            })
+        })?;
+
+        matched.sort_by_key(|&(id, _)| id);
+
+        Ok(matched)
+    }
+
+    /// Returns the total number of regular expressions in this set.
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Returns true if this set contains no regular expressions.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Returns the patterns that this set will match on.
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+}
+
+/// A set of matches returned by a regex set.
+#[derive(Clone, Debug)]
+pub struct SetMatches(Vec<bool>);
+
+impl SetMatches {
+    /// Whether this set contains any matches.
+    pub fn matched_any(&self) -> bool {
+        self.0.iter().any(|&matched| matched)
+    }
+
+    /// Whether the regex at the given index matched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `regex_index` is out of bounds.
+    pub fn matched(&self, regex_index: usize) -> bool {
+        self.0[regex_index]
+    }
+
+    /// The total number of regexes in the set that created these matches.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns an iterator over indexes in the regex that matched.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(_, &matched)| matched)
+            .map(|(index, _)| index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Regex, RegexSet};
+
+    #[test]
+    fn test_bytes_regex() {
+        let text = b"GET /\xc0\xaf HTTP/1.1\r\n\r\n";
+        let re = Regex::new(r"\xc0[\x80-\xbf]").unwrap();
+
+        assert!(re.is_match(text).unwrap());
+        assert_eq!(re.find(text).unwrap().map(|m| m.as_bytes()), Some(&b"\xc0\xaf"[..]));
+        assert_eq!(
+            Regex::new(r"\r\n")
+                .unwrap()
+                .find_iter(text)
+                .unwrap()
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![16..18, 18..20]
+        );
+        assert!(!re.is_match(b"GET / HTTP/1.1").unwrap());
+    }
+
+    #[test]
+    fn test_bytes_regex_set() {
+        let set = RegexSet::new([r"^GET", r"\xc0\xaf", r"POST"]).unwrap();
+
+        assert_eq!(set.len(), 3);
+        assert!(set.is_match(b"GET /\xc0\xaf").unwrap());
+
+        let matches = set.matches(b"GET /\xc0\xaf").unwrap();
+
+        assert!(matches.matched_any());
+        assert!(matches.matched(0));
+        assert!(!matches.matched(2));
+        assert_eq!(matches.iter().collect::<Vec<_>>(), vec![0, 1]);
+
+        assert!(!set.matches(b"PUT /").unwrap().matched_any());
+        assert!(set.offsets.get().is_none());
+
+        assert_eq!(
+            set.matches_with_offsets(b"GET /\xc0\xaf").unwrap(),
+            vec![(0, 0..3), (1, 5..7)]
+        );
+        assert!(set.clone().matches_with_offsets(b"PUT /").unwrap().is_empty());
+        assert!(set.offsets.get().is_some());
+    }
+}
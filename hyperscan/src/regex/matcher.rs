@@ -0,0 +1,346 @@
+use std::ops::{Index, Range, RangeFrom};
+use std::sync::{Arc, OnceLock};
+use std::vec;
+
+use crate::{
+    common::{Block, Database, Mode},
+    compile::Builder,
+    runtime::{Matching, PerThread, Scratch},
+    Error, Result,
+};
+
+/// The haystack searched by a regex, a Unicode string or arbitrary bytes.
+pub trait Haystack: AsRef<[u8]> + Index<Range<usize>, Output = Self> + Index<RangeFrom<usize>, Output = Self> {}
+
+impl Haystack for str {}
+
+impl Haystack for [u8] {}
+
+/// Match represents a single match of a regex in a haystack.
+///
+/// The lifetime parameter `'t` refers to the lifetime of the matched text.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Match<'t, H: ?Sized> {
+    text: &'t H,
+    start: usize,
+    end: usize,
+}
+
+impl<H: ?Sized> Clone for Match<'_, H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<H: ?Sized> Copy for Match<'_, H> {}
+
+impl<'t, H: ?Sized> Match<'t, H> {
+    /// Returns the starting byte offset of the match in the haystack.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the ending byte offset of the match in the haystack.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the range over the starting and ending byte offsets of the
+    /// match in the haystack.
+    #[inline]
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Creates a new match from the given haystack and byte offsets.
+    #[inline]
+    pub(crate) fn new(haystack: &'t H, start: usize, end: usize) -> Self {
+        Match {
+            text: haystack,
+            start,
+            end,
+        }
+    }
+}
+
+impl<'t> Match<'t, str> {
+    /// Returns the matched text.
+    #[inline]
+    pub fn as_str(&self) -> &'t str {
+        &self.text[self.start..self.end]
+    }
+}
+
+impl<'t> Match<'t, [u8]> {
+    /// Returns the matched text.
+    #[inline]
+    pub fn as_bytes(&self) -> &'t [u8] {
+        &self.text[self.start..self.end]
+    }
+}
+
+impl<'t> From<Match<'t, str>> for &'t str {
+    fn from(m: Match<'t, str>) -> &'t str {
+        m.as_str()
+    }
+}
+
+impl<'t, H: ?Sized> From<Match<'t, H>> for Range<usize> {
+    fn from(m: Match<'t, H>) -> Range<usize> {
+        m.range()
+    }
+}
+
+/// An iterator over all non-overlapping matches for a particular haystack.
+///
+/// The iterator yields a `Match` value. The iterator stops when no more
+/// matches can be found.
+///
+/// `'t` is the lifetime of the matched haystack.
+pub struct Matches<'t, H: ?Sized>(&'t H, vec::IntoIter<Range<usize>>);
+
+impl<'t, H: ?Sized> Matches<'t, H> {
+    pub(crate) fn new(text: &'t H, matched: Vec<Range<usize>>) -> Self {
+        Matches(text, matched.into_iter())
+    }
+
+    /// Return the text being searched.
+    pub fn text(&self) -> &'t H {
+        self.0
+    }
+}
+
+impl<'t, H: ?Sized> Iterator for Matches<'t, H> {
+    type Item = Match<'t, H>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.1.next().map(|range| Match::new(self.0, range.start, range.end))
+    }
+}
+
+impl<'t, H: ?Sized> DoubleEndedIterator for Matches<'t, H> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.1
+            .next_back()
+            .map(|range| Match::new(self.0, range.start, range.end))
+    }
+}
+
+/// Yields all substrings delimited by a regular expression match.
+///
+/// `'t` is the lifetime of the haystack being split.
+pub struct Split<'t, H: ?Sized> {
+    finder: Matches<'t, H>,
+    last: usize,
+}
+
+impl<'t, H: ?Sized> Split<'t, H> {
+    pub(crate) fn new(finder: Matches<'t, H>) -> Self {
+        Split { finder, last: 0 }
+    }
+}
+
+impl<'t, H: Haystack + ?Sized> Iterator for Split<'t, H> {
+    type Item = &'t H;
+
+    fn next(&mut self) -> Option<&'t H> {
+        let text = self.finder.text();
+        match self.finder.next() {
+            None => {
+                if self.last > text.as_ref().len() {
+                    None
+                } else {
+                    let s = &text[self.last..];
+                    self.last = text.as_ref().len() + 1; // Next call will return None
+                    Some(s)
+                }
+            }
+            Some(m) => {
+                let matched = &text[self.last..m.start()];
+                self.last = m.end();
+                Some(matched)
+            }
+        }
+    }
+}
+
+/// Yields at most `N` substrings delimited by a regular expression match.
+///
+/// The last substring will be whatever remains after splitting.
+///
+/// `'t` is the lifetime of the haystack being split.
+pub struct SplitN<'t, H: ?Sized> {
+    splits: Split<'t, H>,
+    n: usize,
+}
+
+impl<'t, H: ?Sized> SplitN<'t, H> {
+    pub(crate) fn new(splits: Split<'t, H>, limit: usize) -> Self {
+        SplitN { splits, n: limit }
+    }
+}
+
+impl<'t, H: Haystack + ?Sized> Iterator for SplitN<'t, H> {
+    type Item = &'t H;
+
+    fn next(&mut self) -> Option<&'t H> {
+        if self.n == 0 {
+            return None;
+        }
+
+        self.n -= 1;
+        if self.n > 0 {
+            return self.splits.next();
+        }
+
+        let text = self.splits.finder.text();
+        if self.splits.last > text.as_ref().len() {
+            // We've already returned all substrings.
+            None
+        } else {
+            // self.n == 0, so future calls will return None immediately
+            Some(&text[self.splits.last..])
+        }
+    }
+}
+
+/// Yields all substrings delimited by a regular expression match,
+/// except the trailing empty substring.
+///
+/// `'t` is the lifetime of the haystack being split.
+pub struct SplitTerminator<'t, H: ?Sized> {
+    splits: Split<'t, H>,
+}
+
+impl<'t, H: ?Sized> SplitTerminator<'t, H> {
+    pub(crate) fn new(splits: Split<'t, H>) -> Self {
+        SplitTerminator { splits }
+    }
+}
+
+impl<'t, H: Haystack + ?Sized> Iterator for SplitTerminator<'t, H> {
+    type Item = &'t H;
+
+    fn next(&mut self) -> Option<&'t H> {
+        let s = self.splits.next()?;
+
+        if s.as_ref().is_empty() && self.splits.last > self.splits.finder.text().as_ref().len() {
+            // skip the empty substring after the last match
+            None
+        } else {
+            Some(s)
+        }
+    }
+}
+
+/// The database of a regex and its scratch spaces, shared by the `str` and bytes regexes.
+///
+/// The scratch space is allocated when the expression is compiled, and cloned for every thread using it.
+pub(crate) struct Matcher<T = Block> {
+    pub db: Arc<Database<T>>,
+    pub scratch: Arc<PerThread<Scratch>>,
+}
+
+impl<T> Clone for Matcher<T> {
+    fn clone(&self) -> Self {
+        Matcher {
+            db: self.db.clone(),
+            scratch: self.scratch.clone(),
+        }
+    }
+}
+
+impl<T: Mode> Matcher<T> {
+    /// Compiles the patterns, which should be compiled with the `SOM_LEFTMOST` flag to report the match starts.
+    pub fn new<B: Builder<Err = Error>>(patterns: &B) -> Result<Self> {
+        let db: Database<T> = patterns.build()?;
+        let scratch = db.alloc_scratch()?;
+
+        Ok(Matcher {
+            db: Arc::new(db),
+            scratch: Arc::new(PerThread::new(scratch)),
+        })
+    }
+}
+
+impl Matcher {
+    pub fn is_match(&self, text: &[u8]) -> Result<bool> {
+        self.scratch.with(|s| self.db.contains(text, s))
+    }
+
+    pub fn find(&self, text: &[u8]) -> Result<Option<Range<usize>>> {
+        let mut matched = None;
+
+        self.scratch.with(|s| {
+            self.db.scan_outcome(text, s, |_, from, to, _| {
+                matched = Some(from as usize..to as usize);
+
+                Matching::Terminate
+            })
+        })?;
+
+        Ok(matched)
+    }
+
+    pub fn find_iter(&self, text: &[u8]) -> Result<Vec<Range<usize>>> {
+        let mut matched = Vec::<Range<usize>>::new();
+
+        self.scratch.with(|s| {
+            self.db.scan(text, s, |_, from, to, _| {
+                let range = from as usize..to as usize;
+
+                match matched.last() {
+                    Some(last) if last.start == range.start && last.end < range.end => {
+                        // only the non-overlapping match should be return
+                        *matched.last_mut().unwrap() = range;
+                    }
+                    _ => matched.push(range),
+                }
+
+                Matching::Continue
+            })
+        })?;
+
+        Ok(matched)
+    }
+}
+
+/// A matcher compiled on its first use, and shared by the clones of the regex.
+pub(crate) struct LazyMatcher<T = Block>(Arc<OnceLock<Matcher<T>>>);
+
+impl<T> Clone for LazyMatcher<T> {
+    fn clone(&self) -> Self {
+        LazyMatcher(self.0.clone())
+    }
+}
+
+impl<T> Default for LazyMatcher<T> {
+    fn default() -> Self {
+        LazyMatcher(Arc::default())
+    }
+}
+
+impl<T> LazyMatcher<T> {
+    /// Returns the matcher if it has been compiled.
+    #[cfg(test)]
+    pub fn get(&self) -> Option<&Matcher<T>> {
+        self.0.get()
+    }
+
+    /// Returns the matcher, compiling it with `f` on the first call.
+    pub fn get_or_try_init<F>(&self, f: F) -> Result<&Matcher<T>>
+    where
+        F: FnOnce() -> Result<Matcher<T>>,
+    {
+        if let Some(matcher) = self.0.get() {
+            return Ok(matcher);
+        }
+
+        // another thread may have compiled it concurrently, either one could be used
+        let _ = self.0.set(f()?);
+
+        Ok(self.0.get().expect("compiled matcher"))
+    }
+}
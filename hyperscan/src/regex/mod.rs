@@ -1,6 +1,16 @@
 //! Regex compatible interface
+pub mod bytes;
+
 mod builder;
+mod matcher;
 mod re;
+mod replace;
+mod set;
 
 pub use builder::{RegexBuilder, RegexSetBuilder};
-pub use re::Regex;
+pub use bytes::SetMatches;
+pub use re::{
+    CaptureMatches, Captures, LazyMatches, Match, Matches, Regex, Split, SplitN, SplitTerminator, SubCaptureMatches,
+};
+pub use replace::{NoExpand, Replacer};
+pub use set::RegexSet;
@@ -1,109 +1,290 @@
+use std::collections::{HashMap, VecDeque};
 use std::ops::Range;
+use std::slice;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::vec;
 
+#[cfg(feature = "chimera")]
+use crate::chimera::{self, Builder as _};
+#[cfg(feature = "chimera")]
+use crate::runtime::PerThread;
 use crate::{
-    common::BlockDatabase,
-    compile::{Builder, Flags, Pattern},
-    runtime::Matching,
+    common::Streaming,
+    compile::{Flags, Pattern, SomHorizon},
+    regex::matcher::{self, LazyMatcher, Matcher},
+    runtime::{Matching, Stream},
     Error, Result,
 };
 
-/// Match represents a single match of a regex in a haystack.
+/// Match represents a single match of a regex in a string.
 ///
 /// The lifetime parameter `'t` refers to the lifetime of the matched text.
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub struct Match<'t> {
+pub type Match<'t> = matcher::Match<'t, str>;
+
+/// An iterator over all non-overlapping matches for a particular string.
+///
+/// The iterator yields a `Match` value. The iterator stops when no more
+/// matches can be found.
+///
+/// `'t` is the lifetime of the matched string.
+pub type Matches<'t> = matcher::Matches<'t, str>;
+
+/// Yields all substrings delimited by a regular expression match.
+///
+/// `'t` is the lifetime of the string being split.
+pub type Split<'t> = matcher::Split<'t, str>;
+
+/// Yields at most `N` substrings delimited by a regular expression match.
+///
+/// The last substring will be whatever remains after splitting.
+///
+/// `'t` is the lifetime of the string being split.
+pub type SplitN<'t> = matcher::SplitN<'t, str>;
+
+/// Yields all substrings delimited by a regular expression match,
+/// except the trailing empty substring.
+///
+/// `'t` is the lifetime of the string being split.
+pub type SplitTerminator<'t> = matcher::SplitTerminator<'t, str>;
+
+/// The size of the chunks written to the stream of `LazyMatches`.
+const LAZY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// An iterator over all non-overlapping matches for a particular string, which scans the text on demand.
+///
+/// The text is written to a stream chunk by chunk when the matches of the previous chunks are consumed,
+/// so only the matches of a chunk are buffered. A scan error is yielded once and ends the iteration.
+///
+/// `'t` is the lifetime of the matched string.
+pub struct LazyMatches<'t> {
+    matcher: Matcher<Streaming>,
+    stream: Option<Stream>,
     text: &'t str,
-    start: usize,
-    end: usize,
+    pos: usize,
+    chunk_size: usize,
+    pending: Option<Range<usize>>,
+    ready: VecDeque<Range<usize>>,
 }
 
-impl<'t> Match<'t> {
-    /// Returns the starting byte offset of the match in the haystack.
-    #[inline]
-    pub fn start(&self) -> usize {
-        self.start
+impl<'t> LazyMatches<'t> {
+    /// Return the text being searched.
+    pub fn text(&self) -> &'t str {
+        self.text
+    }
+}
+
+impl<'t> Iterator for LazyMatches<'t> {
+    type Item = Result<Match<'t>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(range) = self.ready.pop_front() {
+                return Some(Ok(Match::new(self.text, range.start, range.end)));
+            }
+
+            let stream = match self.stream.take() {
+                Some(stream) => stream,
+                // the pending match can't be extended after the stream is closed
+                None => {
+                    return self
+                        .pending
+                        .take()
+                        .map(|range| Ok(Match::new(self.text, range.start, range.end)))
+                }
+            };
+
+            let LazyMatches {
+                matcher,
+                text,
+                pos,
+                chunk_size,
+                pending,
+                ready,
+                ..
+            } = self;
+
+            let end = text.len().min(*pos + *chunk_size);
+            let chunk = &text.as_bytes()[*pos..end];
+            let mut on_match = |_, from, to, _| {
+                push_match(pending, ready, from as usize..to as usize);
+
+                Matching::Continue
+            };
+
+            *pos = end;
+
+            let res = matcher.scratch.with(|s| {
+                stream.scan(chunk, s, &mut on_match)?;
+
+                if end < text.len() {
+                    Ok(Some(stream))
+                } else {
+                    stream.close(s, &mut on_match).map(|_| None)
+                }
+            });
+
+            match res {
+                Ok(stream) => self.stream = stream,
+                Err(err) => {
+                    self.pending = None;
+                    self.ready.clear();
+
+                    return Some(Err(err));
+                }
+            }
+        }
     }
+}
 
-    /// Returns the ending byte offset of the match in the haystack.
-    #[inline]
-    pub fn end(&self) -> usize {
-        self.end
+/// Merge a match reported by the stream into the pending match,
+/// which is ready when a following match is reported.
+fn push_match(pending: &mut Option<Range<usize>>, ready: &mut VecDeque<Range<usize>>, range: Range<usize>) {
+    match pending {
+        // the longer match with the same start is reported later
+        Some(last) if last.start == range.start => last.end = last.end.max(range.end),
+        // only the non-overlapping match should be return
+        Some(last) if range.start < last.end => {}
+        _ => {
+            if let Some(last) = pending.replace(range) {
+                ready.push_back(last);
+            }
+        }
     }
+}
 
-    /// Returns the range over the starting and ending byte offsets of the
-    /// match in the haystack.
-    #[inline]
-    pub fn range(&self) -> Range<usize> {
-        self.start..self.end
+/// Captures represents a group of captured strings for a single match.
+///
+/// The 0th capture always corresponds to the entire match.
+///
+/// `'t` is the lifetime of the matched text.
+#[derive(Clone, Debug)]
+pub struct Captures<'t> {
+    text: &'t str,
+    locs: Vec<Option<Range<usize>>>,
+    pub(crate) names: Arc<HashMap<String, usize>>,
+}
+
+impl<'t> Captures<'t> {
+    /// Returns the match associated with the capture group at index `i`.
+    /// If `i` does not correspond to a capture group, or if the capture group did not participate in the match,
+    /// then `None` is returned.
+    pub fn get(&self, i: usize) -> Option<Match<'t>> {
+        self.locs
+            .get(i)
+            .and_then(|loc| loc.as_ref())
+            .map(|range| Match::new(self.text, range.start, range.end))
     }
 
-    /// Returns the matched text.
-    #[inline]
-    pub fn as_str(&self) -> &'t str {
-        &self.text[self.start..self.end]
+    /// Returns the match for the capture group named `name`.
+    /// If `name` isn't a valid capture group or didn't match anything, then `None` is returned.
+    ///
+    /// The names of the capture groups are only known when the `chimera` feature is enabled.
+    pub fn name(&self, name: &str) -> Option<Match<'t>> {
+        self.names.get(name).and_then(|&i| self.get(i))
     }
 
-    /// Creates a new match from the given haystack and byte offsets.
-    #[inline]
-    fn new(haystack: &'t str, start: usize, end: usize) -> Match<'t> {
-        Match {
-            text: haystack,
-            start,
-            end,
+    /// An iterator that yields all capturing matches in the order in which they appear in the regex.
+    /// If a particular capture group didn't participate in the match, then `None` is yielded for that capture.
+    ///
+    /// The first match always corresponds to the overall match of the regex.
+    pub fn iter<'c>(&'c self) -> SubCaptureMatches<'c, 't> {
+        SubCaptureMatches {
+            text: self.text,
+            it: self.locs.iter(),
         }
     }
-}
 
-impl<'t> From<Match<'t>> for &'t str {
-    fn from(m: Match<'t>) -> &'t str {
-        m.as_str()
+    /// Returns the total number of capture groups (even if they didn't match).
+    ///
+    /// This is always at least `1`, since every regex has at least one capture group that corresponds to the full match.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.locs.len()
     }
 }
 
-impl<'t> From<Match<'t>> for Range<usize> {
-    fn from(m: Match<'t>) -> Range<usize> {
-        m.range()
+/// An iterator that yields all capturing matches in the order in which they appear in the regex.
+///
+/// `'c` is the lifetime of the captures and `'t` is the lifetime of the matched text.
+#[derive(Clone, Debug)]
+pub struct SubCaptureMatches<'c, 't> {
+    text: &'t str,
+    it: slice::Iter<'c, Option<Range<usize>>>,
+}
+
+impl<'c, 't> Iterator for SubCaptureMatches<'c, 't> {
+    type Item = Option<Match<'t>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = self.text;
+
+        self.it
+            .next()
+            .map(|loc| loc.as_ref().map(|range| Match::new(text, range.start, range.end)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.it.size_hint()
     }
 }
 
-/// An iterator over all non-overlapping matches for a particular string.
-///
-/// The iterator yields a `Match` value. The iterator stops when no more
-/// matches can be found.
-///
-/// `'r` is the lifetime of the compiled regular expression and `'t` is the
-/// lifetime of the matched string.
-pub struct Matches<'t>(&'t str, vec::IntoIter<Range<usize>>);
+impl<'c, 't> DoubleEndedIterator for SubCaptureMatches<'c, 't> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let text = self.text;
 
-impl<'t> Matches<'t> {
-    /// Return the text being searched.
-    pub fn text(&self) -> &'t str {
-        self.0
+        self.it
+            .next_back()
+            .map(|loc| loc.as_ref().map(|range| Match::new(text, range.start, range.end)))
     }
 }
 
-impl<'t> Iterator for Matches<'t> {
-    type Item = Match<'t>;
+impl<'c, 't> ExactSizeIterator for SubCaptureMatches<'c, 't> {}
+
+/// An iterator that yields all non-overlapping capture groups matching a particular regular expression.
+///
+/// The iterator stops when no more matches can be found.
+///
+/// `'t` is the lifetime of the matched string.
+#[derive(Debug)]
+pub struct CaptureMatches<'t>(vec::IntoIter<Captures<'t>>);
+
+impl<'t> Iterator for CaptureMatches<'t> {
+    type Item = Captures<'t>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.1.next().map(|range| Match::new(self.0, range.start, range.end))
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
     }
 }
 
-impl<'t> DoubleEndedIterator for Matches<'t> {
+impl<'t> DoubleEndedIterator for CaptureMatches<'t> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        self.1
-            .next_back()
-            .map(|range| Match::new(self.0, range.start, range.end))
+        self.0.next_back()
     }
 }
 
 /// A compiled regular expression for matching Unicode strings.
+///
+/// When the `chimera` feature is enabled, the expression is also compiled into a Chimera database
+/// reporting the capturing groups, otherwise only the overall match is captured.
+///
+/// The scratch spaces are allocated when the expression is compiled, and cloned for every thread using it.
 #[derive(Clone)]
-pub struct Regex(pub(crate) Arc<BlockDatabase>);
+pub struct Regex {
+    matcher: Matcher,
+    streaming: LazyMatcher<Streaming>,
+    pattern: Arc<Pattern>,
+    #[cfg(feature = "chimera")]
+    groups: Arc<chimera::Database>,
+    #[cfg(feature = "chimera")]
+    groups_scratch: Arc<PerThread<chimera::Scratch>>,
+    #[cfg(feature = "chimera")]
+    names: Arc<HashMap<String, usize>>,
+}
 
 impl FromStr for Regex {
     type Err = Error;
@@ -125,9 +306,106 @@ impl Regex {
     }
 
     pub(crate) fn with_flags<S: Into<String>>(re: S, flags: Flags) -> Result<Regex> {
-        Pattern::with_flags(re, flags | Flags::SOM_LEFTMOST | Flags::UTF8)?
-            .build()
-            .map(|db| Regex(Arc::new(db)))
+        let pattern = Pattern::with_flags(re, flags | Flags::SOM_LEFTMOST | Flags::UTF8)?;
+        let matcher = Matcher::new(&pattern)?;
+
+        #[cfg(feature = "chimera")]
+        {
+            let groups = Self::chimera_pattern(&pattern).with_groups()?;
+            let groups_scratch = groups.alloc_scratch()?;
+            let names = capture_names(&pattern.expression);
+
+            Ok(Regex {
+                matcher,
+                streaming: LazyMatcher::default(),
+                pattern: Arc::new(pattern),
+                groups: Arc::new(groups),
+                groups_scratch: Arc::new(PerThread::new(groups_scratch)),
+                names: Arc::new(names),
+            })
+        }
+
+        #[cfg(not(feature = "chimera"))]
+        Ok(Regex {
+            matcher,
+            streaming: LazyMatcher::default(),
+            pattern: Arc::new(pattern),
+        })
+    }
+
+    #[cfg(feature = "chimera")]
+    fn chimera_pattern(pattern: &Pattern) -> chimera::Pattern {
+        let mut flags = chimera::Flags::UTF8;
+
+        for &(flag, chimera_flag) in &[
+            (Flags::CASELESS, chimera::Flags::CASELESS),
+            (Flags::MULTILINE, chimera::Flags::MULTILINE),
+            (Flags::DOTALL, chimera::Flags::DOTALL),
+            (Flags::UCP, chimera::Flags::UCP),
+        ] {
+            flags.set(chimera_flag, pattern.flags.contains(flag));
+        }
+
+        chimera::Pattern::with_flags(pattern.expression.as_str(), flags)
+    }
+
+    /// Returns the overall match and the capturing groups of every match, or only the first one if `first`.
+    ///
+    /// The matches skipped by Chimera after hitting the PCRE match or recursion limits aren't reported.
+    #[cfg(feature = "chimera")]
+    fn capture_locations(&self, text: &str, first: bool) -> Result<Vec<Vec<Option<Range<usize>>>>> {
+        let mut locs = vec![];
+
+        self.groups_scratch
+            .with(|s| {
+                self.groups.scan(
+                    text,
+                    s,
+                    |_, _, _, _, captured: Option<&[chimera::Capture]>| {
+                        locs.push(
+                            captured
+                                .unwrap_or_default()
+                                .iter()
+                                .map(|capture| {
+                                    if capture.is_active() {
+                                        Some(capture.range())
+                                    } else {
+                                        None
+                                    }
+                                })
+                                .collect(),
+                        );
+
+                        if first {
+                            chimera::Matching::Terminate
+                        } else {
+                            chimera::Matching::Continue
+                        }
+                    },
+                    chimera::Matching::Skip,
+                )
+            })
+            .or_else(|err| match err {
+                Error::Chimera(chimera::Error::ScanTerminated) => Ok(()),
+                err => Err(err),
+            })?;
+
+        Ok(locs)
+    }
+
+    /// Returns the overall match of every match, or only the first one if `first`,
+    /// Hyperscan doesn't report the capturing groups.
+    #[cfg(not(feature = "chimera"))]
+    fn capture_locations(&self, text: &str, first: bool) -> Result<Vec<Vec<Option<Range<usize>>>>> {
+        if first {
+            Ok(self
+                .try_find(text)?
+                .map(|m| vec![Some(m.range())])
+                .into_iter()
+                .collect())
+        } else {
+            Ok(self.try_find_iter(text)?.map(|m| vec![Some(m.range())]).collect())
+        }
     }
 
     /// Returns true if and only if the regex matches the string given.
@@ -144,17 +422,17 @@ impl Regex {
     /// let text = "I categorically deny having triskaidekaphobia.";
     /// assert!(Regex::new(r"\b\w{13}\b").unwrap().is_match(text));
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scan fails, e.g. the scratch space can't be allocated for the thread, see `try_is_match`.
     pub fn is_match(&self, text: &str) -> bool {
-        let mut matched = false;
-
-        let s = self.0.alloc_scratch().unwrap();
-        let _ = self.0.scan(text, &s, |_, _, _, _| {
-            matched = true;
-
-            Matching::Terminate
-        });
+        self.try_is_match(text).expect("scan text")
+    }
 
-        matched
+    /// Returns true if and only if the regex matches the string given, or the error if the scan fails.
+    pub fn try_is_match(&self, text: &str) -> Result<bool> {
+        self.matcher.is_match(text.as_bytes())
     }
 
     /// Returns the start and end byte range of the leftmost-first match in text. If no match exists, then None is returned.
@@ -172,19 +450,80 @@ impl Regex {
     /// assert_eq!(mat.start(), 2);
     /// assert_eq!(mat.end(), 15);
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scan fails, e.g. the scratch space can't be allocated for the thread, see `try_find`.
     pub fn find<'t>(&self, text: &'t str) -> Option<Match<'t>> {
-        let mut matched = vec![];
+        self.try_find(text).expect("scan text")
+    }
 
-        let s = self.0.alloc_scratch().unwrap();
-        let _ = self.0.scan(text, &s, |_, from, to, _| {
-            matched.push((from as usize, to as usize));
+    /// Returns the leftmost-first match in text like `find`, or the error if the scan fails.
+    pub fn try_find<'t>(&self, text: &'t str) -> Result<Option<Match<'t>>> {
+        Ok(self
+            .matcher
+            .find(text.as_bytes())?
+            .map(|range| Match::new(text, range.start, range.end)))
+    }
 
-            Matching::Terminate
-        });
+    /// Returns the capture groups corresponding to the leftmost-first match in `text`.
+    ///
+    /// The capturing groups are reported by Chimera when the `chimera` feature is enabled,
+    /// otherwise only the overall match (the group 0) is captured, since Hyperscan doesn't report the sub-matches.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::regex::Regex;
+    /// let text = "I categorically deny having triskaidekaphobia.";
+    /// let caps = Regex::new(r"\b\w{13}\b").unwrap().captures(text).unwrap().unwrap();
+    ///
+    /// assert_eq!(caps.get(0).unwrap().as_str(), "categorically");
+    /// ```
+    pub fn captures<'t>(&self, text: &'t str) -> Result<Option<Captures<'t>>> {
+        Ok(self.captures_at(text, true)?.next())
+    }
 
-        matched
-            .first()
-            .map(|&(start, end)| Match::new(&text[start..end], start, end))
+    /// Returns an iterator over all the non-overlapping capture groups matched in `text`.
+    ///
+    /// This is operationally the same as `find_iter`, except it yields information about capturing group matches.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::regex::Regex;
+    /// let re = Regex::new(r"'([^']+)'\s+\((\d{4})\)").unwrap();
+    /// let text = "'Citizen Kane' (1941), 'The Wizard of Oz' (1939), 'M' (1931).";
+    ///
+    /// let titles = re
+    ///     .captures_iter(text)
+    ///     .unwrap()
+    ///     .map(|caps| caps.get(0).unwrap().as_str())
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(titles, vec!["'Citizen Kane' (1941)", "'The Wizard of Oz' (1939)", "'M' (1931)"]);
+    /// ```
+    pub fn captures_iter<'t>(&self, text: &'t str) -> Result<CaptureMatches<'t>> {
+        self.captures_at(text, false)
+    }
+
+    fn captures_at<'t>(&self, text: &'t str, first: bool) -> Result<CaptureMatches<'t>> {
+        #[cfg(feature = "chimera")]
+        let names = self.names.clone();
+        #[cfg(not(feature = "chimera"))]
+        let names = Arc::<HashMap<_, _>>::default();
+
+        Ok(CaptureMatches(
+            self.capture_locations(text, first)?
+                .into_iter()
+                .map(|locs| Captures {
+                    text,
+                    locs,
+                    names: names.clone(),
+                })
+                .collect::<Vec<_>>()
+                .into_iter(),
+        ))
     }
 
     /// Returns an iterator for each successive non-overlapping match in
@@ -203,25 +542,63 @@ impl Regex {
     ///     println!("{:?}", mat);
     /// }
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scan fails, e.g. the scratch space can't be allocated for the thread, see `try_find_iter`.
     pub fn find_iter<'t>(&self, text: &'t str) -> Matches<'t> {
-        let mut matched = Vec::<Range<usize>>::new();
-
-        let s = self.0.alloc_scratch().unwrap();
-        let _ = self.0.scan(text, &s, |_, from, to, _| {
-            let range = from as usize..to as usize;
-
-            match matched.last() {
-                Some(last) if last.start == range.start && last.end < range.end => {
-                    // only the non-overlapping match should be return
-                    *matched.last_mut().unwrap() = range;
-                }
-                _ => matched.push(range),
-            }
+        self.try_find_iter(text).expect("scan text")
+    }
 
-            Matching::Continue
-        });
+    /// Returns an iterator for each successive non-overlapping match in `text` like `find_iter`,
+    /// or the error if the scan fails.
+    pub fn try_find_iter<'t>(&self, text: &'t str) -> Result<Matches<'t>> {
+        Ok(Matches::new(text, self.matcher.find_iter(text.as_bytes())?))
+    }
 
-        Matches(text, matched.into_iter())
+    /// Returns an iterator for each successive non-overlapping match in `text`, like `find_iter`,
+    /// but the text is scanned on demand instead of collecting all the matches up front.
+    ///
+    /// It should be used for the huge text with a lot of matches, to avoid buffering them in memory.
+    ///
+    /// The text is written to a stream in chunks, so the expression sees the whole text as `find_iter` does,
+    /// e.g. `^` only matches at the start of the text. The streaming database is compiled on the first call,
+    /// with the large start of match horizon.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::regex::Regex;
+    /// let re = Regex::new(r"\d+").unwrap();
+    /// let matches = re
+    ///     .find_iter_lazy("a1b22c333")
+    ///     .unwrap()
+    ///     .map(|m| m.map(|m| m.as_str()))
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    /// assert_eq!(matches, vec!["1", "22", "333"]);
+    /// ```
+    pub fn find_iter_lazy<'t>(&self, text: &'t str) -> Result<LazyMatches<'t>> {
+        let matcher = self
+            .streaming
+            .get_or_try_init(|| {
+                Matcher::new(&Pattern {
+                    som: Some(SomHorizon::Large),
+                    ..(*self.pattern).clone()
+                })
+            })?
+            .clone();
+        let stream = matcher.db.open_stream()?;
+
+        Ok(LazyMatches {
+            matcher,
+            stream: Some(stream),
+            text,
+            pos: 0,
+            chunk_size: LAZY_CHUNK_SIZE,
+            pending: None,
+            ready: VecDeque::new(),
+        })
     }
 
     /// Returns an iterator of substrings of `text` delimited by a match of the
@@ -240,11 +617,18 @@ impl Regex {
     /// let fields: Vec<&str> = re.split("a b \t  c\td    e").collect();
     /// assert_eq!(fields, vec!["a", "b", "c", "d", "e"]);
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scan fails, e.g. the scratch space can't be allocated for the thread, see `try_split`.
     pub fn split<'t>(&self, text: &'t str) -> Split<'t> {
-        Split {
-            finder: self.find_iter(text),
-            last: 0,
-        }
+        self.try_split(text).expect("scan text")
+    }
+
+    /// Returns an iterator of substrings of `text` delimited by a match of the regular expression like `split`,
+    /// or the error if the scan fails.
+    pub fn try_split<'t>(&self, text: &'t str) -> Result<Split<'t>> {
+        Ok(Split::new(self.try_find_iter(text)?))
     }
 
     /// Returns an iterator of at most `limit` substrings of `text` delimited
@@ -265,82 +649,239 @@ impl Regex {
     /// let fields: Vec<&str> = re.splitn("Hey! How are you?", 3).collect();
     /// assert_eq!(fields, vec!("Hey", "How", "are you?"));
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scan fails, e.g. the scratch space can't be allocated for the thread, see `try_splitn`.
     pub fn splitn<'t>(&self, text: &'t str, limit: usize) -> SplitN<'t> {
-        SplitN {
-            splits: self.split(text),
-            n: limit,
-        }
+        self.try_splitn(text, limit).expect("scan text")
     }
-}
 
-/// Yields all substrings delimited by a regular expression match.
-///
-/// `'t` is the lifetime of the string being split.
-pub struct Split<'t> {
-    finder: Matches<'t>,
-    last: usize,
+    /// Returns an iterator of at most `limit` substrings of `text` delimited by a match of the regular expression
+    /// like `splitn`, or the error if the scan fails.
+    pub fn try_splitn<'t>(&self, text: &'t str, limit: usize) -> Result<SplitN<'t>> {
+        Ok(SplitN::new(self.try_split(text)?, limit))
+    }
+
+    /// Returns an iterator of substrings of `text` delimited by a match of the
+    /// regular expression, like `split`, except that a trailing empty substring
+    /// is skipped when the text ends with a match.
+    ///
+    /// This method will *not* copy the text given.
+    ///
+    /// # Examples
+    ///
+    /// Split the lines terminated by newlines:
+    ///
+    /// ```rust
+    /// # use hyperscan::regex::Regex;
+    /// let re = Regex::new(r"\r?\n").unwrap();
+    /// let lines: Vec<&str> = re.split_terminator("foo\r\nbar\n").unwrap().collect();
+    /// assert_eq!(lines, vec!["foo", "bar"]);
+    /// ```
+    pub fn split_terminator<'t>(&self, text: &'t str) -> Result<SplitTerminator<'t>> {
+        Ok(SplitTerminator::new(self.try_split(text)?))
+    }
 }
 
-impl<'t> Iterator for Split<'t> {
-    type Item = &'t str;
+/// Returns the index of the named capture groups in the expression, e.g. `(?P<name>...)`, `(?<name>...)`
+/// or `(?'name'...)`, the groups are numbered by their opening parentheses like PCRE.
+#[cfg(feature = "chimera")]
+fn capture_names(expr: &str) -> HashMap<String, usize> {
+    let mut names = HashMap::new();
+    let mut group = 0;
+    let mut in_class = false;
+    let mut chars = expr.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some((_, 'Q')) = chars.next() {
+                    // skip the quoted literal until `\E`
+                    while let Some((_, c)) = chars.next() {
+                        if c == '\\' && matches!(chars.peek(), Some((_, 'E'))) {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+            }
+            '[' if !in_class => {
+                in_class = true;
 
-    fn next(&mut self) -> Option<&'t str> {
-        let text = self.finder.text();
-        match self.finder.next() {
-            None => {
-                if self.last > text.len() {
-                    None
-                } else {
-                    let s = &text[self.last..];
-                    self.last = text.len() + 1; // Next call will return None
-                    Some(s)
+                // a leading `]` is a literal in the class
+                if let Some((_, '^')) = chars.peek() {
+                    chars.next();
+                }
+                if let Some((_, ']')) = chars.peek() {
+                    chars.next();
                 }
             }
-            Some(m) => {
-                let matched = &text[self.last..m.start()];
-                self.last = m.end();
-                Some(matched)
+            ']' if in_class => in_class = false,
+            '(' if !in_class => {
+                let rest = &expr[i + 1..];
+
+                if !rest.starts_with('?') && !rest.starts_with('*') {
+                    group += 1;
+                } else if let Some(name) = group_name(rest) {
+                    group += 1;
+                    names.insert(name.to_owned(), group);
+                }
             }
+            _ => {}
         }
     }
+
+    names
 }
 
-/// Yields at most `N` substrings delimited by a regular expression match.
-///
-/// The last substring will be whatever remains after splitting.
-///
-/// `'t` is the lifetime of the string being split.
-pub struct SplitN<'t> {
-    splits: Split<'t>,
-    n: usize,
+#[cfg(feature = "chimera")]
+fn group_name(rest: &str) -> Option<&str> {
+    let (rest, close) = if let Some(rest) = rest.strip_prefix("?P<") {
+        (rest, '>')
+    } else if let Some(rest) = rest.strip_prefix("?<") {
+        (rest, '>')
+    } else if let Some(rest) = rest.strip_prefix("?'") {
+        (rest, '\'')
+    } else {
+        return None;
+    };
+
+    let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))?;
+
+    if len > 0 && rest[len..].starts_with(close) {
+        Some(&rest[..len])
+    } else {
+        None
+    }
 }
 
-impl<'t> Iterator for SplitN<'t> {
-    type Item = &'t str;
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_find_as_str() {
+        let text = "I categorically deny having triskaidekaphobia.";
+        let m = super::Regex::new(r"\b\w{13}\b").unwrap().find(text).unwrap();
 
-    fn next(&mut self) -> Option<&'t str> {
-        if self.n == 0 {
-            return None;
-        }
+        assert_eq!(m.range(), 2..15);
+        assert_eq!(m.as_str(), "categorically");
+        assert_eq!(m.as_str().as_ptr(), text[2..].as_ptr());
+    }
 
-        self.n -= 1;
-        if self.n > 0 {
-            return self.splits.next();
-        }
+    #[test]
+    fn test_captures_iter() {
+        let text = "I categorically deny having triskaidekaphobia.";
+        let caps = super::Regex::new(r"\b\w{13}\b")
+            .unwrap()
+            .captures(text)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(caps.len(), 1);
+        assert_eq!(caps.get(0).map(|m| m.range()), Some(2..15));
+        assert_eq!(caps.get(1), None);
+        assert_eq!(caps.iter().len(), 1);
+        assert_eq!(
+            caps.iter().rev().map(|m| m.map(|m| m.as_str())).collect::<Vec<_>>(),
+            vec![Some("categorically")]
+        );
 
-        let text = self.splits.finder.text();
-        if self.splits.last > text.len() {
-            // We've already returned all substrings.
-            None
-        } else {
-            // self.n == 0, so future calls will return None immediately
-            Some(&text[self.splits.last..])
+        assert!(super::Regex::new(r"\d+").unwrap().captures(text).unwrap().is_none());
+    }
+
+    #[cfg(feature = "chimera")]
+    #[test]
+    fn test_captures_groups() {
+        let text = "alice@example.com, bob@.com";
+        let re = super::Regex::new(r"(\w+)@(\w+)?\.com").unwrap();
+
+        let caps = re.captures(text).unwrap().unwrap();
+
+        assert_eq!(caps.len(), 3);
+        assert_eq!(caps.get(1).unwrap().as_str(), "alice");
+        assert_eq!(caps.get(2).unwrap().as_str(), "example");
+
+        assert_eq!(
+            re.captures_iter(text)
+                .unwrap()
+                .map(|caps| caps.iter().map(|m| m.map(|m| m.as_str())).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            vec![
+                vec![Some("alice@example.com"), Some("alice"), Some("example")],
+                vec![Some("bob@.com"), Some("bob"), None],
+            ]
+        );
+    }
+
+    fn lazy_ranges(matches: super::LazyMatches<'_>) -> Vec<std::ops::Range<usize>> {
+        matches.map(|m| m.unwrap().range()).collect()
+    }
+
+    #[test]
+    fn test_find_iter_lazy() {
+        let text = "Retroactively relinquishing remunerations is reprehensible.";
+        let re = super::Regex::new(r"\b\w{13}\b").unwrap();
+
+        assert_eq!(
+            lazy_ranges(re.find_iter_lazy(text).unwrap()),
+            re.find_iter(text).map(|m| m.range()).collect::<Vec<_>>()
+        );
+
+        let re = super::Regex::new(r"a+").unwrap();
+
+        assert_eq!(lazy_ranges(re.find_iter_lazy("baaéa").unwrap()), vec![1..3, 5..6]);
+        assert!(lazy_ranges(re.find_iter_lazy("").unwrap()).is_empty());
+
+        // the matches spanning the chunks are extended until a following match is reported
+        let mut matches = re.find_iter_lazy("baaaab aa").unwrap();
+        matches.chunk_size = 2;
+
+        assert_eq!(lazy_ranges(matches), vec![1..5, 7..9]);
+    }
+
+    #[test]
+    fn test_find_iter_lazy_anchored() {
+        let re = super::Regex::new(r"^\d").unwrap();
+
+        for &chunk_size in &[1, 2, super::LAZY_CHUNK_SIZE] {
+            let mut matches = re.find_iter_lazy("123").unwrap();
+            matches.chunk_size = chunk_size;
+
+            assert_eq!(lazy_ranges(matches), vec![0..1]);
         }
+
+        let re = super::Regex::new(r"\b\d").unwrap();
+        let mut matches = re.find_iter_lazy("12 34").unwrap();
+        matches.chunk_size = 1;
+
+        assert_eq!(lazy_ranges(matches), vec![0..1, 3..4]);
+
+        let re = super::Regex::new(r"\d$").unwrap();
+        let mut matches = re.find_iter_lazy("12 34").unwrap();
+        matches.chunk_size = 2;
+
+        assert_eq!(lazy_ranges(matches), vec![4..5]);
+    }
+
+    #[test]
+    fn test_split() {
+        let re = super::Regex::new(r",").unwrap();
+
+        assert_eq!(re.split("a,b,,c,").collect::<Vec<_>>(), vec!["a", "b", "", "c", ""]);
+        assert_eq!(re.splitn("a,b,,c,", 2).collect::<Vec<_>>(), vec!["a", "b,,c,"]);
+        assert_eq!(re.splitn("a,b", 0).count(), 0);
+        assert_eq!(
+            re.try_splitn("a,b,,c,", 2).unwrap().collect::<Vec<_>>(),
+            vec!["a", "b,,c,"]
+        );
+        assert_eq!(
+            re.split_terminator("a,b,,c,").unwrap().collect::<Vec<_>>(),
+            vec!["a", "b", "", "c"]
+        );
+        assert_eq!(re.split_terminator("a,b").unwrap().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(re.split_terminator("").unwrap().count(), 0);
     }
-}
 
-#[cfg(test)]
-mod tests {
     #[test]
     fn test_find_iter() {
         let regex = r"\b\w{13}\b";
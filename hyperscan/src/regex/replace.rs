@@ -0,0 +1,324 @@
+use std::borrow::Cow;
+
+use crate::{
+    regex::{Captures, Regex},
+    Error, Result,
+};
+
+/// Replacer describes types that can be used to replace matches in a string.
+///
+/// It's implemented for the replacement templates, e.g. `&str` or `String`,
+/// and the closures `FnMut(&Captures) -> T` where `T: AsRef<str>`.
+pub trait Replacer {
+    /// Appends text to `dst` to replace the current match.
+    ///
+    /// The current match is represented by `caps`, which is guaranteed to have a match at capture group `0`.
+    ///
+    /// Returns an error if the replacement can't be expanded, e.g. it references a group which doesn't exist.
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) -> Result<()>;
+
+    /// Return a fixed unchanging replacement string, without any capture group reference.
+    ///
+    /// When it returns `Some`, the capture groups don't need to be located.
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        None
+    }
+}
+
+impl Replacer for &str {
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) -> Result<()> {
+        caps.expand(self, dst)
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        no_expansion(self)
+    }
+}
+
+impl Replacer for &String {
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) -> Result<()> {
+        caps.expand(self, dst)
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        no_expansion(self)
+    }
+}
+
+impl Replacer for String {
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) -> Result<()> {
+        caps.expand(self, dst)
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        no_expansion(self)
+    }
+}
+
+impl<F, T> Replacer for F
+where
+    F: FnMut(&Captures<'_>) -> T,
+    T: AsRef<str>,
+{
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) -> Result<()> {
+        dst.push_str((*self)(caps).as_ref());
+
+        Ok(())
+    }
+}
+
+/// `NoExpand` indicates literal string replacement.
+///
+/// The `$` in the replacement string is not treated as a capture group reference.
+#[derive(Clone, Debug)]
+pub struct NoExpand<'t>(pub &'t str);
+
+impl<'t> Replacer for NoExpand<'t> {
+    fn replace_append(&mut self, _: &Captures<'_>, dst: &mut String) -> Result<()> {
+        dst.push_str(self.0);
+
+        Ok(())
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.0))
+    }
+}
+
+fn no_expansion(replacement: &str) -> Option<Cow<'_, str>> {
+    if replacement.contains('$') {
+        None
+    } else {
+        Some(Cow::Borrowed(replacement))
+    }
+}
+
+/// A reference to a capture group in the replacement template.
+enum Ref<'a> {
+    Named(&'a str),
+    Number(usize),
+}
+
+/// Parse the capture group reference after a `$`, returns the reference and the remaining of the template.
+fn capture_ref(rest: &str) -> Option<(Ref<'_>, &str)> {
+    let (name, rest) = if rest.starts_with('{') {
+        let end = rest.find('}')?;
+
+        (&rest[1..end], &rest[end + 1..])
+    } else {
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+
+        rest.split_at(end)
+    };
+
+    if name.is_empty() {
+        None
+    } else {
+        let cap = name.parse().map(Ref::Number).unwrap_or(Ref::Named(name));
+
+        Some((cap, rest))
+    }
+}
+
+impl<'t> Captures<'t> {
+    /// Expands all instances of `$name` in `replacement` to the corresponding capture group `name`,
+    /// and writes them to the `dst` buffer given.
+    ///
+    /// `name` may be an integer corresponding to the index of the capture group (counted by order of opening
+    /// parenthesis where `0` is the entire match) or it can be a name (consisting of letters, digits or
+    /// underscores) corresponding to a named capture group.
+    ///
+    /// If `name` isn't a valid capture group (whether the name doesn't exist or isn't a valid index),
+    /// then `Error::InvalidGroup` is returned, while a valid group which didn't participate in the match
+    /// is replaced with the empty string.
+    ///
+    /// The longest possible name is used. e.g., `$1a` looks up the capture group named `1a` and not the capture
+    /// group at index `1`. To exert more precise control over the name, use braces, e.g., `${1}a`.
+    ///
+    /// To write a literal `$` use `$$`.
+    pub fn expand(&self, mut replacement: &str, dst: &mut String) -> Result<()> {
+        while let Some(pos) = replacement.find('$') {
+            dst.push_str(&replacement[..pos]);
+            replacement = &replacement[pos + 1..];
+
+            if replacement.starts_with('$') {
+                dst.push('$');
+                replacement = &replacement[1..];
+                continue;
+            }
+
+            match capture_ref(replacement) {
+                Some((cap, rest)) => {
+                    let i = match cap {
+                        Ref::Named(name) => self.names.get(name).copied(),
+                        Ref::Number(i) if i < self.len() => Some(i),
+                        Ref::Number(_) => None,
+                    }
+                    .ok_or_else(|| Error::InvalidGroup(replacement[..replacement.len() - rest.len()].to_owned()))?;
+
+                    if let Some(m) = self.get(i) {
+                        dst.push_str(m.as_str());
+                    }
+
+                    replacement = rest;
+                }
+                None => dst.push('$'),
+            }
+        }
+
+        dst.push_str(replacement);
+
+        Ok(())
+    }
+}
+
+impl Regex {
+    /// Replaces the leftmost-first match with the replacement provided.
+    ///
+    /// The replacement can be a regular string (where `$N` and `$name` are expanded to match capture groups)
+    /// or a function that takes the matches' `Captures` and returns the replaced string.
+    ///
+    /// If no match is found, then a copy of the string is returned unchanged.
+    ///
+    /// The matches and the capture groups are reported by Chimera when the `chimera` feature is enabled,
+    /// otherwise by Hyperscan, which only locates the entire match (`$0`), so referencing another group returns
+    /// `Error::InvalidGroup`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::regex::Regex;
+    /// let re = Regex::new("[^01]+").unwrap();
+    ///
+    /// assert_eq!(re.replace("1078910", "").unwrap(), "1010");
+    /// ```
+    pub fn replace<'t, R: Replacer>(&self, text: &'t str, rep: R) -> Result<Cow<'t, str>> {
+        self.replacen(text, 1, rep)
+    }
+
+    /// Replaces all non-overlapping matches in `text` with the replacement provided.
+    ///
+    /// This is the same as calling `replacen` with `limit` set to `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::regex::{Captures, NoExpand, Regex};
+    /// let re = Regex::new(r"\d+").unwrap();
+    ///
+    /// assert_eq!(re.replace_all("a1b22c333", "#").unwrap(), "a#b#c#");
+    /// assert_eq!(re.replace_all("a1b22", NoExpand("$0")).unwrap(), "a$0b$0");
+    /// assert_eq!(re.replace_all("a1b22", "<$0>").unwrap(), "a<1>b<22>");
+    /// assert_eq!(
+    ///     re.replace_all("a1b22", |caps: &Captures| caps.get(0).unwrap().as_str().len().to_string())
+    ///         .unwrap(),
+    ///     "a1b2"
+    /// );
+    /// ```
+    pub fn replace_all<'t, R: Replacer>(&self, text: &'t str, rep: R) -> Result<Cow<'t, str>> {
+        self.replacen(text, 0, rep)
+    }
+
+    /// Replaces at most `limit` non-overlapping matches in `text` with the replacement provided.
+    /// If `limit` is `0`, then all non-overlapping matches are replaced.
+    pub fn replacen<'t, R: Replacer>(&self, text: &'t str, limit: usize, mut rep: R) -> Result<Cow<'t, str>> {
+        let limit = if limit == 0 { usize::MAX } else { limit };
+        let mut it = self.captures_iter(text)?.take(limit).peekable();
+
+        if it.peek().is_none() {
+            return Ok(Cow::Borrowed(text));
+        }
+
+        let fixed = rep.no_expansion().map(Cow::into_owned);
+        let mut new = String::with_capacity(text.len());
+        let mut last = 0;
+
+        for caps in it {
+            let m = caps.get(0).unwrap();
+
+            new.push_str(&text[last..m.start()]);
+
+            match fixed {
+                Some(ref rep) => new.push_str(rep),
+                None => rep.replace_append(&caps, &mut new)?,
+            }
+
+            last = m.end();
+        }
+
+        new.push_str(&text[last..]);
+
+        Ok(Cow::Owned(new))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use crate::{
+        regex::{NoExpand, Regex},
+        Error,
+    };
+
+    #[test]
+    fn test_replace() {
+        let re = Regex::new(r"\d+").unwrap();
+
+        assert!(matches!(re.replace_all("abc", "#").unwrap(), Cow::Borrowed("abc")));
+        assert_eq!(re.replace("a1b22c333", "#").unwrap(), "a#b22c333");
+        assert_eq!(re.replacen("a1b22c333", 2, "#").unwrap(), "a#b#c333");
+        assert_eq!(re.replace_all("a1b22c333", "[$0]").unwrap(), "a[1]b[22]c[333]");
+        assert_eq!(
+            re.replace_all("a1b22", "$$0 ${0}x $0x $").unwrap(),
+            "a$0 1x  $b$0 22x  $"
+        );
+        assert_eq!(re.replace_all("a1b22", NoExpand("$0")).unwrap(), "a$0b$0");
+        assert_eq!(re.replace_all("abc", "$1").unwrap(), "abc");
+    }
+
+    #[cfg(not(feature = "chimera"))]
+    #[test]
+    fn test_replace_without_groups() {
+        let re = Regex::new(r"(\w+)\s+(\w+)").unwrap();
+
+        assert_eq!(re.replace("Springsteen Bruce", "[$0]").unwrap(), "[Springsteen Bruce]");
+        assert_eq!(
+            re.replace("Springsteen Bruce", "$2 $1").unwrap_err(),
+            Error::InvalidGroup("2".to_owned())
+        );
+        assert_eq!(
+            re.replace("Springsteen Bruce", "${first}").unwrap_err(),
+            Error::InvalidGroup("{first}".to_owned())
+        );
+    }
+
+    #[cfg(feature = "chimera")]
+    #[test]
+    fn test_replace_captures() {
+        let re = Regex::new(r"(?P<first>\w+)\s+(\w+)").unwrap();
+
+        assert_eq!(
+            re.replace("Springsteen Bruce", "$2 $first").unwrap(),
+            "Bruce Springsteen"
+        );
+        assert_eq!(
+            re.replace("Springsteen Bruce", "${2}_${1}").unwrap(),
+            "Bruce_Springsteen"
+        );
+        assert_eq!(
+            re.replace("Springsteen Bruce", "$3").unwrap_err(),
+            Error::InvalidGroup("3".to_owned())
+        );
+        assert_eq!(
+            re.replace("Springsteen Bruce", "$last").unwrap_err(),
+            Error::InvalidGroup("last".to_owned())
+        );
+
+        let re = Regex::new(r"(\w+)@(\w+)?\.com").unwrap();
+
+        assert_eq!(re.replace_all("bob@.com", "$1 at [$2]").unwrap(), "bob at []");
+    }
+}
@@ -0,0 +1,98 @@
+use std::ops::Range;
+
+use crate::{
+    compile::Flags,
+    regex::bytes::{self, SetMatches},
+    Result,
+};
+
+/// Match multiple (possibly overlapping) regular expressions in a single scan of a string.
+///
+/// The regular expressions are compiled in UTF-8 mode, see `bytes::RegexSet` to match arbitrary bytes.
+#[derive(Clone)]
+pub struct RegexSet(bytes::RegexSet);
+
+impl RegexSet {
+    /// Create a new regex set with the given regular expressions.
+    ///
+    /// If any of the expressions are invalid, then an error is returned.
+    pub fn new<I, S>(exprs: I) -> Result<RegexSet>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        bytes::RegexSet::with_flags(exprs, Flags::UTF8).map(RegexSet)
+    }
+
+    /// Returns true if and only if one of the regexes in this set matches the string given.
+    pub fn is_match(&self, text: &str) -> Result<bool> {
+        self.0.is_match(text.as_bytes())
+    }
+
+    /// Returns the set of regular expressions that match in the given string.
+    pub fn matches(&self, text: &str) -> Result<SetMatches> {
+        self.0.matches(text.as_bytes())
+    }
+
+    /// Returns the index and the offsets of the first match of every regular expression matching the string,
+    /// ordered by the index.
+    ///
+    /// Every regular expression reports only its first match, i.e. the one ending first,
+    /// with its leftmost starting offset, all in a single scan.
+    ///
+    /// The regular expressions are compiled again with the `SOM_LEFTMOST` flag on the first call,
+    /// which returns an error if one of them doesn't support it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::regex::RegexSet;
+    /// let set = RegexSet::new(&[r"GET", r"\.\./", r"POST"]).unwrap();
+    /// let text = "GET /a/../../etc/passwd";
+    ///
+    /// assert_eq!(
+    ///     set.matches_with_offsets(text).unwrap(),
+    ///     vec![(0, 0..3), (1, 7..10)]
+    /// );
+    /// assert_eq!(&text[7..10], "../");
+    /// ```
+    pub fn matches_with_offsets(&self, text: &str) -> Result<Vec<(usize, Range<usize>)>> {
+        self.0.matches_with_offsets(text.as_bytes())
+    }
+
+    /// Returns the total number of regular expressions in this set.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if this set contains no regular expressions.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the patterns that this set will match on.
+    pub fn patterns(&self) -> &[String] {
+        self.0.patterns()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RegexSet;
+
+    #[test]
+    fn test_regex_set() {
+        let set = RegexSet::new([r"^GET", r"é+", r"POST"]).unwrap();
+        let text = "GET /café";
+
+        assert_eq!(set.len(), 3);
+        assert!(set.is_match(text).unwrap());
+        assert_eq!(set.matches(text).unwrap().iter().collect::<Vec<_>>(), vec![0, 1]);
+        assert!(!set.matches("PUT /").unwrap().matched_any());
+
+        let matches = set.matches_with_offsets(text).unwrap();
+
+        assert_eq!(matches, vec![(0, 0..3), (1, 8..10)]);
+        assert_eq!(&text[matches[1].1.clone()], "é");
+    }
+}
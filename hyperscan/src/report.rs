@@ -0,0 +1,271 @@
+//! Summarization of scan results
+//!
+//! The `Summary` aggregates the match events of one or more scans into a `Finding` per pattern,
+//! which can be exported to a SIEM or any other security pipeline (serialized with `serde`
+//! when the `serde` feature is enabled).
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use hyperscan::{prelude::*, report::{IdMap, Summary}};
+//! let db: BlockDatabase = patterns!["foo", "bar"; SOM_LEFTMOST].build().unwrap();
+//! let s = db.alloc_scratch().unwrap();
+//! let data = "foo bar foo";
+//!
+//! let names: IdMap = vec![(0, "FOO-RULE")].into_iter().collect();
+//! let mut summary = Summary::new().with_id_map(names).with_context(2).with_som();
+//!
+//! db.scan(data, &s, summary.on_match(data)).unwrap();
+//!
+//! let findings = summary.finish();
+//!
+//! assert_eq!(findings.len(), 2);
+//! assert_eq!(findings[0].id, 0);
+//! assert_eq!(findings[0].rule.as_deref(), Some("FOO-RULE"));
+//! assert_eq!(findings[0].count, 2);
+//! assert_eq!(findings[0].first, 0..3);
+//! assert_eq!(findings[0].last, 8..11);
+//! assert_eq!(findings[0].sample.as_deref(), Some("foo b"));
+//! assert_eq!(findings[1].rule, None);
+//! ```
+use std::collections::{BTreeMap, HashMap};
+use std::iter::FromIterator;
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::runtime::Matching;
+
+/// The names of the rules, indexed by the pattern ID.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct IdMap(HashMap<u32, String>);
+
+impl IdMap {
+    /// Construct an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the name of the rule with the pattern ID, returning the previous name.
+    pub fn insert<S: Into<String>>(&mut self, id: u32, name: S) -> Option<String> {
+        self.0.insert(id, name.into())
+    }
+
+    /// Returns the name of the rule with the pattern ID.
+    pub fn get(&self, id: u32) -> Option<&str> {
+        self.0.get(&id).map(String::as_str)
+    }
+
+    /// Returns the number of the named rules.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if no rule is named.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<S: Into<String>> Extend<(u32, S)> for IdMap {
+    fn extend<I: IntoIterator<Item = (u32, S)>>(&mut self, iter: I) {
+        self.0.extend(iter.into_iter().map(|(id, name)| (id, name.into())))
+    }
+}
+
+impl<S: Into<String>> FromIterator<(u32, S)> for IdMap {
+    fn from_iter<I: IntoIterator<Item = (u32, S)>>(iter: I) -> Self {
+        let mut names = IdMap::new();
+        names.extend(iter);
+        names
+    }
+}
+
+/// The aggregated matches of a pattern.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Finding {
+    /// The ID number of the pattern.
+    pub id: u32,
+    /// The name of the rule, if known.
+    pub rule: Option<String>,
+    /// The number of matches.
+    pub count: usize,
+    /// The offsets of the first match, starting at `0` if the start of match isn't reported.
+    pub first: Range<u64>,
+    /// The offsets of the last match, starting at `0` if the start of match isn't reported.
+    pub last: Range<u64>,
+    /// The data around the first match, if the context was requested.
+    pub sample: Option<String>,
+}
+
+/// Summarizes the match events into a `Finding` per pattern.
+#[derive(Clone, Debug, Default)]
+pub struct Summary {
+    names: IdMap,
+    context: Option<usize>,
+    som: bool,
+    findings: BTreeMap<u32, Finding>,
+}
+
+impl Summary {
+    /// Construct an empty summary.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the names of rules, indexed by the pattern ID.
+    pub fn with_names<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = (u32, S)>,
+        S: Into<String>,
+    {
+        self.names.extend(names);
+        self
+    }
+
+    /// Set the names of rules from the map, replacing the names set before.
+    pub fn with_id_map(mut self, names: IdMap) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// Keep a sample of the data around the first match of every pattern,
+    /// with `len` bytes before and after the match.
+    ///
+    /// Unless `with_som` is set, only the match end is known and the sample starts at the end of match.
+    pub fn with_context(mut self, len: usize) -> Self {
+        self.context = Some(len);
+        self
+    }
+
+    /// The matches report their start offset, i.e. the patterns are compiled with the `SOM_LEFTMOST` flag.
+    ///
+    /// Hyperscan reports `0` as the start of match of the other patterns,
+    /// which would sample the context from the beginning of the data.
+    pub fn with_som(mut self) -> Self {
+        self.som = true;
+        self
+    }
+
+    /// Record a match event of the pattern.
+    ///
+    /// The `data` is the scanned data, used to sample the context of the first match.
+    pub fn record(&mut self, id: u32, from: u64, to: u64, data: &[u8]) {
+        let names = &self.names;
+        let context = self.context;
+        let som = self.som;
+
+        self.findings
+            .entry(id)
+            .and_modify(|finding| {
+                finding.count += 1;
+                finding.last = from..to;
+            })
+            .or_insert_with(|| Finding {
+                id,
+                rule: names.get(id).map(str::to_owned),
+                count: 1,
+                first: from..to,
+                last: from..to,
+                sample: context.and_then(|len| {
+                    let start = if som {
+                        (from as usize).saturating_sub(len)
+                    } else {
+                        to as usize
+                    };
+                    let end = (to as usize).saturating_add(len).min(data.len());
+
+                    data.get(start..end).map(|s| String::from_utf8_lossy(s).into_owned())
+                }),
+            });
+    }
+
+    /// Returns a match event handler which records the matches in the `data`.
+    pub fn on_match<'a, T>(&'a mut self, data: &'a T) -> impl FnMut(u32, u64, u64, u32) -> Matching + 'a
+    where
+        T: AsRef<[u8]> + ?Sized,
+    {
+        move |id, from, to, _flags| {
+            self.record(id, from, to, data.as_ref());
+
+            Matching::Continue
+        }
+    }
+
+    /// Returns `true` if no match was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// Returns an iterator over the findings, ordered by the pattern ID.
+    pub fn findings(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.values()
+    }
+
+    /// Consumes the summary, returning the findings ordered by the pattern ID.
+    pub fn finish(self) -> Vec<Finding> {
+        self.findings.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let db: BlockDatabase = "/foo/L".parse().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        let mut summary = Summary::new().with_names(vec![(0, "foo")]);
+
+        db.scan("foo", &s, summary.on_match("foo")).unwrap();
+        db.scan("barfoo", &s, summary.on_match("barfoo")).unwrap();
+
+        assert!(!summary.is_empty());
+        assert_eq!(
+            summary.finish(),
+            vec![Finding {
+                id: 0,
+                rule: Some("foo".into()),
+                count: 2,
+                first: 0..3,
+                last: 3..6,
+                sample: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_summary_context() {
+        let data = "barfoobaz";
+        let names: IdMap = vec![(0, "foo")].into_iter().collect();
+
+        assert_eq!(names.get(0), Some("foo"));
+        assert_eq!(names.get(1), None);
+
+        let db: BlockDatabase = "/foo/".parse().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut summary = Summary::new().with_id_map(names.clone()).with_context(2);
+
+        db.scan(data, &s, summary.on_match(data)).unwrap();
+
+        let findings = summary.finish();
+
+        assert_eq!(findings[0].rule.as_deref(), Some("foo"));
+        assert_eq!(findings[0].first, 0..6);
+        assert_eq!(findings[0].sample.as_deref(), Some("ba"));
+
+        let db: BlockDatabase = "/foo/L".parse().unwrap();
+        let mut summary = Summary::new().with_id_map(names).with_context(2).with_som();
+
+        db.scan(data, &s, summary.on_match(data)).unwrap();
+
+        assert_eq!(summary.finish()[0].sample.as_deref(), Some("arfooba"));
+    }
+}
@@ -0,0 +1,219 @@
+use std::cmp::Reverse;
+use std::collections::{hash_map::Entry, BTreeMap, HashMap};
+
+use crate::{
+    common::catch_unwind,
+    ffi,
+    runtime::{MatchEvent, MatchEventHandler, Matching},
+};
+
+/// The policy to aggregate the matches fed to a `MatchAccumulator`.
+///
+/// The `Longest` and `NonOverlapping` policies depend on the start of match,
+/// so the patterns should be compiled with the `SOM_LEFTMOST` flag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccumulatePolicy {
+    /// Keep the first match reported for each pattern ID.
+    FirstPerId,
+    /// Keep the longest match for each pattern ID, the first one wins a tie.
+    Longest,
+    /// Keep the leftmost non-overlapping matches of all the patterns, the longest one wins at the same start.
+    NonOverlapping,
+    /// Only count the matches of each pattern ID.
+    CountOnly,
+}
+
+/// Aggregates the matches of the scans with a policy, instead of a hand-written collector closure.
+///
+/// The matches of every pattern ID are counted regardless of the policy.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, AccumulatePolicy, MatchAccumulator, MatchEvent};
+/// let db: BlockDatabase = patterns!["a+", "b"; SOM_LEFTMOST].build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut acc = MatchAccumulator::new(AccumulatePolicy::Longest);
+///
+/// db.scan("aaab", &s, &mut acc).unwrap();
+///
+/// assert_eq!(
+///     acc.matches(),
+///     vec![MatchEvent { id: 0, from: 0, to: 3 }, MatchEvent { id: 1, from: 3, to: 4 }]
+/// );
+/// assert_eq!(acc.count(0), 3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MatchAccumulator {
+    policy: AccumulatePolicy,
+    events: Vec<MatchEvent>,
+    index: HashMap<u32, usize>,
+    counts: BTreeMap<u32, usize>,
+}
+
+impl MatchAccumulator {
+    /// Construct an empty accumulator with the policy.
+    pub fn new(policy: AccumulatePolicy) -> Self {
+        MatchAccumulator {
+            policy,
+            events: vec![],
+            index: HashMap::new(),
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// The policy of the accumulator.
+    pub fn policy(&self) -> AccumulatePolicy {
+        self.policy
+    }
+
+    /// Feed a match to the accumulator.
+    pub fn push(&mut self, id: u32, from: u64, to: u64) {
+        let m = MatchEvent { id, from, to };
+
+        *self.counts.entry(id).or_default() += 1;
+
+        match self.policy {
+            AccumulatePolicy::FirstPerId | AccumulatePolicy::Longest => match self.index.entry(id) {
+                Entry::Vacant(e) => {
+                    e.insert(self.events.len());
+                    self.events.push(m);
+                }
+                Entry::Occupied(e) => {
+                    let longest = &mut self.events[*e.get()];
+
+                    if self.policy == AccumulatePolicy::Longest && to - from > longest.to - longest.from {
+                        *longest = m;
+                    }
+                }
+            },
+            AccumulatePolicy::NonOverlapping => self.events.push(m),
+            AccumulatePolicy::CountOnly => {}
+        }
+    }
+
+    /// Returns the aggregated matches.
+    ///
+    /// The matches are ordered by the first report of their pattern IDs for the `FirstPerId` and `Longest` policies,
+    /// by the offsets for the `NonOverlapping` policy, and always empty for the `CountOnly` policy.
+    pub fn matches(&self) -> Vec<MatchEvent> {
+        if self.policy != AccumulatePolicy::NonOverlapping {
+            return self.events.clone();
+        }
+
+        let mut events = self.events.clone();
+
+        events.sort_by_key(|m| (m.from, Reverse(m.to), m.id));
+
+        let mut end = 0;
+
+        events.retain(|m| {
+            if m.from >= end {
+                end = m.to;
+                true
+            } else {
+                false
+            }
+        });
+
+        events
+    }
+
+    /// Returns the number of matches of the pattern ID.
+    pub fn count(&self, id: u32) -> usize {
+        self.counts.get(&id).copied().unwrap_or_default()
+    }
+
+    /// Returns the number of matches of each pattern ID, in the order of the IDs.
+    pub fn counts(&self) -> &BTreeMap<u32, usize> {
+        &self.counts
+    }
+
+    /// Returns the total number of matches.
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Clears the accumulated matches, keeping the policy.
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.index.clear();
+        self.counts.clear();
+    }
+}
+
+impl MatchEventHandler for &mut MatchAccumulator {
+    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut core::ffi::c_void) {
+        unsafe extern "C" fn trampoline(
+            id: u32,
+            from: u64,
+            to: u64,
+            _: u32,
+            ctx: *mut core::ffi::c_void,
+        ) -> core::ffi::c_int {
+            let acc = &mut *(ctx as *mut MatchAccumulator);
+
+            catch_unwind(Matching::Terminate, || {
+                acc.push(id, from, to);
+                Matching::Continue
+            }) as _
+        }
+
+        (Some(trampoline), *self as *mut MatchAccumulator as *mut _)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::{AccumulatePolicy, MatchAccumulator, MatchEvent};
+
+    fn accumulate(policy: AccumulatePolicy) -> MatchAccumulator {
+        let db: BlockDatabase = patterns!["a+", "b"; SOM_LEFTMOST].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut acc = MatchAccumulator::new(policy);
+
+        db.scan("aaab aab", &s, &mut acc).unwrap();
+
+        acc
+    }
+
+    fn events(matches: &[(u32, u64, u64)]) -> Vec<MatchEvent> {
+        matches
+            .iter()
+            .map(|&(id, from, to)| MatchEvent { id, from, to })
+            .collect()
+    }
+
+    #[test]
+    fn test_match_accumulator() {
+        let acc = accumulate(AccumulatePolicy::FirstPerId);
+
+        assert_eq!(acc.matches(), events(&[(0, 0, 1), (1, 3, 4)]));
+        assert_eq!(acc.count(0), 5);
+        assert_eq!(acc.count(1), 2);
+        assert_eq!(acc.count(2), 0);
+        assert_eq!(acc.total(), 7);
+
+        let acc = accumulate(AccumulatePolicy::Longest);
+
+        assert_eq!(acc.matches(), events(&[(0, 0, 3), (1, 3, 4)]));
+
+        let acc = accumulate(AccumulatePolicy::NonOverlapping);
+
+        assert_eq!(acc.matches(), events(&[(0, 0, 3), (1, 3, 4), (0, 5, 7), (1, 7, 8)]));
+
+        let mut acc = accumulate(AccumulatePolicy::CountOnly);
+
+        assert!(acc.matches().is_empty());
+        assert_eq!(
+            acc.counts().iter().map(|(&id, &n)| (id, n)).collect::<Vec<_>>(),
+            vec![(0, 5), (1, 2)]
+        );
+
+        acc.clear();
+
+        assert_eq!(acc.total(), 0);
+        assert_eq!(acc.policy(), AccumulatePolicy::CountOnly);
+    }
+}
@@ -0,0 +1,119 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use futures::channel::oneshot;
+
+use crate::{Error, Result};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The maximum number of the worker threads, or `0` for the available parallelism.
+static MAX_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// The worker threads shared by the blocking scans, which are spawned on demand.
+static WORKERS: Mutex<Option<Workers>> = Mutex::new(None);
+
+struct Workers {
+    jobs: mpsc::Sender<Job>,
+    rx: Arc<Mutex<mpsc::Receiver<Job>>>,
+    idle: Arc<AtomicUsize>,
+    spawned: usize,
+}
+
+impl Workers {
+    fn new() -> Self {
+        let (jobs, rx) = mpsc::channel::<Job>();
+
+        Workers {
+            jobs,
+            rx: Arc::new(Mutex::new(rx)),
+            idle: Arc::new(AtomicUsize::new(0)),
+            spawned: 0,
+        }
+    }
+
+    /// Spawn a worker thread if all the spawned ones are busy, until the maximum number of the worker threads.
+    fn spawn_if_busy(&mut self) {
+        let max_threads = match MAX_THREADS.load(Ordering::Relaxed) {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+
+        if self.idle.load(Ordering::Acquire) > 0 || self.spawned >= max_threads {
+            return;
+        }
+
+        let rx = self.rx.clone();
+        let idle = self.idle.clone();
+
+        let spawned = thread::Builder::new()
+            .name("hyperscan-blocking".into())
+            .spawn(move || loop {
+                idle.fetch_add(1, Ordering::AcqRel);
+
+                let job = match rx.lock() {
+                    Ok(rx) => rx.recv(),
+                    Err(_) => return,
+                };
+
+                idle.fetch_sub(1, Ordering::AcqRel);
+
+                match job {
+                    Ok(job) => job(),
+                    Err(_) => return,
+                }
+            });
+
+        if spawned.is_ok() {
+            self.spawned += 1;
+        }
+    }
+}
+
+/// Set the maximum number of the worker threads shared by the blocking scans, e.g. `BlockDatabase::scan_owned`.
+///
+/// The worker threads are spawned on demand, when all the spawned ones are busy,
+/// up to the available parallelism by default; `0` restores the default.
+///
+/// The worker threads which have been spawned are kept running when the maximum is lowered.
+pub fn set_blocking_threads(max_threads: usize) {
+    MAX_THREADS.store(max_threads, Ordering::Relaxed);
+}
+
+/// Run the closure on a shared worker thread, and wait for its result without blocking the async task.
+///
+/// A panic of the closure is resumed on the awaiting task, the worker thread keeps running the other jobs.
+///
+/// Returns `Error::WorkerExited` if no worker thread could be spawned, or the worker threads exited
+/// before running the closure.
+pub(crate) async fn spawn_blocking<F, R>(f: F) -> Result<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    let job: Job = Box::new(move || {
+        let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(f)));
+    });
+
+    {
+        let mut workers = WORKERS.lock().map_err(|_| Error::WorkerExited)?;
+        let workers = workers.get_or_insert_with(Workers::new);
+
+        workers.spawn_if_busy();
+
+        if workers.spawned == 0 {
+            return Err(Error::WorkerExited);
+        }
+
+        workers.jobs.send(job).map_err(|_| Error::WorkerExited)?;
+    }
+
+    match rx.await {
+        Ok(Ok(res)) => Ok(res),
+        Ok(Err(payload)) => panic::resume_unwind(payload),
+        Err(_) => Err(Error::WorkerExited),
+    }
+}
@@ -0,0 +1,249 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    common::{DatabaseRef, Streaming},
+    runtime::{Matching, ScratchRef, Stream, StreamRef},
+    Error, HsError, Result,
+};
+
+/// A compressed snapshot of the stream state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    offset: u64,
+    state: Vec<u8>,
+}
+
+impl Checkpoint {
+    /// The stream offset when the snapshot was taken.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The compressed representation of the stream state.
+    pub fn state(&self) -> &[u8] {
+        &self.state
+    }
+}
+
+/// A stream that compresses its state every `interval` bytes and retains the last snapshots.
+///
+/// After a downstream error (e.g. a protocol parse error), the stream could be rewound
+/// to a retained checkpoint, and the recent data rescanned from there.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, CheckpointingStream};
+/// let db: StreamingDatabase = pattern! {"test"}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut st = CheckpointingStream::new(&db, 4, 8).unwrap();
+///
+/// let mut matches = vec![];
+/// let mut callback = |_, _, to, _| {
+///     matches.push(to);
+///     Matching::Continue
+/// };
+///
+/// st.scan("foo te", &s, &mut callback).unwrap();
+/// st.scan("st bar", &s, &mut callback).unwrap();
+///
+/// // rewind to the checkpoint at offset 4 and rescan the data from there
+/// assert_eq!(st.rewind(6, &s, &mut callback).unwrap(), Some(4));
+///
+/// st.scan("test bar", &s, &mut callback).unwrap();
+/// st.close(&s, &mut callback).unwrap();
+///
+/// assert_eq!(matches, vec![8, 8]);
+/// ```
+pub struct CheckpointingStream {
+    stream: Stream,
+    interval: u64,
+    capacity: usize,
+    state_size: usize,
+    offset: u64,
+    checkpoints: VecDeque<Checkpoint>,
+}
+
+impl fmt::Debug for CheckpointingStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CheckpointingStream")
+            .field("interval", &self.interval)
+            .field("capacity", &self.capacity)
+            .field("offset", &self.offset)
+            .field("checkpoints", &self.checkpoints)
+            .finish()
+    }
+}
+
+impl CheckpointingStream {
+    /// Open a stream against the database, taking a checkpoint every `interval` bytes
+    /// and retaining at most `capacity` checkpoints.
+    ///
+    /// The first checkpoint is taken at the start of the stream.
+    pub fn new(db: &DatabaseRef<Streaming>, interval: usize, capacity: usize) -> Result<Self> {
+        let mut st = CheckpointingStream {
+            stream: db.open_stream()?,
+            interval: interval.max(1) as u64,
+            capacity: capacity.max(1),
+            state_size: db.stream_size()?,
+            offset: 0,
+            checkpoints: VecDeque::with_capacity(capacity),
+        };
+
+        st.checkpoint()?;
+
+        Ok(st)
+    }
+
+    /// Returns the underlying stream.
+    pub fn stream(&self) -> &StreamRef {
+        &self.stream
+    }
+
+    /// The number of bytes written to the stream.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns an iterator over the retained checkpoints, from the oldest to the latest.
+    pub fn checkpoints(&self) -> impl Iterator<Item = &Checkpoint> {
+        self.checkpoints.iter()
+    }
+
+    /// Write data to be scanned to the stream, taking the checkpoints on every `interval` boundary.
+    pub fn scan<T, F>(&mut self, data: T, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
+    where
+        T: AsRef<[u8]>,
+        F: FnMut(u32, u64, u64, u32) -> Matching,
+    {
+        let mut data = data.as_ref();
+
+        while !data.is_empty() {
+            let remaining = self.interval - self.offset % self.interval;
+            let (chunk, rest) = data.split_at(data.len().min(remaining as usize));
+
+            self.stream.scan(chunk, scratch, &mut on_match_event)?;
+            self.offset += chunk.len() as u64;
+
+            if chunk.len() as u64 == remaining {
+                self.checkpoint()?;
+            }
+
+            data = rest;
+        }
+
+        Ok(())
+    }
+
+    /// Take a checkpoint of the current stream state, evicting the oldest one if the capacity is reached.
+    pub fn checkpoint(&mut self) -> Result<()> {
+        let mut state = if self.checkpoints.len() >= self.capacity {
+            self.checkpoints.pop_front().map(|cp| cp.state).unwrap_or_default()
+        } else {
+            Vec::with_capacity(self.state_size)
+        };
+
+        loop {
+            state.resize(self.state_size, 0);
+
+            match self.stream.compress(&mut state) {
+                Ok(len) => {
+                    state.truncate(len);
+                    break;
+                }
+                Err(Error::Hyperscan(HsError::InsufficientSpace)) => self.state_size *= 2,
+                Err(err) => return Err(err),
+            }
+        }
+
+        self.checkpoints.push_back(Checkpoint {
+            offset: self.offset,
+            state,
+        });
+
+        Ok(())
+    }
+
+    /// Rewind the stream to the latest retained checkpoint taken at or before the `offset`,
+    /// and discard the newer checkpoints.
+    ///
+    /// The stream will first be reset, reporting any EOD matches to the `on_match_event` handler.
+    ///
+    /// Returns the offset of the restored checkpoint, or `None` if no such checkpoint is retained.
+    pub fn rewind<F>(&mut self, offset: u64, scratch: &ScratchRef, on_match_event: F) -> Result<Option<u64>>
+    where
+        F: FnMut(u32, u64, u64, u32) -> Matching,
+    {
+        let idx = match self.checkpoints.iter().rposition(|cp| cp.offset <= offset) {
+            Some(idx) => idx,
+            None => return Ok(None),
+        };
+
+        self.checkpoints.truncate(idx + 1);
+
+        let checkpoint = &self.checkpoints[idx];
+
+        self.stream
+            .reset_and_expand(&checkpoint.state, scratch, on_match_event)?;
+        self.offset = checkpoint.offset;
+
+        Ok(Some(self.offset))
+    }
+
+    /// Close the stream, reporting any EOD matches to the `on_match_event` handler.
+    pub fn close<F>(self, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        F: FnMut(u32, u64, u64, u32) -> Matching,
+    {
+        self.stream.close(scratch, on_match_event)
+    }
+
+    /// Consumes the `CheckpointingStream`, returning the underlying stream.
+    pub fn into_inner(self) -> Stream {
+        self.stream
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::CheckpointingStream;
+
+    #[test]
+    fn test_checkpointing_stream() {
+        let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut st = CheckpointingStream::new(&db, 4, 3).unwrap();
+
+        let mut matches = vec![];
+        let mut callback = |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        };
+
+        st.scan("foo te", &s, &mut callback).unwrap();
+
+        assert_eq!(st.offset(), 6);
+        assert_eq!(st.checkpoints().map(|cp| cp.offset()).collect::<Vec<_>>(), vec![0, 4]);
+
+        st.scan("st bar", &s, &mut callback).unwrap();
+
+        assert_eq!(st.offset(), 12);
+        assert_eq!(
+            st.checkpoints().map(|cp| cp.offset()).collect::<Vec<_>>(),
+            vec![4, 8, 12]
+        );
+
+        assert_eq!(st.rewind(2, &s, &mut callback).unwrap(), None);
+        assert_eq!(st.rewind(7, &s, &mut callback).unwrap(), Some(4));
+        assert_eq!(st.offset(), 4);
+        assert_eq!(st.checkpoints().map(|cp| cp.offset()).collect::<Vec<_>>(), vec![4]);
+
+        st.scan("test", &s, &mut callback).unwrap();
+        st.close(&s, &mut callback).unwrap();
+
+        assert_eq!(matches, vec![4..8, 4..8]);
+    }
+}
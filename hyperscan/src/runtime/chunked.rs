@@ -0,0 +1,364 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    common::{DatabaseRef, Streaming, Vectored},
+    runtime::{Matching, ScanFlags, ScratchRef, Stream, StreamRef},
+    Result,
+};
+
+/// A match reported by `ChunkedStream` or the ring buffer scans, with its offsets resolved into the chunks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkMatch {
+    /// The ID number of the expression that matched.
+    pub id: u32,
+    /// The absolute offset of the start of match in the stream.
+    ///
+    /// It will be zero if the start of match flag is not enabled for the pattern.
+    pub from: u64,
+    /// The absolute offset after the last byte that matches the expression.
+    pub to: u64,
+    /// The chunk index and the offset in the chunk of the first byte of the match.
+    pub start: (usize, u64),
+    /// The chunk index of the last byte of the match, and the offset after it in the chunk.
+    pub end: (usize, u64),
+}
+
+/// A stream recording the boundaries of the written chunks,
+/// which resolves the absolute match offsets back into the application buffers.
+///
+/// The chunks are indexed in the order they are written, including the empty ones.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, ChunkedStream};
+/// let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut st = ChunkedStream::new(&db).unwrap();
+///
+/// let mut matches = vec![];
+///
+/// for data in &["foo te", "st bar"] {
+///     st.scan(data, &s, |m| {
+///         matches.push((m.start, m.end));
+///         Matching::Continue
+///     })
+///     .unwrap();
+/// }
+///
+/// assert_eq!(matches, vec![((0, 4), (1, 2))]);
+/// assert_eq!(st.resolve(7), (1, 1));
+///
+/// st.close(&s, |_| Matching::Continue).unwrap();
+/// ```
+pub struct ChunkedStream {
+    stream: Stream,
+    offset: u64,
+    chunks: Vec<u64>,
+}
+
+impl fmt::Debug for ChunkedStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkedStream")
+            .field("offset", &self.offset)
+            .field("chunks", &self.chunks.len())
+            .finish()
+    }
+}
+
+impl ChunkedStream {
+    /// Open a stream against the database.
+    pub fn new(db: &DatabaseRef<Streaming>) -> Result<Self> {
+        db.open_stream().map(ChunkedStream::from)
+    }
+
+    /// Returns the underlying stream.
+    pub fn stream(&self) -> &StreamRef {
+        &self.stream
+    }
+
+    /// The number of bytes written to the stream.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The number of chunks written to the stream.
+    pub fn chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Resolve an absolute stream offset into the index of the chunk containing it and the offset in the chunk.
+    ///
+    /// The offsets past the written data are resolved into the last chunk.
+    pub fn resolve(&self, offset: u64) -> (usize, u64) {
+        resolve(&self.chunks, offset)
+    }
+
+    /// Write a chunk of data to be scanned to the stream.
+    pub fn scan<T, F>(&mut self, data: T, scratch: &ScratchRef, on_match: F) -> Result<()>
+    where
+        T: AsRef<[u8]>,
+        F: FnMut(ChunkMatch) -> Matching,
+    {
+        self.scan_with_flags(data, ScanFlags::empty(), scratch, on_match)
+    }
+
+    /// Write a chunk of data to be scanned to the stream with the scan flags.
+    pub fn scan_with_flags<T, F>(
+        &mut self,
+        data: T,
+        flags: ScanFlags,
+        scratch: &ScratchRef,
+        mut on_match: F,
+    ) -> Result<()>
+    where
+        T: AsRef<[u8]>,
+        F: FnMut(ChunkMatch) -> Matching,
+    {
+        let data = data.as_ref();
+
+        self.chunks.push(self.offset);
+
+        let chunks = &self.chunks;
+
+        self.stream.scan_with_flags(data, flags, scratch, |id, from, to, _| {
+            on_match(chunk_match(chunks, id, from, to))
+        })?;
+        self.offset += data.len() as u64;
+
+        Ok(())
+    }
+
+    /// Write the data wrapped around the end of a ring buffer to the stream, as the `head` and `tail` chunks,
+    /// without copying it contiguous first.
+    ///
+    /// `head` is the older part of the data, up to the end of the ring buffer,
+    /// and `tail` is the part which has wrapped around to the start of the ring buffer.
+    pub fn scan_ring<F>(&mut self, head: &[u8], tail: &[u8], scratch: &ScratchRef, on_match: F) -> Result<()>
+    where
+        F: FnMut(ChunkMatch) -> Matching,
+    {
+        self.scan_ring_with_flags(head, tail, ScanFlags::empty(), scratch, on_match)
+    }
+
+    /// Write the data wrapped around the end of a ring buffer to the stream with the scan flags.
+    pub fn scan_ring_with_flags<F>(
+        &mut self,
+        head: &[u8],
+        tail: &[u8],
+        flags: ScanFlags,
+        scratch: &ScratchRef,
+        mut on_match: F,
+    ) -> Result<()>
+    where
+        F: FnMut(ChunkMatch) -> Matching,
+    {
+        self.scan_with_flags(head, flags, scratch, &mut on_match)?;
+        self.scan_with_flags(tail, flags, scratch, on_match)
+    }
+
+    /// Close the stream, reporting any EOD matches to the `on_match` handler.
+    pub fn close<F>(self, scratch: &ScratchRef, mut on_match: F) -> Result<()>
+    where
+        F: FnMut(ChunkMatch) -> Matching,
+    {
+        let chunks = self.chunks;
+
+        self.stream
+            .close(scratch, |id, from, to, _| on_match(chunk_match(&chunks, id, from, to)))
+    }
+
+    /// Consumes the `ChunkedStream`, returning the underlying stream.
+    pub fn into_inner(self) -> Stream {
+        self.stream
+    }
+}
+
+impl DatabaseRef<Vectored> {
+    /// Scan the data wrapped around the end of a ring buffer without copying it contiguous first.
+    ///
+    /// `head` is the older part of the data, up to the end of the ring buffer,
+    /// and `tail` is the part which has wrapped around to the start of the ring buffer.
+    /// The match offsets are relative to the start of `head`, and resolved into `head` (chunk 0)
+    /// and `tail` (chunk 1) as `ChunkedStream` does.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: VectoredDatabase = pattern!{"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    ///
+    /// let ring = b"st barfoo te";
+    /// let (tail, head) = ring.split_at(6);
+    /// let mut matches = vec![];
+    ///
+    /// db.scan_ring(head, tail, &s, |m| {
+    ///     matches.push((m.from..m.to, m.start, m.end));
+    ///     Matching::Continue
+    /// }).unwrap();
+    ///
+    /// assert_eq!(matches, vec![(4..8, (0, 4), (1, 2))]);
+    /// ```
+    pub fn scan_ring<F>(&self, head: &[u8], tail: &[u8], scratch: &ScratchRef, on_match: F) -> Result<()>
+    where
+        F: FnMut(ChunkMatch) -> Matching,
+    {
+        self.scan_ring_with_flags(head, tail, ScanFlags::empty(), scratch, on_match)
+    }
+
+    /// Scan the data wrapped around the end of a ring buffer with the scan flags.
+    pub fn scan_ring_with_flags<F>(
+        &self,
+        head: &[u8],
+        tail: &[u8],
+        flags: ScanFlags,
+        scratch: &ScratchRef,
+        mut on_match: F,
+    ) -> Result<()>
+    where
+        F: FnMut(ChunkMatch) -> Matching,
+    {
+        let chunks = [0, head.len() as u64];
+
+        self.scan_with_flags([head, tail], flags, scratch, |id, from, to, _| {
+            on_match(chunk_match(&chunks, id, from, to))
+        })
+    }
+}
+
+impl From<Stream> for ChunkedStream {
+    fn from(stream: Stream) -> Self {
+        ChunkedStream {
+            stream,
+            offset: 0,
+            chunks: vec![],
+        }
+    }
+}
+
+fn chunk_match(chunks: &[u64], id: u32, from: u64, to: u64) -> ChunkMatch {
+    let (chunk, _) = resolve(chunks, to.saturating_sub(1));
+
+    ChunkMatch {
+        id,
+        from,
+        to,
+        start: resolve(chunks, from),
+        end: (chunk, to - chunks.get(chunk).copied().unwrap_or_default()),
+    }
+}
+
+fn resolve(chunks: &[u64], offset: u64) -> (usize, u64) {
+    let chunk = chunks.partition_point(|&start| start <= offset).saturating_sub(1);
+
+    (chunk, offset - chunks.get(chunk).copied().unwrap_or_default())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::{ChunkMatch, ChunkedStream, ScanFlags};
+
+    use super::resolve;
+
+    #[test]
+    fn test_resolve() {
+        let chunks = [0, 3, 3, 5];
+
+        assert_eq!(resolve(&[], 0), (0, 0));
+        assert_eq!(resolve(&chunks, 0), (0, 0));
+        assert_eq!(resolve(&chunks, 2), (0, 2));
+        assert_eq!(resolve(&chunks, 3), (2, 0));
+        assert_eq!(resolve(&chunks, 4), (2, 1));
+        assert_eq!(resolve(&chunks, 9), (3, 4));
+    }
+
+    #[test]
+    fn test_chunked_stream() {
+        let db: StreamingDatabase = patterns!["test", "bar$"; SOM_LEFTMOST].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut st = ChunkedStream::new(&db).unwrap();
+
+        let mut matches = vec![];
+
+        for data in &["foo te", "", "s", "t b", "ar"] {
+            st.scan(data, &s, |m| {
+                matches.push(m);
+                Matching::Continue
+            })
+            .unwrap();
+        }
+
+        assert_eq!(st.chunks(), 5);
+        assert_eq!(st.offset(), 12);
+
+        st.close(&s, |m| {
+            matches.push(m);
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(
+            matches,
+            vec![
+                ChunkMatch {
+                    id: 0,
+                    from: 4,
+                    to: 8,
+                    start: (0, 4),
+                    end: (3, 1)
+                },
+                ChunkMatch {
+                    id: 1,
+                    from: 9,
+                    to: 12,
+                    start: (3, 2),
+                    end: (4, 2)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_ring() {
+        let (head, tail) = (b"foo te", b"st bar");
+        let expected = vec![ChunkMatch {
+            id: 0,
+            from: 4,
+            to: 8,
+            start: (0, 4),
+            end: (1, 2),
+        }];
+
+        let db: VectoredDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan_ring_with_flags(head, tail, ScanFlags::empty(), &s, |m| {
+            matches.push(m);
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, expected);
+
+        let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut st = ChunkedStream::new(&db).unwrap();
+        let mut matches = vec![];
+
+        st.scan_ring(head, tail, &s, |m| {
+            matches.push(m);
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(st.chunks(), 2);
+        assert_eq!(matches, expected);
+
+        st.close(&s, |_| Matching::Continue).unwrap();
+    }
+}
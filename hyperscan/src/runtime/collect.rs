@@ -0,0 +1,235 @@
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "compile")]
+use core::ops::Range;
+
+#[cfg(feature = "compile")]
+use crate::{
+    common::BlockDatabase,
+    compile::{Builder, Patterns},
+};
+use crate::{
+    common::{Block, DatabaseRef, Vectored},
+    runtime::{Matching, ScratchRef, Stream, StreamRef},
+    Result,
+};
+
+/// A match collected by the `scan_matches` functions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MatchEvent {
+    /// The ID number of the expression that matched.
+    pub id: u32,
+    /// The first byte offset of the match, or zero if the start of match flag is not enabled.
+    pub from: u64,
+    /// The offset after the last byte that matches the expression.
+    pub to: u64,
+}
+
+fn collector(matches: &mut Vec<MatchEvent>) -> impl FnMut(u32, u64, u64, u32) -> Matching + '_ {
+    move |id, from, to, _| {
+        matches.push(MatchEvent { id, from, to });
+        Matching::Continue
+    }
+}
+
+impl DatabaseRef<Block> {
+    /// Scan the data and collect all the matches, in the order they are reported.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, MatchEvent};
+    /// let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    ///
+    /// assert_eq!(
+    ///     db.scan_matches("foo test bar", &s).unwrap(),
+    ///     vec![MatchEvent { id: 0, from: 4, to: 8 }]
+    /// );
+    /// ```
+    pub fn scan_matches<T>(&self, data: T, scratch: &ScratchRef) -> Result<Vec<MatchEvent>>
+    where
+        T: AsRef<[u8]>,
+    {
+        let mut matches = vec![];
+
+        self.scan(data, scratch, collector(&mut matches))?;
+
+        Ok(matches)
+    }
+}
+
+#[cfg(feature = "compile")]
+impl Patterns {
+    /// Compile the patterns with `SOM_LEFTMOST`, scan the data and returns the spans of all the matches,
+    /// sorted and without duplicates, e.g. to highlight the matches in a UI.
+    ///
+    /// Hyperscan reports every end offset of the matches, so the spans of a pattern may be nested,
+    /// e.g. `a+` reports `0..1`, `0..2` and `0..3` in `aaa`. With `merge`, the overlapping spans
+    /// are merged into their union, the adjacent spans are kept apart.
+    ///
+    /// The patterns are compiled for every call, since a database compiled without `SOM_LEFTMOST`
+    /// reports all the matches starting at zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let patterns = patterns!["a+b", "ba+", "c"];
+    ///
+    /// assert_eq!(
+    ///     patterns.scan_overlapping("xaabaa cc", false).unwrap(),
+    ///     vec![1..4, 3..5, 3..6, 7..8, 8..9]
+    /// );
+    /// assert_eq!(patterns.scan_overlapping("xaabaa cc", true).unwrap(), vec![1..6, 7..8, 8..9]);
+    /// ```
+    pub fn scan_overlapping<T>(&self, data: T, merge: bool) -> Result<Vec<Range<u64>>>
+    where
+        T: AsRef<[u8]>,
+    {
+        let db: BlockDatabase = self.clone().left_most().build()?;
+        let scratch = db.alloc_scratch()?;
+        let mut spans = vec![];
+
+        db.scan(data, &scratch, |_, from, to, _| {
+            spans.push(from..to);
+            Matching::Continue
+        })?;
+
+        spans.sort_unstable_by_key(|span| (span.start, span.end));
+        spans.dedup();
+
+        if merge {
+            spans.dedup_by(|next, prev| {
+                if next.start < prev.end {
+                    prev.end = prev.end.max(next.end);
+                    true
+                } else {
+                    false
+                }
+            });
+        }
+
+        Ok(spans)
+    }
+}
+
+impl DatabaseRef<Vectored> {
+    /// Scan the buffers and collect all the matches, in the order they are reported.
+    pub fn scan_matches<I, T>(&self, data: I, scratch: &ScratchRef) -> Result<Vec<MatchEvent>>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let mut matches = vec![];
+
+        self.scan(data, scratch, collector(&mut matches))?;
+
+        Ok(matches)
+    }
+}
+
+impl StreamRef {
+    /// Write data to be scanned to the opened stream, and collect the matches reported by this write.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, MatchEvent};
+    /// let db: StreamingDatabase = patterns!["test", "bar$"; SOM_LEFTMOST].build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let st = db.open_stream().unwrap();
+    ///
+    /// assert_eq!(st.scan_matches("foo te", &s).unwrap(), vec![]);
+    /// assert_eq!(
+    ///     st.scan_matches("st bar", &s).unwrap(),
+    ///     vec![MatchEvent { id: 0, from: 4, to: 8 }]
+    /// );
+    /// assert_eq!(
+    ///     st.close_matches(&s).unwrap(),
+    ///     vec![MatchEvent { id: 1, from: 9, to: 12 }]
+    /// );
+    /// ```
+    pub fn scan_matches<T>(&self, data: T, scratch: &ScratchRef) -> Result<Vec<MatchEvent>>
+    where
+        T: AsRef<[u8]>,
+    {
+        let mut matches = vec![];
+
+        self.scan(data, scratch, collector(&mut matches))?;
+
+        Ok(matches)
+    }
+}
+
+impl Stream {
+    /// Close the stream, and collect any matches at the end of the data.
+    pub fn close_matches(self, scratch: &ScratchRef) -> Result<Vec<MatchEvent>> {
+        let mut matches = vec![];
+
+        self.close(scratch, collector(&mut matches))?;
+
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::MatchEvent;
+
+    #[test]
+    fn test_scan_matches() {
+        let db: VectoredDatabase = patterns!["foo", "bar"; SOM_LEFTMOST].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        assert_eq!(
+            db.scan_matches(vec!["fo", "o ba", "r"], &s).unwrap(),
+            vec![
+                MatchEvent { id: 0, from: 0, to: 3 },
+                MatchEvent { id: 1, from: 4, to: 7 }
+            ]
+        );
+        assert!(db.scan_matches(vec!["nothing"], &s).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_scan_overlapping() {
+        let patterns = patterns!["a+", "aa", "b"];
+
+        assert_eq!(
+            patterns.scan_overlapping("aaa bb", false).unwrap(),
+            vec![0..1, 0..2, 0..3, 1..3, 4..5, 5..6]
+        );
+        assert_eq!(
+            patterns.scan_overlapping("aaa bb", true).unwrap(),
+            vec![0..3, 4..5, 5..6]
+        );
+        assert!(patterns.scan_overlapping("nothing", true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_scan_overlapping_without_som() {
+        let patterns = patterns!["a+", "b"];
+
+        // without `SOM_LEFTMOST`, all the matches are reported starting at zero
+        let db: BlockDatabase = patterns.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        assert_eq!(
+            db.scan_matches("xaa b", &s).unwrap(),
+            vec![
+                MatchEvent { id: 0, from: 0, to: 2 },
+                MatchEvent { id: 0, from: 0, to: 3 },
+                MatchEvent { id: 1, from: 0, to: 5 }
+            ]
+        );
+
+        // `scan_overlapping` compiles the patterns with `SOM_LEFTMOST` on its own
+        assert_eq!(
+            patterns.scan_overlapping("xaa b", false).unwrap(),
+            vec![1..2, 1..3, 4..5]
+        );
+        assert_eq!(patterns.scan_overlapping("xaa b", true).unwrap(), vec![1..3, 4..5]);
+    }
+}
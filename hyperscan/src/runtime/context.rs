@@ -0,0 +1,181 @@
+use crate::{
+    common::{catch_unwind, Block, DatabaseRef, Vectored},
+    ffi,
+    runtime::{MatchEventHandler, Matching, ScratchRef, Stream, StreamRef},
+    Result,
+};
+
+/// The match event handler passing the context to a function.
+struct WithContext<'a, T> {
+    context: &'a mut T,
+    on_match_event: fn(&mut T, u32, u64, u64, u32) -> Matching,
+}
+
+impl<'a, T> WithContext<'a, T> {
+    fn new(context: &'a mut T, on_match_event: fn(&mut T, u32, u64, u64, u32) -> Matching) -> Self {
+        WithContext {
+            context,
+            on_match_event,
+        }
+    }
+}
+
+impl<T> MatchEventHandler for WithContext<'_, T> {
+    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut core::ffi::c_void) {
+        (Some(context_trampoline::<T>), self as *mut _ as *mut _)
+    }
+}
+
+unsafe extern "C" fn context_trampoline<T>(
+    id: u32,
+    from: u64,
+    to: u64,
+    flags: u32,
+    ctx: *mut core::ffi::c_void,
+) -> core::ffi::c_int {
+    let handler = &mut *(ctx as *mut WithContext<'_, T>);
+
+    catch_unwind(Matching::Terminate, || {
+        (handler.on_match_event)(handler.context, id, from, to, flags)
+    }) as _
+}
+
+impl DatabaseRef<Block> {
+    /// The block (non-streaming) regular expression scanner, passing the context to the match handler.
+    ///
+    /// The handler is a plain function, so the mutable state is borrowed only for the duration of the call.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let mut matches = vec![];
+    ///
+    /// fn on_match(matches: &mut Vec<(u64, u64)>, _: u32, from: u64, to: u64, _: u32) -> Matching {
+    ///     matches.push((from, to));
+    ///     Matching::Continue
+    /// }
+    ///
+    /// db.scan_with_context("foo test", &s, &mut matches, on_match).unwrap();
+    /// db.scan_with_context("test bar", &s, &mut matches, on_match).unwrap();
+    ///
+    /// assert_eq!(matches, vec![(4, 8), (0, 4)]);
+    /// ```
+    pub fn scan_with_context<D, T>(
+        &self,
+        data: D,
+        scratch: &ScratchRef,
+        context: &mut T,
+        on_match_event: fn(&mut T, u32, u64, u64, u32) -> Matching,
+    ) -> Result<()>
+    where
+        D: AsRef<[u8]>,
+    {
+        self.scan(data, scratch, WithContext::new(context, on_match_event))
+    }
+}
+
+impl DatabaseRef<Vectored> {
+    /// The vectored regular expression scanner, passing the context to the match handler.
+    pub fn scan_with_context<I, D, T>(
+        &self,
+        data: I,
+        scratch: &ScratchRef,
+        context: &mut T,
+        on_match_event: fn(&mut T, u32, u64, u64, u32) -> Matching,
+    ) -> Result<()>
+    where
+        I: IntoIterator<Item = D>,
+        D: AsRef<[u8]>,
+    {
+        self.scan(data, scratch, WithContext::new(context, on_match_event))
+    }
+}
+
+impl StreamRef {
+    /// Write data to be scanned to the opened stream, passing the context to the match handler.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// #[derive(Default)]
+    /// struct Stats {
+    ///     matches: usize,
+    ///     last: Option<u64>,
+    /// }
+    ///
+    /// fn on_match(stats: &mut Stats, _: u32, _: u64, to: u64, _: u32) -> Matching {
+    ///     stats.matches += 1;
+    ///     stats.last = Some(to);
+    ///     Matching::Continue
+    /// }
+    ///
+    /// let db: StreamingDatabase = patterns!["test", "bar$"].build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let st = db.open_stream().unwrap();
+    /// let mut stats = Stats::default();
+    ///
+    /// st.scan_with_context("foo te", &s, &mut stats, on_match).unwrap();
+    /// st.scan_with_context("st bar", &s, &mut stats, on_match).unwrap();
+    ///
+    /// assert_eq!((stats.matches, stats.last), (1, Some(8)));
+    ///
+    /// st.close_with_context(&s, &mut stats, on_match).unwrap();
+    ///
+    /// assert_eq!((stats.matches, stats.last), (2, Some(12)));
+    /// ```
+    pub fn scan_with_context<D, T>(
+        &self,
+        data: D,
+        scratch: &ScratchRef,
+        context: &mut T,
+        on_match_event: fn(&mut T, u32, u64, u64, u32) -> Matching,
+    ) -> Result<()>
+    where
+        D: AsRef<[u8]>,
+    {
+        self.scan(data, scratch, WithContext::new(context, on_match_event))
+    }
+}
+
+impl Stream {
+    /// Close the stream, passing the context to the handler of any EOD matches.
+    pub fn close_with_context<T>(
+        self,
+        scratch: &ScratchRef,
+        context: &mut T,
+        on_match_event: fn(&mut T, u32, u64, u64, u32) -> Matching,
+    ) -> Result<()> {
+        self.close(scratch, WithContext::new(context, on_match_event))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_scan_with_context() {
+        let db: VectoredDatabase = patterns!["foo", "bar"; SOM_LEFTMOST].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut ids = vec![];
+
+        fn on_match(ids: &mut Vec<u32>, id: u32, _: u64, _: u64, _: u32) -> Matching {
+            ids.push(id);
+
+            if ids.len() < 2 {
+                Matching::Continue
+            } else {
+                Matching::Terminate
+            }
+        }
+
+        db.scan_with_context(vec!["fo", "o ba", "r foo"], &s, &mut ids, on_match)
+            .unwrap_err();
+
+        assert_eq!(ids, vec![0, 1]);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{
+    common::catch_unwind,
+    ffi,
+    runtime::{MatchEventHandler, Matching},
+};
+
+/// The start of a match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchStart {
+    /// The start of match offset.
+    ///
+    /// It will be zero if the start of match flag is not enabled for the pattern.
+    Offset(u64),
+    /// The start of match lies outside the start of match horizon chosen by `SomHorizon`.
+    ///
+    /// Hyperscan reports it with the `HS_OFFSET_PAST_HORIZON` sentinel,
+    /// which is only possible when the horizon is not `SomHorizon::Large`.
+    Unknown,
+}
+
+impl MatchStart {
+    /// Returns the start of match offset if it's known.
+    pub fn offset(self) -> Option<u64> {
+        match self {
+            MatchStart::Offset(offset) => Some(offset),
+            MatchStart::Unknown => None,
+        }
+    }
+}
+
+/// A match reported by the scanner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match {
+    /// The ID number of the expression that matched.
+    pub id: u32,
+    /// The start of match.
+    pub start: MatchStart,
+    /// The offset after the last byte that matches the expression.
+    pub end: u64,
+    /// This is provided for future use and is unused at present.
+    pub flags: u32,
+}
+
+impl Match {
+    fn new(id: u32, from: u64, to: u64, flags: u32) -> Self {
+        Match {
+            id,
+            start: if from == ffi::HS_OFFSET_PAST_HORIZON as u64 {
+                MatchStart::Unknown
+            } else {
+                MatchStart::Offset(from)
+            },
+            end: to,
+            flags,
+        }
+    }
+}
+
+/// A match event handler receiving the matches as `Match`,
+/// which reports an unknown start of match instead of a bogus huge `from` offset.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, Match, MatchStart, OnMatch};
+/// let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+///
+/// let mut matches = vec![];
+///
+/// db.scan("foo test bar", &s, OnMatch::new(|m: Match| {
+///     matches.push(m);
+///     Matching::Continue
+/// })).unwrap();
+///
+/// assert_eq!(matches[0].start, MatchStart::Offset(4));
+/// assert_eq!(matches[0].end, 8);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct OnMatch<F>(F);
+
+impl<F> OnMatch<F>
+where
+    F: FnMut(Match) -> Matching,
+{
+    /// Wrap the callback as a match event handler.
+    pub fn new(on_match: F) -> Self {
+        OnMatch(on_match)
+    }
+}
+
+impl<F> MatchEventHandler for OnMatch<F>
+where
+    F: FnMut(Match) -> Matching,
+{
+    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut core::ffi::c_void) {
+        unsafe extern "C" fn trampoline<F>(
+            id: u32,
+            from: u64,
+            to: u64,
+            flags: u32,
+            ctx: *mut core::ffi::c_void,
+        ) -> core::ffi::c_int
+        where
+            F: FnMut(Match) -> Matching,
+        {
+            let on_match = &mut *(ctx as *mut F);
+
+            catch_unwind(Matching::Terminate, || on_match(Match::new(id, from, to, flags))) as _
+        }
+
+        (Some(trampoline::<F>), &mut self.0 as *mut F as *mut _)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::{Match, MatchStart, OnMatch, SomHorizon};
+
+    #[test]
+    fn test_match_start_past_horizon() {
+        let mut p = pattern! {"a.*b"; SOM_LEFTMOST};
+        p.som = Some(SomHorizon::Small);
+
+        let db: StreamingDatabase = p.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let st = db.open_stream().unwrap();
+
+        let mut matches = vec![];
+        let mut on_match = |m: Match| {
+            matches.push((m.start, m.end));
+            Matching::Continue
+        };
+
+        st.scan("xab", &s, OnMatch::new(&mut on_match)).unwrap();
+        st.scan("a", &s, OnMatch::new(&mut on_match)).unwrap();
+        st.scan(vec![b'x'; 70_000], &s, OnMatch::new(&mut on_match)).unwrap();
+        st.scan("b", &s, OnMatch::new(&mut on_match)).unwrap();
+        st.close(&s, ()).unwrap();
+
+        assert_eq!(matches, vec![(MatchStart::Offset(1), 3), (MatchStart::Unknown, 70_005)]);
+        assert_eq!(matches[1].0.offset(), None);
+    }
+}
@@ -0,0 +1,203 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+use core::ptr;
+
+use crate::{
+    ffi,
+    runtime::{MatchEventHandler, Matching},
+};
+
+const BITS: usize = 64;
+
+/// A set of pattern IDs which are enabled at scan time.
+///
+/// The matches of the disabled patterns are suppressed before reaching the match event handler,
+/// so one compiled database could serve multiple policies without recompiling.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, ScanFilter};
+/// let db: BlockDatabase = patterns!["foo", "bar", "baz"; SOM_LEFTMOST].build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+///
+/// let mut filter = ScanFilter::new();
+/// filter.disable(1);
+///
+/// let mut matches = vec![];
+///
+/// db.scan("foo bar baz", &s, filter.apply(|id, _, _, _| {
+///     matches.push(id);
+///     Matching::Continue
+/// })).unwrap();
+///
+/// assert_eq!(matches, vec![0, 2]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScanFilter {
+    bits: Vec<u64>,
+    enabled: bool,
+}
+
+impl Default for ScanFilter {
+    fn default() -> Self {
+        ScanFilter::new()
+    }
+}
+
+impl FromIterator<u32> for ScanFilter {
+    fn from_iter<I: IntoIterator<Item = u32>>(ids: I) -> Self {
+        ScanFilter::only(ids)
+    }
+}
+
+impl ScanFilter {
+    /// Construct a filter with all patterns enabled.
+    pub fn new() -> Self {
+        ScanFilter {
+            bits: vec![],
+            enabled: true,
+        }
+    }
+
+    /// Construct a filter with only the given patterns enabled.
+    pub fn only<I: IntoIterator<Item = u32>>(ids: I) -> Self {
+        let mut filter = ScanFilter {
+            bits: vec![],
+            enabled: false,
+        };
+
+        for id in ids {
+            filter.enable(id);
+        }
+
+        filter
+    }
+
+    /// Returns `true` if the matches of the pattern are reported.
+    pub fn is_enabled(&self, id: u32) -> bool {
+        let (idx, bit) = (id as usize / BITS, id as usize % BITS);
+        let toggled = self.bits.get(idx).copied().unwrap_or_default() & (1 << bit) != 0;
+
+        self.enabled != toggled
+    }
+
+    /// Report the matches of the pattern.
+    pub fn enable(&mut self, id: u32) {
+        self.set(id, true)
+    }
+
+    /// Suppress the matches of the pattern.
+    pub fn disable(&mut self, id: u32) {
+        self.set(id, false)
+    }
+
+    fn set(&mut self, id: u32, enabled: bool) {
+        let (idx, bit) = (id as usize / BITS, id as usize % BITS);
+
+        if self.bits.len() <= idx {
+            self.bits.resize(idx + 1, 0);
+        }
+
+        if enabled == self.enabled {
+            self.bits[idx] &= !(1 << bit);
+        } else {
+            self.bits[idx] |= 1 << bit;
+        }
+    }
+
+    /// Wrap the match event handler, suppressing the matches of the disabled patterns.
+    pub fn apply<F: MatchEventHandler>(&self, on_match_event: F) -> Filtered<'_, F> {
+        Filtered {
+            filter: self,
+            on_match_event,
+            inner: (None, ptr::null_mut()),
+        }
+    }
+}
+
+/// A match event handler which only receives the matches of the patterns enabled by a `ScanFilter`.
+#[derive(Debug)]
+pub struct Filtered<'a, F> {
+    filter: &'a ScanFilter,
+    on_match_event: F,
+    inner: (ffi::match_event_handler, *mut core::ffi::c_void),
+}
+
+impl<F: MatchEventHandler> MatchEventHandler for Filtered<'_, F> {
+    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut core::ffi::c_void) {
+        unsafe extern "C" fn trampoline<F>(
+            id: u32,
+            from: u64,
+            to: u64,
+            flags: u32,
+            ctx: *mut core::ffi::c_void,
+        ) -> core::ffi::c_int {
+            let filtered = &*(ctx as *const Filtered<'_, F>);
+
+            match filtered.inner {
+                (Some(callback), userdata) if filtered.filter.is_enabled(id) => callback(id, from, to, flags, userdata),
+                _ => Matching::Continue as _,
+            }
+        }
+
+        self.inner = self.on_match_event.split();
+
+        (Some(trampoline::<F>), self as *mut _ as *mut _)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::ScanFilter;
+
+    #[test]
+    fn test_scan_filter() {
+        let mut filter = ScanFilter::only(vec![1, 100]);
+
+        assert!(!filter.is_enabled(0));
+        assert!(filter.is_enabled(1));
+        assert!(filter.is_enabled(100));
+        assert!(!filter.is_enabled(1000));
+
+        filter.disable(1);
+        filter.enable(2);
+
+        assert!(!filter.is_enabled(1));
+        assert!(filter.is_enabled(2));
+
+        let mut filter = ScanFilter::new();
+
+        filter.disable(100);
+
+        assert!(filter.is_enabled(0));
+        assert!(!filter.is_enabled(100));
+        assert!(filter.is_enabled(1000));
+    }
+
+    #[test]
+    fn test_scan_with_filter() {
+        let db: BlockDatabase = patterns!["foo", "bar", "baz"; SOM_LEFTMOST].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let filter = [0, 2].iter().copied().collect::<ScanFilter>();
+
+        let mut matches = vec![];
+
+        db.scan(
+            "foo bar baz",
+            &s,
+            filter.apply(|id, from, to, _| {
+                matches.push((id, from..to));
+                Matching::Continue
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(matches, vec![(0, 0..3), (2, 8..11)]);
+
+        db.scan("foo bar baz", &s, ScanFilter::only(vec![1]).apply(Matching::Terminate))
+            .unwrap_err();
+    }
+}
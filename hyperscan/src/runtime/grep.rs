@@ -0,0 +1,239 @@
+use std::io::BufRead;
+use std::ops::Range;
+
+use crate::{
+    common::{Block, DatabaseRef},
+    runtime::{Matching, ScratchRef},
+    Result,
+};
+
+/// A matching line of the text scanned by `DatabaseRef::grep`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line<'t> {
+    /// The line number, starting from 1.
+    pub number: usize,
+    /// The offset of the line start in the text.
+    pub offset: usize,
+    /// The line without the trailing newline.
+    pub text: &'t [u8],
+    /// The pattern ID and the offsets within the line of every match ending on the line.
+    pub matches: Vec<(u32, Range<usize>)>,
+}
+
+struct Cursor<'t> {
+    text: &'t [u8],
+    number: usize,
+    start: usize,
+    end: usize,
+}
+
+impl<'t> Cursor<'t> {
+    fn new(text: &'t [u8]) -> Self {
+        Cursor {
+            text,
+            number: 1,
+            start: 0,
+            end: Self::line_end(text, 0),
+        }
+    }
+
+    fn line_end(text: &[u8], start: usize) -> usize {
+        text[start..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(text.len(), |off| start + off)
+    }
+
+    /// Move to the line containing the offset, the newline belongs to the line it terminates.
+    fn seek(&mut self, offset: usize) {
+        while offset > self.end && self.end < self.text.len() {
+            self.number += 1;
+            self.start = self.end + 1;
+            self.end = Self::line_end(self.text, self.start);
+        }
+    }
+}
+
+impl DatabaseRef<Block> {
+    /// Scan the text once and return the matching lines, with their line numbers
+    /// and the ranges of the matches within the lines.
+    ///
+    /// The start of the matches are only reported when the patterns are compiled with `SOM_LEFTMOST`,
+    /// otherwise the ranges start at the beginning of the lines.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: BlockDatabase = pattern! {"foo"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    ///
+    /// for line in db.grep("bar\nfoo baz foo\n", &s).unwrap() {
+    ///     assert_eq!(line.number, 2);
+    ///     assert_eq!(line.text, b"foo baz foo");
+    ///     assert_eq!(line.matches, vec![(0, 0..3), (0, 8..11)]);
+    /// }
+    /// ```
+    pub fn grep<'t, T>(&self, text: &'t T, scratch: &ScratchRef) -> Result<impl Iterator<Item = Line<'t>>>
+    where
+        T: AsRef<[u8]> + ?Sized,
+    {
+        let text = text.as_ref();
+        let mut matches = vec![];
+
+        self.scan(text, scratch, |id, from, to, _| {
+            matches.push((id, from as usize, to as usize));
+            Matching::Continue
+        })?;
+
+        let mut cursor = Cursor::new(text);
+        let mut lines = Vec::<Line<'t>>::new();
+
+        for (id, from, to) in matches {
+            cursor.seek(if to > from { to - 1 } else { to });
+
+            let range = from.max(cursor.start) - cursor.start..to.min(cursor.end) - cursor.start;
+
+            match lines.last_mut() {
+                Some(line) if line.number == cursor.number => line.matches.push((id, range)),
+                _ => lines.push(Line {
+                    number: cursor.number,
+                    offset: cursor.start,
+                    text: &text[cursor.start..cursor.end],
+                    matches: vec![(id, range)],
+                }),
+            }
+        }
+
+        Ok(lines.into_iter())
+    }
+
+    /// Read the lines from the reader and scan them one by one, calling `on_line` with the line number,
+    /// the line without the trailing newline, and the pattern ID and the offsets within the line of every match.
+    ///
+    /// The lines are buffered until their newlines, so a line may span any number of reads.
+    /// Only the matching lines are reported, the scan stops when `on_line` returns `Matching::Terminate`.
+    /// Returns the number of lines read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: BlockDatabase = pattern! {"foo"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let mut lines = vec![];
+    ///
+    /// let n = db
+    ///     .scan_lines(&b"bar\nfoo baz foo\nbaz"[..], &s, |number, line, matches| {
+    ///         lines.push((number, line.to_vec(), matches.to_vec()));
+    ///         Matching::Continue
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(n, 3);
+    /// assert_eq!(lines, vec![(2, b"foo baz foo".to_vec(), vec![(0, 0..3), (0, 8..11)])]);
+    /// ```
+    pub fn scan_lines<R, F>(&self, mut reader: R, scratch: &ScratchRef, mut on_line: F) -> Result<usize>
+    where
+        R: BufRead,
+        F: FnMut(usize, &[u8], &[(u32, Range<usize>)]) -> Matching,
+    {
+        let mut buf = vec![];
+        let mut matches = vec![];
+        let mut number = 0;
+
+        loop {
+            buf.clear();
+
+            let read = reader.read_until(b'\n', &mut buf)?;
+
+            if read == 0 {
+                return Ok(number);
+            }
+
+            number += 1;
+
+            let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+
+            matches.clear();
+
+            self.scan(line, scratch, |id, from, to, _| {
+                matches.push((id, from as usize..to as usize));
+                Matching::Continue
+            })?;
+
+            if !matches.is_empty() && on_line(number, line, &matches) == Matching::Terminate {
+                return Ok(number);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::BufReader;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_grep() {
+        let db: BlockDatabase = patterns!["foo", "ba+r"; SOM_LEFTMOST].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        let lines = db
+            .grep("foo\nnothing\nbar foo baar\n", &s)
+            .unwrap()
+            .map(|line| (line.number, line.offset, line.text, line.matches))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            lines,
+            vec![
+                (1, 0, &b"foo"[..], vec![(0, 0..3)]),
+                (3, 12, &b"bar foo baar"[..], vec![(1, 0..3), (0, 4..7), (1, 8..12)]),
+            ]
+        );
+
+        assert_eq!(db.grep("nothing", &s).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_scan_lines() {
+        let db: BlockDatabase = patterns!["foo", "ba+r"; SOM_LEFTMOST].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        // a tiny buffer to split the lines across the reads
+        let text = &b"foo\nnothing\nbar foo baar\nfoo"[..];
+        let reader = BufReader::with_capacity(2, text);
+        let mut lines = vec![];
+
+        let n = db
+            .scan_lines(reader, &s, |number, line, matches| {
+                lines.push((number, line.to_vec(), matches.to_vec()));
+                Matching::Continue
+            })
+            .unwrap();
+
+        assert_eq!(n, 4);
+        assert_eq!(
+            lines,
+            vec![
+                (1, b"foo".to_vec(), vec![(0, 0..3)]),
+                (3, b"bar foo baar".to_vec(), vec![(1, 0..3), (0, 4..7), (1, 8..12)]),
+                (4, b"foo".to_vec(), vec![(0, 0..3)]),
+            ]
+        );
+
+        let n = db
+            .scan_lines(text, &s, |number, _, _| {
+                if number == 3 {
+                    Matching::Terminate
+                } else {
+                    Matching::Continue
+                }
+            })
+            .unwrap();
+
+        assert_eq!(n, 3);
+    }
+}
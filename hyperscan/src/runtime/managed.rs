@@ -0,0 +1,161 @@
+use crate::{
+    common::{DatabaseRef, Streaming},
+    error::Error,
+    runtime::{MatchEventHandler, ScanOutcome, ScratchRef, Stream, StreamRef},
+    Result,
+};
+
+/// The behavior of a `ManagedStream` after the match callback returned `Matching::Terminate`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnTerminate {
+    /// The stream is left terminated, further scans return `Error::StreamTerminated` until it is reset.
+    #[default]
+    Stop,
+    /// The stream is reset to the initial state (without reporting the EOD matches),
+    /// further scans start a new stream.
+    Reset,
+}
+
+/// A stream tracking whether it was terminated by the match callback.
+///
+/// Hyperscan doesn't scan any more data on a terminated stream, `ManagedStream` reflects that state:
+/// the scan returns a `ScanOutcome`, and the further scans on a terminated stream return
+/// `Error::StreamTerminated` instead of silently ignoring the data.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, Error, ManagedStream, ScanOutcome};
+/// let db: StreamingDatabase = pattern! {"test"}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut st = ManagedStream::open(&db).unwrap();
+///
+/// assert_eq!(st.scan("foo", &s, Matching::Terminate).unwrap(), ScanOutcome::Completed);
+/// assert_eq!(st.scan("test", &s, Matching::Terminate).unwrap(), ScanOutcome::Terminated);
+/// assert!(st.is_terminated());
+/// assert_eq!(st.scan("bar", &s, ()).unwrap_err(), Error::StreamTerminated);
+///
+/// st.reset(&s, ()).unwrap();
+///
+/// assert_eq!(st.scan("bar", &s, ()).unwrap(), ScanOutcome::Completed);
+///
+/// st.close(&s, ()).unwrap();
+/// ```
+pub struct ManagedStream {
+    stream: Stream,
+    on_terminate: OnTerminate,
+    terminated: bool,
+}
+
+impl ManagedStream {
+    /// Open and initialise a stream against the database.
+    pub fn open(db: &DatabaseRef<Streaming>) -> Result<Self> {
+        db.open_stream().map(ManagedStream::from)
+    }
+
+    /// Set the behavior after the match callback returned `Matching::Terminate`.
+    pub fn on_terminate(mut self, on_terminate: OnTerminate) -> Self {
+        self.on_terminate = on_terminate;
+        self
+    }
+
+    /// Returns the underlying stream.
+    pub fn stream(&self) -> &StreamRef {
+        &self.stream
+    }
+
+    /// Returns `true` if the stream was terminated by the match callback.
+    pub fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+
+    /// Write data to be scanned to the stream.
+    pub fn scan<T, F>(&mut self, data: T, scratch: &ScratchRef, on_match_event: F) -> Result<ScanOutcome>
+    where
+        T: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        if self.terminated {
+            return Err(Error::StreamTerminated);
+        }
+
+        let outcome = self.stream.scan_outcome(data, scratch, on_match_event)?;
+
+        if outcome.is_terminated() {
+            match self.on_terminate {
+                OnTerminate::Stop => self.terminated = true,
+                OnTerminate::Reset => self.stream.reset(scratch, ())?,
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    /// Reset the stream to an initial state, reporting any EOD matches to the `on_match_event` handler.
+    pub fn reset<F>(&mut self, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        F: MatchEventHandler,
+    {
+        self.stream.reset(scratch, on_match_event)?;
+        self.terminated = false;
+
+        Ok(())
+    }
+
+    /// Close the stream, reporting any EOD matches to the `on_match_event` handler.
+    pub fn close<F>(self, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        F: MatchEventHandler,
+    {
+        self.stream.close(scratch, on_match_event)
+    }
+
+    /// Consumes the `ManagedStream`, returning the underlying stream.
+    pub fn into_inner(self) -> Stream {
+        self.stream
+    }
+}
+
+impl From<Stream> for ManagedStream {
+    fn from(stream: Stream) -> Self {
+        ManagedStream {
+            stream,
+            on_terminate: OnTerminate::default(),
+            terminated: false,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::{ManagedStream, OnTerminate, ScanOutcome};
+
+    #[test]
+    fn test_managed_stream_reset_on_terminate() {
+        let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut st = ManagedStream::open(&db).unwrap().on_terminate(OnTerminate::Reset);
+
+        let mut matches = vec![];
+        let outcomes = ["foo te", "st", "test"]
+            .iter()
+            .map(|data| {
+                st.scan(data, &s, |_, from, to, _| {
+                    matches.push(from..to);
+                    Matching::Terminate
+                })
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            outcomes,
+            vec![ScanOutcome::Completed, ScanOutcome::Terminated, ScanOutcome::Terminated]
+        );
+        assert!(!st.is_terminated());
+        assert_eq!(matches, vec![4..8, 0..4]);
+
+        st.close(&s, ()).unwrap();
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{
+    common::{discard_unwind, DbMode},
+    ffi,
+};
+
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+
+#[cfg(feature = "metrics")]
+use crate::{
+    common::catch_unwind,
+    metrics::{scan_metrics, ScanKind, ScanMetrics},
+};
+
+/// The match callback wrapped to count the matches.
+#[cfg(feature = "metrics")]
+struct Metered<'a> {
+    metrics: &'a dyn ScanMetrics,
+    kind: ScanKind,
+    callback: ffi::match_event_handler,
+    userdata: *mut core::ffi::c_void,
+}
+
+#[cfg(feature = "metrics")]
+unsafe extern "C" fn metered_trampoline(
+    id: u32,
+    from: u64,
+    to: u64,
+    flags: u32,
+    ctx: *mut core::ffi::c_void,
+) -> core::ffi::c_int {
+    let metered = &*(ctx as *const Metered<'_>);
+
+    catch_unwind((), || metered.metrics.on_match(metered.kind, id));
+
+    match metered.callback {
+        Some(callback) => callback(id, from, to, flags, metered.userdata),
+        None => 0,
+    }
+}
+
+/// Invoke the scan with the match callback, and report the metrics to the installed `ScanMetrics` if any.
+///
+/// The match callback is wrapped to count the matches only if the metrics are installed.
+#[cfg(feature = "metrics")]
+pub(crate) unsafe fn metered<F>(
+    mode: DbMode,
+    bytes: usize,
+    callback: ffi::match_event_handler,
+    userdata: *mut core::ffi::c_void,
+    scan: F,
+) -> ffi::hs_error_t
+where
+    F: FnOnce(ffi::match_event_handler, *mut core::ffi::c_void) -> ffi::hs_error_t,
+{
+    // a panic left by a call which wasn't resumed must not be resumed by this scan
+    discard_unwind();
+
+    let metrics = match scan_metrics() {
+        Some(metrics) => metrics,
+        None => return scan(callback, userdata),
+    };
+    let kind = ScanKind::from(mode);
+    let mut metered = Metered {
+        metrics: &*metrics,
+        kind,
+        callback,
+        userdata,
+    };
+
+    let started = Instant::now();
+    let res = scan(Some(metered_trampoline), &mut metered as *mut _ as *mut _);
+    let elapsed = started.elapsed();
+
+    metrics.on_scan(kind, bytes, elapsed);
+
+    if res == ffi::HS_SCAN_TERMINATED {
+        metrics.on_terminated(kind);
+    }
+
+    res
+}
+
+/// Invoke the scan with the match callback.
+#[cfg(not(feature = "metrics"))]
+pub(crate) unsafe fn metered<F>(
+    _mode: DbMode,
+    _bytes: usize,
+    callback: ffi::match_event_handler,
+    userdata: *mut core::ffi::c_void,
+    scan: F,
+) -> ffi::hs_error_t
+where
+    F: FnOnce(ffi::match_event_handler, *mut core::ffi::c_void) -> ffi::hs_error_t,
+{
+    // a panic left by a call which wasn't resumed must not be resumed by this scan
+    discard_unwind();
+
+    scan(callback, userdata)
+}
@@ -1,11 +1,77 @@
-mod closure;
+#[cfg(feature = "std")]
+mod accumulate;
+#[cfg(feature = "async")]
+mod blocking;
+mod checkpoint;
+mod chunked;
+mod collect;
+mod context;
+mod event;
+mod filter;
+#[cfg(feature = "std")]
+mod grep;
+mod managed;
+mod metered;
+mod outcome;
+#[cfg(all(feature = "rayon", feature = "std"))]
+mod par;
 #[cfg(feature = "pattern")]
 mod pattern;
+#[cfg(feature = "std")]
+mod per_thread;
+#[cfg(feature = "std")]
+mod pool;
+#[cfg(feature = "std")]
+mod reload;
 mod scan;
+#[cfg(feature = "std")]
+mod scanner;
+#[cfg(feature = "std")]
+mod scoped;
 mod scratch;
+#[cfg(feature = "std")]
+mod scratch_pool;
+#[cfg(feature = "std")]
+mod session;
+#[cfg(feature = "std")]
+mod sink;
 mod stream;
+mod vectored;
+mod warm;
 
-pub use self::closure::split_closure;
-pub use self::scan::{MatchEventHandler, Matching};
-pub use self::scratch::{Scratch, ScratchRef};
+#[cfg(feature = "std")]
+pub use self::accumulate::{AccumulatePolicy, MatchAccumulator};
+#[cfg(feature = "async")]
+pub use self::blocking::set_blocking_threads;
+pub use self::checkpoint::{Checkpoint, CheckpointingStream};
+pub use self::chunked::{ChunkMatch, ChunkedStream};
+pub use self::collect::MatchEvent;
+pub use self::event::{Match, MatchStart, OnMatch};
+pub use self::filter::{Filtered, ScanFilter};
+#[cfg(feature = "std")]
+pub use self::grep::Line;
+pub use self::managed::{ManagedStream, OnTerminate};
+pub(crate) use self::metered::metered;
+pub use self::outcome::ScanOutcome;
+#[cfg(feature = "std")]
+pub use self::per_thread::PerThread;
+#[cfg(feature = "std")]
+pub use self::pool::{Matches, ScanPool};
+#[cfg(feature = "std")]
+pub use self::reload::{ReloadStats, ReloadableDatabase, ScanGuard};
+pub use self::scan::{MatchEventHandler, Matching, ScanFlags};
+#[cfg(feature = "std")]
+pub use self::scanner::StreamScanner;
+#[cfg(feature = "std")]
+pub use self::scoped::ScopedStream;
+#[cfg(feature = "debug-scratch")]
+pub use self::scratch::ScratchStats;
+pub use self::scratch::{AnyDatabase, Scratch, ScratchRef};
+#[cfg(feature = "std")]
+pub use self::scratch_pool::{PooledScratch, SyncScratchPool};
+#[cfg(feature = "std")]
+pub use self::session::{SessionMap, StreamMap};
+#[cfg(feature = "std")]
+pub use self::sink::StreamSink;
 pub use self::stream::{Stream, StreamRef};
+pub use self::vectored::{OverlapScanner, VectoredData};
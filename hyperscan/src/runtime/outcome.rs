@@ -0,0 +1,116 @@
+use crate::{
+    common::{Block, DatabaseRef, Error as HsError, Vectored},
+    error::Error,
+    runtime::{MatchEventHandler, ScratchRef, StreamRef},
+    Result,
+};
+
+/// The outcome of a scan.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanOutcome {
+    /// All the data were scanned.
+    Completed,
+    /// The match callback returned `Matching::Terminate`.
+    Terminated,
+}
+
+impl ScanOutcome {
+    /// Returns `true` if the scan was terminated by the match callback.
+    pub fn is_terminated(self) -> bool {
+        self == ScanOutcome::Terminated
+    }
+
+    /// Convert the result of a scan, the termination by the match callback isn't an error.
+    pub(crate) fn from_result(res: Result<()>) -> Result<Self> {
+        match res {
+            Ok(()) => Ok(ScanOutcome::Completed),
+            Err(Error::Hyperscan(HsError::ScanTerminated)) => Ok(ScanOutcome::Terminated),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl DatabaseRef<Block> {
+    /// The block (non-streaming) regular expression scanner, returning whether the scan was terminated.
+    ///
+    /// Unlike `scan`, the termination by the match callback is reported as `ScanOutcome::Terminated`
+    /// instead of an `Error::Hyperscan(HsError::ScanTerminated)` error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, ScanOutcome};
+    /// let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let mut first = None;
+    ///
+    /// let outcome = db
+    ///     .scan_outcome("foo test test", &s, |_, from, to, _| {
+    ///         first = Some(from..to);
+    ///         Matching::Terminate
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(outcome, ScanOutcome::Terminated);
+    /// assert_eq!(first, Some(4..8));
+    /// assert_eq!(db.scan_outcome("foo bar", &s, ()).unwrap(), ScanOutcome::Completed);
+    /// ```
+    pub fn scan_outcome<T, F>(&self, data: T, scratch: &ScratchRef, on_match_event: F) -> Result<ScanOutcome>
+    where
+        T: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        ScanOutcome::from_result(self.scan(data, scratch, on_match_event))
+    }
+}
+
+impl DatabaseRef<Vectored> {
+    /// The vectored regular expression scanner, returning whether the scan was terminated.
+    pub fn scan_outcome<I, T, F>(&self, data: I, scratch: &ScratchRef, on_match_event: F) -> Result<ScanOutcome>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        ScanOutcome::from_result(self.scan(data, scratch, on_match_event))
+    }
+}
+
+impl StreamRef {
+    /// Write data to be scanned to the opened stream, returning whether the scan was terminated.
+    ///
+    /// Once terminated, the stream doesn't report any more matches until it is reset.
+    pub fn scan_outcome<T, F>(&self, data: T, scratch: &ScratchRef, on_match_event: F) -> Result<ScanOutcome>
+    where
+        T: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        ScanOutcome::from_result(self.scan(data, scratch, on_match_event))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::ScanOutcome;
+
+    #[test]
+    fn test_scan_outcome() {
+        let db: VectoredDatabase = patterns!["foo", "bar"].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        assert_eq!(
+            db.scan_outcome(vec!["fo", "o ba", "r"], &s, Matching::Continue)
+                .unwrap(),
+            ScanOutcome::Completed
+        );
+        assert!(db
+            .scan_outcome(vec!["fo", "o ba", "r"], &s, Matching::Terminate)
+            .unwrap()
+            .is_terminated());
+        assert!(!db
+            .scan_outcome(vec!["nothing"], &s, Matching::Terminate)
+            .unwrap()
+            .is_terminated());
+    }
+}
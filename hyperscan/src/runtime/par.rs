@@ -0,0 +1,85 @@
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    common::{Block, DatabaseRef},
+    runtime::{Matches, Matching, PerThread, ScratchRef},
+    Result,
+};
+
+impl DatabaseRef<Block> {
+    /// Scan the inputs in parallel on the rayon thread pool, returning the matches in the order of the inputs.
+    ///
+    /// Every worker thread scans with its own clone of the scratch space.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    ///
+    /// let inputs = vec!["foo test", "bar", "test"];
+    /// let matches = db.par_scan_many(inputs, &s).unwrap();
+    ///
+    /// assert_eq!(matches, vec![vec![(0, 4..8)], vec![], vec![(0, 0..4)]]);
+    /// ```
+    pub fn par_scan_many<I, D>(&self, inputs: I, scratch: &ScratchRef) -> Result<Vec<Matches>>
+    where
+        I: IntoParallelIterator<Item = D>,
+        D: AsRef<[u8]>,
+    {
+        let scratch = PerThread::new(scratch.to_owned());
+
+        inputs
+            .into_par_iter()
+            .map(|data| {
+                scratch.with(|s| {
+                    let mut matches = vec![];
+
+                    self.scan(data, s, |id, from, to, _| {
+                        matches.push((id, from..to));
+                        Matching::Continue
+                    })
+                    .map(|_| matches)
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::Matches;
+
+    #[test]
+    fn test_par_scan_many() {
+        let db: BlockDatabase = patterns!["foo", "bar"; SOM_LEFTMOST].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        let inputs = (0..1000)
+            .map(|i| {
+                if i % 2 == 0 {
+                    format!("{} foo", i)
+                } else {
+                    format!("{} bar", i)
+                }
+            })
+            .collect::<Vec<_>>();
+        let expected = inputs
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let start = s.len() as u64 - 3;
+
+                vec![((i % 2) as u32, start..start + 3)]
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(db.par_scan_many(&inputs, &s).unwrap(), expected);
+        assert_eq!(
+            db.par_scan_many(Vec::<&[u8]>::new(), &s).unwrap(),
+            Vec::<Matches>::new()
+        );
+    }
+}
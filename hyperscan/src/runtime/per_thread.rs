@@ -0,0 +1,254 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::{self, ThreadId};
+
+/// A value which is lazily cloned from a prototype for every thread that uses it.
+///
+/// `Scratch` is `Send` but not `Sync`: it may be moved to another thread,
+/// but it must never be used by two concurrent callers. `PerThread<Scratch>` is `Sync`,
+/// so it can be shared between threads (e.g. through an `Arc`) while every thread
+/// scans with its own scratch space, cloned from the prototype on first use.
+///
+/// Nested calls on the same thread receive a separate clone,
+/// so a match callback may safely scan another database with the same `PerThread`.
+///
+/// The value of a thread is dropped when the thread exits.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use std::thread;
+/// # use hyperscan::{prelude::*, PerThread};
+/// let db: Arc<BlockDatabase> = Arc::new(pattern! {"test"; SOM_LEFTMOST}.build().unwrap());
+/// let scratch = Arc::new(PerThread::new(db.alloc_scratch().unwrap()));
+///
+/// let handles = (0..4)
+///     .map(|_| {
+///         let db = db.clone();
+///         let scratch = scratch.clone();
+///
+///         thread::spawn(move || {
+///             let mut matches = vec![];
+///
+///             scratch.with(|s| {
+///                 db.scan("foo test bar", s, |_, from, to, _| {
+///                     matches.push(from..to);
+///                     Matching::Continue
+///                 })
+///             })
+///             .unwrap();
+///
+///             matches
+///         })
+///     })
+///     .collect::<Vec<_>>();
+///
+/// for h in handles {
+///     assert_eq!(h.join().unwrap(), vec![4..8]);
+/// }
+/// ```
+pub struct PerThread<T> {
+    prototype: Mutex<T>,
+    values: Arc<Values<T>>,
+}
+
+/// The values cached for the threads.
+struct Values<T>(Mutex<HashMap<ThreadId, T>>);
+
+trait Evict {
+    /// Drop the value cached for the exited thread.
+    fn evict(&self, id: ThreadId);
+}
+
+impl<T> Evict for Values<T> {
+    fn evict(&self, id: ThreadId) {
+        let value = self.0.lock().unwrap_or_else(|err| err.into_inner()).remove(&id);
+
+        drop(value)
+    }
+}
+
+/// The `PerThread` values used by the current thread, which are evicted when the thread exits.
+struct ThreadValues {
+    id: ThreadId,
+    values: RefCell<Vec<Weak<dyn Evict>>>,
+}
+
+impl Drop for ThreadValues {
+    fn drop(&mut self) {
+        for values in self.values.get_mut().drain(..) {
+            if let Some(values) = values.upgrade() {
+                values.evict(self.id);
+            }
+        }
+    }
+}
+
+thread_local! {
+    static THREAD_VALUES: ThreadValues = ThreadValues {
+        id: thread::current().id(),
+        values: RefCell::new(Vec::new()),
+    };
+}
+
+impl<T> fmt::Debug for PerThread<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PerThread")
+            .field("threads", &self.values.0.lock().map_or(0, |values| values.len()))
+            .finish()
+    }
+}
+
+impl<T> From<T> for PerThread<T> {
+    fn from(prototype: T) -> Self {
+        PerThread::new(prototype)
+    }
+}
+
+impl<T> PerThread<T> {
+    /// Construct a per-thread value from the prototype which will be cloned for each thread.
+    pub fn new(prototype: T) -> Self {
+        PerThread {
+            prototype: Mutex::new(prototype),
+            values: Arc::new(Values(Mutex::new(HashMap::new()))),
+        }
+    }
+
+    /// Drop the values cached for all threads.
+    ///
+    /// The value of an exited thread is already dropped, unless the thread was exiting when it was cached.
+    pub fn clear(&self) {
+        self.values.0.lock().unwrap().clear()
+    }
+
+    /// Consumes the `PerThread`, returning the prototype.
+    pub fn into_inner(self) -> T {
+        self.prototype.into_inner().unwrap()
+    }
+}
+
+impl<T: Clone + 'static> PerThread<T> {
+    /// Calls a closure with the value owned by the current thread.
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        let id = thread::current().id();
+        let value = self.values.0.lock().unwrap().remove(&id);
+        let value = value.unwrap_or_else(|| {
+            self.evict_on_exit();
+            self.prototype.lock().unwrap().clone()
+        });
+
+        let res = f(&value);
+
+        self.values.0.lock().unwrap().insert(id, value);
+
+        res
+    }
+
+    /// Register the values to be evicted when the current thread exits.
+    fn evict_on_exit(&self) {
+        let values = Arc::downgrade(&self.values) as Weak<dyn Evict>;
+
+        let _ = THREAD_VALUES.try_with(|thread| {
+            let mut registered = thread.values.borrow_mut();
+
+            registered.retain(|values| values.strong_count() > 0);
+
+            if !registered.iter().any(|registered| registered.ptr_eq(&values)) {
+                registered.push(values);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use static_assertions::{assert_impl_all, assert_not_impl_any};
+
+    use crate::prelude::*;
+    use crate::{PerThread, ScratchRef, StreamRef};
+
+    assert_impl_all!(BlockDatabase: Send, Sync);
+    assert_impl_all!(StreamingDatabase: Send, Sync);
+    assert_impl_all!(VectoredDatabase: Send, Sync);
+
+    assert_impl_all!(Scratch: Send);
+    assert_not_impl_any!(Scratch: Sync);
+    assert_not_impl_any!(ScratchRef: Sync);
+
+    assert_impl_all!(Stream: Send);
+    assert_not_impl_any!(Stream: Sync);
+    assert_not_impl_any!(StreamRef: Sync);
+
+    assert_impl_all!(PerThread<Scratch>: Send, Sync);
+
+    #[test]
+    fn test_per_thread_scratch() {
+        let db: Arc<BlockDatabase> = Arc::new(pattern! {"test"; SOM_LEFTMOST}.build().unwrap());
+        let scratch = Arc::new(PerThread::new(db.alloc_scratch().unwrap()));
+
+        let handles = (0..4)
+            .map(|_| {
+                let db = db.clone();
+                let scratch = scratch.clone();
+
+                thread::spawn(move || {
+                    let mut matches = vec![];
+
+                    for _ in 0..16 {
+                        scratch
+                            .with(|s| {
+                                db.scan("foo test bar", s, |_, from, to, _| {
+                                    matches.push(from..to);
+                                    Matching::Continue
+                                })
+                            })
+                            .unwrap();
+                    }
+
+                    matches
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for h in handles {
+            assert_eq!(h.join().unwrap(), vec![4..8; 16]);
+        }
+
+        assert!(scratch.values.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_per_thread_nested() {
+        let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let scratch = PerThread::new(db.alloc_scratch().unwrap());
+        let mut matches = vec![];
+
+        scratch
+            .with(|s| {
+                db.scan("test", s, |_, _, _, _| {
+                    scratch
+                        .with(|s| {
+                            db.scan("foo test", s, |_, from, to, _| {
+                                matches.push(from..to);
+                                Matching::Continue
+                            })
+                        })
+                        .unwrap();
+
+                    Matching::Continue
+                })
+            })
+            .unwrap();
+
+        assert_eq!(matches, vec![4..8]);
+    }
+}
@@ -0,0 +1,247 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
+
+use crate::{
+    common::BlockDatabase,
+    runtime::{Matching, Scratch},
+    Error, Result,
+};
+
+/// The matches of an input, as the pattern ID and the offsets of every match.
+pub type Matches = Vec<(u32, Range<u64>)>;
+
+type Job = (
+    usize,
+    Box<dyn AsRef<[u8]> + Send>,
+    mpsc::Sender<(usize, Result<Matches>)>,
+);
+
+struct Worker {
+    jobs: Option<mpsc::Sender<Job>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// A pool of worker threads scanning with a shared block database.
+///
+/// Every worker owns its scratch space, and could be pinned to a CPU core
+/// (with the `affinity` feature), so the inputs of a partition are always scanned
+/// on the same core with the same, cache-local scratch space.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use hyperscan::{prelude::*, ScanPool};
+/// let db: Arc<BlockDatabase> = Arc::new(pattern! {"test"; SOM_LEFTMOST}.build().unwrap());
+/// let pool = ScanPool::new(db, 2).unwrap();
+///
+/// let inputs = vec!["foo test", "bar", "test"];
+/// let matches = pool.scan_partitioned(inputs, |idx, _| idx).unwrap();
+///
+/// assert_eq!(matches, vec![vec![(0, 4..8)], vec![], vec![(0, 0..4)]]);
+/// ```
+pub struct ScanPool {
+    workers: Vec<Worker>,
+}
+
+impl fmt::Debug for ScanPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScanPool")
+            .field("workers", &self.workers.len())
+            .finish()
+    }
+}
+
+impl Drop for ScanPool {
+    fn drop(&mut self) {
+        for worker in &mut self.workers {
+            worker.jobs.take();
+        }
+
+        for worker in &mut self.workers {
+            if let Some(handle) = worker.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+impl ScanPool {
+    /// Spawn a pool with `workers` threads scanning with the database.
+    pub fn new(db: Arc<BlockDatabase>, workers: usize) -> Result<Self> {
+        let scratch = db.alloc_scratch()?;
+
+        Ok(ScanPool {
+            workers: (0..workers.max(1))
+                .map(|_| Self::spawn(db.clone(), scratch.clone(), || {}))
+                .collect(),
+        })
+    }
+
+    /// Spawn a pool with a worker thread pinned to each of the CPU cores.
+    ///
+    /// Returns `Error::NoWorkers` if `cores` is empty.
+    #[cfg(feature = "affinity")]
+    pub fn with_cores(db: Arc<BlockDatabase>, cores: Vec<core_affinity::CoreId>) -> Result<Self> {
+        if cores.is_empty() {
+            return Err(Error::NoWorkers);
+        }
+
+        let scratch = db.alloc_scratch()?;
+
+        Ok(ScanPool {
+            workers: cores
+                .into_iter()
+                .map(|core| {
+                    Self::spawn(db.clone(), scratch.clone(), move || {
+                        core_affinity::set_for_current(core);
+                    })
+                })
+                .collect(),
+        })
+    }
+
+    fn spawn<F>(db: Arc<BlockDatabase>, scratch: Scratch, init: F) -> Worker
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let (jobs, rx) = mpsc::channel::<Job>();
+
+        let handle = thread::spawn(move || {
+            init();
+
+            for (idx, data, results) in rx {
+                let mut matches = vec![];
+
+                let res = db
+                    .scan((*data).as_ref(), &scratch, |id, from, to, _| {
+                        matches.push((id, from..to));
+                        Matching::Continue
+                    })
+                    .map(|_| matches);
+
+                let _ = results.send((idx, res));
+            }
+        });
+
+        Worker {
+            jobs: Some(jobs),
+            handle: Some(handle),
+        }
+    }
+
+    /// The number of worker threads.
+    pub fn len(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Returns `true` if the pool has no worker thread.
+    pub fn is_empty(&self) -> bool {
+        self.workers.is_empty()
+    }
+
+    /// Scan the inputs on the workers, returning the matches in the order of the inputs.
+    ///
+    /// The `partitioner` is called with the index of every input and the input,
+    /// and returns the worker to route the input to (modulo the number of workers),
+    /// e.g. a hash of the flow, so that the inputs of a flow are always scanned by the same worker.
+    ///
+    /// Returns `Error::WorkerExited` if a worker thread exited before scanning its inputs.
+    pub fn scan_partitioned<I, T, P>(&self, inputs: I, mut partitioner: P) -> Result<Vec<Matches>>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]> + Send + 'static,
+        P: FnMut(usize, &T) -> usize,
+    {
+        if self.workers.is_empty() {
+            return Err(Error::NoWorkers);
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let mut count = 0;
+
+        for (idx, data) in inputs.into_iter().enumerate() {
+            let worker = &self.workers[partitioner(idx, &data) % self.workers.len()];
+
+            worker
+                .jobs
+                .as_ref()
+                .ok_or(Error::WorkerExited)?
+                .send((idx, Box::new(data), tx.clone()))
+                .map_err(|_| Error::WorkerExited)?;
+
+            count += 1;
+        }
+
+        drop(tx);
+
+        let mut results = rx.iter().take(count).collect::<Vec<_>>();
+
+        if results.len() != count {
+            return Err(Error::WorkerExited);
+        }
+
+        results.sort_by_key(|&(idx, _)| idx);
+        results.into_iter().map(|(_, res)| res).collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Arc;
+
+    use crate::prelude::*;
+    use crate::ScanPool;
+
+    #[test]
+    fn test_scan_pool() {
+        let db: Arc<BlockDatabase> = Arc::new(patterns!["foo", "bar"; SOM_LEFTMOST].build().unwrap());
+        let pool = ScanPool::new(db, 4).unwrap();
+
+        assert_eq!(pool.len(), 4);
+
+        let inputs = (0..100)
+            .map(|i| {
+                if i % 2 == 0 {
+                    format!("{} foo", i)
+                } else {
+                    format!("{} bar", i)
+                }
+            })
+            .collect::<Vec<_>>();
+        let expected = inputs
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let start = s.len() as u64 - 3;
+
+                vec![((i % 2) as u32, start..start + 3)]
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(pool.scan_partitioned(inputs, |_, s| s.len()).unwrap(), expected);
+    }
+
+    #[cfg(feature = "affinity")]
+    #[test]
+    fn test_scan_pool_without_cores() {
+        let db: Arc<BlockDatabase> = Arc::new(pattern! {"foo"}.build().unwrap());
+
+        assert_eq!(ScanPool::with_cores(db, vec![]).unwrap_err(), crate::Error::NoWorkers);
+    }
+
+    #[test]
+    fn test_scan_pool_worker_exited() {
+        let db: Arc<BlockDatabase> = Arc::new(pattern! {"foo"}.build().unwrap());
+        let mut pool = ScanPool::new(db, 2).unwrap();
+
+        pool.workers[1].jobs.take();
+
+        assert_eq!(
+            pool.scan_partitioned(vec!["foo", "bar"], |idx, _| idx).unwrap_err(),
+            crate::Error::WorkerExited
+        );
+    }
+}
@@ -0,0 +1,417 @@
+use std::fmt;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::{
+    common::{Database, DatabaseRef},
+    runtime::{PooledScratch, Scratch, SyncScratchPool},
+    Result,
+};
+
+type CompileHook = Arc<dyn Fn(u64, std::result::Result<(), &crate::Error>) + Send + Sync>;
+type ActivateHook<T> = Arc<dyn Fn(&DatabaseRef<T>, u64) + Send + Sync>;
+type RetireHook<T> = Arc<dyn Fn(&DatabaseRef<T>, u64, usize) + Send + Sync>;
+
+/// Clone the registered hooks, so they are called without holding the lock,
+/// and could register other hooks.
+fn hooks<H: Clone>(hooks: &Mutex<Vec<H>>) -> Vec<H> {
+    hooks.lock().unwrap().clone()
+}
+
+struct Generation<T> {
+    db: Database<T>,
+    scratch: SyncScratchPool,
+    generation: u64,
+    in_flight: AtomicUsize,
+    stats: Arc<Counters>,
+}
+
+impl<T> Drop for Generation<T> {
+    fn drop(&mut self) {
+        self.stats.freed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    compiled: AtomicU64,
+    failed: AtomicU64,
+    activated: AtomicU64,
+    retired: AtomicU64,
+    freed: AtomicU64,
+}
+
+/// The counters of a `ReloadableDatabase`, for monitoring.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReloadStats {
+    /// The generation of the active database.
+    pub generation: u64,
+    /// The number of databases built by `ReloadableDatabase::reload`.
+    pub compiled: u64,
+    /// The number of failed builds.
+    pub failed: u64,
+    /// The number of activated databases.
+    pub activated: u64,
+    /// The number of retired databases.
+    pub retired: u64,
+    /// The number of retired databases which have been freed, after all their in-flight scans completed.
+    pub freed: u64,
+    /// The number of in-flight scans on the active database.
+    pub in_flight: usize,
+}
+
+/// A holder of the active database which could be replaced while scans are in flight.
+///
+/// The scans borrow the active database with `ReloadableDatabase::load`;
+/// a retired database is only freed after all its in-flight scans complete.
+///
+/// A scratch space is allocated for every generation when it's activated,
+/// so the scans should use `ScanGuard::scratch` instead of a scratch space allocated for a previous generation.
+///
+/// The lifecycle of the databases can be observed with the `on_compile`, `on_activate` and `on_retire` hooks.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::sync::{Arc, Mutex};
+/// # use hyperscan::{prelude::*, ReloadableDatabase};
+/// let db: BlockDatabase = pattern! {"foo"}.build().unwrap();
+/// let reloadable = ReloadableDatabase::new(db).unwrap();
+/// let retired = Arc::new(Mutex::new(vec![]));
+///
+/// reloadable.on_retire({
+///     let retired = retired.clone();
+///
+///     move |_, generation, in_flight_scans| retired.lock().unwrap().push((generation, in_flight_scans))
+/// });
+///
+/// let scan = reloadable.load();
+///
+/// reloadable.reload(|| pattern! {"bar"}.build()).unwrap();
+///
+/// // the retired database is still usable by the in-flight scan
+/// scan.scan("foo", &scan.scratch(), Matching::Terminate).unwrap_err();
+/// drop(scan);
+///
+/// let scan = reloadable.load();
+///
+/// assert!(scan.contains("bar", &scan.scratch()).unwrap());
+/// drop(scan);
+///
+/// assert_eq!(*retired.lock().unwrap(), vec![(0, 1)]);
+/// assert_eq!(reloadable.stats().freed, 1);
+/// ```
+pub struct ReloadableDatabase<T> {
+    current: RwLock<Arc<Generation<T>>>,
+    stats: Arc<Counters>,
+    on_compile: Mutex<Vec<CompileHook>>,
+    on_activate: Mutex<Vec<ActivateHook<T>>>,
+    on_retire: Mutex<Vec<RetireHook<T>>>,
+}
+
+impl<T> fmt::Debug for ReloadableDatabase<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReloadableDatabase")
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl<T> ReloadableDatabase<T> {
+    /// Construct a holder with the initial database as the generation 0.
+    ///
+    /// Returns an error if the scratch space couldn't be allocated for the database.
+    pub fn new(db: Database<T>) -> Result<Self> {
+        let stats = Arc::new(Counters::default());
+        let scratch = SyncScratchPool::new(db.alloc_scratch()?);
+
+        Ok(ReloadableDatabase {
+            current: RwLock::new(Arc::new(Generation {
+                db,
+                scratch,
+                generation: 0,
+                in_flight: AtomicUsize::new(0),
+                stats: stats.clone(),
+            })),
+            stats,
+            on_compile: Mutex::new(vec![]),
+            on_activate: Mutex::new(vec![]),
+            on_retire: Mutex::new(vec![]),
+        })
+    }
+
+    /// Register a hook called with the generation and the result after a database is built by `reload`.
+    pub fn on_compile<F>(&self, f: F)
+    where
+        F: Fn(u64, std::result::Result<(), &crate::Error>) + Send + Sync + 'static,
+    {
+        self.on_compile.lock().unwrap().push(Arc::new(f))
+    }
+
+    /// Register a hook called with the database and its generation after it was activated.
+    pub fn on_activate<F>(&self, f: F)
+    where
+        F: Fn(&DatabaseRef<T>, u64) + Send + Sync + 'static,
+    {
+        self.on_activate.lock().unwrap().push(Arc::new(f))
+    }
+
+    /// Register a hook called with the database, its generation and the number of in-flight scans
+    /// after it was retired.
+    pub fn on_retire<F>(&self, f: F)
+    where
+        F: Fn(&DatabaseRef<T>, u64, usize) + Send + Sync + 'static,
+    {
+        self.on_retire.lock().unwrap().push(Arc::new(f))
+    }
+
+    /// Borrow the active database for a scan.
+    pub fn load(&self) -> ScanGuard<T> {
+        let current = self.current.read().unwrap();
+
+        // count the scan while holding the read lock, so a retired generation never misses it
+        current.in_flight.fetch_add(1, Ordering::AcqRel);
+
+        ScanGuard {
+            generation: current.clone(),
+        }
+    }
+
+    /// Build a new database and activate it, retiring the current one.
+    ///
+    /// The build fails if the scratch space couldn't be allocated for the database.
+    pub fn reload<F>(&self, build: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<Database<T>>,
+    {
+        let res = build().and_then(|db| {
+            let scratch = db.alloc_scratch()?;
+
+            Ok((db, scratch))
+        });
+
+        match res {
+            Ok((db, scratch)) => {
+                self.stats.compiled.fetch_add(1, Ordering::Relaxed);
+                self.swap(db, scratch, |generation| {
+                    for hook in hooks(&self.on_compile) {
+                        hook(generation, Ok(()))
+                    }
+                });
+                Ok(())
+            }
+            Err(err) => {
+                let generation = self.current.read().unwrap().generation + 1;
+
+                for hook in hooks(&self.on_compile) {
+                    hook(generation, Err(&err))
+                }
+
+                self.stats.failed.fetch_add(1, Ordering::Relaxed);
+                Err(err)
+            }
+        }
+    }
+
+    /// Activate the database, retiring the current one.
+    ///
+    /// Returns an error if the scratch space couldn't be allocated for the database.
+    pub fn activate(&self, db: Database<T>) -> Result<()> {
+        let scratch = db.alloc_scratch()?;
+
+        self.swap(db, scratch, |_| {});
+
+        Ok(())
+    }
+
+    /// Replace the current generation, calling `built` with the generation of the activated database
+    /// before the activation hooks.
+    fn swap<F>(&self, db: Database<T>, scratch: Scratch, built: F)
+    where
+        F: FnOnce(u64),
+    {
+        let (active, retired) = {
+            let mut current = self.current.write().unwrap();
+            let next = Arc::new(Generation {
+                db,
+                scratch: SyncScratchPool::new(scratch),
+                generation: current.generation + 1,
+                in_flight: AtomicUsize::new(0),
+                stats: self.stats.clone(),
+            });
+
+            (next.clone(), std::mem::replace(&mut *current, next))
+        };
+
+        built(active.generation);
+
+        self.stats.activated.fetch_add(1, Ordering::Relaxed);
+
+        for hook in hooks(&self.on_activate) {
+            hook(&active.db, active.generation)
+        }
+
+        self.stats.retired.fetch_add(1, Ordering::Relaxed);
+
+        let in_flight_scans = retired.in_flight.load(Ordering::Acquire);
+
+        for hook in hooks(&self.on_retire) {
+            hook(&retired.db, retired.generation, in_flight_scans)
+        }
+    }
+
+    /// Returns the counters for monitoring.
+    pub fn stats(&self) -> ReloadStats {
+        let current = self.current.read().unwrap();
+
+        ReloadStats {
+            generation: current.generation,
+            compiled: self.stats.compiled.load(Ordering::Relaxed),
+            failed: self.stats.failed.load(Ordering::Relaxed),
+            activated: self.stats.activated.load(Ordering::Relaxed),
+            retired: self.stats.retired.load(Ordering::Relaxed),
+            freed: self.stats.freed.load(Ordering::Relaxed),
+            in_flight: current.in_flight.load(Ordering::Acquire),
+        }
+    }
+}
+
+/// A database borrowed from a `ReloadableDatabase` for an in-flight scan.
+pub struct ScanGuard<T> {
+    generation: Arc<Generation<T>>,
+}
+
+impl<T> fmt::Debug for ScanGuard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScanGuard")
+            .field("generation", &self.generation.generation)
+            .finish()
+    }
+}
+
+impl<T> ScanGuard<T> {
+    /// The generation of the borrowed database.
+    pub fn generation(&self) -> u64 {
+        self.generation.generation
+    }
+
+    /// Check out a scratch space allocated for the borrowed database.
+    pub fn scratch(&self) -> PooledScratch<'_> {
+        self.generation.scratch.get()
+    }
+}
+
+impl<T> Deref for ScanGuard<T> {
+    type Target = DatabaseRef<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.generation.db
+    }
+}
+
+impl<T> Drop for ScanGuard<T> {
+    fn drop(&mut self) {
+        self.generation.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::prelude::*;
+    use crate::{ReloadStats, ReloadableDatabase};
+
+    #[test]
+    fn test_reloadable_database() {
+        let db: BlockDatabase = pattern! {"foo"}.build().unwrap();
+        let reloadable = ReloadableDatabase::new(db).unwrap();
+        let events = Arc::new(Mutex::new(vec![]));
+
+        reloadable.on_compile({
+            let events = events.clone();
+            move |generation, res| {
+                events
+                    .lock()
+                    .unwrap()
+                    .push(format!("compile {} {}", generation, res.is_ok()))
+            }
+        });
+        reloadable.on_activate({
+            let events = events.clone();
+            move |_, generation| events.lock().unwrap().push(format!("activate {}", generation))
+        });
+        reloadable.on_retire({
+            let events = events.clone();
+            move |_, generation, in_flight| {
+                events
+                    .lock()
+                    .unwrap()
+                    .push(format!("retire {} {}", generation, in_flight))
+            }
+        });
+
+        let scan = reloadable.load();
+
+        assert_eq!(scan.generation(), 0);
+        assert_eq!(reloadable.stats().in_flight, 1);
+
+        reloadable.reload(|| pattern! {"bar"}.build()).unwrap();
+        reloadable.reload(|| pattern! {"a*"}.build()).unwrap_err();
+
+        assert_eq!(reloadable.stats().freed, 0);
+
+        scan.scan("foo", &scan.scratch(), Matching::Terminate).unwrap_err();
+        drop(scan);
+
+        let scan = reloadable.load();
+
+        assert_eq!(scan.generation(), 1);
+        assert!(scan.contains("bar", &scan.scratch()).unwrap());
+
+        drop(scan);
+
+        assert_eq!(
+            reloadable.stats(),
+            ReloadStats {
+                generation: 1,
+                compiled: 1,
+                failed: 1,
+                activated: 1,
+                retired: 1,
+                freed: 1,
+                in_flight: 0,
+            }
+        );
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["compile 1 true", "activate 1", "retire 0 1", "compile 2 false"]
+        );
+    }
+
+    #[test]
+    fn test_register_hook_from_hook() {
+        let db: BlockDatabase = pattern! {"foo"}.build().unwrap();
+        let reloadable = Arc::new(ReloadableDatabase::new(db).unwrap());
+        let activated = Arc::new(Mutex::new(vec![]));
+
+        reloadable.on_activate({
+            let reloadable = Arc::downgrade(&reloadable);
+            let activated = activated.clone();
+
+            move |_, generation| {
+                let activated = activated.clone();
+
+                if let Some(reloadable) = reloadable.upgrade() {
+                    reloadable.on_activate(move |_, next| activated.lock().unwrap().push((generation, next)));
+                }
+            }
+        });
+
+        reloadable.reload(|| pattern! {"bar"}.build()).unwrap();
+        reloadable.reload(|| pattern! {"baz"}.build()).unwrap();
+
+        assert_eq!(*activated.lock().unwrap(), vec![(1, 2)]);
+    }
+}
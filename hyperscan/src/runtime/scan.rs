@@ -1,21 +1,32 @@
+use alloc::vec::Vec;
+use core::ops::BitOr;
+use core::ptr;
+#[cfg(feature = "std")]
 use std::io::Read;
-use std::mem;
-use std::ptr;
 
+use core::ffi::{c_char, c_uint};
 use foreign_types::ForeignTypeRef;
-use libc::{c_char, c_uint};
 
 use crate::{
-    common::{Block, DatabaseRef, Streaming, Vectored},
+    common::{catch_unwind, resume_unwind, Block, DatabaseRef, DbMode, Streaming, Vectored},
     error::AsResult,
     ffi,
-    runtime::{split_closure, ScratchRef, StreamRef},
+    runtime::{metered, ScanOutcome, ScratchRef, StreamRef},
     Result,
 };
 
+#[cfg(feature = "async")]
+use std::sync::Arc;
+
 #[cfg(feature = "async")]
 use futures::io::{AsyncRead, AsyncReadExt};
 
+#[cfg(feature = "async")]
+use crate::{
+    common::BlockDatabase,
+    runtime::{blocking::spawn_blocking, Scratch},
+};
+
 /// Indicating whether or not matching should continue on the target data.
 #[repr(i32)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -32,6 +43,44 @@ impl Default for Matching {
     }
 }
 
+/// The flags passed to the scan functions.
+///
+/// No flag is defined by Hyperscan yet, the raw bits are passed through as they are,
+/// so the flags of the future versions or the compatible libraries (e.g. Vectorscan) could be used.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ScanFlags(u32);
+
+impl ScanFlags {
+    /// Returns the empty flags.
+    pub const fn empty() -> Self {
+        ScanFlags(0)
+    }
+
+    /// Construct the flags from the raw bits.
+    pub const fn from_bits(bits: u32) -> Self {
+        ScanFlags(bits)
+    }
+
+    /// Returns the raw bits of the flags.
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns true if no flag is set.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for ScanFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        ScanFlags(self.0 | rhs.0)
+    }
+}
+
 /// Definition of the match event callback function type.
 ///
 /// A callback function matching the defined type must be provided by the
@@ -52,24 +101,39 @@ impl Default for Matching {
 /// example, scanning a different database in a new stream and with new scratch
 /// space), but reusing data structures like stream state and/or scratch space
 /// will produce undefined behavior.
+///
+/// If a start of match flag is enabled for the pattern, the `from` offset is the start of match,
+/// assuming it lies within the start of match horizon chosen by `SomHorizon`; otherwise
+/// `from` is set to the `HS_OFFSET_PAST_HORIZON` sentinel, i.e. `u64::MAX`. Use `OnMatch`
+/// to receive it as `MatchStart::Unknown`. The `from` offset is zero if the start of match flag is not enabled.
+///
+/// If the callback panics, the matching is terminated and the panic is resumed after the scan call returns,
+/// instead of unwinding through the Hyperscan frames. Without the `std` feature, the panic can't be caught,
+/// so the `no_std` targets should abort on panic.
 pub trait MatchEventHandler {
     /// Split the match event handler to callback and userdata.
     ///
     /// # Safety
     ///
     /// Do not implement this trait directly, use `()`, `Matching` or `|id, from, to, flags| -> Matching`.
-    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut libc::c_void);
+    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut core::ffi::c_void);
 }
 
 impl MatchEventHandler for () {
-    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut libc::c_void) {
+    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut core::ffi::c_void) {
         (None, ptr::null_mut())
     }
 }
 
 impl MatchEventHandler for Matching {
-    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut libc::c_void) {
-        unsafe extern "C" fn trampoline(_: u32, _: u64, _: u64, _: u32, ctx: *mut ::libc::c_void) -> ::libc::c_int {
+    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut core::ffi::c_void) {
+        unsafe extern "C" fn trampoline(
+            _: u32,
+            _: u64,
+            _: u64,
+            _: u32,
+            ctx: *mut ::core::ffi::c_void,
+        ) -> ::core::ffi::c_int {
             ctx.cast::<Matching>().read() as _
         }
 
@@ -77,8 +141,8 @@ impl MatchEventHandler for Matching {
     }
 }
 
-impl MatchEventHandler for (ffi::match_event_handler, *mut libc::c_void) {
-    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut libc::c_void) {
+impl MatchEventHandler for (ffi::match_event_handler, *mut core::ffi::c_void) {
+    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut core::ffi::c_void) {
         *self
     }
 }
@@ -87,10 +151,23 @@ impl<F> MatchEventHandler for F
 where
     F: FnMut(u32, u64, u64, u32) -> Matching,
 {
-    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut libc::c_void) {
-        let (callback, userdata) = split_closure(self);
+    unsafe fn split(&mut self) -> (ffi::match_event_handler, *mut core::ffi::c_void) {
+        unsafe extern "C" fn trampoline<F>(
+            id: u32,
+            from: u64,
+            to: u64,
+            flags: u32,
+            ctx: *mut core::ffi::c_void,
+        ) -> core::ffi::c_int
+        where
+            F: FnMut(u32, u64, u64, u32) -> Matching,
+        {
+            let callback = &mut *(ctx as *mut F);
+
+            catch_unwind(Matching::Terminate, || callback(id, from, to, flags)) as _
+        }
 
-        (Some(mem::transmute(callback)), userdata)
+        (Some(trampoline::<F>), self as *mut _ as *mut _)
     }
 }
 
@@ -114,28 +191,150 @@ impl DatabaseRef<Block> {
     ///
     /// assert_eq!(matches, vec![4..8]);
     /// ```
-    pub fn scan<T, F>(&self, data: T, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
+    pub fn scan<T, F>(&self, data: T, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        T: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        self.scan_with_flags(data, ScanFlags::empty(), scratch, on_match_event)
+    }
+
+    /// The block (non-streaming) regular expression scanner with the scan flags.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, ScanFlags};
+    /// let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    ///
+    /// db.scan_with_flags("foo test bar", ScanFlags::empty(), &s, Matching::Terminate)
+    ///     .unwrap_err();
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "scan",
+            level = "trace",
+            skip_all,
+            fields(mode = "Block", bytes = data.as_ref().len())
+        )
+    )]
+    pub fn scan_with_flags<T, F>(
+        &self,
+        data: T,
+        flags: ScanFlags,
+        scratch: &ScratchRef,
+        mut on_match_event: F,
+    ) -> Result<()>
     where
         T: AsRef<[u8]>,
         F: MatchEventHandler,
     {
         let data = data.as_ref();
 
+        let _in_use = scratch.acquire()?;
+
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_scan(
-                self.as_ptr(),
-                data.as_ptr() as *const c_char,
-                data.len() as u32,
-                0,
-                scratch.as_ptr(),
+            resume_unwind(metered(
+                DbMode::Block,
+                data.len(),
                 callback,
                 userdata,
-            )
+                |callback, userdata| {
+                    ffi::hs_scan(
+                        self.as_ptr(),
+                        data.as_ptr() as *const c_char,
+                        data.len() as u32,
+                        flags.bits(),
+                        scratch.as_ptr(),
+                        callback,
+                        userdata,
+                    )
+                },
+            ))
             .ok()
         }
     }
+
+    /// Returns `true` if any pattern matches the data.
+    ///
+    /// This is the fastest possible yes/no check: the scan is terminated on the first match,
+    /// and no closure or match details are involved. For the cheapest database,
+    /// compile the patterns without `SOM_LEFTMOST` (and optionally with `SINGLEMATCH`).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: BlockDatabase = patterns!["foo", "bar"].build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    ///
+    /// assert!(db.contains("some bar", &s).unwrap());
+    /// assert!(!db.contains("some baz", &s).unwrap());
+    /// ```
+    pub fn contains<T>(&self, data: T, scratch: &ScratchRef) -> Result<bool>
+    where
+        T: AsRef<[u8]>,
+    {
+        self.scan_outcome(data, scratch, Matching::Terminate)
+            .map(ScanOutcome::is_terminated)
+    }
+}
+
+#[cfg(feature = "async")]
+impl BlockDatabase {
+    /// The block (non-streaming) regular expression scanner for the owned data in a blocking thread.
+    ///
+    /// The data, scratch space and match event handler are moved into a worker thread shared by the scans,
+    /// so no borrowed data have to be held across an await point;
+    /// the scratch space and the handler are returned with the result of the scan when it finished,
+    /// even if the scan failed or was terminated by the handler.
+    ///
+    /// A panic of the match event handler is resumed on the awaiting task.
+    ///
+    /// Returns `Error::WorkerExited` if the scan couldn't run on a worker thread,
+    /// the scratch space and the handler are lost in that case. See `set_blocking_threads`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use std::sync::Arc;
+    /// # use hyperscan::prelude::*;
+    /// # use tokio_test;
+    /// let db: Arc<BlockDatabase> = Arc::new(pattern! {"test"; SOM_LEFTMOST}.build().unwrap());
+    /// let s = db.alloc_scratch().unwrap();
+    /// let data = b"foo test bar".to_vec();
+    /// let mut matches = vec![];
+    ///
+    /// let (_s, _, res) = tokio_test::block_on(db.scan_owned(data, s, move |_, from, to, _| {
+    ///     matches.push(from..to);
+    ///     assert_eq!(matches, vec![4..8]);
+    ///     Matching::Continue
+    /// }))
+    /// .unwrap();
+    ///
+    /// assert!(res.is_ok());
+    /// ```
+    pub async fn scan_owned<T, F>(
+        self: Arc<Self>,
+        data: T,
+        scratch: Scratch,
+        mut on_match_event: F,
+    ) -> Result<(Scratch, F, Result<()>)>
+    where
+        T: AsRef<[u8]> + Send + 'static,
+        F: FnMut(u32, u64, u64, u32) -> Matching + Send + 'static,
+    {
+        spawn_blocking(move || {
+            let res = self.scan(data, &scratch, &mut on_match_event);
+
+            (scratch, on_match_event, res)
+        })
+        .await
+    }
 }
 
 impl DatabaseRef<Vectored> {
@@ -159,7 +358,36 @@ impl DatabaseRef<Vectored> {
     ///
     /// assert_eq!(matches, vec![3..7]);
     /// ```
-    pub fn scan<I, T, F>(&self, data: I, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
+    pub fn scan<I, T, F>(&self, data: I, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        self.scan_with_flags(data, ScanFlags::empty(), scratch, on_match_event)
+    }
+
+    /// The vectored regular expression scanner with the scan flags.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "scan",
+            level = "trace",
+            skip_all,
+            fields(
+                mode = "Vectored",
+                buffers = tracing::field::Empty,
+                bytes = tracing::field::Empty
+            )
+        )
+    )]
+    pub fn scan_with_flags<I, T, F>(
+        &self,
+        data: I,
+        flags: ScanFlags,
+        scratch: &ScratchRef,
+        mut on_match_event: F,
+    ) -> Result<()>
     where
         I: IntoIterator<Item = T>,
         T: AsRef<[u8]>,
@@ -174,27 +402,89 @@ impl DatabaseRef<Vectored> {
             })
             .unzip();
 
+        #[cfg(feature = "tracing")]
+        if tracing::level_enabled!(tracing::Level::TRACE) {
+            tracing::Span::current()
+                .record("buffers", ptrs.len())
+                .record("bytes", lens.iter().map(|&len| len as usize).sum::<usize>());
+        }
+
+        let _in_use = scratch.acquire()?;
+
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_scan_vector(
-                self.as_ptr(),
-                ptrs.as_slice().as_ptr() as *const *const c_char,
-                lens.as_slice().as_ptr() as *const _,
-                ptrs.len() as u32,
-                0,
-                scratch.as_ptr(),
+            resume_unwind(metered(
+                DbMode::Vectored,
+                lens.iter().map(|&len| len as usize).sum(),
                 callback,
                 userdata,
-            )
+                |callback, userdata| {
+                    ffi::hs_scan_vector(
+                        self.as_ptr(),
+                        ptrs.as_slice().as_ptr() as *const *const c_char,
+                        lens.as_slice().as_ptr() as *const _,
+                        ptrs.len() as u32,
+                        flags.bits(),
+                        scratch.as_ptr(),
+                        callback,
+                        userdata,
+                    )
+                },
+            ))
             .ok()
         }
     }
 }
 
-const SCAN_BUF_SIZE: usize = 4096;
+#[cfg(feature = "std")]
+pub(crate) const SCAN_BUF_SIZE: usize = 4096;
 
 impl DatabaseRef<Streaming> {
+    /// Pattern matching takes place for stream-mode pattern databases over the chunks of the data,
+    /// e.g. the payloads of the packets in a flow.
+    ///
+    /// A stream is opened for the chunks and closed after the last one, so the matches may span the chunks.
+    /// Unlike `scan`, no reader is involved, which makes it available without the `std` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: StreamingDatabase = pattern! { "test"; SOM_LEFTMOST }.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let mut matches = vec![];
+    ///
+    /// db.scan_chunks(vec!["foo te", "st b", "ar test"], &s, |_, from, to, _| {
+    ///     matches.push(from..to);
+    ///     Matching::Continue
+    /// })
+    /// .unwrap();
+    ///
+    /// assert_eq!(matches, vec![4..8, 13..17]);
+    /// ```
+    pub fn scan_chunks<I, T, F>(&self, chunks: I, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        let stream = self.open_stream()?;
+
+        let (callback, userdata) = unsafe { on_match_event.split() };
+
+        for chunk in chunks {
+            if let Err(err) = stream.scan(chunk, scratch, (callback, userdata)) {
+                // close the stream without reporting the end of data matches of the failed scan
+                let _ = stream.close(scratch, ());
+
+                return Err(err);
+            }
+        }
+
+        stream.close(scratch, (callback, userdata))
+    }
+
     /// Pattern matching takes place for stream-mode pattern databases.
     ///
     /// # Examples
@@ -221,6 +511,7 @@ impl DatabaseRef<Streaming> {
     ///
     /// assert_eq!(matches, vec![(4095, 4096), (4095, 4097), (4095, 4098)]);
     /// ```
+    #[cfg(feature = "std")]
     pub fn scan<R, F>(&self, reader: &mut R, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
     where
         R: Read,
@@ -291,6 +582,65 @@ impl DatabaseRef<Streaming> {
 
         stream.close(scratch, (callback, userdata))
     }
+
+    /// Pattern matching takes place for stream-mode pattern databases using `tokio::io::AsyncRead`,
+    /// e.g. `tokio::fs::File` or `tokio::net::TcpStream`.
+    ///
+    /// An error of the reader other than `ErrorKind::Interrupted` is returned as `Error::Io`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use std::io::Cursor;
+    /// # use hyperscan::prelude::*;
+    /// # use tokio_test;
+    /// let db: StreamingDatabase = pattern! { "a+"; SOM_LEFTMOST }.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let mut cur = Cursor::new(b"xxbaab");
+    /// let mut matches = vec![];
+    ///
+    /// tokio_test::block_on(async {
+    ///     db.async_scan_tokio(&mut cur, &s, |_, from, to, _| {
+    ///         matches.push((from, to));
+    ///
+    ///         Matching::Continue
+    ///     }).await.unwrap();
+    /// });
+    ///
+    /// assert_eq!(matches, vec![(3, 4), (3, 5)]);
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn async_scan_tokio<R, F>(
+        &self,
+        reader: &mut R,
+        scratch: &ScratchRef,
+        mut on_match_event: F,
+    ) -> Result<()>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+        F: MatchEventHandler,
+    {
+        use std::io::ErrorKind;
+        use tokio::io::AsyncReadExt;
+
+        let stream = self.open_stream()?;
+        let mut buf = [0; SCAN_BUF_SIZE];
+
+        let (callback, userdata) = unsafe { on_match_event.split() };
+
+        loop {
+            let len = match reader.read(&mut buf[..]).await {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            stream.scan(&buf[..len], scratch, (callback, userdata))?;
+        }
+
+        stream.close(scratch, (callback, userdata))
+    }
 }
 
 impl StreamRef {
@@ -299,6 +649,10 @@ impl StreamRef {
     /// This is the function call in which the actual pattern matching takes place as data is written to the stream.
     /// Matches will be returned via the `on_match_event` callback supplied.
     ///
+    /// Returns `HsError::ScanTerminated` if the callback terminated the scan, the stream doesn't report
+    /// any more matches until it is reset. Use `StreamRef::scan_outcome` to get the terminated state as
+    /// a `ScanOutcome`, or `ManagedStream` to reject the further scans of a terminated stream.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -325,26 +679,213 @@ impl StreamRef {
     ///
     /// assert_eq!(matches, vec![(4, 8)]);
     /// ```
-    pub fn scan<T, F>(&self, data: T, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
+    pub fn scan<T, F>(&self, data: T, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        T: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        self.scan_with_flags(data, ScanFlags::empty(), scratch, on_match_event)
+    }
+
+    /// Write data to be scanned to the opened stream with the scan flags.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "scan",
+            level = "trace",
+            skip_all,
+            fields(mode = "Streaming", bytes = data.as_ref().len())
+        )
+    )]
+    pub fn scan_with_flags<T, F>(
+        &self,
+        data: T,
+        flags: ScanFlags,
+        scratch: &ScratchRef,
+        mut on_match_event: F,
+    ) -> Result<()>
     where
         T: AsRef<[u8]>,
         F: MatchEventHandler,
     {
         let data = data.as_ref();
 
+        let _in_use = scratch.acquire()?;
+
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_scan_stream(
-                self.as_ptr(),
-                data.as_ptr() as *const c_char,
-                data.len() as u32,
-                0,
-                scratch.as_ptr(),
+            resume_unwind(metered(
+                DbMode::Streaming,
+                data.len(),
                 callback,
                 userdata,
-            )
+                |callback, userdata| {
+                    ffi::hs_scan_stream(
+                        self.as_ptr(),
+                        data.as_ptr() as *const c_char,
+                        data.len() as u32,
+                        flags.bits(),
+                        scratch.as_ptr(),
+                        callback,
+                        userdata,
+                    )
+                },
+            ))
             .ok()
         }
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_scan_with_flags() {
+        use crate::ScanFlags;
+
+        assert!(ScanFlags::empty().is_empty());
+        assert_eq!((ScanFlags::from_bits(1) | ScanFlags::from_bits(4)).bits(), 5);
+
+        let db: VectoredDatabase = pattern! {"foo"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan_with_flags(vec!["fo", "o"], ScanFlags::empty(), &s, |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        })
+        .unwrap();
+
+        let db: StreamingDatabase = pattern! {"foo"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let st = db.open_stream().unwrap();
+
+        st.scan_with_flags("xfoo", ScanFlags::default(), &s, |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![0..3, 1..4]);
+    }
+
+    #[test]
+    fn test_panic_in_callback() {
+        let db: StreamingDatabase = patterns!["foo", "bar$"].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let st = db.open_stream().unwrap();
+        let mut matches = vec![];
+
+        let err = panic::catch_unwind(AssertUnwindSafe(|| {
+            st.scan("foo foo", &s, |id, _, to, _| {
+                matches.push((id, to));
+                panic!("boom")
+            })
+        }))
+        .unwrap_err();
+
+        assert_eq!(err.downcast_ref::<&str>(), Some(&"boom"));
+        assert_eq!(matches, vec![(0, 3)]);
+
+        st.close(&s, ()).unwrap();
+
+        let st = db.open_stream().unwrap();
+
+        st.scan("bar", &s, ()).unwrap();
+
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| st.close(&s, |_, _, _, _| panic!("eod")))).is_err());
+
+        let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+
+        let s = db.alloc_scratch().unwrap();
+
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| {
+            db.scan("test", &s, |_, _, _, _| -> Matching { panic!("block") })
+        }))
+        .is_err());
+        assert_eq!(db.scan_matches("test", &s).unwrap().len(), 1);
+
+        // the panic caught without being resumed is not resumed by the next scan
+        crate::common::catch_unwind((), || panic!("stale"));
+
+        assert_eq!(db.scan_matches("test", &s).unwrap().len(), 1);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_scan_owned() {
+        use std::sync::{Arc, Mutex};
+
+        let db: Arc<BlockDatabase> = Arc::new(pattern! {"test"; SOM_LEFTMOST}.build().unwrap());
+        let s = db.alloc_scratch().unwrap();
+        let matches = Arc::new(Mutex::new(vec![]));
+
+        let (s, _, res) = tokio_test::block_on(db.clone().scan_owned("foo test bar test", s, {
+            let matches = matches.clone();
+
+            move |_, from, to, _| {
+                matches.lock().unwrap().push(from..to);
+                Matching::Continue
+            }
+        }))
+        .unwrap();
+
+        assert!(res.is_ok());
+        assert_eq!(*matches.lock().unwrap(), vec![4..8, 13..17]);
+
+        // the scratch space and the handler are returned when the scan is terminated
+        let (s, mut on_match_event, res) =
+            tokio_test::block_on(db.clone().scan_owned("test test", s, |_, _, _, _| Matching::Terminate)).unwrap();
+
+        assert_eq!(
+            res.unwrap_err(),
+            crate::Error::Hyperscan(crate::common::Error::ScanTerminated)
+        );
+        assert_eq!(on_match_event(0, 0, 0, 0), Matching::Terminate);
+
+        let err = panic::catch_unwind(AssertUnwindSafe(|| {
+            tokio_test::block_on(
+                db.clone()
+                    .scan_owned("test", s, |_, _, _, _| -> Matching { panic!("owned") }),
+            )
+            .map(|_| ())
+        }))
+        .unwrap_err();
+
+        assert_eq!(err.downcast_ref::<&str>(), Some(&"owned"));
+
+        // the worker thread survives the panic of the handler
+        let s = db.alloc_scratch().unwrap();
+
+        assert!(tokio_test::block_on(db.scan_owned("test", s, |_, _, _, _| Matching::Continue)).is_ok());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_async_scan_tokio_read_error() {
+        use std::io::{self, ErrorKind};
+
+        use crate::Error;
+
+        let db: StreamingDatabase = pattern! {"foo"}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut reader = tokio_test::io::Builder::new()
+            .read(b"xxfoo")
+            .read_error(io::Error::new(ErrorKind::BrokenPipe, "broken"))
+            .build();
+        let mut matches = vec![];
+
+        let err = tokio_test::block_on(db.async_scan_tokio(&mut reader, &s, |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        }))
+        .unwrap_err();
+
+        assert!(matches!(err, Error::Io(ref err) if err.kind() == ErrorKind::BrokenPipe));
+        assert_eq!(matches, vec![0..5]);
+    }
+}
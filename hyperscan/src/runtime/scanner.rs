@@ -0,0 +1,228 @@
+use std::fmt;
+use std::io::{ErrorKind, Read};
+
+use crate::{
+    common::{DatabaseRef, Streaming},
+    runtime::{scan::SCAN_BUF_SIZE, Matching, ScratchRef, StreamRef},
+    Result,
+};
+
+/// A configurable scanner of a reader through a stream.
+///
+/// By default, the reader is scanned in 4KB chunks through a new stream, which is closed at the end of the reader,
+/// and the matches are reported with the offsets relative to the start of the stream.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::io::Cursor;
+/// # use hyperscan::{prelude::*, StreamScanner};
+/// let db: StreamingDatabase = pattern! {"a+"; SOM_LEFTMOST}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut matches = vec![];
+///
+/// let scanned = StreamScanner::new(&db)
+///     .buffer_size(64 * 1024)
+///     .scan(&mut Cursor::new("xxbaab"), &s, |_, from, to, _| {
+///         matches.push(from..to);
+///         Matching::Continue
+///     })
+///     .unwrap();
+///
+/// assert_eq!(scanned, 6);
+/// assert_eq!(matches, vec![3..4, 3..5]);
+/// ```
+#[derive(Clone, Copy)]
+pub struct StreamScanner<'a> {
+    db: &'a DatabaseRef<Streaming>,
+    stream: Option<(&'a StreamRef, u64)>,
+    buffer_size: usize,
+    absolute_offsets: bool,
+}
+
+impl fmt::Debug for StreamScanner<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamScanner")
+            .field("buffer_size", &self.buffer_size)
+            .field("absolute_offsets", &self.absolute_offsets)
+            .finish()
+    }
+}
+
+impl<'a> StreamScanner<'a> {
+    /// Construct a scanner of the streaming database with the default options.
+    pub fn new(db: &'a DatabaseRef<Streaming>) -> Self {
+        StreamScanner {
+            db,
+            stream: None,
+            buffer_size: SCAN_BUF_SIZE,
+            absolute_offsets: true,
+        }
+    }
+
+    /// Set the size of the chunks read from the reader.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is zero.
+    pub fn buffer_size(&mut self, size: usize) -> &mut Self {
+        assert!(size > 0, "buffer size must be non-zero");
+
+        self.buffer_size = size;
+        self
+    }
+
+    /// Scan through an existing open stream, which is left open at the end of the reader,
+    /// so the caller could write more data or close it later.
+    ///
+    /// The `written` is the number of bytes already written to the stream,
+    /// which is used to compute the relative offsets.
+    pub fn stream(&mut self, stream: &'a StreamRef, written: u64) -> &mut Self {
+        self.stream = Some((stream, written));
+        self
+    }
+
+    /// Set whether the offsets are absolute across the reads (the default),
+    /// or relative to the chunk in which the match ends.
+    ///
+    /// When the offsets are relative, the start of a match beginning in a previous chunk is reported as zero.
+    pub fn absolute_offsets(&mut self, yes: bool) -> &mut Self {
+        self.absolute_offsets = yes;
+        self
+    }
+
+    /// Scan the data from the reader until the end of the reader,
+    /// and returns the number of bytes scanned.
+    ///
+    /// The reader is read until it returns zero. An error other than `ErrorKind::Interrupted` is returned
+    /// as `Error::Io`, after the matches of the previous chunks have been reported.
+    pub fn scan<R, F>(&self, reader: &mut R, scratch: &ScratchRef, mut on_match_event: F) -> Result<u64>
+    where
+        R: Read,
+        F: FnMut(u32, u64, u64, u32) -> Matching,
+    {
+        let owned = match self.stream {
+            Some(_) => None,
+            None => Some(self.db.open_stream()?),
+        };
+        let (stream, written) = self.stream.unwrap_or_else(|| (owned.as_deref().unwrap(), 0));
+
+        let absolute = self.absolute_offsets;
+        let mut buf = vec![0; self.buffer_size];
+        let mut scanned = 0;
+        let mut chunk_start = written;
+
+        let mut report = |chunk_start: u64, id, from: u64, to: u64, flags| {
+            if absolute {
+                on_match_event(id, from, to, flags)
+            } else {
+                on_match_event(
+                    id,
+                    from.saturating_sub(chunk_start),
+                    to.saturating_sub(chunk_start),
+                    flags,
+                )
+            }
+        };
+
+        loop {
+            let len = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            chunk_start = written + scanned;
+
+            stream.scan(&buf[..len], scratch, |id, from, to, flags| {
+                report(chunk_start, id, from, to, flags)
+            })?;
+
+            scanned += len as u64;
+        }
+
+        if let Some(stream) = owned {
+            stream.close(scratch, |id, from, to, flags| report(chunk_start, id, from, to, flags))?;
+        }
+
+        Ok(scanned)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::{self, Cursor, ErrorKind, Read};
+
+    use crate::prelude::*;
+    use crate::{Error, StreamScanner};
+
+    struct BrokenReader;
+
+    impl Read for BrokenReader {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(ErrorKind::BrokenPipe, "broken"))
+        }
+    }
+
+    #[test]
+    fn test_stream_scanner() {
+        let db: StreamingDatabase = patterns!["foo", "bar$"; SOM_LEFTMOST].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        let scanned = StreamScanner::new(&db)
+            .buffer_size(5)
+            .absolute_offsets(false)
+            .scan(&mut Cursor::new("xxfoo foo bar"), &s, |id, from, to, _| {
+                matches.push((id, from..to));
+                Matching::Continue
+            })
+            .unwrap();
+
+        assert_eq!(scanned, 13);
+        assert_eq!(matches, vec![(0, 2..5), (0, 1..4), (1, 0..3)]);
+
+        let st = db.open_stream().unwrap();
+        st.scan("foo ", &s, ()).unwrap();
+
+        matches.clear();
+
+        let scanned = StreamScanner::new(&db)
+            .stream(&st, 4)
+            .scan(&mut Cursor::new("foo bar"), &s, |id, from, to, _| {
+                matches.push((id, from..to));
+                Matching::Continue
+            })
+            .unwrap();
+
+        assert_eq!(scanned, 7);
+        assert_eq!(matches, vec![(0, 4..7)]);
+
+        st.close(&s, |id, from, to, _| {
+            matches.push((id, from..to));
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![(0, 4..7), (1, 8..11)]);
+    }
+
+    #[test]
+    fn test_stream_scanner_read_error() {
+        let db: StreamingDatabase = pattern! {"foo"}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut reader = Cursor::new("xxfoo").chain(BrokenReader);
+        let mut matches = vec![];
+
+        let err = StreamScanner::new(&db)
+            .scan(&mut reader, &s, |id, from, to, _| {
+                matches.push((id, from..to));
+                Matching::Continue
+            })
+            .unwrap_err();
+
+        assert!(matches!(err, Error::Io(ref err) if err.kind() == ErrorKind::BrokenPipe));
+        assert_eq!(matches, vec![(0, 0..5)]);
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt;
+use std::ops::Deref;
+use std::thread;
+
+use crate::{
+    common::{DatabaseRef, Streaming},
+    ffi,
+    runtime::{MatchEventHandler, ScratchRef, Stream, StreamRef},
+    Result,
+};
+
+/// A stream bound to a scratch space and a match event handler, which is closed when dropped.
+///
+/// Dropping a `Stream` without closing it frees the stream state, but never reports the matches
+/// of the expressions anchored to the end of the data. `ScopedStream` closes the stream on drop,
+/// reporting those matches to the handler, use `ScopedStream::close` to check the error of closing.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::prelude::*;
+/// let db: StreamingDatabase = pattern! {"test$"}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut matches = vec![];
+///
+/// {
+///     let mut st = db
+///         .open_stream_scoped(&s, |_, _, to, _| {
+///             matches.push(to);
+///             Matching::Continue
+///         })
+///         .unwrap();
+///
+///     st.scan("foo te").unwrap();
+///     st.scan("st").unwrap();
+/// }
+///
+/// assert_eq!(matches, vec![8]);
+/// ```
+#[must_use = "the stream is closed immediately if it is not used"]
+pub struct ScopedStream<'s, F: MatchEventHandler> {
+    stream: Option<Stream>,
+    scratch: &'s ScratchRef,
+    on_match_event: F,
+}
+
+impl<F: MatchEventHandler> fmt::Debug for ScopedStream<'_, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScopedStream").finish()
+    }
+}
+
+impl<F: MatchEventHandler> Deref for ScopedStream<'_, F> {
+    type Target = StreamRef;
+
+    fn deref(&self) -> &Self::Target {
+        self.stream.as_ref().unwrap()
+    }
+}
+
+impl<F: MatchEventHandler> Drop for ScopedStream<'_, F> {
+    fn drop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            // don't call the handler again if it is the one panicking
+            if thread::panicking() {
+                let _ = stream.close(self.scratch, ());
+            } else {
+                let _ = stream.close(self.scratch, unsafe { self.on_match_event.split() });
+            }
+        }
+    }
+}
+
+impl<'s, F: MatchEventHandler> ScopedStream<'s, F> {
+    /// Bind the stream to the scratch space and the match event handler.
+    pub fn new(stream: Stream, scratch: &'s ScratchRef, on_match_event: F) -> Self {
+        ScopedStream {
+            stream: Some(stream),
+            scratch,
+            on_match_event,
+        }
+    }
+
+    /// Write data to be scanned to the stream, reporting the matches to the handler.
+    pub fn scan<T: AsRef<[u8]>>(&mut self, data: T) -> Result<()> {
+        let on_match_event = self.split();
+
+        self.stream.as_ref().unwrap().scan(data, self.scratch, on_match_event)
+    }
+
+    /// Flush the stream at a message boundary, reporting the end of data matches to the handler.
+    pub fn flush(&mut self) -> Result<()> {
+        let on_match_event = self.split();
+
+        self.stream.as_ref().unwrap().flush(self.scratch, on_match_event)
+    }
+
+    /// Close the stream, reporting the end of data matches to the handler.
+    pub fn close(mut self) -> Result<()> {
+        let on_match_event = self.split();
+
+        self.stream.take().unwrap().close(self.scratch, on_match_event)
+    }
+
+    /// Consumes the guard without closing the stream, returning the stream.
+    pub fn into_inner(mut self) -> Stream {
+        self.stream.take().unwrap()
+    }
+
+    fn split(&mut self) -> (ffi::match_event_handler, *mut core::ffi::c_void) {
+        unsafe { self.on_match_event.split() }
+    }
+}
+
+impl DatabaseRef<Streaming> {
+    /// Open a stream bound to the scratch space and the match event handler, which is closed when dropped.
+    pub fn open_stream_scoped<'s, F>(&self, scratch: &'s ScratchRef, on_match_event: F) -> Result<ScopedStream<'s, F>>
+    where
+        F: MatchEventHandler,
+    {
+        self.open_stream()
+            .map(|stream| ScopedStream::new(stream, scratch, on_match_event))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_scoped_stream() {
+        let db: StreamingDatabase = patterns!["test", "bar$"; SOM_LEFTMOST].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        {
+            let mut st = db
+                .open_stream_scoped(&s, |id, from, to, _| {
+                    matches.push((id, from..to));
+                    Matching::Continue
+                })
+                .unwrap();
+
+            st.scan("foo te").unwrap();
+            st.scan("st bar").unwrap();
+            st.flush().unwrap();
+            st.scan("bar").unwrap();
+        }
+
+        assert_eq!(matches, vec![(0, 4..8), (1, 9..12), (1, 0..3)]);
+
+        let mut matches = vec![];
+        let mut st = db
+            .open_stream_scoped(&s, |id, from, to, _| {
+                matches.push((id, from..to));
+                Matching::Continue
+            })
+            .unwrap();
+
+        st.scan("bar").unwrap();
+        st.into_inner().close(&s, ()).unwrap();
+
+        assert!(matches.is_empty());
+    }
+}
@@ -1,12 +1,20 @@
-use std::mem::MaybeUninit;
-use std::ptr::NonNull;
+use alloc::borrow::ToOwned;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
 
 use foreign_types::{foreign_type, ForeignType, ForeignTypeRef};
 
-use crate::{common::DatabaseRef, error::AsResult, ffi, Result};
+use crate::{
+    common::{Database, DatabaseRef, Error as HsError},
+    error::AsResult,
+    ffi, Result,
+};
 
 foreign_type! {
     /// A large enough region of scratch space to support a given database.
+    ///
+    /// The scratch space is `Send` but not `Sync`: it may be moved to another thread,
+    /// but every concurrent caller needs its own scratch space, see `PerThread` and `SyncScratchPool`.
     pub unsafe type Scratch: Send {
         type CType = ffi::hs_scratch_t;
 
@@ -16,6 +24,8 @@ foreign_type! {
 }
 
 unsafe fn free_scratch(s: *mut ffi::hs_scratch_t) {
+    InUse::forget(s);
+
     ffi::hs_free_scratch(s).expect("free scratch");
 }
 
@@ -25,6 +35,16 @@ unsafe fn clone_scratch(s: *mut ffi::hs_scratch_t) -> *mut ffi::hs_scratch_t {
     p.assume_init()
 }
 
+// `foreign-types` only implements `ToOwned` for the references with its `std` feature.
+#[cfg(not(feature = "std"))]
+impl ToOwned for ScratchRef {
+    type Owned = Scratch;
+
+    fn to_owned(&self) -> Scratch {
+        unsafe { Scratch::from_ptr(clone_scratch(self.as_ptr())) }
+    }
+}
+
 impl Scratch {
     /// Allocate a "scratch" space for use by Hyperscan.
     ///
@@ -41,11 +61,84 @@ impl Scratch {
         let mut p = self.as_ptr();
 
         ffi::hs_alloc_scratch(db.as_ptr(), &mut p).map(|_| {
+            if p != self.as_ptr() {
+                InUse::forget(self.as_ptr());
+            }
+
             self.0 = NonNull::new_unchecked(p);
         })
     }
 }
 
+/// A database of any mode, which a scratch space could be allocated for.
+pub trait AnyDatabase {
+    /// Allocate a scratch space for the database.
+    fn new_scratch(&self) -> Result<Scratch>;
+
+    /// Grow the scratch space to be suitable for the database.
+    fn grow_scratch(&self, s: &mut Scratch) -> Result<()>;
+}
+
+impl<T> AnyDatabase for DatabaseRef<T> {
+    fn new_scratch(&self) -> Result<Scratch> {
+        unsafe { Scratch::alloc(self) }
+    }
+
+    fn grow_scratch(&self, s: &mut Scratch) -> Result<()> {
+        unsafe { s.realloc(self) }
+    }
+}
+
+impl<T> AnyDatabase for Database<T> {
+    fn new_scratch(&self) -> Result<Scratch> {
+        (**self).new_scratch()
+    }
+
+    fn grow_scratch(&self, s: &mut Scratch) -> Result<()> {
+        (**self).grow_scratch(s)
+    }
+}
+
+impl Scratch {
+    /// Allocate a scratch space suitable for all the databases, whatever their modes.
+    ///
+    /// Returns `HsError::Invalid` if no database is given.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, Scratch};
+    /// let block: BlockDatabase = pattern! {"test"}.build().unwrap();
+    /// let streaming: StreamingDatabase = pattern! {"foo.*bar"}.build().unwrap();
+    ///
+    /// let s = Scratch::for_databases(&[&block, &streaming]).unwrap();
+    ///
+    /// assert!(block.contains("a test", &s).unwrap());
+    ///
+    /// let st = streaming.open_stream().unwrap();
+    /// st.scan("foo", &s, ()).unwrap();
+    /// st.close(&s, ()).unwrap();
+    /// ```
+    pub fn for_databases(dbs: &[&dyn AnyDatabase]) -> Result<Scratch> {
+        let (first, rest) = dbs.split_first().ok_or(HsError::Invalid)?;
+        let mut s = first.new_scratch()?;
+
+        for db in rest {
+            db.grow_scratch(&mut s)?;
+        }
+
+        Ok(s)
+    }
+
+    /// Grow the scratch space to be suitable for the database, as well as the databases it was allocated for.
+    pub fn grow<D>(&mut self, db: &D) -> Result<&mut Self>
+    where
+        D: AnyDatabase + ?Sized,
+    {
+        db.grow_scratch(self).map(move |_| self)
+    }
+}
+
 impl ScratchRef {
     /// Provides the size of the given scratch space.
     pub fn size(&self) -> Result<usize> {
@@ -53,6 +146,115 @@ impl ScratchRef {
 
         unsafe { ffi::hs_scratch_size(self.as_ptr(), size.as_mut_ptr()).map(|_| size.assume_init()) }
     }
+
+    /// Mark the scratch space in use until the returned guard is dropped.
+    ///
+    /// With the `debug-scratch` feature, returns `Error::ScratchInUse` with the backtrace of the conflicting user
+    /// if the scratch space is already in use, e.g. scanning from the match callback with the same scratch space,
+    /// instead of the `HsError::ScratchInUse` returned by Hyperscan.
+    pub(crate) fn acquire(&self) -> Result<InUse> {
+        InUse::acquire(self.as_ptr())
+    }
+
+    /// Provides the usage statistics of the scratch space.
+    #[cfg(feature = "debug-scratch")]
+    pub fn stats(&self) -> ScratchStats {
+        let usage = InUse::usage(self.as_ptr());
+
+        ScratchStats {
+            acquired: usage.acquired.load(Ordering::Relaxed),
+            conflicts: usage.conflicts.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The usage statistics of a scratch space, with the `debug-scratch` feature.
+#[cfg(feature = "debug-scratch")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScratchStats {
+    /// The number of the scans which acquired the scratch space, including the conflicting ones.
+    pub acquired: u64,
+    /// The number of the scans which found the scratch space already in use.
+    pub conflicts: u64,
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "debug-scratch")] {
+        use std::backtrace::Backtrace;
+        use std::collections::HashMap;
+        use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+        use std::sync::{Arc, OnceLock, RwLock};
+
+        use crate::Error;
+
+        /// The in-use flag and the usage counters of a scratch space.
+        #[derive(Default)]
+        struct Usage {
+            in_use: AtomicBool,
+            acquired: AtomicU64,
+            conflicts: AtomicU64,
+        }
+
+        /// The usages of the scratch spaces, registered on the first use and removed when freed.
+        static USAGES: OnceLock<RwLock<HashMap<usize, Arc<Usage>>>> = OnceLock::new();
+
+        pub(crate) struct InUse(Arc<Usage>);
+
+        impl InUse {
+            fn usage(s: *mut ffi::hs_scratch_t) -> Arc<Usage> {
+                let usages = USAGES.get_or_init(Default::default);
+
+                if let Some(usage) = usages.read().unwrap_or_else(|err| err.into_inner()).get(&(s as usize)) {
+                    return usage.clone();
+                }
+
+                usages
+                    .write()
+                    .unwrap_or_else(|err| err.into_inner())
+                    .entry(s as usize)
+                    .or_default()
+                    .clone()
+            }
+
+            fn acquire(s: *mut ffi::hs_scratch_t) -> Result<InUse> {
+                let usage = Self::usage(s);
+
+                usage.acquired.fetch_add(1, Ordering::Relaxed);
+
+                if usage.in_use.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+                    usage.conflicts.fetch_add(1, Ordering::Relaxed);
+
+                    return Err(Error::ScratchInUse(Backtrace::force_capture().to_string()));
+                }
+
+                Ok(InUse(usage))
+            }
+
+            fn forget(s: *mut ffi::hs_scratch_t) {
+                if let Some(usages) = USAGES.get() {
+                    usages.write().unwrap_or_else(|err| err.into_inner()).remove(&(s as usize));
+                }
+            }
+        }
+
+        impl Drop for InUse {
+            fn drop(&mut self) {
+                self.0.in_use.store(false, Ordering::Release);
+            }
+        }
+    } else {
+        pub(crate) struct InUse;
+
+        impl InUse {
+            #[inline(always)]
+            fn acquire(_s: *mut ffi::hs_scratch_t) -> Result<InUse> {
+                Ok(InUse)
+            }
+
+            #[inline(always)]
+            fn forget(_s: *mut ffi::hs_scratch_t) {}
+        }
+    }
 }
 
 impl<T> DatabaseRef<T> {
@@ -66,6 +268,36 @@ impl<T> DatabaseRef<T> {
         unsafe { s.realloc(self) }.map(|_| s)
     }
 
+    /// Returns `true` if the scratch space is large enough for the database, without reallocating it.
+    ///
+    /// A copy of the scratch space is reallocated for the database, which doesn't grow
+    /// if the scratch space is already suitable, e.g. to validate the wiring of a long-lived service at startup
+    /// instead of failing on the first scan.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let block: BlockDatabase = pattern! {"test"}.build().unwrap();
+    /// let vectored: VectoredDatabase = pattern! {"foobar"}.build().unwrap();
+    /// let mut s = block.alloc_scratch().unwrap();
+    ///
+    /// assert!(block.is_compatible_with_scratch(&s).unwrap());
+    /// assert!(!vectored.is_compatible_with_scratch(&s).unwrap());
+    ///
+    /// vectored.realloc_scratch(&mut s).unwrap();
+    ///
+    /// assert!(vectored.is_compatible_with_scratch(&s).unwrap());
+    /// ```
+    pub fn is_compatible_with_scratch(&self, scratch: &ScratchRef) -> Result<bool> {
+        let size = scratch.size()?;
+        let mut s = scratch.to_owned();
+
+        unsafe { s.realloc(self)? };
+
+        Ok(s.size()? == size)
+    }
+
     /// Allocate a "scratch" space for use by Hyperscan.
     #[deprecated = "use `alloc_scratch` instead"]
     pub fn alloc(&self) -> Result<Scratch> {
@@ -103,4 +335,63 @@ pub mod tests {
 
         assert!(s2.size().unwrap() > s.size().unwrap());
     }
+
+    #[test]
+    fn test_scratch_for_databases() {
+        let block: BlockDatabase = "test".parse().unwrap();
+        let vectored: VectoredDatabase = "foobar".parse().unwrap();
+
+        assert!(Scratch::for_databases(&[]).is_err());
+
+        let mut s = Scratch::for_databases(&[&block, &vectored]).unwrap();
+
+        assert!(s.size().unwrap() >= vectored.alloc_scratch().unwrap().size().unwrap());
+
+        let streaming: StreamingDatabase = "foo.*bar".parse().unwrap();
+        let size = s.grow(&streaming).unwrap().size().unwrap();
+
+        assert!(size >= streaming.alloc_scratch().unwrap().size().unwrap());
+        assert!(block.contains("a test", &s).unwrap());
+    }
+
+    #[test]
+    fn test_is_compatible_with_scratch() {
+        let block: BlockDatabase = "test".parse().unwrap();
+        let vectored: VectoredDatabase = "foobar".parse().unwrap();
+        let s = block.alloc_scratch().unwrap();
+        let size = s.size().unwrap();
+
+        assert!(block.is_compatible_with_scratch(&s).unwrap());
+        assert!(!vectored.is_compatible_with_scratch(&s).unwrap());
+        assert_eq!(s.size().unwrap(), size);
+
+        let s = Scratch::for_databases(&[&block, &vectored]).unwrap();
+
+        assert!(block.is_compatible_with_scratch(&s).unwrap());
+        assert!(vectored.is_compatible_with_scratch(&s).unwrap());
+    }
+
+    #[cfg(feature = "debug-scratch")]
+    #[test]
+    fn test_scratch_in_use() {
+        let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut nested = None;
+
+        db.scan("test", &s, |_, _, _, _| {
+            nested = Some(db.scan("test", &s, ()));
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert!(matches!(nested, Some(Err(crate::Error::ScratchInUse(_)))));
+        assert!(db.contains("test", &s).unwrap());
+        assert_eq!(
+            s.stats(),
+            super::ScratchStats {
+                acquired: 3,
+                conflicts: 1
+            }
+        );
+    }
 }
@@ -0,0 +1,128 @@
+use crate::{common, runtime::Scratch};
+
+/// A pool of scratch spaces which can be shared between threads.
+///
+/// `Scratch` is `Send` but not `Sync`, so it can't be shared by the concurrent callers.
+/// `SyncScratchPool` is `Sync`: every caller checks out an idle scratch space, cloned from the prototype
+/// when all of them are in use, and returns it to the pool when the `PooledScratch` is dropped.
+///
+/// Unlike `PerThread`, the scratch spaces are not bound to the threads, so the pool only grows
+/// to the number of concurrent callers, e.g. the tasks of an async runtime migrating between threads.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use std::thread;
+/// # use hyperscan::{prelude::*, SyncScratchPool};
+/// let db: Arc<BlockDatabase> = Arc::new(pattern! {"test"; SOM_LEFTMOST}.build().unwrap());
+/// let pool = Arc::new(SyncScratchPool::new(db.alloc_scratch().unwrap()));
+///
+/// let handles = (0..4)
+///     .map(|_| {
+///         let db = db.clone();
+///         let pool = pool.clone();
+///
+///         thread::spawn(move || {
+///             let mut matches = vec![];
+///
+///             db.scan("foo test bar", &pool.get(), |_, from, to, _| {
+///                 matches.push(from..to);
+///                 Matching::Continue
+///             })
+///             .unwrap();
+///
+///             matches
+///         })
+///     })
+///     .collect::<Vec<_>>();
+///
+/// for h in handles {
+///     assert_eq!(h.join().unwrap(), vec![4..8]);
+/// }
+///
+/// assert!(pool.idle() <= 4);
+/// ```
+pub type SyncScratchPool = common::ScratchPool<Scratch>;
+
+/// A scratch space checked out from a `SyncScratchPool`, which is returned to the pool when dropped.
+pub type PooledScratch<'a> = common::PooledScratch<'a, Scratch>;
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use static_assertions::{assert_impl_all, assert_not_impl_any};
+
+    use crate::prelude::*;
+    use crate::{PooledScratch, SyncScratchPool};
+
+    assert_impl_all!(SyncScratchPool: Send, Sync);
+    assert_impl_all!(PooledScratch<'static>: Send);
+    assert_not_impl_any!(PooledScratch<'static>: Sync);
+
+    #[test]
+    fn test_sync_scratch_pool() {
+        let db: Arc<BlockDatabase> = Arc::new(pattern! {"test"; SOM_LEFTMOST}.build().unwrap());
+        let pool = Arc::new(SyncScratchPool::new(db.alloc_scratch().unwrap()));
+
+        let handles = (0..4)
+            .map(|_| {
+                let db = db.clone();
+                let pool = pool.clone();
+
+                thread::spawn(move || {
+                    let mut matches = vec![];
+
+                    for _ in 0..16 {
+                        pool.with(|s| {
+                            db.scan("foo test bar", s, |_, from, to, _| {
+                                matches.push(from..to);
+                                Matching::Continue
+                            })
+                        })
+                        .unwrap();
+                    }
+
+                    matches
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for h in handles {
+            assert_eq!(h.join().unwrap(), vec![4..8; 16]);
+        }
+
+        assert!(pool.idle() >= 1 && pool.idle() <= 4);
+
+        pool.shrink();
+
+        assert_eq!(pool.idle(), 0);
+    }
+
+    #[test]
+    fn test_sync_scratch_pool_nested() {
+        let db: BlockDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let pool = SyncScratchPool::new(db.alloc_scratch().unwrap());
+        let mut matches = vec![];
+
+        {
+            let s = pool.get();
+
+            db.scan("test", &s, |_, _, _, _| {
+                db.scan("foo test", &pool.get(), |_, from, to, _| {
+                    matches.push(from..to);
+                    Matching::Continue
+                })
+                .unwrap();
+
+                Matching::Continue
+            })
+            .unwrap();
+        }
+
+        assert_eq!(matches, vec![4..8]);
+        assert_eq!(pool.idle(), 2);
+    }
+}
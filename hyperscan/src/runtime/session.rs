@@ -0,0 +1,404 @@
+use std::collections::{hash_map::Entry, BTreeMap, HashMap};
+use std::fmt;
+use std::hash::Hash;
+use std::mem;
+use std::time::{Duration, Instant};
+
+use crate::{
+    common::{DatabaseRef, Streaming},
+    runtime::{MatchEventHandler, Matching, ScratchRef, Stream, StreamRef},
+    Result,
+};
+
+struct Session {
+    stream: Stream,
+    last_active: Instant,
+    last_used: u64,
+}
+
+type OnEvict<'a, K> = Box<dyn FnMut(&K, u32, u64, u64, u32) -> Matching + 'a>;
+
+/// A map of the open streams keyed by session, e.g. the flows of a network capture.
+///
+/// The streams are opened on the first scan of a session, and closed in bulk
+/// by `retain`, `close_idle` or `close_all`, delivering the EOD matches of every closed stream.
+///
+/// The number of open streams could be bounded with `SessionMap::with_capacity`,
+/// the least recently scanned stream is closed to make room for a new session,
+/// delivering its EOD matches to the `on_evict` handler.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, SessionMap};
+/// let db: StreamingDatabase = pattern! {"test$"}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut sessions = SessionMap::new(&db);
+///
+/// sessions.scan(1, "foo te", &s, ()).unwrap();
+/// sessions.scan(2, "bar te", &s, ()).unwrap();
+/// sessions.scan(1, "st", &s, ()).unwrap();
+///
+/// let mut matches = vec![];
+///
+/// sessions
+///     .close_all(&s, |&key, _, _, to, _| {
+///         matches.push((key, to));
+///         Matching::Continue
+///     })
+///     .unwrap();
+///
+/// assert_eq!(matches, vec![(1, 8)]);
+/// assert!(sessions.is_empty());
+/// ```
+pub struct SessionMap<'a, K> {
+    db: &'a DatabaseRef<Streaming>,
+    sessions: HashMap<K, Session>,
+    /// The sessions ordered by their last scan, the first one is the least recently used.
+    lru: BTreeMap<u64, K>,
+    capacity: Option<usize>,
+    clock: u64,
+    on_evict: Option<OnEvict<'a, K>>,
+}
+
+/// A map of the open streams keyed by user-defined IDs, which is an alias of `SessionMap`.
+pub type StreamMap<'a, K> = SessionMap<'a, K>;
+
+impl<K: fmt::Debug> fmt::Debug for SessionMap<'_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.sessions.keys()).finish()
+    }
+}
+
+impl<'a, K: Eq + Hash> SessionMap<'a, K> {
+    /// Construct an empty map opening the streams against the database.
+    pub fn new(db: &'a DatabaseRef<Streaming>) -> Self {
+        SessionMap {
+            db,
+            sessions: HashMap::new(),
+            lru: BTreeMap::new(),
+            capacity: None,
+            clock: 0,
+            on_evict: None,
+        }
+    }
+
+    /// Construct an empty map keeping at most `capacity` open streams,
+    /// the least recently scanned stream is closed when a new session is scanned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::{prelude::*, StreamMap};
+    /// let db: StreamingDatabase = pattern! {"test$"}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    /// let mut evicted = vec![];
+    ///
+    /// let mut streams = StreamMap::with_capacity(&db, 2).on_evict(|&key, _, _, to, _| {
+    ///     evicted.push((key, to));
+    ///     Matching::Continue
+    /// });
+    ///
+    /// streams.scan(1, "test", &s, ()).unwrap();
+    /// streams.scan(2, "foo", &s, ()).unwrap();
+    /// streams.scan(1, "bar test", &s, ()).unwrap();
+    /// streams.scan(3, "baz", &s, ()).unwrap();
+    ///
+    /// assert_eq!(streams.len(), 2);
+    /// assert!(streams.get(&2).is_none());
+    ///
+    /// streams.scan(4, "qux", &s, ()).unwrap();
+    /// streams.close_all(&s, |_, _, _, _, _| Matching::Continue).unwrap();
+    /// drop(streams);
+    ///
+    /// assert_eq!(evicted, vec![(1, 12)]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity is zero.
+    pub fn with_capacity(db: &'a DatabaseRef<Streaming>, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be non-zero");
+
+        SessionMap {
+            capacity: Some(capacity),
+            sessions: HashMap::with_capacity(capacity),
+            ..Self::new(db)
+        }
+    }
+
+    /// Set the handler of the EOD matches of the streams closed to make room for a new session.
+    pub fn on_evict<F>(mut self, on_evict: F) -> Self
+    where
+        F: FnMut(&K, u32, u64, u64, u32) -> Matching + 'a,
+    {
+        self.on_evict = Some(Box::new(on_evict));
+        self
+    }
+
+    /// The maximum number of open streams, `None` if it's unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// The number of open streams.
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Returns `true` if there is no open stream.
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Returns the open stream of the session.
+    pub fn get(&self, key: &K) -> Option<&StreamRef> {
+        self.sessions.get(key).map(|session| &*session.stream)
+    }
+
+    /// Returns an iterator over the sessions and their open streams, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &StreamRef)> {
+        self.sessions.iter().map(|(key, session)| (key, &*session.stream))
+    }
+
+    /// Write data to be scanned to the stream of the session, opening the stream if it doesn't exist.
+    ///
+    /// If the map is full, the least recently scanned stream is closed before opening a new one,
+    /// the error of closing it is returned without opening the new stream.
+    pub fn scan<T, F>(&mut self, key: K, data: T, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        K: Clone,
+        T: AsRef<[u8]>,
+        F: MatchEventHandler,
+    {
+        if matches!(self.capacity, Some(capacity) if self.sessions.len() >= capacity)
+            && !self.sessions.contains_key(&key)
+        {
+            self.evict(scratch)?;
+        }
+
+        self.clock += 1;
+
+        let session = match self.sessions.entry(key) {
+            Entry::Occupied(entry) => {
+                self.lru.remove(&entry.get().last_used);
+                self.lru.insert(self.clock, entry.key().clone());
+
+                entry.into_mut()
+            }
+            Entry::Vacant(entry) => {
+                let stream = self.db.open_stream()?;
+
+                self.lru.insert(self.clock, entry.key().clone());
+
+                entry.insert(Session {
+                    stream,
+                    last_active: Instant::now(),
+                    last_used: 0,
+                })
+            }
+        };
+
+        session.last_active = Instant::now();
+        session.last_used = self.clock;
+        session.stream.scan(data, scratch, on_match_event)
+    }
+
+    /// Close the least recently scanned stream, reporting any EOD matches to the `on_evict` handler.
+    fn evict(&mut self, scratch: &ScratchRef) -> Result<()> {
+        let key = match self.lru.keys().next().copied() {
+            Some(last_used) => self.lru.remove(&last_used).unwrap(),
+            None => return Ok(()),
+        };
+        let session = self.sessions.remove(&key).expect("evicted session");
+        let on_evict = &mut self.on_evict;
+
+        session.stream.close(scratch, |id, from, to, flags| match on_evict {
+            Some(on_evict) => on_evict(&key, id, from, to, flags),
+            None => Matching::Continue,
+        })
+    }
+
+    /// Close the stream of the session, reporting any EOD matches to the `on_match_event` handler.
+    ///
+    /// Returns `false` if the session has no open stream.
+    pub fn close<F>(&mut self, key: &K, scratch: &ScratchRef, on_match_event: F) -> Result<bool>
+    where
+        F: MatchEventHandler,
+    {
+        match self.sessions.remove(key) {
+            Some(session) => {
+                self.lru.remove(&session.last_used);
+
+                session.stream.close(scratch, on_match_event).map(|_| true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Close the streams of the sessions for which the predicate returns `false`,
+    /// reporting any EOD matches with the session to the `on_match_event` handler.
+    ///
+    /// All the rejected streams are closed even if some of them fail, the first error is returned.
+    pub fn retain<P, F>(&mut self, scratch: &ScratchRef, mut predicate: P, on_match_event: F) -> Result<()>
+    where
+        P: FnMut(&K, &StreamRef) -> bool,
+        F: FnMut(&K, u32, u64, u64, u32) -> Matching,
+    {
+        self.close_where(scratch, |key, session| !predicate(key, &session.stream), on_match_event)
+            .map(|_| ())
+    }
+
+    /// Close the streams of the sessions which have not been scanned for `older_than`,
+    /// reporting any EOD matches with the session to the `on_match_event` handler.
+    ///
+    /// Returns the number of closed streams.
+    pub fn close_idle<F>(&mut self, older_than: Duration, scratch: &ScratchRef, on_match_event: F) -> Result<usize>
+    where
+        F: FnMut(&K, u32, u64, u64, u32) -> Matching,
+    {
+        let now = Instant::now();
+
+        self.close_where(
+            scratch,
+            |_, session| now.duration_since(session.last_active) >= older_than,
+            on_match_event,
+        )
+    }
+
+    /// Close all the streams, reporting any EOD matches with the session to the `on_match_event` handler.
+    pub fn close_all<F>(&mut self, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        F: FnMut(&K, u32, u64, u64, u32) -> Matching,
+    {
+        self.close_where(scratch, |_, _| true, on_match_event).map(|_| ())
+    }
+
+    fn close_where<P, F>(&mut self, scratch: &ScratchRef, mut predicate: P, mut on_match_event: F) -> Result<usize>
+    where
+        P: FnMut(&K, &Session) -> bool,
+        F: FnMut(&K, u32, u64, u64, u32) -> Matching,
+    {
+        let mut closed = 0;
+        let mut res = Ok(());
+
+        for (key, session) in mem::take(&mut self.sessions) {
+            if !predicate(&key, &session) {
+                self.sessions.insert(key, session);
+                continue;
+            }
+
+            self.lru.remove(&session.last_used);
+
+            let r = session
+                .stream
+                .close(scratch, |id, from, to, flags| on_match_event(&key, id, from, to, flags));
+
+            closed += 1;
+            res = res.and(r);
+        }
+
+        res.map(|_| closed)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+
+    use crate::prelude::*;
+    use crate::SessionMap;
+
+    #[test]
+    fn test_session_map() {
+        let db: StreamingDatabase = pattern! {"test$"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut sessions = SessionMap::new(&db);
+
+        for (key, data) in &[(1, "foo te"), (2, "bar te"), (3, "test"), (1, "st")] {
+            sessions.scan(*key, data, &s, ()).unwrap();
+        }
+
+        assert_eq!(sessions.len(), 3);
+        assert!(sessions.get(&2).is_some());
+
+        let mut keys = sessions.iter().map(|(&key, _)| key).collect::<Vec<_>>();
+        keys.sort_unstable();
+        assert_eq!(keys, vec![1, 2, 3]);
+
+        let mut matches = vec![];
+
+        sessions
+            .retain(
+                &s,
+                |&key, _| key != 1,
+                |&key, _, from, to, _| {
+                    matches.push((key, from..to));
+                    Matching::Continue
+                },
+            )
+            .unwrap();
+
+        assert_eq!(matches, vec![(1, 4..8)]);
+        assert!(sessions.get(&1).is_none());
+
+        assert_eq!(
+            sessions
+                .close_idle(Duration::from_secs(3600), &s, |_, _, _, _, _| Matching::Continue)
+                .unwrap(),
+            0
+        );
+        assert!(sessions.close(&2, &s, ()).unwrap());
+        assert!(!sessions.close(&2, &s, ()).unwrap());
+
+        matches.clear();
+
+        assert_eq!(
+            sessions
+                .close_idle(Duration::from_secs(0), &s, |&key, _, from, to, _| {
+                    matches.push((key, from..to));
+                    Matching::Continue
+                })
+                .unwrap(),
+            1
+        );
+        assert_eq!(matches, vec![(3, 0..4)]);
+        assert!(sessions.is_empty());
+    }
+
+    #[test]
+    fn test_session_map_evict() {
+        let db: StreamingDatabase = pattern! {"test$"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut evicted = vec![];
+
+        {
+            let mut sessions = SessionMap::with_capacity(&db, 3).on_evict(|&key, _, _, _, _| {
+                evicted.push(key);
+                Matching::Continue
+            });
+
+            for (key, data) in &[
+                (1, "te"),
+                (2, "te"),
+                (3, "te"),
+                (1, "st"),
+                (2, "st"),
+                (4, "te"),
+                (5, "te"),
+            ] {
+                sessions.scan(*key, data, &s, ()).unwrap();
+
+                assert!(sessions.len() <= 3);
+            }
+
+            let mut keys = sessions.iter().map(|(&key, _)| key).collect::<Vec<_>>();
+            keys.sort_unstable();
+            assert_eq!(keys, vec![2, 4, 5]);
+
+            sessions.close_all(&s, |_, _, _, _, _| Matching::Continue).unwrap();
+        }
+
+        assert_eq!(evicted, vec![3, 1]);
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+use std::io::{self, ErrorKind, Write};
+
+#[cfg(feature = "async")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "async")]
+use futures::io::AsyncWrite;
+
+use crate::{
+    common::{DatabaseRef, Streaming},
+    runtime::{Matching, ScratchRef, Stream},
+    Result,
+};
+
+/// A sink feeding the written bytes into an open stream, and invoking the match callback.
+///
+/// It implements `std::io::Write` (and `futures::io::AsyncWrite` with the `async` feature),
+/// so the data could be scanned with `io::copy` or an async pipeline without a manual chunking loop.
+///
+/// The stream is closed when the sink is closed by `close` or `AsyncWrite::poll_close`,
+/// the writes after the stream is closed fail with `ErrorKind::BrokenPipe`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::io;
+/// # use hyperscan::prelude::*;
+/// let db: StreamingDatabase = pattern! {"test$"; SOM_LEFTMOST}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut matches = vec![];
+///
+/// let mut sink = db
+///     .sink(&s, |_, from, to, _| {
+///         matches.push(from..to);
+///         Matching::Continue
+///     })
+///     .unwrap();
+///
+/// io::copy(&mut &b"foo test"[..], &mut sink).unwrap();
+/// sink.close().unwrap();
+///
+/// assert_eq!(matches, vec![4..8]);
+/// ```
+pub struct StreamSink<'a, F> {
+    stream: Option<Stream>,
+    scratch: &'a ScratchRef,
+    on_match_event: F,
+}
+
+impl<F> fmt::Debug for StreamSink<'_, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamSink")
+            .field("closed", &self.stream.is_none())
+            .finish()
+    }
+}
+
+impl DatabaseRef<Streaming> {
+    /// Open a stream, and returns a sink feeding the written bytes into it.
+    pub fn sink<'a, F>(&self, scratch: &'a ScratchRef, on_match_event: F) -> Result<StreamSink<'a, F>>
+    where
+        F: FnMut(u32, u64, u64, u32) -> Matching,
+    {
+        self.open_stream()
+            .map(|stream| StreamSink::new(stream, scratch, on_match_event))
+    }
+}
+
+impl<'a, F> StreamSink<'a, F>
+where
+    F: FnMut(u32, u64, u64, u32) -> Matching,
+{
+    /// Construct a sink feeding the written bytes into the open stream.
+    pub fn new(stream: Stream, scratch: &'a ScratchRef, on_match_event: F) -> Self {
+        StreamSink {
+            stream: Some(stream),
+            scratch,
+            on_match_event,
+        }
+    }
+
+    /// Close the stream if it is still open, reporting any EOD matches.
+    pub fn close(mut self) -> Result<()> {
+        self.close_stream()
+    }
+
+    fn scan(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let stream = self
+            .stream
+            .as_ref()
+            .ok_or_else(|| io::Error::new(ErrorKind::BrokenPipe, "stream closed"))?;
+
+        stream
+            .scan(buf, self.scratch, &mut self.on_match_event)
+            .map(|_| buf.len())
+            .map_err(io::Error::other)
+    }
+
+    fn close_stream(&mut self) -> Result<()> {
+        match self.stream.take() {
+            Some(stream) => stream.close(self.scratch, &mut self.on_match_event),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<F> Write for StreamSink<'_, F>
+where
+    F: FnMut(u32, u64, u64, u32) -> Matching,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.scan(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<F> AsyncWrite for StreamSink<'_, F>
+where
+    F: FnMut(u32, u64, u64, u32) -> Matching + Unpin,
+{
+    fn poll_write(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.get_mut().scan(buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.get_mut().close_stream().map_err(io::Error::other))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::{ErrorKind, Write};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_stream_sink() {
+        let db: StreamingDatabase = patterns!["foo", "bar$"; SOM_LEFTMOST].build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        let mut sink = db
+            .sink(&s, |id, from, to, _| {
+                matches.push((id, from..to));
+                Matching::Continue
+            })
+            .unwrap();
+
+        sink.write_all(b"fo").unwrap();
+        sink.write_all(b"o ba").unwrap();
+        sink.write_all(b"r").unwrap();
+        sink.flush().unwrap();
+        sink.close().unwrap();
+
+        assert_eq!(matches, vec![(0, 0..3), (1, 4..7)]);
+
+        let mut sink = db.sink(&s, |_, _, _, _| Matching::Terminate).unwrap();
+
+        assert_eq!(sink.write(b"foo").unwrap_err().kind(), ErrorKind::Other);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_async_stream_sink() {
+        use futures::io::{AsyncWriteExt, Cursor};
+
+        let db: StreamingDatabase = pattern! {"test$"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        tokio_test::block_on(async {
+            let mut sink = db
+                .sink(&s, |_, from, to, _| {
+                    matches.push(from..to);
+                    Matching::Continue
+                })
+                .unwrap();
+
+            futures::io::copy(Cursor::new(b"foo test"), &mut sink).await.unwrap();
+            AsyncWriteExt::close(&mut sink).await.unwrap();
+
+            assert_eq!(
+                AsyncWriteExt::write(&mut sink, b"more").await.unwrap_err().kind(),
+                ErrorKind::BrokenPipe
+            );
+        });
+
+        assert_eq!(matches, vec![4..8]);
+    }
+}
@@ -1,13 +1,16 @@
-use std::mem::MaybeUninit;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+use core::ptr;
 
 use foreign_types::{foreign_type, ForeignType, ForeignTypeRef};
 
 use crate::{
-    common::{DatabaseRef, Streaming},
+    common::{resume_unwind, streams, DatabaseRef, DbMode, Error as HsError, Streaming},
     error::AsResult,
     ffi,
-    runtime::{MatchEventHandler, ScratchRef},
-    Result,
+    runtime::{metered, MatchEventHandler, ScanFlags, ScratchRef},
+    Error, Result,
 };
 
 impl DatabaseRef<Streaming> {
@@ -19,15 +22,32 @@ impl DatabaseRef<Streaming> {
     }
 
     /// Open and initialise a stream.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub fn open_stream(&self) -> Result<Stream> {
         let mut s = MaybeUninit::uninit();
 
-        unsafe { ffi::hs_open_stream(self.as_ptr(), 0, s.as_mut_ptr()).map(|_| Stream::from_ptr(s.assume_init())) }
+        unsafe {
+            ffi::hs_open_stream(self.as_ptr(), 0, s.as_mut_ptr()).map(|_| {
+                let s = s.assume_init();
+
+                streams::track(self.as_ptr(), s);
+
+                Stream::from_ptr(s)
+            })
+        }
     }
 }
 
 foreign_type! {
     /// A pattern matching state can be maintained across multiple blocks of target data
+    ///
+    /// The stream is `Send` but not `Sync`: it may be moved to another thread,
+    /// but must only be written by one caller at a time.
+    ///
+    /// The stream must be closed with `Stream::close` to report the matches at the end of the data,
+    /// or use `ScopedStream` which closes the stream when dropped.
+    /// Dropping the stream frees its state without reporting those matches.
+    #[must_use = "the stream should be closed to report the end of data matches"]
     pub unsafe type Stream: Send {
         type CType = ffi::hs_stream_t;
 
@@ -36,7 +56,12 @@ foreign_type! {
     }
 }
 
-fn drop_stream(_s: *mut ffi::hs_stream_t) {}
+/// Free the stream state without a scratch space, which never reports the matches at the end of the data.
+unsafe fn drop_stream(s: *mut ffi::hs_stream_t) {
+    ffi::hs_close_stream(s, ptr::null_mut(), None, ptr::null_mut());
+
+    streams::untrack(s);
+}
 
 /// Duplicate the given stream.
 ///
@@ -46,7 +71,21 @@ unsafe fn clone_stream(s: *mut ffi::hs_stream_t) -> *mut ffi::hs_stream_t {
 
     ffi::hs_copy_stream(p.as_mut_ptr(), s).expect("copy stream");
 
-    p.assume_init()
+    let p = p.assume_init();
+
+    streams::track_copy(s, p);
+
+    p
+}
+
+// `foreign-types` only implements `ToOwned` for the references with its `std` feature.
+#[cfg(not(feature = "std"))]
+impl alloc::borrow::ToOwned for StreamRef {
+    type Owned = Stream;
+
+    fn to_owned(&self) -> Stream {
+        unsafe { Stream::from_ptr(clone_stream(self.as_ptr())) }
+    }
 }
 
 impl StreamRef {
@@ -93,17 +132,90 @@ impl StreamRef {
     ///
     /// assert_eq!(matches, vec![(4, 8), (4, 8)]);
     /// ```
-    pub fn reset<F>(&self, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
+    pub fn reset<F>(&self, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        F: MatchEventHandler,
+    {
+        self.reset_with_flags(ScanFlags::empty(), scratch, on_match_event)
+    }
+
+    /// Reset a stream to an initial state with the flags.
+    ///
+    /// The flags are reserved for future use by Hyperscan, and passed through as is.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "reset_stream", level = "debug", skip_all)
+    )]
+    pub fn reset_with_flags<F>(&self, flags: ScanFlags, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
     where
         F: MatchEventHandler,
     {
+        let _in_use = scratch.acquire()?;
+
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_reset_stream(self.as_ptr(), 0, scratch.as_ptr(), callback, userdata).ok()
+            resume_unwind(metered(
+                DbMode::Streaming,
+                0,
+                callback,
+                userdata,
+                |callback, userdata| {
+                    ffi::hs_reset_stream(self.as_ptr(), flags.bits(), scratch.as_ptr(), callback, userdata)
+                },
+            ))
+            .ok()
         }
     }
 
+    /// Flush the stream at a message boundary, reporting the matches of the expressions
+    /// anchored to the end of the data (e.g. via the `$` meta-character), and reset it to an initial state.
+    ///
+    /// The stream remains usable, so the messages of a connection could be scanned one by one
+    /// without closing and reopening the stream. The match offsets restart from zero after the flush.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: StreamingDatabase = pattern! {"end$"}.build().unwrap();
+    ///
+    /// let s = db.alloc_scratch().unwrap();
+    /// let st = db.open_stream().unwrap();
+    ///
+    /// let mut matches = vec![];
+    ///
+    /// let mut callback = |_, _, to, _| {
+    ///     matches.push(to);
+    ///
+    ///     Matching::Continue
+    /// };
+    ///
+    /// st.scan("the e", &s, &mut callback).unwrap();
+    /// st.scan("nd", &s, &mut callback).unwrap();
+    /// st.flush(&s, &mut callback).unwrap();
+    ///
+    /// st.scan("no end here", &s, &mut callback).unwrap();
+    /// st.discard().unwrap();
+    ///
+    /// st.scan("end", &s, &mut callback).unwrap();
+    /// st.close(&s, callback).unwrap();
+    ///
+    /// assert_eq!(matches, vec![7, 3]);
+    /// ```
+    pub fn flush<F>(&self, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        F: MatchEventHandler,
+    {
+        self.reset(scratch, on_match_event)
+    }
+
+    /// Discard the data written to the stream and reset it to an initial state,
+    /// without reporting the matches of the expressions anchored to the end of the data.
+    pub fn discard(&self) -> Result<()> {
+        unsafe { ffi::hs_reset_stream(self.as_ptr(), 0, ptr::null_mut(), None, ptr::null_mut()).ok() }
+    }
+
     /// Duplicate the given `from` stream state onto the stream.
     ///
     /// The stream will first be reset (reporting any EOD matches if a `on_match_event` callback handler is provided).
@@ -145,10 +257,21 @@ impl StreamRef {
     where
         F: MatchEventHandler,
     {
+        let _in_use = scratch.acquire()?;
+
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_reset_and_copy_stream(self.as_ptr(), from.as_ptr(), scratch.as_ptr(), callback, userdata).ok()
+            resume_unwind(metered(
+                DbMode::Streaming,
+                0,
+                callback,
+                userdata,
+                |callback, userdata| {
+                    ffi::hs_reset_and_copy_stream(self.as_ptr(), from.as_ptr(), scratch.as_ptr(), callback, userdata)
+                },
+            ))
+            .ok()
         }
     }
 }
@@ -162,14 +285,28 @@ impl Stream {
     ///
     /// This function must be called for any stream created with `StreamingDatabase::open_stream`,
     /// even if scanning has been terminated by a non-zero return from the match callback function.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "close_stream", level = "debug", skip_all)
+    )]
     pub fn close<F>(self, scratch: &ScratchRef, mut on_match_event: F) -> Result<()>
     where
         F: MatchEventHandler,
     {
+        let _in_use = scratch.acquire()?;
+        let s = self.into_ptr();
+
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_close_stream(self.as_ptr(), scratch.as_ptr(), callback, userdata).ok()
+            let res = metered(DbMode::Streaming, 0, callback, userdata, |callback, userdata| {
+                ffi::hs_close_stream(s, scratch.as_ptr(), callback, userdata)
+            });
+
+            // the stream state has been freed, even if the match handler panicked
+            streams::untrack(s);
+
+            resume_unwind(res).ok()
         }
     }
 }
@@ -220,6 +357,56 @@ impl StreamRef {
         }
     }
 
+    /// Provides the size of the buffer required by the compressed representation of the stream.
+    pub fn compressed_size(&self) -> Result<usize> {
+        let mut size = 0;
+
+        match unsafe { ffi::hs_compress_stream(self.as_ptr(), ptr::null_mut(), 0, &mut size) }.ok() {
+            Ok(_) | Err(Error::Hyperscan(HsError::InsufficientSpace)) => Ok(size),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Creates a compressed representation of the provided stream in a buffer of the required size.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    ///
+    /// let s = db.alloc_scratch().unwrap();
+    /// let st = db.open_stream().unwrap();
+    ///
+    /// st.scan("foo te", &s, ()).unwrap();
+    ///
+    /// let buf = st.compress_to_vec().unwrap();
+    ///
+    /// assert_eq!(buf.len(), st.compressed_size().unwrap());
+    ///
+    /// st.close(&s, ()).unwrap();
+    ///
+    /// let st2 = db.expand_stream(&buf).unwrap();
+    /// let mut matches = vec![];
+    ///
+    /// st2.scan("st", &s, |_, from, to, _| {
+    ///     matches.push((from, to));
+    ///     Matching::Continue
+    /// })
+    /// .unwrap();
+    /// st2.close(&s, ()).unwrap();
+    ///
+    /// assert_eq!(matches, vec![(4, 8)]);
+    /// ```
+    pub fn compress_to_vec(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![0; self.compressed_size()?];
+        let len = self.compress(&mut buf)?;
+
+        buf.truncate(len);
+
+        Ok(buf)
+    }
+
     /// Decompresses a compressed representation created by `StreamRef::compress` on top of the stream.
     /// The stream will first be reset (reporting any EOD matches).
     ///
@@ -263,17 +450,27 @@ impl StreamRef {
     where
         F: MatchEventHandler,
     {
+        let _in_use = scratch.acquire()?;
+
         unsafe {
             let (callback, userdata) = on_match_event.split();
 
-            ffi::hs_reset_and_expand_stream(
-                self.as_ptr(),
-                buf.as_ptr() as *const _,
-                buf.len(),
-                scratch.as_ptr(),
+            resume_unwind(metered(
+                DbMode::Streaming,
+                0,
                 callback,
                 userdata,
-            )
+                |callback, userdata| {
+                    ffi::hs_reset_and_expand_stream(
+                        self.as_ptr(),
+                        buf.as_ptr() as *const _,
+                        buf.len(),
+                        scratch.as_ptr(),
+                        callback,
+                        userdata,
+                    )
+                },
+            ))
             .ok()
         }
     }
@@ -322,7 +519,63 @@ impl DatabaseRef<Streaming> {
         unsafe {
             ffi::hs_expand_stream(self.as_ptr(), stream.as_mut_ptr(), buf.as_ptr() as *const _, buf.len())
                 .ok()
-                .map(|_| Stream::from_ptr(stream.assume_init()))
+                .map(|_| {
+                    let stream = stream.assume_init();
+
+                    streams::track(self.as_ptr(), stream);
+
+                    Stream::from_ptr(stream)
+                })
+        }
+    }
+
+    /// Decompresses a compressed representation created by `StreamRef::compress()` on top of an existing stream,
+    /// e.g. a stream taken from a pool, instead of allocating a new stream.
+    ///
+    /// The stream is reset without reporting any EOD matches.
+    ///
+    /// Note: the stream must be opened against the database, and `buf` must correspond to a complete compressed
+    /// representation created by `StreamRef::compress()` of a stream that was opened against the database.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: StreamingDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+    ///
+    /// let s = db.alloc_scratch().unwrap();
+    /// let st = db.open_stream().unwrap();
+    ///
+    /// st.scan("foo te", &s, ()).unwrap();
+    ///
+    /// let buf = st.compress_to_vec().unwrap();
+    /// let mut pooled = db.open_stream().unwrap();
+    ///
+    /// db.expand_stream_into(&buf, &mut pooled).unwrap();
+    ///
+    /// let mut matches = vec![];
+    ///
+    /// pooled.scan("st", &s, |_, from, to, _| {
+    ///     matches.push((from, to));
+    ///     Matching::Continue
+    /// })
+    /// .unwrap();
+    ///
+    /// assert_eq!(matches, vec![(4, 8)]);
+    /// # st.close(&s, ()).unwrap();
+    /// # pooled.close(&s, ()).unwrap();
+    /// ```
+    pub fn expand_stream_into(&self, buf: &[u8], stream: &mut StreamRef) -> Result<()> {
+        unsafe {
+            ffi::hs_reset_and_expand_stream(
+                stream.as_ptr(),
+                buf.as_ptr() as *const _,
+                buf.len(),
+                ptr::null_mut(),
+                None,
+                ptr::null_mut(),
+            )
+            .ok()
         }
     }
 }
@@ -0,0 +1,418 @@
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::Deref;
+use core::ptr;
+
+use core::ffi::{c_char, c_uint};
+use foreign_types::ForeignTypeRef;
+
+use crate::{
+    common::{resume_unwind, DatabaseRef, DbMode, Vectored},
+    error::AsResult,
+    ffi,
+    runtime::{metered, MatchEventHandler, Matching, ScratchRef},
+    Result,
+};
+
+/// A set of data blocks prepared for the vectored scanner.
+///
+/// The pointer and length arrays passed to Hyperscan are kept between scans,
+/// so a packet pipeline can `clear` (or `recycle`) and refill the same `VectoredData`
+/// without allocating them again for every burst.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, VectoredData};
+/// let db: VectoredDatabase = pattern!{"test"; SOM_LEFTMOST}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+///
+/// let mut data = VectoredData::with_capacity(3);
+/// data.push(b"foo");
+/// data.push(b"test");
+/// data.push(b"bar");
+///
+/// let mut matches = vec![];
+///
+/// db.scan_prepared(&data, &s, |_, from, to, _| {
+///     matches.push(from..to);
+///     Matching::Continue
+/// }).unwrap();
+///
+/// assert_eq!(matches, vec![3..7]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct VectoredData<'a> {
+    ptrs: Vec<*const c_char>,
+    lens: Vec<c_uint>,
+    phantom: PhantomData<&'a [u8]>,
+}
+
+unsafe impl Send for VectoredData<'_> {}
+unsafe impl Sync for VectoredData<'_> {}
+
+impl<'a> VectoredData<'a> {
+    /// Constructs a new, empty `VectoredData`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs a new, empty `VectoredData` with the specified capacity of data blocks.
+    pub fn with_capacity(capacity: usize) -> Self {
+        VectoredData {
+            ptrs: Vec::with_capacity(capacity),
+            lens: Vec::with_capacity(capacity),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Appends a data block to be scanned.
+    pub fn push(&mut self, data: &'a [u8]) {
+        self.ptrs.push(data.as_ptr() as *const c_char);
+        self.lens.push(data.len() as c_uint);
+    }
+
+    /// Removes all data blocks, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.ptrs.clear();
+        self.lens.clear();
+    }
+
+    /// Removes all data blocks and reuses the allocated capacity for data with another lifetime.
+    pub fn recycle<'b>(mut self) -> VectoredData<'b> {
+        self.clear();
+
+        VectoredData {
+            ptrs: mem::take(&mut self.ptrs),
+            lens: mem::take(&mut self.lens),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the number of data blocks.
+    pub fn len(&self) -> usize {
+        self.ptrs.len()
+    }
+
+    /// Returns `true` if there is no data block.
+    pub fn is_empty(&self) -> bool {
+        self.ptrs.is_empty()
+    }
+
+    /// Returns the total length of all data blocks in bytes.
+    pub fn total_len(&self) -> usize {
+        self.lens.iter().map(|&len| len as usize).sum()
+    }
+}
+
+impl<'a> Extend<&'a [u8]> for VectoredData<'a> {
+    fn extend<I: IntoIterator<Item = &'a [u8]>>(&mut self, iter: I) {
+        for data in iter {
+            self.push(data)
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a [u8]> for VectoredData<'a> {
+    fn from_iter<I: IntoIterator<Item = &'a [u8]>>(iter: I) -> Self {
+        let mut data = VectoredData::new();
+        data.extend(iter);
+        data
+    }
+}
+
+impl DatabaseRef<Vectored> {
+    /// The vectored regular expression scanner with the prepared data blocks.
+    ///
+    /// Unlike `DatabaseRef::scan`, no temporary pointer or length arrays are built for the scan.
+    pub fn scan_prepared<F>(&self, data: &VectoredData<'_>, scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        F: MatchEventHandler,
+    {
+        self.scan_vector(&data.ptrs, &data.lens, scratch, on_match_event)
+    }
+}
+
+/// The maximum number of data blocks whose pointer and length arrays are built on the stack by `scan_slices`.
+const STACK_BLOCKS: usize = 16;
+
+impl DatabaseRef<Vectored> {
+    /// The vectored regular expression scanner over a slice of data blocks, e.g. `&[IoSlice]` or `&[&[u8]]`.
+    ///
+    /// The pointer and length arrays are built on the stack for up to 16 data blocks,
+    /// so a small burst of packets is scanned without any heap allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use std::io::IoSlice;
+    /// # use hyperscan::prelude::*;
+    /// let db: VectoredDatabase = pattern!{"test"; SOM_LEFTMOST}.build().unwrap();
+    /// let s = db.alloc_scratch().unwrap();
+    ///
+    /// let mut matches = vec![];
+    ///
+    /// db.scan_slices(&[IoSlice::new(b"foo te"), IoSlice::new(b"st bar")], &s, |_, from, to, _| {
+    ///     matches.push(from..to);
+    ///     Matching::Continue
+    /// }).unwrap();
+    ///
+    /// assert_eq!(matches, vec![4..8]);
+    /// ```
+    pub fn scan_slices<T, F>(&self, data: &[T], scratch: &ScratchRef, on_match_event: F) -> Result<()>
+    where
+        T: Deref<Target = [u8]>,
+        F: MatchEventHandler,
+    {
+        if data.len() <= STACK_BLOCKS {
+            let mut ptrs = [ptr::null(); STACK_BLOCKS];
+            let mut lens = [0; STACK_BLOCKS];
+
+            for (i, buf) in data.iter().enumerate() {
+                ptrs[i] = buf.as_ptr() as *const c_char;
+                lens[i] = buf.len() as c_uint;
+            }
+
+            self.scan_vector(&ptrs[..data.len()], &lens[..data.len()], scratch, on_match_event)
+        } else {
+            let (ptrs, lens): (Vec<_>, Vec<_>) = data
+                .iter()
+                .map(|buf| (buf.as_ptr() as *const c_char, buf.len() as c_uint))
+                .unzip();
+
+            self.scan_vector(&ptrs, &lens, scratch, on_match_event)
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "scan",
+            level = "trace",
+            skip_all,
+            fields(
+                mode = "Vectored",
+                buffers = ptrs.len(),
+                bytes = lens.iter().map(|&len| len as usize).sum::<usize>()
+            )
+        )
+    )]
+    fn scan_vector<F>(
+        &self,
+        ptrs: &[*const c_char],
+        lens: &[c_uint],
+        scratch: &ScratchRef,
+        mut on_match_event: F,
+    ) -> Result<()>
+    where
+        F: MatchEventHandler,
+    {
+        let _in_use = scratch.acquire()?;
+
+        unsafe {
+            let (callback, userdata) = on_match_event.split();
+
+            resume_unwind(metered(
+                DbMode::Vectored,
+                lens.iter().map(|&len| len as usize).sum(),
+                callback,
+                userdata,
+                |callback, userdata| {
+                    ffi::hs_scan_vector(
+                        self.as_ptr(),
+                        ptrs.as_ptr(),
+                        lens.as_ptr(),
+                        ptrs.len() as u32,
+                        0,
+                        scratch.as_ptr(),
+                        callback,
+                        userdata,
+                    )
+                },
+            ))
+            .ok()
+        }
+    }
+}
+
+/// A vectored scanner over consecutive, possibly overlapping windows of a reassembled stream.
+///
+/// When the reassembled segments are re-scanned with the tail of the previous window,
+/// a match in the overlap would be reported again, e.g. a match ending exactly at the buffer boundary.
+/// `OverlapScanner` tracks the matches of the previous call and suppresses the duplicated
+/// pattern and end offset pairs.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, OverlapScanner, VectoredData};
+/// let db: VectoredDatabase = pattern!{"test"; SOM_LEFTMOST}.build().unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut scanner = OverlapScanner::new();
+///
+/// let mut matches = vec![];
+/// let mut callback = |_, from, to, _| {
+///     matches.push(from..to);
+///     Matching::Continue
+/// };
+///
+/// // the window [0, 8)
+/// let data = [&b"foo "[..], &b"test"[..]].iter().copied().collect::<VectoredData>();
+/// scanner.scan(&db, &data, 0, &s, &mut callback).unwrap();
+///
+/// // the window [4, 12) overlaps the tail of the previous one
+/// let data = [&b"test"[..], &b" bar"[..]].iter().copied().collect::<VectoredData>();
+/// scanner.scan(&db, &data, 4, &s, &mut callback).unwrap();
+///
+/// assert_eq!(matches, vec![4..8]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct OverlapScanner {
+    previous: Vec<(u32, u64)>,
+    current: Vec<(u32, u64)>,
+}
+
+impl OverlapScanner {
+    /// Constructs a new scanner without any previous window.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scan the window starting at the stream `offset`, suppressing the matches reported by the previous call.
+    ///
+    /// The offsets reported to the `on_match_event` handler are relative to the start of the stream.
+    pub fn scan<F>(
+        &mut self,
+        db: &DatabaseRef<Vectored>,
+        data: &VectoredData<'_>,
+        offset: u64,
+        scratch: &ScratchRef,
+        mut on_match_event: F,
+    ) -> Result<()>
+    where
+        F: FnMut(u32, u64, u64, u32) -> Matching,
+    {
+        let previous = &self.previous;
+        let current = &mut self.current;
+
+        current.clear();
+
+        let res = db.scan_prepared(data, scratch, |id, from, to, flags| {
+            let (from, to) = (offset + from, offset + to);
+
+            current.push((id, to));
+
+            if previous.contains(&(id, to)) {
+                Matching::Continue
+            } else {
+                on_match_event(id, from, to, flags)
+            }
+        });
+
+        mem::swap(&mut self.previous, &mut self.current);
+
+        res
+    }
+
+    /// Forget the matches of the previous call, e.g. after a gap in the stream.
+    pub fn reset(&mut self) {
+        self.previous.clear();
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::{OverlapScanner, VectoredData};
+
+    #[test]
+    fn test_vectored_data() {
+        let db: VectoredDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        let mut data = ["foo", "te", "st"]
+            .iter()
+            .map(|s| s.as_bytes())
+            .collect::<VectoredData<'_>>();
+
+        assert_eq!(data.len(), 3);
+        assert_eq!(data.total_len(), 7);
+
+        let mut matches = vec![];
+
+        db.scan_prepared(&data, &s, |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![3..7]);
+
+        data.clear();
+        assert!(data.is_empty());
+
+        let packet = b"test bar".to_vec();
+        let mut data = data.recycle();
+        data.push(&packet);
+
+        db.scan_prepared(&data, &s, |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![3..7, 0..4]);
+    }
+
+    #[test]
+    fn test_scan_slices() {
+        let db: VectoredDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+
+        let mut matches = vec![];
+        let mut callback = |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        };
+
+        db.scan_slices(&[&b"te"[..], &b"st"[..]], &s, &mut callback).unwrap();
+
+        let blocks = vec![&b"t"[..]; 20];
+        let mut data = blocks.clone();
+        data.extend([&b"es"[..], &b"t"[..]]);
+
+        db.scan_slices(&data, &s, &mut callback).unwrap();
+        db.scan_slices(&blocks[..4], &s, &mut callback).unwrap();
+
+        assert_eq!(matches, vec![0..4, 19..23]);
+    }
+
+    #[test]
+    fn test_overlap_scanner() {
+        let db: VectoredDatabase = pattern! {"test"; SOM_LEFTMOST}.build().unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let mut scanner = OverlapScanner::new();
+
+        let mut matches = vec![];
+        let mut callback = |_, from, to, _| {
+            matches.push(from..to);
+            Matching::Continue
+        };
+
+        for (offset, window) in &[(0, ["foo ", "test"]), (4, ["test", " tes"]), (8, [" tes", "t ba"])] {
+            let data = window.iter().map(|s| s.as_bytes()).collect::<VectoredData<'_>>();
+
+            scanner.scan(&db, &data, *offset, &s, &mut callback).unwrap();
+        }
+
+        scanner.reset();
+
+        let data = ["test"].iter().map(|s| s.as_bytes()).collect::<VectoredData<'_>>();
+
+        scanner.scan(&db, &data, 9, &s, &mut callback).unwrap();
+
+        assert_eq!(matches, vec![4..8, 9..13, 9..13]);
+    }
+}
@@ -0,0 +1,92 @@
+use core::iter;
+
+use crate::{
+    common::{check_platform, Block, DatabaseRef, Streaming, Vectored},
+    runtime::{Scratch, ScratchRef},
+    Result,
+};
+
+/// A throwaway buffer with every byte value, touching the character reach tables of the database.
+fn warm_up_data() -> [u8; 256] {
+    let mut data = [0; 256];
+
+    for (i, b) in data.iter_mut().enumerate() {
+        *b = i as u8;
+    }
+
+    data
+}
+
+impl<T> DatabaseRef<T> {
+    /// Check the database is ready to scan on the current host.
+    ///
+    /// The platform is validated with `check_platform`, which reports the missing instruction set
+    /// as `Error::Platform`, and a scratch space is allocated for the database,
+    /// which is returned to be reused by the scans or `warm_up`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use hyperscan::prelude::*;
+    /// let db: StreamingDatabase = pattern! {"test"}.build().unwrap();
+    /// let s = db.readiness().unwrap();
+    ///
+    /// db.warm_up(&s).unwrap();
+    /// ```
+    pub fn readiness(&self) -> Result<Scratch> {
+        check_platform()?;
+
+        self.alloc_scratch()
+    }
+}
+
+impl DatabaseRef<Block> {
+    /// Perform a tiny throwaway scan to fault in the database pages and populate the CPU caches,
+    /// so a latency-sensitive service could pre-warm during startup instead of on the first request.
+    pub fn warm_up(&self, scratch: &ScratchRef) -> Result<()> {
+        self.scan(&warm_up_data()[..], scratch, ())
+    }
+}
+
+impl DatabaseRef<Vectored> {
+    /// Perform a tiny throwaway scan to fault in the database pages and populate the CPU caches,
+    /// so a latency-sensitive service could pre-warm during startup instead of on the first request.
+    pub fn warm_up(&self, scratch: &ScratchRef) -> Result<()> {
+        self.scan(iter::once(&warm_up_data()[..]), scratch, ())
+    }
+}
+
+impl DatabaseRef<Streaming> {
+    /// Perform a tiny throwaway scan on a temporary stream to fault in the database pages
+    /// and populate the CPU caches, so a latency-sensitive service could pre-warm
+    /// during startup instead of on the first request.
+    pub fn warm_up(&self, scratch: &ScratchRef) -> Result<()> {
+        let st = self.open_stream()?;
+
+        st.scan(&warm_up_data()[..], scratch, ())?;
+        st.close(scratch, ())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_warm_up() {
+        let db: BlockDatabase = pattern! {"test"}.build().unwrap();
+        let s = db.readiness().unwrap();
+
+        db.warm_up(&s).unwrap();
+
+        let db: VectoredDatabase = pattern! {"test"}.build().unwrap();
+        let s = db.readiness().unwrap();
+
+        db.warm_up(&s).unwrap();
+
+        let db: StreamingDatabase = pattern! {"test"}.build().unwrap();
+        let s = db.readiness().unwrap();
+
+        db.warm_up(&s).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+//! Shadow testing of pattern set updates
+//!
+//! The `Compare` scans the same corpus through an old and a new database, e.g. before and after
+//! a rule update, and reports the matches added or removed by the update for every pattern ID,
+//! which could be checked before hot-swapping the databases.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use hyperscan::{prelude::*, shadow::{Change, Compare}};
+//! let old: BlockDatabase = patterns!["foo", "bar"; SOM_LEFTMOST].build().unwrap();
+//! let new: BlockDatabase = patterns!["foo", "ba[rz]"; SOM_LEFTMOST].build().unwrap();
+//!
+//! let cmp = Compare::new(&old, &new).unwrap();
+//! let diffs = cmp.run(["foo bar", "bar baz"]).unwrap();
+//!
+//! assert_eq!(diffs.len(), 1);
+//! assert_eq!(diffs[0].id, 1);
+//! assert_eq!(diffs[0].change, Change::Changed);
+//! assert_eq!(diffs[0].added[0].input, 1);
+//! assert_eq!(diffs[0].added[0].range, 4..7);
+//! assert!(diffs[0].removed.is_empty());
+//! ```
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::Range;
+
+use crate::{
+    common::{Block, DatabaseRef},
+    runtime::{Matching, Scratch},
+    Result,
+};
+
+/// How the matches of a pattern changed between the old and the new database.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// The pattern only matches the corpus with the new database.
+    Added,
+    /// The pattern only matches the corpus with the old database.
+    Removed,
+    /// The pattern matches the corpus with both databases, but not at the same places.
+    Changed,
+}
+
+/// A match of a pattern in the corpus.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Occurrence {
+    /// The index of the input in the corpus.
+    pub input: usize,
+    /// The offsets of the match in the input.
+    pub range: Range<u64>,
+}
+
+/// The differences of the matches of a pattern.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diff {
+    /// The pattern ID.
+    pub id: u32,
+    /// How the matches of the pattern changed.
+    pub change: Change,
+    /// The matches only reported by the new database.
+    pub added: Vec<Occurrence>,
+    /// The matches only reported by the old database.
+    pub removed: Vec<Occurrence>,
+}
+
+/// Compare the matches of an old and a new database on the same corpus.
+///
+/// A single scratch space is allocated for both databases, and reused across the scanned inputs.
+pub struct Compare<'a> {
+    old: &'a DatabaseRef<Block>,
+    new: &'a DatabaseRef<Block>,
+    scratch: Scratch,
+}
+
+impl fmt::Debug for Compare<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Compare").finish()
+    }
+}
+
+/// The input index and the offsets of the matches by pattern ID.
+type Matches = BTreeMap<u32, BTreeSet<(usize, u64, u64)>>;
+
+fn occurrences<'m>(matches: impl Iterator<Item = &'m (usize, u64, u64)>) -> Vec<Occurrence> {
+    matches
+        .map(|&(input, from, to)| Occurrence { input, range: from..to })
+        .collect()
+}
+
+impl<'a> Compare<'a> {
+    /// Construct a comparison between the databases, with a scratch space suitable for both.
+    pub fn new(old: &'a DatabaseRef<Block>, new: &'a DatabaseRef<Block>) -> Result<Self> {
+        let mut scratch = old.alloc_scratch()?;
+
+        new.realloc_scratch(&mut scratch)?;
+
+        Ok(Compare { old, new, scratch })
+    }
+
+    /// Scan the corpus with both databases, and returns the differences ordered by pattern ID.
+    ///
+    /// The patterns matching the same places with both databases are not reported.
+    pub fn run<I, T>(&self, corpus: I) -> Result<Vec<Diff>>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let mut old = Matches::new();
+        let mut new = Matches::new();
+
+        for (input, data) in corpus.into_iter().enumerate() {
+            let data = data.as_ref();
+
+            self.collect(self.old, input, data, &mut old)?;
+            self.collect(self.new, input, data, &mut new)?;
+        }
+
+        let ids = old.keys().chain(new.keys()).copied().collect::<BTreeSet<_>>();
+        let empty = BTreeSet::new();
+
+        Ok(ids
+            .into_iter()
+            .filter_map(|id| {
+                let old = old.get(&id).unwrap_or(&empty);
+                let new = new.get(&id).unwrap_or(&empty);
+
+                if old == new {
+                    return None;
+                }
+
+                Some(Diff {
+                    id,
+                    change: if old.is_empty() {
+                        Change::Added
+                    } else if new.is_empty() {
+                        Change::Removed
+                    } else {
+                        Change::Changed
+                    },
+                    added: occurrences(new.difference(old)),
+                    removed: occurrences(old.difference(new)),
+                })
+            })
+            .collect())
+    }
+
+    fn collect(&self, db: &DatabaseRef<Block>, input: usize, data: &[u8], matches: &mut Matches) -> Result<()> {
+        db.scan(data, &self.scratch, |id, from, to, _| {
+            matches.entry(id).or_default().insert((input, from, to));
+            Matching::Continue
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::shadow::{Change, Compare, Occurrence};
+
+    #[test]
+    fn test_compare() {
+        let old: BlockDatabase = patterns!["foo", "bar", "baz"; SOM_LEFTMOST].build().unwrap();
+        let new: BlockDatabase = "1:/foo/L\n1:/qux/L\n2:/ba[rz]/L\n4:/test/L"
+            .parse::<Patterns>()
+            .unwrap()
+            .build()
+            .unwrap();
+        let cmp = Compare::new(&old, &new).unwrap();
+
+        assert!(cmp.run(["nothing"]).unwrap().is_empty());
+
+        let diffs = cmp
+            .run(["foo bar", "baz qux"])
+            .unwrap()
+            .into_iter()
+            .map(|diff| (diff.id, diff.change, diff.added, diff.removed))
+            .collect::<Vec<_>>();
+
+        let at = |input, range| Occurrence { input, range };
+
+        assert_eq!(
+            diffs,
+            vec![
+                (0, Change::Removed, vec![], vec![at(0, 0..3)]),
+                (1, Change::Changed, vec![at(0, 0..3), at(1, 4..7)], vec![at(0, 4..7)]),
+                (2, Change::Changed, vec![at(0, 4..7)], vec![]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::FromIterator;
+
+use crate::{
+    common::{Block, Database, DatabaseRef, Mode, Vectored},
+    compile::{Builder, Pattern, Patterns, PlatformRef},
+    runtime::{Matching, ScratchRef},
+    Error, Result,
+};
+
+/// The patterns associated with the metadata, e.g. the rule names and severities.
+///
+/// The metadata is keyed by the pattern ID, a pattern without ID is identified by its index,
+/// or by a fresh ID if its index is the ID of another pattern.
+///
+/// # Examples
+///
+/// ```rust
+/// # use hyperscan::{prelude::*, BlockMode, TaggedDatabase, TaggedPatterns};
+/// let db: TaggedDatabase<BlockMode, &str> = vec![
+///     (pattern! {"foo"}, "rule-foo"),
+///     (pattern! {"bar"}, "rule-bar"),
+/// ]
+/// .into_iter()
+/// .collect::<TaggedPatterns<_>>()
+/// .build()
+/// .unwrap();
+/// let s = db.alloc_scratch().unwrap();
+/// let mut matches = vec![];
+///
+/// db.scan("foo bar", &s, |&rule, _, to| {
+///     matches.push((rule, to));
+///     Matching::Continue
+/// })
+/// .unwrap();
+///
+/// assert_eq!(matches, vec![("rule-foo", 3), ("rule-bar", 7)]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TaggedPatterns<M> {
+    patterns: Vec<(Pattern, M)>,
+}
+
+impl<M> Default for TaggedPatterns<M> {
+    fn default() -> Self {
+        TaggedPatterns { patterns: vec![] }
+    }
+}
+
+impl<M> FromIterator<(Pattern, M)> for TaggedPatterns<M> {
+    fn from_iter<T: IntoIterator<Item = (Pattern, M)>>(iter: T) -> Self {
+        TaggedPatterns {
+            patterns: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<M> Extend<(Pattern, M)> for TaggedPatterns<M> {
+    fn extend<T: IntoIterator<Item = (Pattern, M)>>(&mut self, iter: T) {
+        self.patterns.extend(iter)
+    }
+}
+
+impl<M> TaggedPatterns<M> {
+    /// Construct an empty set of patterns.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a pattern with the metadata.
+    pub fn push(&mut self, pattern: Pattern, tag: M) -> &mut Self {
+        self.patterns.push((pattern, tag));
+        self
+    }
+
+    /// Returns the number of patterns.
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Returns `true` if there is no pattern.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Compile the patterns into a database which reports the metadata of the matched patterns.
+    ///
+    /// Returns `Error::InvalidId` if multiple patterns share an ID, or an ID doesn't fit in `u32`.
+    pub fn build<T: Mode>(self) -> Result<TaggedDatabase<T, M>> {
+        self.for_platform(None)
+    }
+
+    /// Compile the patterns for a target platform into a database which reports the metadata of the matched patterns.
+    ///
+    /// Returns `Error::InvalidId` if multiple patterns share an ID, or an ID doesn't fit in `u32`.
+    pub fn for_platform<T: Mode>(self, platform: Option<&PlatformRef>) -> Result<TaggedDatabase<T, M>> {
+        let explicit = self
+            .patterns
+            .iter()
+            .filter_map(|(pattern, _)| pattern.id)
+            .collect::<HashSet<_>>();
+        let mut fresh = explicit.iter().max().map_or(0, |&id| id + 1).max(self.patterns.len());
+        let mut tags = HashMap::with_capacity(self.patterns.len());
+        let mut patterns = Vec::with_capacity(self.patterns.len());
+
+        for (i, (pattern, tag)) in self.patterns.into_iter().enumerate() {
+            let id = match pattern.id {
+                Some(id) => id,
+                None if !explicit.contains(&i) => i,
+                None => {
+                    fresh += 1;
+                    fresh - 1
+                }
+            };
+
+            if tags
+                .insert(u32::try_from(id).map_err(|_| Error::InvalidId(id))?, tag)
+                .is_some()
+            {
+                return Err(Error::InvalidId(id));
+            }
+
+            patterns.push(Pattern {
+                id: Some(id),
+                ..pattern
+            });
+        }
+
+        let db = Patterns(patterns).for_platform(platform)?;
+
+        Ok(TaggedDatabase { db, tags })
+    }
+}
+
+/// A database which reports the metadata of the matched patterns instead of their IDs.
+pub struct TaggedDatabase<T, M> {
+    db: Database<T>,
+    tags: HashMap<u32, M>,
+}
+
+impl<T, M: fmt::Debug> fmt::Debug for TaggedDatabase<T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaggedDatabase").field("tags", &self.tags).finish()
+    }
+}
+
+impl<T, M> std::ops::Deref for TaggedDatabase<T, M> {
+    type Target = DatabaseRef<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.db
+    }
+}
+
+impl<T, M> TaggedDatabase<T, M> {
+    /// Returns the metadata of the pattern ID.
+    pub fn tag(&self, id: u32) -> Option<&M> {
+        self.tags.get(&id)
+    }
+
+    /// Consumes the database, returning the compiled database and the metadata keyed by the pattern IDs.
+    pub fn into_inner(self) -> (Database<T>, HashMap<u32, M>) {
+        (self.db, self.tags)
+    }
+
+    /// Wrap a callback receiving the metadata of the matched pattern into a match event handler,
+    /// which could be passed to any scan of the database, e.g. `StreamRef::scan` or `Stream::close`.
+    ///
+    /// The matches of a pattern without metadata (e.g. a quiet sub-pattern of a combination) are ignored.
+    pub fn on_match<'a, F>(&'a self, mut f: F) -> impl FnMut(u32, u64, u64, u32) -> Matching + 'a
+    where
+        F: FnMut(&'a M, u64, u64) -> Matching + 'a,
+    {
+        move |id, from, to, _| match self.tags.get(&id) {
+            Some(tag) => f(tag, from, to),
+            None => Matching::Continue,
+        }
+    }
+}
+
+impl<M> TaggedDatabase<Block, M> {
+    /// The block (non-streaming) regular expression scanner, which reports the metadata of the matched patterns.
+    pub fn scan<D, F>(&self, data: D, scratch: &ScratchRef, f: F) -> Result<()>
+    where
+        D: AsRef<[u8]>,
+        F: FnMut(&M, u64, u64) -> Matching,
+    {
+        self.db.scan(data, scratch, self.on_match(f))
+    }
+}
+
+impl<M> TaggedDatabase<Vectored, M> {
+    /// The vectored regular expression scanner, which reports the metadata of the matched patterns.
+    pub fn scan<I, D, F>(&self, data: I, scratch: &ScratchRef, f: F) -> Result<()>
+    where
+        I: IntoIterator<Item = D>,
+        D: AsRef<[u8]>,
+        F: FnMut(&M, u64, u64) -> Matching,
+    {
+        self.db.scan(data, scratch, self.on_match(f))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::prelude::*;
+    use crate::{BlockMode, StreamingMode, TaggedDatabase, TaggedPatterns};
+
+    #[derive(Debug, PartialEq)]
+    struct Rule {
+        name: &'static str,
+        severity: u8,
+    }
+
+    #[test]
+    fn test_tagged_database() {
+        let mut patterns = TaggedPatterns::new();
+
+        patterns
+            .push(
+                pattern! {"foo"},
+                Rule {
+                    name: "foo",
+                    severity: 1,
+                },
+            )
+            .push(
+                Pattern {
+                    id: Some(10),
+                    ..pattern! {"bar"}
+                },
+                Rule {
+                    name: "bar",
+                    severity: 2,
+                },
+            )
+            .push(
+                pattern! {"baz"},
+                Rule {
+                    name: "baz",
+                    severity: 3,
+                },
+            );
+
+        assert_eq!(patterns.len(), 3);
+
+        let db: TaggedDatabase<BlockMode, _> = patterns.build().unwrap();
+
+        assert_eq!(db.tag(10).unwrap().name, "bar");
+        assert_eq!(db.tag(2).unwrap().name, "baz");
+        assert!(db.tag(1).is_none());
+
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan("baz foo bar", &s, |rule, _, to| {
+            matches.push((rule.name, rule.severity, to));
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec![("baz", 3, 3), ("foo", 1, 7), ("bar", 2, 11)]);
+    }
+
+    #[test]
+    fn test_tagged_ids() {
+        let db: TaggedDatabase<BlockMode, _> = vec![
+            (pattern! {"foo"}, "foo"),
+            (pattern! {"bar"}, "bar"),
+            (
+                Pattern {
+                    id: Some(1),
+                    ..pattern! {"baz"}
+                },
+                "baz",
+            ),
+        ]
+        .into_iter()
+        .collect::<TaggedPatterns<_>>()
+        .build()
+        .unwrap();
+
+        assert_eq!(db.tag(0), Some(&"foo"));
+        assert_eq!(db.tag(1), Some(&"baz"));
+        assert_eq!(db.tag(3), Some(&"bar"));
+
+        let s = db.alloc_scratch().unwrap();
+        let mut matches = vec![];
+
+        db.scan("foo bar baz", &s, |&rule, _, _| {
+            matches.push(rule);
+            Matching::Continue
+        })
+        .unwrap();
+
+        assert_eq!(matches, vec!["foo", "bar", "baz"]);
+
+        let res: crate::Result<TaggedDatabase<BlockMode, _>> = vec![
+            (
+                Pattern {
+                    id: Some(1),
+                    ..pattern! {"foo"}
+                },
+                "foo",
+            ),
+            (
+                Pattern {
+                    id: Some(1),
+                    ..pattern! {"bar"}
+                },
+                "bar",
+            ),
+        ]
+        .into_iter()
+        .collect::<TaggedPatterns<_>>()
+        .build();
+
+        assert_eq!(res.unwrap_err(), crate::Error::InvalidId(1));
+    }
+
+    #[test]
+    fn test_tagged_stream() {
+        let db: TaggedDatabase<StreamingMode, _> = vec![(pattern! {"test"}, "rule")]
+            .into_iter()
+            .collect::<TaggedPatterns<_>>()
+            .build()
+            .unwrap();
+        let s = db.alloc_scratch().unwrap();
+        let st = db.open_stream().unwrap();
+        let mut matches = vec![];
+
+        for data in &["foo te", "st bar"] {
+            st.scan(
+                data,
+                &s,
+                db.on_match(|&rule, _, to| {
+                    matches.push((rule, to));
+                    Matching::Continue
+                }),
+            )
+            .unwrap();
+        }
+
+        st.close(&s, db.on_match(|_, _, _| Matching::Continue)).unwrap();
+
+        assert_eq!(matches, vec![("rule", 8)]);
+    }
+}